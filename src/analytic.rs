@@ -0,0 +1,138 @@
+use ballistics_engine::constants::{CD_TO_RETARD, G_ACCEL_MPS2};
+use ballistics_engine::drag::get_drag_coefficient;
+use ballistics_engine::transonic_drag::{get_projectile_shape, transonic_correction};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult, WindConditions};
+use nalgebra::Vector3;
+
+use crate::drag;
+use crate::units::*;
+
+// Closed-form and table-based flat-fire solvers, for the `solver` models that
+// trade accuracy for speed. They work along the bore line, where a flat-fire
+// trajectory's velocity and time depend on range alone and gravity only bends
+// the path, so drop follows from the velocity as
+//
+//   d²y/dx² = -g / v(x)²
+//
+// Crosswind drift is Didion's lag rule, w · (t - x / V0); range (head or tail)
+// wind is ignored, and so is the shooting angle, as the engine's solver does.
+
+/// Range the engine's solver stops at unless told otherwise, metres
+const DEFAULT_MAX_RANGE_M: f64 = 1000.0;
+/// Spacing of the points, metres
+const POINT_SPACING_M: f64 = 1.0;
+
+/// Velocity, time and drop along the bore line
+pub trait FlatFire {
+    /// `(velocity m/s, time s, drop below the bore line m)` at `range_m`, or
+    /// None past where the model holds
+    fn at(&self, range_m: f64) -> Option<(f64, f64, f64)>;
+}
+
+/// Drag of the solver's tables, in the same terms as the engine's integrator
+pub struct Drag<'a> {
+    inputs: &'a BallisticInputs,
+    shape: ballistics_engine::transonic_drag::ProjectileShape,
+    speed_of_sound_fps: f64,
+    density_scale: f64,
+}
+
+impl<'a> Drag<'a> {
+    /// For `inputs` already prepared by `drag::apply`
+    pub fn new(inputs: &'a BallisticInputs, atmosphere: &AtmosphericConditions) -> Self {
+        Self {
+            inputs,
+            shape: get_projectile_shape(inputs.bullet_diameter, inputs.bullet_mass / GRAINS_TO_KG, &inputs.bc_type.to_string()),
+            speed_of_sound_fps: drag::solver_speed_of_sound_mps(atmosphere) / FPS_TO_MPS,
+            density_scale: drag::solver_air_density(atmosphere) / 1.225,
+        }
+    }
+
+    /// Retardation distance v / |dv/dx| at `velocity_fps`, feet
+    ///
+    /// The engine's drag slows the bullet by a = v² · CD_TO_RETARD · Cd(M) · (ρ / 1.225) / BC,
+    /// so over a distance dx it loses dv = v · dx / F with F = BC / (CD_TO_RETARD · Cd(M) · (ρ / 1.225)).
+    pub fn retardation_ft(&self, velocity_fps: f64) -> f64 {
+        let mach = velocity_fps / self.speed_of_sound_fps;
+        let cd = transonic_correction(mach, get_drag_coefficient(mach, &self.inputs.bc_type), self.shape, false);
+        let bc = self
+            .inputs
+            .bc_segments_data
+            .as_ref()
+            .and_then(|segments| segments.iter().find(|segment| velocity_fps >= segment.velocity_min && velocity_fps < segment.velocity_max))
+            .map_or(self.inputs.bc_value, |segment| segment.bc_value);
+        bc / (CD_TO_RETARD * cd * self.density_scale)
+    }
+}
+
+/// Range to solve to, metres: `max_range_m` or the engine's default
+pub fn max_range_m(max_range_m: Option<f64>) -> f64 {
+    max_range_m.unwrap_or(DEFAULT_MAX_RANGE_M)
+}
+
+/// Launch angle of `model` that puts the trajectory on the line of sight at the zero distance, radians
+pub fn zero_angle(model: &impl FlatFire, inputs: &BallisticInputs) -> Result<f64, String> {
+    if inputs.target_distance <= 0.0 {
+        return Ok(0.0);
+    }
+    let (_, _, drop) = model
+        .at(inputs.target_distance)
+        .ok_or_else(|| format!("the {:.0} m zero distance is beyond the analytic solver's reach", inputs.target_distance))?;
+    Ok(((inputs.sight_height + drop) / inputs.target_distance).atan())
+}
+
+/// Zero `inputs`, unless given the `muzzle_angle` to fire at, and solve
+/// `model` to `max_range_m` as an engine result
+///
+/// `inputs.muzzle_angle` is set to the launch angle used.
+pub fn solve(
+    model: &impl FlatFire,
+    inputs: &mut BallisticInputs,
+    wind: &WindConditions,
+    max_range_m: f64,
+    muzzle_angle: Option<f64>,
+) -> Result<EngineTrajectoryResult, String> {
+    inputs.muzzle_angle = match muzzle_angle {
+        Some(muzzle_angle) => muzzle_angle,
+        None => zero_angle(model, inputs)?,
+    };
+    let slope = inputs.muzzle_angle.tan();
+    let crosswind = wind.speed * wind.direction.sin();
+
+    let count = (max_range_m / POINT_SPACING_M).ceil() as usize;
+    let points = (0..=count)
+        .map(|index| (index as f64 * POINT_SPACING_M).min(max_range_m))
+        .map_while(|range_m| model.at(range_m).map(|flight| (range_m, flight)))
+        .map(|(range_m, (velocity, time, drop))| EngineTrajectoryPoint {
+            time,
+            position: Vector3::new(
+                crosswind * (time - range_m / inputs.muzzle_velocity),
+                inputs.muzzle_height + range_m * slope - drop,
+                range_m,
+            ),
+            velocity_magnitude: velocity,
+            kinetic_energy: 0.5 * inputs.bullet_mass * velocity * velocity,
+        })
+        .collect::<Vec<_>>();
+
+    let last = points.last().ok_or("No trajectory points generated")?;
+    Ok(EngineTrajectoryResult {
+        max_range: last.position.z,
+        max_height: points.iter().map(|point| point.position.y).fold(f64::MIN, f64::max),
+        time_of_flight: last.time,
+        impact_velocity: last.velocity_magnitude,
+        impact_energy: last.kinetic_energy,
+        points,
+        sampled_points: None,
+        min_pitch_damping: None,
+        transonic_mach: None,
+        angular_state: None,
+        max_yaw_angle: None,
+        max_precession_angle: None,
+    })
+}
+
+/// Gravity, ft/s²
+pub fn gravity_fps2() -> f64 {
+    G_ACCEL_MPS2 / FEET_TO_METERS
+}
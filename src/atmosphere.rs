@@ -0,0 +1,413 @@
+use std::cell::RefCell;
+
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::{get_kwargs, scan_args}};
+use ballistics_engine::AtmosphericConditions;
+
+use crate::coerce;
+use crate::config;
+use crate::error;
+use crate::metric;
+use crate::units::*;
+use crate::validation::{self, Problems};
+
+/// Atmosphere in the imperial units accepted from Ruby
+#[derive(Clone, Copy, PartialEq)]
+pub struct ImperialAtmosphere {
+    pub temperature_f: f64,
+    pub pressure_inhg: f64,
+    pub humidity_percent: f64,
+    pub altitude_feet: f64,
+}
+
+impl Default for ImperialAtmosphere {
+    /// Standard ICAO sea-level atmosphere
+    fn default() -> Self {
+        Self {
+            temperature_f: 59.0,  // 15°C
+            pressure_inhg: 29.92, // 1013.25 hPa
+            humidity_percent: 50.0,
+            altitude_feet: 0.0,
+        }
+    }
+}
+
+/// Reference atmosphere that BCs are published against, selected by `standard`
+///
+/// The engine's drag is referenced to ICAO air; many older G1 BCs were
+/// measured against Army Standard Metro, about 2% thinner.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Standard {
+    /// ICAO: 59 °F, 29.92 inHg, 1.225 kg/m³
+    #[default]
+    Icao,
+    /// Army Standard Metro: 59 °F, 29.53 inHg, 78% humidity, 0.0751265 lb/ft³
+    Asm,
+}
+
+/// Names accepted as `standard`
+pub const STANDARDS: &[(&str, Standard)] = &[("icao", Standard::Icao), ("asm", Standard::Asm)];
+
+/// Army Standard Metro air density, kg/m³
+const ASM_DENSITY: f64 = 0.075_126_5 * 16.018_46;
+
+impl Standard {
+    pub fn name(self) -> &'static str {
+        STANDARDS.iter().find(|(_, standard)| *standard == self).map_or("", |(name, _)| name)
+    }
+
+    /// The standard's sea-level conditions
+    pub fn conditions(self) -> ImperialAtmosphere {
+        match self {
+            Self::Icao => ImperialAtmosphere::default(),
+            Self::Asm => ImperialAtmosphere { temperature_f: 59.0, pressure_inhg: 29.5275, humidity_percent: 78.0, altitude_feet: 0.0 },
+        }
+    }
+
+    /// Factor taking a BC published against this standard to the ICAO one the engine uses
+    ///
+    /// A BC is the ratio of the standard projectile's drag to the bullet's in
+    /// the reference air, so the same drag in denser reference air needs a
+    /// proportionally larger BC.
+    pub fn bc_factor(self) -> f64 {
+        match self {
+            Self::Icao => 1.0,
+            Self::Asm => ASM_DENSITY / standard_density(0.0),
+        }
+    }
+}
+
+/// Read a `standard` name (nil is the configured one), recording an unsupported one
+pub fn read_standard(name: Option<String>, problems: &mut Problems) -> Standard {
+    let Some(name) = name else {
+        return config::defaults().standard;
+    };
+    match STANDARDS.iter().find(|(standard_name, _)| standard_name.eq_ignore_ascii_case(&name)) {
+        Some((_, standard)) => *standard,
+        None => {
+            let valid = STANDARDS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+            problems.add("standard", format!("standard {:?} is not supported (supported: {})", name, valid));
+            Standard::default()
+        }
+    }
+}
+
+/// Which of the atmosphere's values were given rather than defaulted
+#[derive(Clone, Copy, Default)]
+pub struct Given {
+    pub temperature: bool,
+    pub pressure: bool,
+    pub humidity: bool,
+}
+
+fn given(value: Option<Value>) -> bool {
+    value.is_some_and(|value| !value.is_nil())
+}
+
+impl Given {
+    /// The humidity counts as given with `humidity_percent` or `dew_point_f`
+    fn from_kwargs(temperature_f: Option<Value>, pressure_inhg: Option<Value>, humidity_percent: Option<Value>, dew_point_f: Option<Value>) -> Self {
+        Self {
+            temperature: given(temperature_f),
+            pressure: given(pressure_inhg),
+            humidity: given(humidity_percent) || given(dew_point_f),
+        }
+    }
+
+    fn from_hash(atm_hash: RHash) -> Self {
+        Self::from_kwargs(
+            validation::entry(atm_hash, "temperature_f"),
+            validation::entry(atm_hash, "pressure_inhg"),
+            validation::entry(atm_hash, "humidity_percent"),
+            validation::entry(atm_hash, "dew_point_f"),
+        )
+    }
+}
+
+/// An optional number such as `density_altitude_feet`, None if nil or not given
+fn read_number(field: &str, value: Option<Value>, problems: &mut Problems) -> Option<f64> {
+    value.filter(|value| !value.is_nil()).map(|value| problems.required(field, Some(value)))
+}
+
+/// Saturation vapour pressure over water at `temperature_f`, hPa (Buck 1996)
+fn saturation_vapor_pressure(temperature_f: f64) -> f64 {
+    let celsius = (temperature_f - 32.0) * 5.0 / 9.0;
+    6.1121 * ((18.678 - celsius / 234.5) * (celsius / (257.14 + celsius))).exp()
+}
+
+/// Air density of the ICAO standard atmosphere at `altitude_feet`, kg/m³
+fn standard_density(altitude_feet: f64) -> f64 {
+    1.225 * (1.0 - 6.875_59e-6 * altitude_feet).powf(4.2559)
+}
+
+/// Density altitude of an air density: the ICAO standard atmosphere's altitude with it, feet
+pub fn density_altitude_feet(air_density: f64) -> f64 {
+    (1.0 - (air_density / 1.225).powf(1.0 / 4.2559)) / 6.875_59e-6
+}
+
+impl ImperialAtmosphere {
+    /// Read atmosphere from a string-keyed Ruby hash, recording every invalid entry
+    pub fn from_hash(atm_hash: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let atm_hash = metric::to_imperial(atm_hash, metric::ATMOSPHERE, "atmosphere.", problems)?;
+        let default = config::defaults().atmosphere;
+        let atmosphere = Self {
+            temperature_f: problems.lookup(atm_hash, "atmosphere.temperature_f", "temperature_f", default.temperature_f),
+            pressure_inhg: problems.lookup(atm_hash, "atmosphere.pressure_inhg", "pressure_inhg", default.pressure_inhg),
+            humidity_percent: problems.lookup(atm_hash, "atmosphere.humidity_percent", "humidity_percent", default.humidity_percent),
+            altitude_feet: problems.lookup(atm_hash, "atmosphere.altitude_feet", "altitude_feet", default.altitude_feet),
+        };
+        let absolute = problems.lookup(atm_hash, "atmosphere.pressure_is_absolute", "pressure_is_absolute", true);
+        let dew_point = read_number("atmosphere.dew_point_f", validation::entry(atm_hash, "dew_point_f"), problems);
+        let density_altitude = read_number("atmosphere.density_altitude_feet", validation::entry(atm_hash, "density_altitude_feet"), problems);
+        let air_density = read_number("atmosphere.air_density_kg_m3", validation::entry(atm_hash, "air_density_kg_m3"), problems);
+        let speed_of_sound = read_number("atmosphere.speed_of_sound_fps", validation::entry(atm_hash, "speed_of_sound_fps"), problems);
+        let atmosphere = atmosphere
+            .with_dew_point(dew_point, given(validation::entry(atm_hash, "humidity_percent")), problems)
+            .with_pressure_reference(absolute)
+            .with_density_altitude(density_altitude, Given::from_hash(atm_hash), problems)
+            .with_air_density(air_density, density_altitude.is_some(), Given::from_hash(atm_hash), problems)
+            .with_speed_of_sound(speed_of_sound, Given::from_hash(atm_hash), problems);
+        atmosphere.check(problems);
+        Ok(atmosphere)
+    }
+
+    /// Read atmosphere from a symbol-keyed keyword hash, rejecting unknown keys
+    pub fn from_kwargs(kw: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let kw = metric::to_imperial(kw, metric::ATMOSPHERE, "atmosphere.", problems)?;
+        type Optional = (Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>);
+        let kwargs = get_kwargs::<_, (), Optional, ()>(
+            kw,
+            &[],
+            &[
+                "temperature_f",
+                "pressure_inhg",
+                "humidity_percent",
+                "altitude_feet",
+                "pressure_is_absolute",
+                "dew_point_f",
+                "density_altitude_feet",
+                "air_density_kg_m3",
+                "speed_of_sound_fps",
+            ],
+        )
+        .map_err(|e| error::as_input_error(&Ruby::get_with(kw), e))?;
+        let (
+            temperature_f,
+            pressure_inhg,
+            humidity_percent,
+            altitude_feet,
+            pressure_is_absolute,
+            dew_point_f,
+            density_altitude_feet,
+            air_density_kg_m3,
+            speed_of_sound_fps,
+        ) = kwargs.optional;
+        let default = config::defaults().atmosphere;
+
+        let atmosphere = Self {
+            temperature_f: problems.optional("atmosphere.temperature_f", temperature_f, default.temperature_f),
+            pressure_inhg: problems.optional("atmosphere.pressure_inhg", pressure_inhg, default.pressure_inhg),
+            humidity_percent: problems.optional("atmosphere.humidity_percent", humidity_percent, default.humidity_percent),
+            altitude_feet: problems.optional("atmosphere.altitude_feet", altitude_feet, default.altitude_feet),
+        };
+        let absolute = problems.optional("atmosphere.pressure_is_absolute", pressure_is_absolute, true);
+        let dew_point = read_number("atmosphere.dew_point_f", dew_point_f, problems);
+        let density_altitude = read_number("atmosphere.density_altitude_feet", density_altitude_feet, problems);
+        let air_density = read_number("atmosphere.air_density_kg_m3", air_density_kg_m3, problems);
+        let speed_of_sound = read_number("atmosphere.speed_of_sound_fps", speed_of_sound_fps, problems);
+        let humidity_given = given(humidity_percent);
+        let given = Given::from_kwargs(temperature_f, pressure_inhg, humidity_percent, dew_point_f);
+        let atmosphere = atmosphere
+            .with_dew_point(dew_point, humidity_given, problems)
+            .with_pressure_reference(absolute)
+            .with_density_altitude(density_altitude, given, problems)
+            .with_air_density(air_density, density_altitude.is_some(), given, problems)
+            .with_speed_of_sound(speed_of_sound, given, problems);
+        atmosphere.check(problems);
+        Ok(atmosphere)
+    }
+
+    /// Read atmosphere from an optional keyword value: nil, an `Atmosphere`, or a symbol-keyed hash (or anything with `to_h`)
+    pub fn from_option(value: Option<Value>, problems: &mut Problems) -> Result<Self, Error> {
+        match value {
+            None => Ok(config::defaults().atmosphere),
+            Some(value) if value.is_nil() => Ok(config::defaults().atmosphere),
+            Some(value) => match <&Atmosphere>::try_convert(value) {
+                Ok(atmosphere) => {
+                    let atmosphere = atmosphere.atmosphere();
+                    atmosphere.check(problems);
+                    Ok(atmosphere)
+                }
+                Err(e) => match coerce::hash_like(value)? {
+                    Some(kw) => Self::from_kwargs(kw, problems),
+                    None => Err(e),
+                },
+            },
+        }
+    }
+
+    /// `self` with the humidity worked out from `dew_point_f`, if given
+    ///
+    /// The relative humidity is the vapour pressure at the dew point over the
+    /// saturation vapour pressure at `temperature_f`.
+    pub fn with_dew_point(self, dew_point_f: Option<f64>, humidity_given: bool, problems: &mut Problems) -> Self {
+        let Some(dew_point_f) = dew_point_f else {
+            return self;
+        };
+        problems.check("atmosphere.dew_point_f", !humidity_given, "cannot be given with humidity_percent");
+        problems.check("atmosphere.dew_point_f", dew_point_f <= self.temperature_f, "cannot be above temperature_f");
+        let humidity_percent = 100.0 * saturation_vapor_pressure(dew_point_f) / saturation_vapor_pressure(self.temperature_f);
+        Self { humidity_percent: humidity_percent.min(100.0), ..self }
+    }
+
+    /// `self` with the pressure taken as station pressure, or else as an
+    /// altimeter setting (corrected to sea level) and converted to station
+    /// pressure at `altitude_feet` through the standard atmosphere
+    pub fn with_pressure_reference(self, absolute: bool) -> Self {
+        if absolute {
+            return self;
+        }
+        Self { pressure_inhg: self.pressure_inhg * (1.0 - 6.875_59e-6 * self.altitude_feet).powf(5.2559), ..self }
+    }
+
+    /// `self` with `density_altitude_feet` applied, if given
+    ///
+    /// The result is a dry atmosphere at `altitude_feet` whose air density, as
+    /// the solver works it out, is the standard atmosphere's at the density
+    /// altitude. A given temperature is kept (it sets the speed of sound), and
+    /// otherwise is the standard one at the density altitude; the pressure is
+    /// derived, so giving it or the humidity as well is a problem.
+    pub fn with_density_altitude(self, density_altitude_feet: Option<f64>, given: Given, problems: &mut Problems) -> Self {
+        let Some(density_altitude_feet) = density_altitude_feet else {
+            return self;
+        };
+        problems.check(
+            "atmosphere.density_altitude_feet",
+            !given.pressure && !given.humidity,
+            "cannot be given with pressure_inhg, humidity_percent or dew_point_f",
+        );
+        problems.check("atmosphere.density_altitude_feet", density_altitude_feet < 36_000.0, "must be below 36000 feet");
+        let temperature_f = if given.temperature { self.temperature_f } else { 59.0 - 0.003_566_2 * density_altitude_feet };
+        let temperature_k = (temperature_f - 32.0) * 5.0 / 9.0 + 273.15;
+        let lapse = (self.altitude_feet * FEET_TO_METERS / 8000.0).exp();
+        let pressure_hpa = standard_density(density_altitude_feet) * 287.058 * temperature_k / 100.0 * lapse;
+        Self { temperature_f, pressure_inhg: pressure_hpa / INHG_TO_HPA, humidity_percent: 0.0, ..self }
+    }
+
+    /// `self` with `air_density_kg_m3` applied, if given
+    ///
+    /// The result is a dry atmosphere at `altitude_feet` whose air density, as
+    /// the solver works it out, is the one given: the pressure is derived
+    /// from it and the temperature, which is kept for the speed of sound.
+    /// Giving the pressure, humidity or a density altitude as well is a problem.
+    pub fn with_air_density(self, air_density_kg_m3: Option<f64>, density_altitude_given: bool, given: Given, problems: &mut Problems) -> Self {
+        let Some(air_density_kg_m3) = air_density_kg_m3 else {
+            return self;
+        };
+        problems.check(
+            "atmosphere.air_density_kg_m3",
+            !given.pressure && !given.humidity && !density_altitude_given,
+            "cannot be given with pressure_inhg, humidity_percent, dew_point_f or density_altitude_feet",
+        );
+        problems.check_positive("atmosphere.air_density_kg_m3", air_density_kg_m3);
+        let temperature_k = (self.temperature_f - 32.0) * 5.0 / 9.0 + 273.15;
+        let lapse = (self.altitude_feet * FEET_TO_METERS / 8000.0).exp();
+        let pressure_hpa = air_density_kg_m3 * 287.058 * temperature_k / 100.0 * lapse;
+        Self { pressure_inhg: pressure_hpa / INHG_TO_HPA, humidity_percent: 0.0, ..self }
+    }
+
+    /// `self` with `speed_of_sound_fps` applied, if given
+    ///
+    /// The result is a dry atmosphere whose speed of sound, as the solver
+    /// works it out from the temperature, is the one given. The temperature is
+    /// set to match and the pressure moved with it, so the air density the
+    /// solver works out stays the same. Giving the humidity as well is a
+    /// problem, as the dry atmosphere would drop it.
+    pub fn with_speed_of_sound(self, speed_of_sound_fps: Option<f64>, given: Given, problems: &mut Problems) -> Self {
+        let Some(speed_of_sound_fps) = speed_of_sound_fps else {
+            return self;
+        };
+        problems.check("atmosphere.speed_of_sound_fps", !given.humidity, "cannot be given with humidity_percent or dew_point_f");
+        problems.check_positive("atmosphere.speed_of_sound_fps", speed_of_sound_fps);
+        if !(speed_of_sound_fps > 0.0 && speed_of_sound_fps.is_finite()) {
+            return self;
+        }
+        let temperature_k = (speed_of_sound_fps * FPS_TO_MPS).powi(2) / (1.4 * 287.05);
+        let given_k = (self.temperature_f - 32.0) * 5.0 / 9.0 + 273.15;
+        Self {
+            temperature_f: (temperature_k - 273.15) * 9.0 / 5.0 + 32.0,
+            pressure_inhg: self.pressure_inhg * temperature_k / given_k,
+            humidity_percent: 0.0,
+            ..self
+        }
+    }
+
+    /// Record every out-of-range value
+    pub fn check(&self, problems: &mut Problems) {
+        problems.check("atmosphere.temperature_f", self.temperature_f.is_finite() && self.temperature_f > -459.67, "must be above absolute zero");
+        problems.check_positive("atmosphere.pressure_inhg", self.pressure_inhg);
+        problems.check_range("atmosphere.humidity_percent", self.humidity_percent, 0.0, 100.0);
+        problems.check("atmosphere.altitude_feet", self.altitude_feet.is_finite(), "must be finite");
+    }
+
+    /// Symbol-keyed hash accepted by `from_kwargs`
+    pub fn to_h(self, ruby: &Ruby) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("temperature_f"), self.temperature_f)?;
+        hash.aset(ruby.to_symbol("pressure_inhg"), self.pressure_inhg)?;
+        hash.aset(ruby.to_symbol("humidity_percent"), self.humidity_percent)?;
+        hash.aset(ruby.to_symbol("altitude_feet"), self.altitude_feet)?;
+        Ok(hash)
+    }
+
+    /// Convert to engine atmospheric conditions (°C, hPa, %, m)
+    pub fn to_conditions(self) -> AtmosphericConditions {
+        AtmosphericConditions {
+            temperature: (self.temperature_f - 32.0) * 5.0 / 9.0,
+            pressure: self.pressure_inhg * INHG_TO_HPA,
+            humidity: self.humidity_percent,
+            altitude: self.altitude_feet * FEET_TO_METERS,
+        }
+    }
+}
+
+/// Reusable atmospheric conditions
+#[magnus::wrap(class = "BallisticsEngine::Atmosphere", free_immediately, size)]
+pub struct Atmosphere(RefCell<ImperialAtmosphere>);
+
+impl Atmosphere {
+    /// Atmosphere.new(temperature_f: 59.0, pressure_inhg: 29.92, humidity_percent: 50.0, altitude_feet: 0.0)
+    ///
+    /// Or `Atmosphere.new(density_altitude_feet: 4500)`, with the pressure derived.
+    fn new(ruby: &Ruby, args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let atmosphere = ImperialAtmosphere::from_kwargs(args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+        Ok(Self(RefCell::new(atmosphere)))
+    }
+
+    /// Snapshot of the current values
+    pub fn atmosphere(&self) -> ImperialAtmosphere {
+        *self.0.borrow()
+    }
+}
+
+impl From<ImperialAtmosphere> for Atmosphere {
+    fn from(atmosphere: ImperialAtmosphere) -> Self {
+        Self(RefCell::new(atmosphere))
+    }
+}
+
+field_accessors!(Atmosphere {
+    temperature_f / set_temperature_f: f64 = config::defaults().atmosphere.temperature_f,
+    pressure_inhg / set_pressure_inhg: f64 = config::defaults().atmosphere.pressure_inhg,
+    humidity_percent / set_humidity_percent: f64 = config::defaults().atmosphere.humidity_percent,
+    altitude_feet / set_altitude_feet: f64 = config::defaults().atmosphere.altitude_feet,
+});
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("Atmosphere", ruby.class_object())?;
+    class.define_singleton_method("new", function!(Atmosphere::new, -1))?;
+    Atmosphere::define_field_accessors(class)?;
+    Ok(())
+}
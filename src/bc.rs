@@ -0,0 +1,152 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, scan_args::{get_kwargs, scan_args}};
+use ballistics_engine::AtmosphericConditions;
+use ballistics_engine::constants::CD_TO_RETARD;
+use ballistics_engine::drag::get_drag_coefficient;
+
+use crate::atmosphere::ImperialAtmosphere;
+use crate::drag;
+use crate::error;
+use crate::metric;
+use crate::projectile;
+use crate::units::*;
+use crate::validation::Problems;
+
+// BC utilities that work on numbers rather than a whole load.
+
+/// Sectional density, lb/in²: weight in pounds over diameter in inches squared
+pub fn sectional_density(bullet_weight_grains: f64, bullet_diameter_inches: f64) -> f64 {
+    bullet_weight_grains / GRAINS_PER_POUND / bullet_diameter_inches.powi(2)
+}
+
+/// Checked weight and diameter from `bullet_weight_grains:`/`bullet_diameter_inches:` values
+fn read_bullet((weight, diameter): (Value, Value), problems: &mut Problems) -> (f64, f64) {
+    let bullet_weight_grains: f64 = problems.required("bullet_weight_grains", Some(weight));
+    let bullet_diameter_inches: f64 = problems.required("bullet_diameter_inches", Some(diameter));
+    problems.check_positive("bullet_weight_grains", bullet_weight_grains);
+    problems.check_positive("bullet_diameter_inches", bullet_diameter_inches);
+    (bullet_weight_grains, bullet_diameter_inches)
+}
+
+/// BallisticsEngine.sectional_density(bullet_weight_grains:, bullet_diameter_inches:)
+///
+/// Metric `bullet_weight_grams:`/`bullet_diameter_mm:` are accepted too.
+fn sectional_density_kw(ruby: &Ruby, args: &[Value]) -> Result<f64, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let mut problems = Problems::default();
+    let kw = metric::to_imperial(args.keywords, metric::PROJECTILE, "", &mut problems)?;
+    let kwargs = get_kwargs::<_, (Value, Value), (), ()>(kw, &["bullet_weight_grains", "bullet_diameter_inches"], &[])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let (bullet_weight_grains, bullet_diameter_inches) = read_bullet(kwargs.required, &mut problems);
+    problems.into_result(ruby)?;
+    Ok(sectional_density(bullet_weight_grains, bullet_diameter_inches))
+}
+
+/// BallisticsEngine.form_factor(bc:, bullet_weight_grains:, bullet_diameter_inches:)
+///
+/// The form factor the BC implies against its drag model: sectional density / BC.
+fn form_factor_kw(ruby: &Ruby, args: &[Value]) -> Result<f64, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let mut problems = Problems::default();
+    let kw = metric::to_imperial(args.keywords, metric::PROJECTILE, "", &mut problems)?;
+    let kwargs = get_kwargs::<_, (Value, Value, Value), (), ()>(kw, &["bc", "bullet_weight_grains", "bullet_diameter_inches"], &[])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let (bc, weight, diameter) = kwargs.required;
+    let bc: f64 = problems.required("bc", Some(bc));
+    problems.check_positive("bc", bc);
+    let (bullet_weight_grains, bullet_diameter_inches) = read_bullet((weight, diameter), &mut problems);
+    problems.into_result(ruby)?;
+    Ok(sectional_density(bullet_weight_grains, bullet_diameter_inches) / bc)
+}
+
+/// BallisticsEngine.convert_bc(value, from:, to:, velocity_fps:)
+///
+/// A BC against drag model `to` giving the same drag as `value` against
+/// `from` at `velocity_fps`, in the standard atmosphere (59 °F). The drag
+/// models' shapes differ, so the conversion only holds near that velocity:
+/// BC_to = BC_from · Cd_to(M) / Cd_from(M).
+fn convert_bc(ruby: &Ruby, args: &[Value]) -> Result<f64, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (value,) = args.required;
+    let kwargs = get_kwargs::<_, (Value, Value, Value), (), ()>(args.keywords, &["from", "to", "velocity_fps"], &[])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let (from, to, velocity_fps) = kwargs.required;
+
+    let mut problems = Problems::default();
+    let bc: f64 = problems.required("bc", Some(value));
+    let from = projectile::drag_model_from_name(&problems.required::<String>("from", Some(from)), &mut problems);
+    let to = projectile::drag_model_from_name(&problems.required::<String>("to", Some(to)), &mut problems);
+    let velocity_fps: f64 = problems.required("velocity_fps", Some(velocity_fps));
+    problems.check_positive("bc", bc);
+    problems.check_positive("velocity_fps", velocity_fps);
+    problems.into_result(ruby)?;
+
+    let speed_of_sound_fps = drag::solver_speed_of_sound_mps(&AtmosphericConditions::default()) / FPS_TO_MPS;
+    let mach = velocity_fps / speed_of_sound_fps;
+    Ok(bc * get_drag_coefficient(mach, &to) / get_drag_coefficient(mach, &from))
+}
+
+/// Steps of the velocity integral in `estimate_bc` (Simpson's rule, so even)
+const ESTIMATE_STEPS: usize = 200;
+
+/// BallisticsEngine.estimate_bc(near_velocity_fps:, far_velocity_fps:, distance_yards:, atmosphere: nil, drag_model: nil)
+///
+/// The BC that slows the bullet from the near to the far velocity over the
+/// distance between the chronographs, as the solver models drag (without its
+/// transonic correction, which needs the bullet's dimensions). Gravity is
+/// ignored: over chronograph distances it changes the path length by far less
+/// than chronograph error. Along the path the solver's drag gives
+///
+///   dv/dx = -v · CD_TO_RETARD · Cd(M) · (ρ / 1.225) / BC
+///
+/// so BC = distance · CD_TO_RETARD · (ρ / 1.225) / ∫ dv / (v · Cd(M)).
+fn estimate_bc(ruby: &Ruby, args: &[Value]) -> Result<f64, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let kwargs = get_kwargs::<_, (Value, Value, Value), (Option<Value>, Option<Value>), ()>(
+        args.keywords,
+        &["near_velocity_fps", "far_velocity_fps", "distance_yards"],
+        &["atmosphere", "drag_model"],
+    )
+    .map_err(|e| error::as_input_error(ruby, e))?;
+    let (near_velocity_fps, far_velocity_fps, distance_yards) = kwargs.required;
+    let (atmosphere, drag_model) = kwargs.optional;
+
+    let mut problems = Problems::default();
+    let near_velocity_fps: f64 = problems.required("near_velocity_fps", Some(near_velocity_fps));
+    let far_velocity_fps: f64 = problems.required("far_velocity_fps", Some(far_velocity_fps));
+    let distance_yards: f64 = problems.required("distance_yards", Some(distance_yards));
+    let atmosphere = ImperialAtmosphere::from_option(atmosphere, &mut problems)?;
+    let drag_model = projectile::drag_model_or_default(problems.optional("drag_model", drag_model, None), &mut problems);
+    problems.check_positive("far_velocity_fps", far_velocity_fps);
+    problems.check("near_velocity_fps", near_velocity_fps > far_velocity_fps, "must be greater than far_velocity_fps");
+    problems.check_positive("distance_yards", distance_yards);
+    problems.into_result(ruby)?;
+
+    let atmosphere = atmosphere.to_conditions();
+    let speed_of_sound_fps = drag::solver_speed_of_sound_mps(&atmosphere) / FPS_TO_MPS;
+    let integrand = |velocity_fps: f64| 1.0 / (velocity_fps * get_drag_coefficient(velocity_fps / speed_of_sound_fps, &drag_model));
+    let step = (near_velocity_fps - far_velocity_fps) / ESTIMATE_STEPS as f64;
+    let integral = (0..=ESTIMATE_STEPS)
+        .map(|index| {
+            let weight = match index {
+                0 => 1.0,
+                index if index == ESTIMATE_STEPS => 1.0,
+                index if index % 2 == 1 => 4.0,
+                _ => 2.0,
+            };
+            weight * integrand(far_velocity_fps + index as f64 * step)
+        })
+        .sum::<f64>()
+        * step
+        / 3.0;
+
+    let distance_feet = distance_yards * 3.0;
+    Ok(distance_feet * CD_TO_RETARD * (drag::solver_air_density(&atmosphere) / 1.225) / integral)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("convert_bc", function!(convert_bc, -1))?;
+    module.define_module_function("estimate_bc", function!(estimate_bc, -1))?;
+    module.define_module_function("sectional_density", function!(sectional_density_kw, -1))?;
+    module.define_module_function("form_factor", function!(form_factor_kw, -1))?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use magnus::{Error, RArray, RHash, RModule, Ruby, Symbol, Value, function, prelude::*};
+
+use crate::projectile;
+use crate::validation::{self, Problems};
+
+// Factory bullets by name, so `bullet: "Berger 6.5mm 140 Hybrid"` fills in
+// the BC, weight, diameter and length. BCs are the manufacturers' published
+// figures; anything given alongside `bullet` replaces the library's value.
+
+/// One library bullet, imperial units
+pub struct Bullet {
+    pub name: &'static str,
+    pub manufacturer: &'static str,
+    pub part_number: &'static str,
+    pub weight_grains: f64,
+    pub diameter_inches: f64,
+    pub length_inches: f64,
+    pub g1_bc: f64,
+    pub g7_bc: f64,
+}
+
+/// `bullet(name, manufacturer, part_number, [weight, diameter, length], [g1_bc, g7_bc])`
+const fn bullet(
+    name: &'static str,
+    manufacturer: &'static str,
+    part_number: &'static str,
+    [weight_grains, diameter_inches, length_inches]: [f64; 3],
+    [g1_bc, g7_bc]: [f64; 2],
+) -> Bullet {
+    Bullet { name, manufacturer, part_number, weight_grains, diameter_inches, length_inches, g1_bc, g7_bc }
+}
+
+pub const BULLETS: &[Bullet] = &[
+    bullet("Berger 6mm 105 Hybrid", "Berger", "24427", [105.0, 0.243, 1.222], [0.536, 0.275]),
+    bullet("Berger 6.5mm 140 Hybrid", "Berger", "26714", [140.0, 0.264, 1.399], [0.607, 0.311]),
+    bullet("Berger .30 215 Hybrid", "Berger", "30427", [215.0, 0.308, 1.548], [0.691, 0.354]),
+    bullet("Hornady .224 73 ELD Match", "Hornady", "22774", [73.0, 0.224, 0.995], [0.398, 0.200]),
+    bullet("Hornady 6mm 108 ELD Match", "Hornady", "24561", [108.0, 0.243, 1.230], [0.536, 0.270]),
+    bullet("Hornady 6.5mm 140 ELD Match", "Hornady", "26331", [140.0, 0.264, 1.370], [0.646, 0.326]),
+    bullet("Sierra .224 77 MatchKing", "Sierra", "9377", [77.0, 0.224, 0.995], [0.372, 0.187]),
+    bullet("Sierra .308 168 MatchKing", "Sierra", "2200", [168.0, 0.308, 1.215], [0.462, 0.218]),
+    bullet("Sierra .308 175 MatchKing", "Sierra", "2275", [175.0, 0.308, 1.240], [0.505, 0.243]),
+];
+
+/// Lowercase with runs of whitespace as single spaces
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Look up a bullet by name, part number or manufacturer and part number
+///
+/// Case and spacing are ignored: `"berger 6.5mm 140 hybrid"`, `"26714"` and
+/// `"Berger 26714"` are the same bullet.
+pub fn find(query: &str) -> Option<&'static Bullet> {
+    let query = normalize(query);
+    BULLETS.iter().find(|bullet| {
+        normalize(bullet.name) == query
+            || bullet.part_number == query
+            || normalize(&format!("{} {}", bullet.manufacturer, bullet.part_number)) == query
+    })
+}
+
+/// The library bullet called `name`, recording a problem if there is none
+pub fn named(name: &str, problems: &mut Problems) -> Option<&'static Bullet> {
+    let bullet = find(name);
+    if bullet.is_none() {
+        problems.add("bullet", format!("bullet {:?} is not in the bullet library (see BallisticsEngine.bullets)", name));
+    }
+    bullet
+}
+
+impl Bullet {
+    /// BC against a `drag_model` name; G7 unless G1 is asked for
+    pub fn bc(&self, drag_model: Option<&str>, problems: &mut Problems) -> f64 {
+        match drag_model.map(str::to_uppercase).as_deref() {
+            None | Some("G7") => self.g7_bc,
+            Some("G1") => self.g1_bc,
+            Some(other) => {
+                problems.add("bullet", format!("bullet {:?} has G1 and G7 BCs, not one for drag_model {}", self.name, other));
+                self.g7_bc
+            }
+        }
+    }
+
+    /// `{name:, manufacturer:, part_number:, bullet_weight_grains:, ...}`
+    fn to_h(&self, ruby: &Ruby) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("name"), self.name)?;
+        hash.aset(ruby.to_symbol("manufacturer"), self.manufacturer)?;
+        hash.aset(ruby.to_symbol("part_number"), self.part_number)?;
+        hash.aset(ruby.to_symbol("bullet_weight_grains"), self.weight_grains)?;
+        hash.aset(ruby.to_symbol("bullet_diameter_inches"), self.diameter_inches)?;
+        hash.aset(ruby.to_symbol("bullet_length_inches"), self.length_inches)?;
+        hash.aset(ruby.to_symbol("g1_bc"), self.g1_bc)?;
+        hash.aset(ruby.to_symbol("g7_bc"), self.g7_bc)?;
+        Ok(hash)
+    }
+}
+
+/// Replace a `bullet` entry of `hash` by the library values it stands for
+///
+/// Only keys `hash` does not already have are filled, in the kind (String or
+/// Symbol) of the `bullet` key. The BC is left out when a `form_factor` or
+/// measured drag curve replaces it; `drag_model` is set to `"G7"` when it is missing.
+/// `hash` must be the caller's copy, as from `metric::to_imperial`.
+pub fn fill(hash: RHash, problems: &mut Problems) -> Result<(), Error> {
+    let ruby = Ruby::get_with(hash);
+    let (key, value) = match (hash.get(ruby.to_symbol("bullet")), hash.get("bullet")) {
+        (Some(value), _) => (ruby.to_symbol("bullet").as_value(), value),
+        (None, Some(value)) => (ruby.str_new("bullet").as_value(), value),
+        (None, None) => return Ok(()),
+    };
+    hash.delete::<_, Value>(key)?;
+    let Some(name) = problems.optional::<Option<String>>("bullet", Some(value), None) else {
+        return Ok(());
+    };
+    let Some(bullet) = named(&name, problems) else {
+        return Ok(());
+    };
+
+    let symbols = Symbol::from_value(key).is_some();
+    let given = |name: &str| validation::entry(hash, name).is_some_and(|value| !value.is_nil());
+    let set = |name: &str, value: Value| -> Result<(), Error> {
+        if symbols { hash.aset(ruby.to_symbol(name), value) } else { hash.aset(name, value) }
+    };
+
+    for (name, value) in [
+        ("bullet_weight_grains", bullet.weight_grains),
+        ("bullet_diameter_inches", bullet.diameter_inches),
+        ("bullet_length_inches", bullet.length_inches),
+    ] {
+        if !given(name) {
+            set(name, ruby.into_value(value))?;
+        }
+    }
+    let drag_model = problems.optional::<Option<String>>("drag_model", validation::entry(hash, "drag_model"), None);
+    if !given("bc") && !given("form_factor") && !given("drag_curve") && !projectile::is_custom(drag_model.as_deref()) {
+        set("bc", ruby.into_value(bullet.bc(drag_model.as_deref(), problems)))?;
+        if drag_model.is_none() {
+            set("drag_model", ruby.str_new("G7").as_value())?;
+        }
+    }
+    Ok(())
+}
+
+/// BallisticsEngine.bullets - every library bullet as a symbol-keyed hash
+fn bullets(ruby: &Ruby) -> Result<RArray, Error> {
+    ruby.ary_try_from_iter(BULLETS.iter().map(|bullet| bullet.to_h(ruby)))
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("bullets", function!(bullets, 0))?;
+    Ok(())
+}
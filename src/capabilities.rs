@@ -0,0 +1,126 @@
+use magnus::{Error, RHash, RModule, Ruby, function};
+
+use crate::atmosphere;
+use crate::cdm;
+use crate::config;
+use crate::output_units::UNIT_NAMES;
+use crate::point;
+use crate::projectile::{DEFAULT_IS_RIGHT_TWIST, DEFAULT_SHOOTING_ANGLE_DEGREES, DEFAULT_SPIN_DRIFT, DEFAULT_TWIST_RATE_INCHES, DRAG_MODELS};
+use crate::solver::{self, Model};
+use crate::wind_field;
+
+// What this build of the extension supports, for UIs that populate choices
+// and feature-gate options instead of hardcoding them.
+
+/// Optional features, named as in `capabilities[:features]`
+const FEATURES: &[&str] = &[
+    "json_input",
+    "metric_inputs",
+    "si",
+    "environments",
+    "kestrel",
+    "metar",
+    "range_card",
+    "solver_options",
+    "integration_tolerance",
+    "target_range",
+    "stop_at_velocity",
+    "stop_at_energy",
+    "output_step",
+    "launch_angle",
+    "max_range",
+    "zero_angle",
+    "mpbr",
+    "incline",
+    "target_elevation_change",
+    "lead",
+    "firing_solution",
+    "velocity_truing",
+    "true",
+    "zero_crossings",
+    "danger_space",
+    "max_ordinate",
+    "events",
+    "warnings",
+    "warning_details",
+    "columns_format",
+    "cubic_interpolation",
+    "drag_curve",
+    "cdm",
+    "register_drag_model",
+    "bc_bands",
+    "form_factor",
+    "standard",
+    "estimate_drag",
+    "bullet_library",
+    "drag_scale_factor",
+    "powder_temperature",
+    "wind_gradient",
+    "wind_zones",
+    "vertical_wind",
+    "wind_clock",
+    "wind_uncertainty",
+    "spin_drift",
+    "magnus",
+    "coriolis",
+    "eotvos",
+    "earth_curvature",
+    "wgs84_gravity",
+    "cant",
+    "stability",
+    "marshal",
+    "ractor_shareable",
+];
+
+/// BallisticsEngine.drag_models - names accepted as `drag_model`, registered ones last
+fn drag_models() -> Vec<String> {
+    DRAG_MODELS.iter().map(|(name, _)| name.to_string()).chain(cdm::registered()).collect()
+}
+
+/// BallisticsEngine.defaults - the values used for inputs that are not given
+///
+/// Reflects `configure`; a fresh hash each call.
+fn defaults(ruby: &Ruby) -> Result<RHash, Error> {
+    let defaults = config::defaults();
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("drag_model"), defaults.drag_model.to_string())?;
+    hash.aset(ruby.to_symbol("shooting_angle_degrees"), DEFAULT_SHOOTING_ANGLE_DEGREES)?;
+    hash.aset(ruby.to_symbol("twist_rate_inches"), DEFAULT_TWIST_RATE_INCHES)?;
+    hash.aset(ruby.to_symbol("is_right_twist"), DEFAULT_IS_RIGHT_TWIST)?;
+    hash.aset(ruby.to_symbol("spin_drift"), DEFAULT_SPIN_DRIFT)?;
+    hash.aset(ruby.to_symbol("solver"), Model::default().name())?;
+    hash.aset(ruby.to_symbol("standard"), defaults.standard.name())?;
+    hash.aset(ruby.to_symbol("wind"), defaults.wind.to_h(ruby)?)?;
+    hash.aset(ruby.to_symbol("atmosphere"), defaults.atmosphere.to_h(ruby)?)?;
+    hash.aset(ruby.to_symbol("output_units"), defaults.output_units.to_h(ruby)?)?;
+    Ok(hash)
+}
+
+/// BallisticsEngine.capabilities
+fn capabilities(ruby: &Ruby) -> Result<RHash, Error> {
+    let units = ruby.hash_new();
+    for (kind, names) in UNIT_NAMES {
+        units.aset(ruby.to_symbol(kind), names.to_vec())?;
+    }
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("version"), env!("CARGO_PKG_VERSION"))?;
+    hash.aset(ruby.to_symbol("drag_models"), drag_models())?;
+    hash.aset(ruby.to_symbol("output_units"), units)?;
+    hash.aset(ruby.to_symbol("solvers"), solver::MODELS.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+    hash.aset(ruby.to_symbol("integrators"), solver::INTEGRATORS.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+    hash.aset(ruby.to_symbol("standards"), atmosphere::STANDARDS.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+    hash.aset(ruby.to_symbol("wind_shears"), wind_field::SHEARS.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+    hash.aset(ruby.to_symbol("interpolation"), vec!["linear", "cubic"])?;
+    hash.aset(ruby.to_symbol("point_formats"), vec!["objects", "columns"])?;
+    hash.aset(ruby.to_symbol("csv_columns"), point::COLUMNS.to_vec())?;
+    hash.aset(ruby.to_symbol("features"), FEATURES.to_vec())?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("drag_models", function!(drag_models, 0))?;
+    module.define_module_function("defaults", function!(defaults, 0))?;
+    module.define_module_function("capabilities", function!(capabilities, 0))?;
+    Ok(())
+}
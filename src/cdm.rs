@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use magnus::{Error, RArray, RModule, Ruby, Symbol, Value, function, prelude::*};
+
+use crate::coerce;
+use crate::drag;
+use crate::projectile;
+use crate::validation::Problems;
+
+// Doppler radar drag files (CDM, as Lapua publishes them): Mach and Cd columns
+// of CSV text, loaded with `BallisticsEngine.load_cdm` and then solved with
+// `drag_model: handle` as a measured drag curve. Curves registered by name
+// with `BallisticsEngine.register_drag_model` are solved the same way.
+
+/// Prefix of the handles `load_cdm` returns, e.g. `"cdm:1"`
+const HANDLE_PREFIX: &str = "cdm:";
+
+static CURVES: Mutex<Vec<Vec<(f64, f64)>>> = Mutex::new(Vec::new());
+/// A registered curve, with the name as registered
+type Registered = (String, Vec<(f64, f64)>);
+/// Registered curves by lowercased name
+static NAMED: Mutex<BTreeMap<String, Registered>> = Mutex::new(BTreeMap::new());
+
+/// Whether `name` looks like a `load_cdm` handle, loaded or not
+pub fn is_handle(name: &str) -> bool {
+    name.get(..HANDLE_PREFIX.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(HANDLE_PREFIX))
+}
+
+/// The curve loaded as `handle`, or registered as `handle` (ignoring case), if there is one
+pub fn curve(handle: &str) -> Option<Vec<(f64, f64)>> {
+    if !is_handle(handle) {
+        return NAMED.lock().unwrap_or_else(|e| e.into_inner()).get(&handle.to_lowercase()).map(|(_, curve)| curve.clone());
+    }
+    let index = handle[HANDLE_PREFIX.len()..].parse::<usize>().ok()?.checked_sub(1)?;
+    CURVES.lock().unwrap_or_else(|e| e.into_inner()).get(index).cloned()
+}
+
+/// Names registered with `register_drag_model`, sorted
+pub fn registered() -> Vec<String> {
+    NAMED.lock().unwrap_or_else(|e| e.into_inner()).values().map(|(name, _)| name.clone()).collect()
+}
+
+/// Read `(mach, cd)` rows from CDM text, recording every invalid line
+///
+/// Blank lines, `#` comments and header lines before the first row are
+/// skipped; only the first two columns are read. Columns are separated by
+/// commas, tabs or spaces, or by semicolons with decimal commas. Rows are
+/// returned in increasing Mach order, whatever order the file lists them in.
+pub fn parse(text: &str, problems: &mut Problems) -> Vec<(f64, f64)> {
+    let mut curve = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = fields(line);
+        let value = |index: usize| fields.get(index).and_then(|field| field.parse::<f64>().ok());
+        match (value(0), value(1)) {
+            (Some(mach), Some(cd)) => curve.push((mach, cd)),
+            _ if curve.is_empty() => continue,
+            _ => problems.add("cdm", format!("cdm line {} is invalid (expected mach and cd, got {:?})", number + 1, line)),
+        }
+    }
+    curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+    curve
+}
+
+/// The fields of a CSV-like line: split on commas, tabs or spaces, or on
+/// semicolons with decimal commas, and unquoted
+pub fn fields(line: &str) -> Vec<String> {
+    let unquote = |field: &str| field.trim().trim_matches('"').to_string();
+    if line.contains(';') {
+        line.split(';').map(|field| unquote(&field.replace(',', "."))).collect()
+    } else if line.contains([',', '\t']) {
+        line.split([',', '\t']).map(unquote).collect()
+    } else {
+        line.split_whitespace().map(unquote).collect()
+    }
+}
+
+/// BallisticsEngine.load_cdm(path_or_string) - a handle to solve with as `drag_model`
+///
+/// Takes a path (String or Pathname) to a CDM file, or the file's text.
+fn load_cdm(ruby: &Ruby, source: Value) -> Result<String, Error> {
+    let text = coerce::text_or_file(ruby, source, "cdm")?;
+
+    let mut problems = Problems::default();
+    let curve = parse(&text, &mut problems);
+    if curve.is_empty() {
+        problems.add("cdm", "cdm has no mach/cd rows (not a readable file or CDM text)".to_string());
+    }
+    drag::check_curve_as("cdm", &curve, &mut problems);
+    problems.into_result(ruby)?;
+
+    let mut curves = CURVES.lock().unwrap_or_else(|e| e.into_inner());
+    curves.push(curve);
+    Ok(format!("{}{}", HANDLE_PREFIX, curves.len()))
+}
+
+/// BallisticsEngine.register_drag_model(name, table)
+///
+/// `table` is `[[mach, cd], ...]` (as `drag_curve`), CDM text or a `load_cdm`
+/// handle. The curve is read once and then solved with `drag_model: name`.
+/// Registering a name again replaces it; standard model names are refused.
+fn register_drag_model(ruby: &Ruby, name: Value, table: Value) -> Result<(), Error> {
+    let mut problems = Problems::default();
+    let name = match Symbol::from_value(name) {
+        Some(_) => name.funcall::<_, _, String>("to_s", ())?,
+        None => problems.required::<String>("name", Some(name)),
+    };
+    if projectile::is_reserved_drag_model_name(&name) {
+        problems.add("name", format!("name {:?} is a built-in drag model name", name));
+    }
+    let curve = if RArray::from_value(table).is_some() {
+        drag::read_curve_as("table", Some(table), &mut problems).unwrap_or_default()
+    } else {
+        let text = problems.required::<String>("table", Some(table));
+        curve(&text).unwrap_or_else(|| parse(&text, &mut problems))
+    };
+    drag::check_curve_as("table", &curve, &mut problems);
+    problems.into_result(ruby)?;
+
+    NAMED.lock().unwrap_or_else(|e| e.into_inner()).insert(name.to_lowercase(), (name, curve));
+    Ok(())
+}
+
+/// BallisticsEngine.unregister_drag_model(name) - true if it was registered
+fn unregister_drag_model(name: Value) -> Result<bool, Error> {
+    let name: String = name.funcall("to_s", ())?;
+    Ok(NAMED.lock().unwrap_or_else(|e| e.into_inner()).remove(&name.to_lowercase()).is_some())
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("load_cdm", function!(load_cdm, 1))?;
+    module.define_module_function("register_drag_model", function!(register_drag_model, 2))?;
+    module.define_module_function("unregister_drag_model", function!(unregister_drag_model, 1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rows_in_mach_order_past_headers_and_comments() {
+        let mut problems = Problems::default();
+        let curve = parse("# Lapua CDM\nMach,CD\n1.0,0.400\n\n0.5,0.230\n2.0,\"0.310\"\n", &mut problems);
+        assert!(problems.is_empty());
+        assert_eq!(curve, vec![(0.5, 0.23), (1.0, 0.4), (2.0, 0.31)]);
+    }
+
+    #[test]
+    fn reads_semicolons_with_decimal_commas_and_whitespace() {
+        let mut problems = Problems::default();
+        assert_eq!(parse("0,5;0,230\n1,0;0,400\n", &mut problems), vec![(0.5, 0.23), (1.0, 0.4)]);
+        assert_eq!(parse("0.5\t0.230\n1.0 0.400\n", &mut problems), vec![(0.5, 0.23), (1.0, 0.4)]);
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn records_an_invalid_line_after_the_first_row() {
+        let mut problems = Problems::default();
+        parse("Mach,CD\n0.5,0.230\nmach,cd\n", &mut problems);
+        assert_eq!(problems.messages(), vec!["cdm line 3 is invalid (expected mach and cd, got \"mach,cd\")".to_string()]);
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use magnus::{Error, RHash, RString, Ruby, TryConvert, Value, prelude::*};
+
+use crate::error;
+
+/// Conversion of Ruby input values, lenient about numeric types
+///
+/// Numeric fields accept Floats, Integers (and other Numerics) and numeric
+/// Strings such as `"2700"` from web forms. Errors are plain messages so the
+/// caller can name the offending field.
+pub trait Coerce: Sized {
+    fn coerce(value: Value) -> Result<Self, String>;
+}
+
+impl Coerce for f64 {
+    fn coerce(value: Value) -> Result<Self, String> {
+        if let Some(string) = RString::from_value(value) {
+            let string = string.to_string().map_err(|e| e.to_string())?;
+            return match string.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(number),
+                _ => Err(format!("expected a number, got {:?}", string)),
+            };
+        }
+        f64::try_convert(value).map_err(|_| format!("expected a number, got {}", value.inspect()))
+    }
+}
+
+macro_rules! coerce_via_try_convert {
+    ($($t:ty),*) => {
+        $(
+            impl Coerce for $t {
+                fn coerce(value: Value) -> Result<Self, String> {
+                    <$t>::try_convert(value).map_err(|e| e.to_string())
+                }
+            }
+        )*
+    };
+}
+
+coerce_via_try_convert!(bool, String, Option<String>);
+
+impl Coerce for Option<RHash> {
+    fn coerce(value: Value) -> Result<Self, String> {
+        if value.is_nil() {
+            return Ok(None);
+        }
+        match hash_like(value) {
+            Ok(Some(hash)) => Ok(Some(hash)),
+            Ok(None) => Err(format!("expected a Hash or an object responding to to_h, got {}", value.inspect())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// A Hash as-is, or the `to_h` of any other object that has one (Struct, OpenStruct, ...)
+///
+/// nil and objects without `to_h` give `None`.
+pub fn hash_like(value: Value) -> Result<Option<RHash>, Error> {
+    if let Some(hash) = RHash::from_value(value) {
+        return Ok(Some(hash));
+    }
+    if value.is_nil() || !value.respond_to("to_h", false)? {
+        return Ok(None);
+    }
+    value.funcall("to_h", ()).map(Some)
+}
+
+/// The text of `source`: a path (String or Pathname) to a file, or the file's text
+///
+/// Files are read as UTF-8, with anything else (such as a Latin-1 `°`)
+/// replaced. An unreadable file raises `InputError` naming it as a `kind` file.
+pub fn text_or_file(ruby: &Ruby, source: Value, kind: &str) -> Result<String, Error> {
+    let read = |path: &str| {
+        std::fs::read(path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|e| Error::new(error::input_error(ruby), format!("Invalid inputs: {} file {} could not be read ({})", kind, path, e)))
+    };
+    if source.respond_to("to_path", false)? {
+        return read(&source.funcall::<_, _, String>("to_path", ())?);
+    }
+    let source = RString::try_convert(source)?.to_string()?;
+    if Path::new(&source).is_file() { read(&source) } else { Ok(source) }
+}
@@ -0,0 +1,172 @@
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+use magnus::{Error, RHash, RModule, Ruby, Value, function, method, prelude::*};
+use ballistics_engine::DragModel;
+
+use crate::atmosphere::{self, Atmosphere, ImperialAtmosphere, Standard};
+use crate::error;
+use crate::output_units::OutputUnits;
+use crate::projectile::{invalid_drag_model_message, parse_drag_model_name};
+use crate::validation::Problems;
+use crate::wind::{ImperialWind, Wind};
+
+/// Defaults applied under per-call inputs, set with `BallisticsEngine.configure`
+#[derive(Clone, Copy)]
+pub struct Defaults {
+    pub drag_model: DragModel,
+    /// Reference atmosphere BCs are read against
+    pub standard: Standard,
+    pub atmosphere: ImperialAtmosphere,
+    pub wind: ImperialWind,
+    pub output_units: OutputUnits,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            drag_model: DragModel::G7,
+            standard: Standard::default(),
+            atmosphere: ImperialAtmosphere::default(),
+            wind: ImperialWind::default(),
+            output_units: OutputUnits::default(),
+        }
+    }
+}
+
+// None until `configure` is first called
+static DEFAULTS: Mutex<Option<Defaults>> = Mutex::new(None);
+
+/// The configured defaults
+pub fn defaults() -> Defaults {
+    DEFAULTS.lock().unwrap_or_else(|e| e.into_inner()).unwrap_or_default()
+}
+
+fn set_defaults(defaults: Option<Defaults>) {
+    *DEFAULTS.lock().unwrap_or_else(|e| e.into_inner()) = defaults;
+}
+
+/// Settings yielded by `BallisticsEngine.configure`, applied when the block returns
+#[magnus::wrap(class = "BallisticsEngine::Configuration", free_immediately, size)]
+struct Configuration(RefCell<Defaults>);
+
+impl Configuration {
+    fn default_drag_model(&self) -> String {
+        self.0.borrow().drag_model.to_string()
+    }
+
+    /// nil restores G7
+    fn set_default_drag_model(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        let drag_model = match name {
+            Some(name) => parse_drag_model_name(&name)
+                .ok_or_else(|| Error::new(error::unsupported_drag_model_error(ruby), invalid_drag_model_message(&name)))?,
+            None => Defaults::default().drag_model,
+        };
+        rb_self.0.borrow_mut().drag_model = drag_model;
+        Ok(())
+    }
+
+    fn standard(&self) -> &'static str {
+        self.0.borrow().standard.name()
+    }
+
+    /// Also replaces the default atmosphere with the standard's sea-level
+    /// conditions, so set it before `default_atmosphere`; nil restores ICAO
+    fn set_standard(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let standard = name.map_or(Standard::default(), |name| atmosphere::read_standard(Some(name), &mut problems));
+        problems.into_result(ruby)?;
+        let mut defaults = rb_self.0.borrow_mut();
+        defaults.standard = standard;
+        defaults.atmosphere = standard.conditions();
+        Ok(())
+    }
+
+    fn default_atmosphere(&self) -> Atmosphere {
+        self.0.borrow().atmosphere.into()
+    }
+
+    /// An `Atmosphere` or symbol-keyed hash; keys not given keep their configured value
+    ///
+    /// nil restores the standard atmosphere.
+    fn set_default_atmosphere(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let atmosphere = if value.is_nil() {
+            ImperialAtmosphere::default()
+        } else {
+            ImperialAtmosphere::from_option(Some(value), &mut problems)?
+        };
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().atmosphere = atmosphere;
+        Ok(())
+    }
+
+    fn default_wind(&self) -> Wind {
+        self.0.borrow().wind.into()
+    }
+
+    /// A `Wind` or symbol-keyed hash; keys not given keep their configured value
+    ///
+    /// nil restores still air.
+    fn set_default_wind(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let wind = if value.is_nil() {
+            ImperialWind::default()
+        } else {
+            ImperialWind::from_option(Some(value), &mut problems)?
+        };
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().wind = wind;
+        Ok(())
+    }
+
+    fn output_units(ruby: &Ruby, rb_self: &Self) -> Result<RHash, Error> {
+        rb_self.0.borrow().output_units.to_h(ruby)
+    }
+
+    /// An `output_units` hash; nil restores the imperial defaults
+    fn set_output_units(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let units = match problems.optional::<Option<RHash>>("output_units", Some(value), None) {
+            Some(hash) => OutputUnits::from_hash(hash, &mut problems)?,
+            None => OutputUnits::default(),
+        };
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().output_units = units;
+        Ok(())
+    }
+}
+
+/// BallisticsEngine.configure { |config| ... }
+///
+/// Nothing is applied if the block raises.
+fn configure(ruby: &Ruby) -> Result<(), Error> {
+    let config = ruby.obj_wrap(Configuration(RefCell::new(defaults())));
+    let _: Value = ruby.yield_value(config)?;
+    set_defaults(Some(*config.0.borrow()));
+    Ok(())
+}
+
+/// Restore the built-in defaults
+fn reset_configuration() {
+    set_defaults(None);
+}
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    module.define_module_function("configure", function!(configure, 0))?;
+    module.define_module_function("reset_configuration", function!(reset_configuration, 0))?;
+
+    let class = module.define_class("Configuration", ruby.class_object())?;
+    class.undef_default_alloc_func();
+    class.define_method("default_drag_model", method!(Configuration::default_drag_model, 0))?;
+    class.define_method("default_drag_model=", method!(Configuration::set_default_drag_model, 1))?;
+    class.define_method("standard", method!(Configuration::standard, 0))?;
+    class.define_method("standard=", method!(Configuration::set_standard, 1))?;
+    class.define_method("default_atmosphere", method!(Configuration::default_atmosphere, 0))?;
+    class.define_method("default_atmosphere=", method!(Configuration::set_default_atmosphere, 1))?;
+    class.define_method("default_wind", method!(Configuration::default_wind, 0))?;
+    class.define_method("default_wind=", method!(Configuration::set_default_wind, 1))?;
+    class.define_method("output_units", method!(Configuration::output_units, 0))?;
+    class.define_method("output_units=", method!(Configuration::set_output_units, 1))?;
+    Ok(())
+}
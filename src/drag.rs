@@ -0,0 +1,283 @@
+use magnus::{RArray, RHash, Value, prelude::*};
+use ballistics_engine::constants::CD_TO_RETARD;
+use ballistics_engine::drag::{DragTable, get_drag_coefficient};
+use ballistics_engine::transonic_drag::{get_projectile_shape, transonic_correction};
+use ballistics_engine::{AtmosphericConditions, BCSegmentData, BallisticInputs, DragModel};
+
+use crate::coerce::Coerce;
+use crate::units::*;
+use crate::validation::Problems;
+
+// Drag beyond a single BC: velocity-banded BCs (`bc: [{velocity_fps:, bc:}, ...]`),
+// measured drag curves (`drag_model: "CUSTOM"`, `drag_curve: [[mach, cd], ...]`)
+// and drag scale factors (`drag_scale_factor: 1.02` or `[[mach, factor], ...]`).
+//
+// The engine's solver only reads drag from its G tables, scaled by the BC,
+// though it does switch BC by velocity band. Banded BCs map onto that
+// directly, and a drag scale factor divides the BC. A curve, or a BC scaled
+// by Mach, is solved as narrow velocity bands whose BC makes the table drag
+// equal the measured (or scaled) drag:
+//
+//   table:    a = v² · CD_TO_RETARD · Cd_table(M) · (ρ / 1.225) / BC   (ft/s²)
+//   measured: a = ρ · v² · Cd(M) · (π d² / 4) / (2 m)                  (m/s²)
+//
+// The bands depend on the speed of sound, so they are built per atmosphere.
+
+/// `drag_model` name for a measured drag curve
+pub const CUSTOM: &str = "CUSTOM";
+
+/// Table the bands' BCs are relative to; any table gives the same drag
+pub const CURVE_REFERENCE: DragModel = DragModel::G1;
+
+/// Width of each velocity band, in Mach
+const BAND_MACH: f64 = 0.01;
+/// Bands cover up to this multiple of the muzzle velocity, for headwinds
+const BAND_VELOCITY_MARGIN: f64 = 1.25;
+
+/// Velocity-banded BCs are only scaled by a single factor
+pub const SCALE_BY_MACH_WITH_BANDS: &str = "drag_scale_factor by Mach cannot be combined with bc bands";
+
+/// Read a `drag_curve` value, an array of `[mach, cd]` pairs, recording every invalid entry
+///
+/// nil gives `None`.
+pub fn read_curve(value: Option<Value>, problems: &mut Problems) -> Option<Vec<(f64, f64)>> {
+    read_curve_as("drag_curve", value, problems)
+}
+
+/// `read_curve` for a curve given as `field`
+pub fn read_curve_as(field: &str, value: Option<Value>, problems: &mut Problems) -> Option<Vec<(f64, f64)>> {
+    let value = value.filter(|value| !value.is_nil())?;
+    match RArray::from_value(value) {
+        Some(pairs) => Some(read_pairs(field, "[mach, cd]", pairs, problems)),
+        None => {
+            problems.add(field, format!("{} is invalid (expected an array of [mach, cd] pairs, got {})", field, value.inspect()));
+            Some(Vec::new())
+        }
+    }
+}
+
+fn read_pairs(field: &str, shape: &str, pairs: RArray, problems: &mut Problems) -> Vec<(f64, f64)> {
+    let mut read = Vec::with_capacity(pairs.len());
+    for (index, pair) in pairs.into_iter().enumerate() {
+        match read_pair(pair, shape) {
+            Ok(point) => read.push(point),
+            Err(reason) => problems.add(field, format!("{}[{}] is invalid ({})", field, index, reason)),
+        }
+    }
+    read
+}
+
+fn read_pair(pair: Value, shape: &str) -> Result<(f64, f64), String> {
+    let values = RArray::from_value(pair)
+        .filter(|values| values.len() == 2)
+        .ok_or_else(|| format!("expected {}, got {}", shape, pair.inspect()))?;
+    let value = |index| values.entry::<Value>(index).map_err(|e| e.to_string()).and_then(f64::coerce);
+    Ok((value(0)?, value(1)?))
+}
+
+/// Read `bc`: a number, or an array of `{velocity_fps:, bc:}` bands
+///
+/// Returns the BC and the bands, fastest first; the BC of banded input is the
+/// fastest band's. `bc` may be left out when `optional` (with a drag curve).
+pub fn read_bc(value: Option<Value>, optional: bool, problems: &mut Problems) -> (f64, Option<Vec<(f64, f64)>>) {
+    let Some(bands) = value.and_then(RArray::from_value) else {
+        let bc = if optional { problems.optional("bc", value, 0.0) } else { problems.required("bc", value) };
+        return (bc, None);
+    };
+
+    let mut read = Vec::with_capacity(bands.len());
+    for (index, band) in bands.into_iter().enumerate() {
+        let field = format!("bc[{}]", index);
+        let Some(band) = RHash::from_value(band) else {
+            problems.add(&field, format!("{} is invalid (expected {{velocity_fps:, bc:}}, got {})", field, band.inspect()));
+            continue;
+        };
+        let velocity_fps = problems.fetch(band, &format!("{}.velocity_fps", field), "velocity_fps");
+        let bc = problems.fetch(band, &format!("{}.bc", field), "bc");
+        read.push((velocity_fps, bc));
+    }
+    banded(read)
+}
+
+/// BC and bands for `(velocity_fps, bc)` bands in any order
+pub fn banded(mut bands: Vec<(f64, f64)>) -> (f64, Option<Vec<(f64, f64)>>) {
+    bands.sort_by(|a, b| b.0.total_cmp(&a.0));
+    (bands.first().map_or(0.0, |(_, bc)| *bc), Some(bands))
+}
+
+/// Record every problem with BC bands
+pub fn check_bands(bands: &[(f64, f64)], problems: &mut Problems) {
+    problems.check("bc", !bands.is_empty(), "must have at least one band");
+    problems.check("bc", bands.iter().all(|(velocity_fps, _)| *velocity_fps >= 0.0), "must not have negative band velocities");
+    problems.check("bc", bands.iter().all(|(_, bc)| *bc > 0.0), "must have positive band BCs");
+    problems.check("bc", bands.windows(2).all(|pair| pair[0].0 != pair[1].0), "must not repeat a band velocity");
+}
+
+/// Engine BC segments for bands sorted fastest first
+///
+/// Each band's BC applies from its velocity up to the next faster band; the
+/// fastest has no upper limit and the slowest also covers everything below.
+pub fn segments(bands: &[(f64, f64)]) -> Vec<BCSegmentData> {
+    let count = bands.len();
+    bands
+        .iter()
+        .enumerate()
+        .map(|(index, (velocity_fps, bc))| BCSegmentData {
+            velocity_min: if index + 1 == count { 0.0 } else { *velocity_fps },
+            velocity_max: if index == 0 { f64::INFINITY } else { bands[index - 1].0 },
+            bc_value: *bc,
+        })
+        .collect()
+}
+
+/// The band BC at `velocity_fps`
+pub fn band_bc(bands: &[(f64, f64)], velocity_fps: f64) -> Option<f64> {
+    bands.iter().find(|(band_velocity_fps, _)| velocity_fps >= *band_velocity_fps).or(bands.last()).map(|(_, bc)| *bc)
+}
+
+/// Symbol-keyed `{velocity_fps:, bc:}` hashes for bands
+pub fn bands_to_a(ruby: &magnus::Ruby, bands: &[(f64, f64)]) -> Result<RArray, magnus::Error> {
+    ruby.ary_try_from_iter(bands.iter().map(|(velocity_fps, bc)| {
+        let band = ruby.hash_new();
+        band.aset(ruby.to_symbol("velocity_fps"), *velocity_fps)?;
+        band.aset(ruby.to_symbol("bc"), *bc)?;
+        Ok(band)
+    }))
+}
+
+/// Read `drag_scale_factor`: a number, or an array of `[mach, factor]` points
+///
+/// Returns the factor and the points, in increasing Mach order; nil is a
+/// factor of 1. The factor of points is the fastest point's.
+pub fn read_scale(value: Option<Value>, problems: &mut Problems) -> (f64, Option<Vec<(f64, f64)>>) {
+    let Some(points) = value.and_then(RArray::from_value) else {
+        return (problems.optional("drag_scale_factor", value, 1.0), None);
+    };
+    scaled(read_pairs("drag_scale_factor", "[mach, factor]", points, problems))
+}
+
+/// Factor and points for `(mach, factor)` points in any order
+pub fn scaled(mut points: Vec<(f64, f64)>) -> (f64, Option<Vec<(f64, f64)>>) {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    (points.last().map_or(1.0, |(_, factor)| *factor), Some(points))
+}
+
+/// Record every problem with a drag scale factor, given as one factor or by Mach
+pub fn check_scale(factor: f64, points: Option<&[(f64, f64)]>, problems: &mut Problems) {
+    let Some(points) = points else {
+        problems.check_positive("drag_scale_factor", factor);
+        return;
+    };
+    problems.check("drag_scale_factor", !points.is_empty(), "must have at least one [mach, factor] point");
+    problems.check("drag_scale_factor", points.iter().all(|(mach, _)| *mach >= 0.0), "must not have negative Mach numbers");
+    problems.check("drag_scale_factor", points.iter().all(|(_, factor)| *factor > 0.0), "must have positive factors");
+    problems.check("drag_scale_factor", points.windows(2).all(|pair| pair[0].0 != pair[1].0), "must not repeat a Mach number");
+}
+
+/// The factor at `mach`: interpolated between points, held past the ends
+pub fn scale_at(points: &[(f64, f64)], mach: f64) -> f64 {
+    match points.iter().position(|(point_mach, _)| *point_mach > mach) {
+        Some(0) => points[0].1,
+        Some(index) => {
+            let ((mach_0, factor_0), (mach_1, factor_1)) = (points[index - 1], points[index]);
+            factor_0 + (factor_1 - factor_0) * (mach - mach_0) / (mach_1 - mach_0)
+        }
+        None => points.last().map_or(1.0, |(_, factor)| *factor),
+    }
+}
+
+/// `curve` with its drag multiplied by the factor at each Mach number
+///
+/// Sampled at the Mach numbers of both within the curve, so the product is
+/// exact at every point and the curve still ends where it did.
+pub fn scale_curve(curve: &[(f64, f64)], points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let table = table(curve);
+    let (first, last) = (curve.first().map_or(0.0, |(mach, _)| *mach), curve.last().map_or(0.0, |(mach, _)| *mach));
+    let mut machs = curve
+        .iter()
+        .chain(points.iter().filter(|(mach, _)| (first..=last).contains(mach)))
+        .map(|(mach, _)| *mach)
+        .collect::<Vec<_>>();
+    machs.sort_by(f64::total_cmp);
+    machs.dedup();
+    machs.into_iter().map(|mach| (mach, table.interpolate(mach) * scale_at(points, mach))).collect()
+}
+
+/// Record every problem with a drag curve
+pub fn check_curve(curve: &[(f64, f64)], problems: &mut Problems) {
+    check_curve_as("drag_curve", curve, problems);
+}
+
+/// `check_curve` for a curve read from `field`
+pub fn check_curve_as(field: &str, curve: &[(f64, f64)], problems: &mut Problems) {
+    problems.check(field, curve.len() >= 2, "must have at least two [mach, cd] pairs");
+    problems.check(field, curve.iter().all(|(mach, _)| *mach >= 0.0), "must not have negative Mach numbers");
+    problems.check(field, curve.iter().all(|(_, cd)| *cd > 0.0), "must have positive drag coefficients");
+    problems.check(field, curve.windows(2).all(|pair| pair[0].0 < pair[1].0), "must be in increasing Mach order");
+}
+
+/// Engine drag table for a curve
+pub fn table(curve: &[(f64, f64)]) -> DragTable {
+    DragTable::new(curve.iter().map(|(mach, _)| *mach).collect(), curve.iter().map(|(_, cd)| *cd).collect())
+}
+
+/// Speed of sound the engine's solver uses for drag lookups, m/s (dry air at the temperature)
+pub fn solver_speed_of_sound_mps(atmosphere: &AtmosphericConditions) -> f64 {
+    (1.4 * 287.05 * (atmosphere.temperature + 273.15)).sqrt()
+}
+
+/// BC by Mach for a BC scaled by `(mach, factor)` points
+///
+/// Kept as the engine's Mach-based `bc_segments`, which its solver does not
+/// read, until `apply` turns them into velocity bands.
+pub fn scaled_bc_segments(bc: f64, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    points.iter().map(|(mach, factor)| (*mach, bc / factor)).collect()
+}
+
+/// Air density the engine's solver scales drag by, kg/m³ (dry air, with an altitude lapse)
+pub fn solver_air_density(atmosphere: &AtmosphericConditions) -> f64 {
+    atmosphere.pressure * 100.0 / (287.058 * (atmosphere.temperature + 273.15)) * (-atmosphere.altitude / 8000.0).exp()
+}
+
+/// Prepare `inputs` for solving in `atmosphere`
+///
+/// A measured curve in `custom_drag_table`, or Mach-based `bc_segments`
+/// (neither of which the engine's solver reads), is turned into
+/// velocity-banded BCs, with the BC of the fastest band used above them.
+/// Mach-based BCs are interpolated in 1/BC, so the drag is. Other inputs are
+/// left as they are.
+pub fn apply(inputs: &mut BallisticInputs, atmosphere: &AtmosphericConditions) {
+    let muzzle_velocity_fps = inputs.muzzle_velocity / FPS_TO_MPS;
+    let (bc_value, bands) = if let Some(curve) = &inputs.custom_drag_table {
+        let shape = get_projectile_shape(inputs.bullet_diameter, inputs.bullet_mass / GRAINS_TO_KG, &inputs.bc_type.to_string());
+        let scale = CD_TO_RETARD * 8.0 * inputs.bullet_mass / (FPS_TO_MPS * 1.225 * std::f64::consts::PI * inputs.bullet_diameter.powi(2));
+        band(muzzle_velocity_fps, atmosphere, |mach| {
+            let table_cd = transonic_correction(mach, get_drag_coefficient(mach, &inputs.bc_type), shape, false);
+            scale * table_cd / curve.interpolate(mach)
+        })
+    } else if let Some(segments) = &inputs.bc_segments {
+        let retardation = segments.iter().map(|(mach, bc)| (*mach, 1.0 / bc)).collect::<Vec<_>>();
+        band(muzzle_velocity_fps, atmosphere, |mach| 1.0 / scale_at(&retardation, mach))
+    } else {
+        return;
+    };
+    inputs.bc_value = bc_value;
+    inputs.bc_segments_data = Some(bands);
+}
+
+/// 0.01-Mach velocity bands of `bc(mach)` up past the muzzle velocity, and the BC above them
+fn band(muzzle_velocity_fps: f64, atmosphere: &AtmosphericConditions, bc: impl Fn(f64) -> f64) -> (f64, Vec<BCSegmentData>) {
+    let speed_of_sound_fps = solver_speed_of_sound_mps(atmosphere) / FPS_TO_MPS;
+    let top_mach = muzzle_velocity_fps * BAND_VELOCITY_MARGIN / speed_of_sound_fps;
+    let bands = (0..(top_mach / BAND_MACH).ceil() as usize)
+        .map(|band| {
+            let mach = band as f64 * BAND_MACH;
+            BCSegmentData {
+                velocity_min: mach * speed_of_sound_fps,
+                velocity_max: (mach + BAND_MACH) * speed_of_sound_fps,
+                bc_value: bc(mach + BAND_MACH / 2.0),
+            }
+        })
+        .collect();
+    (bc(top_mach), bands)
+}
@@ -0,0 +1,273 @@
+use ballistics_engine::TrajectoryResult as EngineTrajectoryResult;
+use ballistics_engine::constants::G_ACCEL_MPS2;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::interpolation::{self, Sample};
+use crate::units::*;
+use crate::validation::Problems;
+use crate::wind_field::Interval;
+
+// Effects of the Earth's rotation, which the engine's solver leaves out. On a
+// range turning with the Earth the bullet feels the Coriolis acceleration
+// -2 Ω × v, with Ω the Earth's rotation resolved at `latitude_degrees` into
+// the axes of a shot fired at `azimuth_degrees` (clockwise from true north).
+// Its horizontal part, from the Earth's spin about the local vertical,
+// deflects the bullet right in the northern hemisphere and left in the
+// southern whichever way it is fired. Its vertical part is the Eötvös effect,
+// from the spin about the north axis: a bullet moving east goes round with the
+// Earth faster than the range, so gravity seems weaker and it is lifted, and
+// one moving west is dropped, by 2 Ω cos(latitude) times its eastward speed.
+// It is kept apart from the horizontal part, as the one that vanishes firing
+// north or south and is largest east or west. The acceleration is tiny beside drag
+// and gravity, so it is taken along the solved trajectory and its deflection
+// added to the points afterwards, the deflection's velocity relaxing at the
+// drag rate between points as a changing wind's does in `wind_field`.
+//
+// The engine also takes the ground to be a flat plane with gravity straight
+// down it. With `earth_curvature` the level surface through the muzzle falls
+// away below that plane as the Earth curves, R - sqrt(R² - x²) at x
+// downrange, and gravity turns with it to point at the Earth's centre: x / R
+// radians back towards the muzzle, and a little weaker across the plane. The
+// turned gravity is taken along the solved trajectory like the Coriolis
+// acceleration. Slowed along the range, the bullet reaches each distance a
+// little lower; the change is far less than the fall of the level surface,
+// which aiming at the target already allows for.
+//
+// The solvers' gravity is fixed everywhere: 9.81 m/s² in the engine's
+// integrator, the standard 9.80665 in the flat-fire models. With
+// `wgs84_gravity` it is the WGS-84 normal gravity instead, strongest at the
+// poles and weakening with height, at the latitude of fire (45° without
+// `latitude_degrees`) and the height of the bullet above sea level. The
+// difference from the fixed value is taken along the solved trajectory in the
+// same way.
+
+/// Sidereal rotation rate of the Earth, rad/s
+pub const ROTATION_RATE: f64 = 7.292_115e-5;
+/// Direction of fire without `azimuth_degrees`: true north
+pub const DEFAULT_AZIMUTH_DEGREES: f64 = 0.0;
+/// Mean radius of the Earth, m
+pub const RADIUS: f64 = 6_371_000.0;
+/// Flat ground unless `earth_curvature` is given
+pub const DEFAULT_EARTH_CURVATURE: bool = false;
+/// Standard gravity unless `wgs84_gravity` is given
+pub const DEFAULT_WGS84_GRAVITY: bool = false;
+/// Latitude of the normal gravity without `latitude_degrees`
+pub const DEFAULT_GRAVITY_LATITUDE_DEGREES: f64 = 45.0;
+/// Gravity the engine's integrator uses, m/s²
+pub const SOLVER_GRAVITY: f64 = 9.81;
+
+/// Where the shot is fired and which way
+#[derive(Clone, Copy)]
+pub struct Earth {
+    /// Radians, positive north
+    latitude: f64,
+    /// Radians clockwise from true north
+    azimuth: f64,
+}
+
+impl Earth {
+    pub fn new(latitude_degrees: f64, azimuth_degrees: f64) -> Self {
+        Self { latitude: latitude_degrees * DEGREES_TO_RADIANS, azimuth: azimuth_degrees * DEGREES_TO_RADIANS }
+    }
+
+    /// Coriolis acceleration at `velocity`, both in the engine's axes (X right, Y up, Z downrange)
+    fn coriolis_acceleration(&self, velocity: Vector3<f64>) -> Vector3<f64> {
+        let (sin_azimuth, cos_azimuth) = self.azimuth.sin_cos();
+        // East, north and up, where the cross product is right-handed
+        let velocity = Vector3::new(
+            velocity.x * cos_azimuth + velocity.z * sin_azimuth,
+            velocity.z * cos_azimuth - velocity.x * sin_azimuth,
+            velocity.y,
+        );
+        let rotation = ROTATION_RATE * Vector3::new(0.0, self.latitude.cos(), self.latitude.sin());
+        let acceleration = -2.0 * rotation.cross(&velocity);
+        Vector3::new(
+            acceleration.x * cos_azimuth - acceleration.y * sin_azimuth,
+            acceleration.z,
+            acceleration.x * sin_azimuth + acceleration.y * cos_azimuth,
+        )
+    }
+
+    /// Coriolis and Eötvös deflection of every solved point
+    pub fn coriolis(&self, result: &EngineTrajectoryResult) -> Coriolis {
+        let mut coriolis = Coriolis { horizontal: Vec::with_capacity(result.points.len()), eotvos: Vec::with_capacity(result.points.len()) };
+        let Some(muzzle) = result.points.first().map(|point| point.position) else {
+            return coriolis;
+        };
+        let (mut deflection, mut velocity) = (Vector3::zeros(), Vector3::zeros());
+        coriolis.horizontal.push(0.0);
+        coriolis.eotvos.push(0.0);
+        for pair in result.points.windows(2) {
+            let interval = Interval::new(&pair[0], &pair[1], muzzle);
+            if interval.dt > 0.0 {
+                interval.relax(&mut deflection, &mut velocity, self.coriolis_acceleration(interval.velocity));
+            }
+            coriolis.horizontal.push(deflection.x);
+            coriolis.eotvos.push(deflection.y);
+        }
+        coriolis
+    }
+}
+
+/// Fall of the level surface through the muzzle below the flat ground plane at `range` metres downrange
+pub fn level_drop(range: f64) -> f64 {
+    let range = range.abs().min(RADIUS);
+    RADIUS - (RADIUS * RADIUS - range * range).sqrt()
+}
+
+/// Deflection of every solved point by gravity turning towards the Earth's centre
+pub fn curvature(result: &EngineTrajectoryResult) -> Curvature {
+    let mut curvature = Curvature { rise: Vec::with_capacity(result.points.len()), range: Vec::with_capacity(result.points.len()) };
+    let Some(muzzle) = result.points.first().map(|point| point.position) else {
+        return curvature;
+    };
+    let (mut deflection, mut velocity) = (Vector3::zeros(), Vector3::zeros());
+    curvature.rise.push(0.0);
+    curvature.range.push(0.0);
+    for pair in result.points.windows(2) {
+        let interval = Interval::new(&pair[0], &pair[1], muzzle);
+        if interval.dt > 0.0 {
+            let (sin_turn, cos_turn) = (interval.range / RADIUS).sin_cos();
+            let acceleration = G_ACCEL_MPS2 * Vector3::new(0.0, 1.0 - cos_turn, -sin_turn);
+            interval.relax(&mut deflection, &mut velocity, acceleration);
+        }
+        curvature.rise.push(deflection.y);
+        curvature.range.push(deflection.z);
+    }
+    curvature
+}
+
+/// WGS-84 normal gravity at `latitude_degrees` and `height` metres above the ellipsoid, m/s²
+///
+/// Somigliana's formula on the ellipsoid, with the second-order height correction.
+pub fn normal_gravity(latitude_degrees: f64, height: f64) -> f64 {
+    const EQUATORIAL_GRAVITY: f64 = 9.780_325_335_9;
+    const SOMIGLIANA: f64 = 0.001_931_852_652_41;
+    const ECCENTRICITY_SQUARED: f64 = 0.006_694_379_990_13;
+    const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+    const FLATTENING: f64 = 1.0 / 298.257_223_563;
+    // ω²a²b / GM
+    const GRAVITY_RATIO: f64 = 0.003_449_786_506_84;
+    let sin_squared = (latitude_degrees * DEGREES_TO_RADIANS).sin().powi(2);
+    let surface = EQUATORIAL_GRAVITY * (1.0 + SOMIGLIANA * sin_squared) / (1.0 - ECCENTRICITY_SQUARED * sin_squared).sqrt();
+    let height_factor = 1.0 - 2.0 / SEMI_MAJOR_AXIS * (1.0 + FLATTENING + GRAVITY_RATIO - 2.0 * FLATTENING * sin_squared) * height
+        + 3.0 * height * height / (SEMI_MAJOR_AXIS * SEMI_MAJOR_AXIS);
+    surface * height_factor
+}
+
+/// Deflection of every solved point by the normal gravity's difference from
+/// `solved_gravity`, the gravity it was solved with, fired at
+/// `latitude_degrees` from `altitude` metres
+pub fn gravity(result: &EngineTrajectoryResult, solved_gravity: f64, latitude_degrees: f64, altitude: f64) -> Gravity {
+    let mut gravity = Gravity { rise: Vec::with_capacity(result.points.len()) };
+    let Some(muzzle) = result.points.first().map(|point| point.position) else {
+        return gravity;
+    };
+    let (mut deflection, mut velocity) = (Vector3::zeros(), Vector3::zeros());
+    gravity.rise.push(0.0);
+    for pair in result.points.windows(2) {
+        let interval = Interval::new(&pair[0], &pair[1], muzzle);
+        if interval.dt > 0.0 {
+            let weakening = solved_gravity - normal_gravity(latitude_degrees, altitude + interval.rise);
+            interval.relax(&mut deflection, &mut velocity, Vector3::new(0.0, weakening, 0.0));
+        }
+        gravity.rise.push(deflection.y);
+    }
+    gravity
+}
+
+/// Record an out-of-range latitude or azimuth
+pub fn check(latitude_degrees: Option<f64>, azimuth_degrees: f64, problems: &mut Problems) {
+    if let Some(latitude) = latitude_degrees {
+        problems.check_range("latitude_degrees", latitude, -90.0, 90.0);
+    }
+    problems.check_range("azimuth_degrees", azimuth_degrees, 0.0, 360.0);
+}
+
+/// Deflection by the Earth's rotation at each solved point; the part along
+/// the line of fire only moves the bullet along its path, so it is left out
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Coriolis {
+    /// Metres, positive to the right
+    horizontal: Vec<f64>,
+    /// The vertical deflection, metres, positive up
+    eotvos: Vec<f64>,
+}
+
+impl Coriolis {
+    /// `(horizontal, Eötvös)` metres at a point's index
+    pub fn at(&self, index: usize) -> (f64, f64) {
+        (self.horizontal.get(index).copied().unwrap_or_default(), self.eotvos.get(index).copied().unwrap_or_default())
+    }
+
+    /// The deflection at the points `samples` takes in place of the solved ones
+    pub fn resample(&self, samples: &[Sample]) -> Self {
+        Self { horizontal: interpolation::resample(&self.horizontal, samples), eotvos: interpolation::resample(&self.eotvos, samples) }
+    }
+
+    /// Add the deflection to the lateral position and height of every point
+    pub fn apply(&self, result: &mut EngineTrajectoryResult) {
+        for (index, point) in result.points.iter_mut().enumerate() {
+            let (horizontal, eotvos) = self.at(index);
+            point.position.x += horizontal;
+            point.position.y += eotvos;
+        }
+    }
+}
+
+/// Deflection by the Earth's curvature at each solved point
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Curvature {
+    /// Metres, positive up
+    rise: Vec<f64>,
+    /// Metres along the range, negative back towards the muzzle
+    range: Vec<f64>,
+}
+
+impl Curvature {
+    /// `(rise, range)` metres at a point's index
+    pub fn at(&self, index: usize) -> (f64, f64) {
+        (self.rise.get(index).copied().unwrap_or_default(), self.range.get(index).copied().unwrap_or_default())
+    }
+
+    /// The deflection at the points `samples` takes in place of the solved ones
+    pub fn resample(&self, samples: &[Sample]) -> Self {
+        Self { rise: interpolation::resample(&self.rise, samples), range: interpolation::resample(&self.range, samples) }
+    }
+
+    /// Add the deflection to the height and range of every point
+    pub fn apply(&self, result: &mut EngineTrajectoryResult) {
+        for (index, point) in result.points.iter_mut().enumerate() {
+            let (rise, range) = self.at(index);
+            point.position.y += rise;
+            point.position.z += range;
+        }
+    }
+}
+
+/// Deflection by the normal gravity's difference from the solvers' at each solved point
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Gravity {
+    /// Metres, positive up
+    rise: Vec<f64>,
+}
+
+impl Gravity {
+    /// Metres at a point's index
+    pub fn at(&self, index: usize) -> f64 {
+        self.rise.get(index).copied().unwrap_or_default()
+    }
+
+    /// The deflection at the points `samples` takes in place of the solved ones
+    pub fn resample(&self, samples: &[Sample]) -> Self {
+        Self { rise: interpolation::resample(&self.rise, samples) }
+    }
+
+    /// Add the deflection to the height of every point
+    pub fn apply(&self, result: &mut EngineTrajectoryResult) {
+        for (index, point) in result.points.iter_mut().enumerate() {
+            point.position.y += self.at(index);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use magnus::{Error, RArray, RHash, RModule, Ruby, Symbol, Value, function, prelude::*, scan_args::get_kwargs};
+use ballistics_engine::AtmosphericConditions;
+
+use crate::atmosphere::ImperialAtmosphere;
+use crate::config;
+use crate::error;
+use crate::si;
+use crate::validation::{self, Problems};
+use crate::wind::{self, ImperialWind};
+use crate::wind_field::WindField;
+
+/// A named atmosphere/wind preset, set with `BallisticsEngine.register_environment`
+///
+/// A part left out falls back to the configured default when solving.
+#[derive(Clone, Copy, Default)]
+pub struct Environment {
+    pub wind: Option<ImperialWind>,
+    pub atmosphere: Option<ImperialAtmosphere>,
+}
+
+impl Environment {
+    /// The preset wind, or the configured default
+    pub fn wind_or_default(&self) -> ImperialWind {
+        self.wind.unwrap_or_else(|| config::defaults().wind)
+    }
+
+    /// The preset atmosphere, or the configured default
+    pub fn atmosphere_or_default(&self) -> ImperialAtmosphere {
+        self.atmosphere.unwrap_or_else(|| config::defaults().atmosphere)
+    }
+
+    /// Wind from a `wind:` keyword value, which replaces the preset when given
+    pub fn wind_from_option(&self, value: Option<Value>, problems: &mut Problems) -> Result<ImperialWind, Error> {
+        match value {
+            Some(value) if !value.is_nil() => ImperialWind::from_option(Some(value), problems),
+            _ => Ok(self.wind_or_default()),
+        }
+    }
+
+    /// The wind solved in from a `wind:` keyword value: `wind_from_option`'s, or an array of wind zones
+    pub fn wind_field_from_option(&self, value: Option<Value>, problems: &mut Problems) -> Result<WindField, Error> {
+        match value.and_then(RArray::from_value) {
+            Some(zones) => wind::read_zones(zones, problems),
+            None => Ok(self.wind_from_option(value, problems)?.to_conditions()),
+        }
+    }
+
+    /// Atmosphere from an `atmosphere:` keyword value, which replaces the preset when given
+    pub fn atmosphere_from_option(&self, value: Option<Value>, problems: &mut Problems) -> Result<ImperialAtmosphere, Error> {
+        match value {
+            Some(value) if !value.is_nil() => ImperialAtmosphere::from_option(Some(value), problems),
+            _ => Ok(self.atmosphere_or_default()),
+        }
+    }
+
+    /// `si::wind_from_option`, preferring the preset over the SI default
+    pub fn si_wind_from_option(&self, value: Option<Value>, problems: &mut Problems) -> Result<WindField, Error> {
+        match (value.filter(|value| !value.is_nil()), self.wind) {
+            (None, Some(preset)) => Ok(preset.to_conditions()),
+            (value, _) => si::wind_from_option(value, problems).map(WindField::from),
+        }
+    }
+
+    /// `si::atmosphere_from_option`, preferring the preset over the SI default
+    pub fn si_atmosphere_from_option(&self, value: Option<Value>, problems: &mut Problems) -> Result<AtmosphericConditions, Error> {
+        match (value.filter(|value| !value.is_nil()), self.atmosphere) {
+            (None, Some(preset)) => Ok(preset.to_conditions()),
+            (value, _) => si::atmosphere_from_option(value, problems),
+        }
+    }
+}
+
+static ENVIRONMENTS: Mutex<BTreeMap<String, Environment>> = Mutex::new(BTreeMap::new());
+
+/// Look up a registered environment by name, or read a preset hash in its place
+///
+/// nil (or no `environment` key) gives the configured defaults; an unknown
+/// name is recorded as a problem.
+pub fn read(value: Option<Value>, problems: &mut Problems) -> Result<Environment, Error> {
+    if let Some(preset_hash) = value.and_then(RHash::from_value) {
+        return preset(preset_hash, problems);
+    }
+    let name = match value {
+        Some(value) if Symbol::from_value(value).is_some() => Some(value.funcall::<_, _, String>("to_s", ())?),
+        value => problems.optional::<Option<String>>("environment", value, None),
+    };
+    Ok(match name {
+        Some(name) => named(&name, problems),
+        None => Environment::default(),
+    })
+}
+
+/// The environment registered as `name`, recording a problem if there is none
+pub fn named(name: &str, problems: &mut Problems) -> Environment {
+    match ENVIRONMENTS.lock().unwrap_or_else(|e| e.into_inner()).get(name) {
+        Some(environment) => *environment,
+        None => {
+            problems.add("environment", format!("environment {:?} is not registered", name));
+            Environment::default()
+        }
+    }
+}
+
+/// An `{atmosphere: ..., wind: ...}` preset; a part left out is None
+fn preset(preset: RHash, problems: &mut Problems) -> Result<Environment, Error> {
+    let kwargs = get_kwargs::<_, (), (Option<Value>, Option<Value>), ()>(
+        validation::copy(preset)?,
+        &[],
+        &["atmosphere", "wind"],
+    )
+    .map_err(|e| error::as_input_error(&Ruby::get_with(preset), e))?;
+    let (atmosphere, wind) = kwargs.optional;
+
+    Ok(Environment {
+        wind: match wind {
+            Some(wind) if !wind.is_nil() => Some(ImperialWind::from_option(Some(wind), problems)?),
+            _ => None,
+        },
+        atmosphere: match atmosphere {
+            Some(atmosphere) if !atmosphere.is_nil() => Some(ImperialAtmosphere::from_option(Some(atmosphere), problems)?),
+            _ => None,
+        },
+    })
+}
+
+/// BallisticsEngine.register_environment(name, {atmosphere: ..., wind: ...})
+///
+/// `atmosphere`/`wind` take `Atmosphere`/`Wind` objects or symbol-keyed
+/// hashes, as `configure` does. Registering a name again replaces it.
+fn register_environment(ruby: &Ruby, name: Value, preset: Value) -> Result<(), Error> {
+    let mut problems = Problems::default();
+    let name = match Symbol::from_value(name) {
+        Some(_) => name.funcall::<_, _, String>("to_s", ())?,
+        None => problems.required::<String>("name", Some(name)),
+    };
+    let preset_hash = problems.required::<Option<RHash>>("environment", Some(preset)).unwrap_or_else(|| ruby.hash_new());
+    let environment = self::preset(preset_hash, &mut problems)?;
+    problems.into_result(ruby)?;
+    ENVIRONMENTS.lock().unwrap_or_else(|e| e.into_inner()).insert(name, environment);
+    Ok(())
+}
+
+/// BallisticsEngine.environments - registered names, sorted
+fn environments() -> Vec<String> {
+    ENVIRONMENTS.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect()
+}
+
+/// BallisticsEngine.unregister_environment(name) - true if it was registered
+fn unregister_environment(name: Value) -> Result<bool, Error> {
+    let name: String = name.funcall("to_s", ())?;
+    Ok(ENVIRONMENTS.lock().unwrap_or_else(|e| e.into_inner()).remove(&name).is_some())
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("register_environment", function!(register_environment, 2))?;
+    module.define_module_function("unregister_environment", function!(unregister_environment, 1))?;
+    module.define_module_function("environments", function!(environments, 0))?;
+    Ok(())
+}
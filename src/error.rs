@@ -0,0 +1,81 @@
+use magnus::{Error, ErrorType, ExceptionClass, RModule, Ruby, prelude::*, value::Lazy};
+
+// BallisticsEngine::Error < StandardError
+//   InputError                   - missing, malformed or out-of-range inputs
+//     UnsupportedDragModelError  - unknown drag model name
+//     UnstableBulletError        - not gyroscopically stable, when stability is required
+//   SolverError                  - the engine failed to produce a trajectory
+//     ConvergenceError           - an iterative search (e.g. zeroing) did not converge
+/// Every error class of the module and its parent, parents first; `None` is `StandardError`
+pub const CLASSES: &[(&str, Option<&str>)] = &[
+    ("Error", None),
+    ("InputError", Some("Error")),
+    ("UnsupportedDragModelError", Some("InputError")),
+    ("UnstableBulletError", Some("InputError")),
+    ("SolverError", Some("Error")),
+    ("ConvergenceError", Some("SolverError")),
+];
+
+static BASE_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "Error"));
+static INPUT_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "InputError"));
+static UNSUPPORTED_DRAG_MODEL_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "UnsupportedDragModelError"));
+static UNSTABLE_BULLET_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "UnstableBulletError"));
+static SOLVER_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "SolverError"));
+static CONVERGENCE_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| lookup(ruby, "ConvergenceError"));
+
+fn lookup(ruby: &Ruby, name: &str) -> ExceptionClass {
+    let module: RModule = ruby.class_object().const_get("BallisticsEngine").unwrap();
+    module.const_get(name).unwrap()
+}
+
+pub fn base_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&BASE_ERROR)
+}
+
+pub fn input_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&INPUT_ERROR)
+}
+
+pub fn unsupported_drag_model_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&UNSUPPORTED_DRAG_MODEL_ERROR)
+}
+
+pub fn unstable_bullet_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&UNSTABLE_BULLET_ERROR)
+}
+
+pub fn solver_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&SOLVER_ERROR)
+}
+
+pub fn convergence_error(ruby: &Ruby) -> ExceptionClass {
+    ruby.get_inner(&CONVERGENCE_ERROR)
+}
+
+/// The message of an error, without the class name `Display` adds
+pub fn message(e: &Error) -> String {
+    match e.error_type() {
+        ErrorType::Error(_, message) => message.to_string(),
+        _ => e.to_string(),
+    }
+}
+
+/// Re-raise Ruby's keyword `ArgumentError`s (missing/unknown keywords) as `InputError`
+pub fn as_input_error(ruby: &Ruby, e: Error) -> Error {
+    if e.is_kind_of(ruby.exception_arg_error()) {
+        Error::new(input_error(ruby), e.to_string())
+    } else {
+        e
+    }
+}
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    for (name, parent) in CLASSES {
+        let parent = match parent {
+            Some(parent) => module.const_get(*parent)?,
+            None => ruby.exception_standard_error(),
+        };
+        module.define_error(*name, parent)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::{get_kwargs, scan_args}};
+
+use crate::error;
+use crate::incline;
+use crate::projectile::ImperialInputs;
+use crate::result;
+use crate::solver::{self, SolveOptions, Solver};
+use crate::units::*;
+use crate::validation::{self, Problems};
+use crate::warnings;
+
+// The firing solution for a target in one call: the elevation and windage to
+// dial, and what the bullet arrives with. The target is described as a
+// rangefinder and compass give it: its line-of-sight range, its bearing, the
+// azimuth the Coriolis deflection is worked out for given the profile's
+// `latitude_degrees`, and its height above or below the shooter, which tips
+// the line of sight as in `BallisticsEngine.incline`. The holds correct the
+// drop and drift at the target, positive up and right, and the clicks are
+// those holds on a turret of `click_moa` or `click_mil`.
+
+/// Turret click without `click_moa` or `click_mil`, MOA
+const DEFAULT_CLICK_MOA: f64 = 0.25;
+/// Range solved to past the target, as a fraction of it
+const RANGE_MARGIN: f64 = 1.05;
+
+/// BallisticsEngine.firing_solution(profile, target:, environment: nil, wind: nil, atmosphere: nil, click_moa: nil, click_mil: nil)
+///
+/// Elevation and windage holds, time of flight, impact velocity and energy,
+/// and the warnings that apply out to the target, for
+/// `target: {range_yards:, bearing_degrees: nil, elevation_change_feet: nil}`.
+fn firing_solution(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (profile,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let target = kw.delete::<_, Option<Value>>(ruby.to_symbol("target"))?;
+    let click_moa = kw.delete::<_, Option<Value>>(ruby.to_symbol("click_moa"))?;
+    let click_mil = kw.delete::<_, Option<Value>>(ruby.to_symbol("click_mil"))?;
+    let (wind, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let mut inputs = ImperialInputs::from_value(profile, &mut problems)?;
+    let target = problems.required::<Option<RHash>>("target", target).unwrap_or_else(|| ruby.hash_new());
+    let kwargs = get_kwargs::<_, (), (Option<Value>, Option<Value>, Option<Value>), ()>(
+        validation::copy(target)?,
+        &[],
+        &["range_yards", "bearing_degrees", "elevation_change_feet"],
+    )
+    .map_err(|e| error::as_input_error(ruby, e))?;
+    let (range, bearing, elevation_change) = kwargs.optional;
+
+    let number = |field: &'static str, value: Option<Value>, problems: &mut Problems| {
+        value.filter(|value| !value.is_nil()).map(|value| problems.required::<f64>(field, Some(value)))
+    };
+    let range_yards: f64 = problems.required("target.range_yards", range);
+    problems.check_positive("target.range_yards", range_yards);
+    let range_m = range_yards * YARDS_TO_METERS;
+    if let Some(bearing_degrees) = number("target.bearing_degrees", bearing, &mut problems) {
+        problems.check_range("target.bearing_degrees", bearing_degrees, 0.0, 360.0);
+        inputs.azimuth_degrees = bearing_degrees;
+    }
+    let elevation_change_m = number("target.elevation_change_feet", elevation_change, &mut problems).unwrap_or(0.0) * FEET_TO_METERS;
+    problems.check("target.elevation_change_feet", elevation_change_m.abs() <= range_m, "must not exceed target.range_yards");
+    let click = match (number("click_moa", click_moa, &mut problems), number("click_mil", click_mil, &mut problems)) {
+        (Some(_), Some(_)) => {
+            problems.add("click_mil", "click_moa and click_mil cannot both be given".to_string());
+            DEFAULT_CLICK_MOA / RADIANS_TO_MOA
+        }
+        (Some(moa), None) => {
+            problems.check_positive("click_moa", moa);
+            moa / RADIANS_TO_MOA
+        }
+        (None, Some(mil)) => {
+            problems.check_positive("click_mil", mil);
+            mil / RADIANS_TO_MILS
+        }
+        (None, None) => DEFAULT_CLICK_MOA / RADIANS_TO_MOA,
+    };
+    let bearing_degrees = inputs.azimuth_degrees;
+    let solver = Solver::from_inputs(inputs);
+    problems.into_result(ruby)?;
+
+    let angle = (elevation_change_m / range_m).asin();
+    let options = SolveOptions { max_range_m: Some(range_m * RANGE_MARGIN), ..SolveOptions::default() };
+    let depth_m = (-angle.sin()).max(0.0) * range_m * RANGE_MARGIN;
+    let (solved, solved_inputs, ..) = solver.solve_inclined(ruby, wind, &atmosphere, angle, depth_m, options)?;
+    let sight = incline::sight(&solved.points, solver.sight_height_m(), angle);
+    let (point, drop_m) = incline::at_sight_range(&solved.points, sight, angle, range_m).ok_or_else(|| {
+        let reached_m = solved.points.last().map_or(0.0, |point| point.position.z);
+        Error::new(
+            error::solver_error(ruby),
+            format!("the trajectory ends at {:.1} yards, short of target.range_yards {:.1}", reached_m / YARDS_TO_METERS, range_yards),
+        )
+    })?;
+    let windage_m = point.position.x;
+    let warnings: Vec<String> = warnings::collect(&solved_inputs, &atmosphere, &solved, result::speed_of_sound_mps(&atmosphere))
+        .into_iter()
+        .filter(|warning| warning.range_m.is_none_or(|at_m| at_m <= range_m))
+        .map(|warning| warning.message)
+        .collect();
+
+    let (elevation, windage) = (-drop_m.atan2(range_m), -windage_m.atan2(range_m));
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("range_yards"), range_yards)?;
+    hash.aset(ruby.to_symbol("bearing_degrees"), bearing_degrees)?;
+    hash.aset(ruby.to_symbol("shooting_angle_degrees"), angle / DEGREES_TO_RADIANS)?;
+    hash.aset(ruby.to_symbol("elevation_moa"), elevation * RADIANS_TO_MOA)?;
+    hash.aset(ruby.to_symbol("elevation_mil"), elevation * RADIANS_TO_MILS)?;
+    hash.aset(ruby.to_symbol("elevation_clicks"), (elevation / click).round() as i64)?;
+    hash.aset(ruby.to_symbol("windage_moa"), windage * RADIANS_TO_MOA)?;
+    hash.aset(ruby.to_symbol("windage_mil"), windage * RADIANS_TO_MILS)?;
+    hash.aset(ruby.to_symbol("windage_clicks"), (windage / click).round() as i64)?;
+    hash.aset(ruby.to_symbol("drop_in"), drop_m / INCHES_TO_METERS)?;
+    hash.aset(ruby.to_symbol("windage_in"), windage_m / INCHES_TO_METERS)?;
+    hash.aset(ruby.to_symbol("time_of_flight"), point.time)?;
+    hash.aset(ruby.to_symbol("impact_velocity_fps"), point.velocity_magnitude / FPS_TO_MPS)?;
+    hash.aset(ruby.to_symbol("impact_energy_ftlbs"), point.kinetic_energy * JOULES_TO_FTLBS)?;
+    hash.aset(ruby.to_symbol("warnings"), warnings)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("firing_solution", function!(firing_solution, -1))?;
+    Ok(())
+}
@@ -0,0 +1,406 @@
+use magnus::{Error, RArray, RHash, RModule, Ruby, Symbol, Value, function, prelude::*, scan_args::scan_args};
+use ballistics_engine::AtmosphericConditions;
+
+use crate::error;
+use crate::interpolation::{self, Method};
+use crate::point::TrajectoryPoint;
+use crate::projectile::{ImperialInputs, Projectile};
+use crate::result;
+use crate::solver::{self, SolveOptions, Solver};
+use crate::spin::Spin;
+use crate::units::*;
+use crate::validation::{self, Problems};
+use crate::wind_field::WindField;
+
+// Truing to observed drops: the BC (or drag scale factor, or muzzle velocity)
+// whose trajectory best matches drops measured at several ranges. The sum of
+// squared residuals is minimized by golden-section search over the value's
+// logarithm, solving (and re-zeroing) the trajectory for every candidate.
+// `BallisticsEngine.true` fits the muzzle velocity and drag scale factor
+// together, to DOPE in MOA: the velocity is searched with the best factor for
+// each candidate found by a search of its own. The velocity moves the drops at
+// every range and the factor mostly the long ones, so two ranges or more tell
+// them apart.
+
+/// Search from a third to three times the starting value
+const SEARCH_SPAN: f64 = 3.0;
+/// Search a muzzle velocity from 80% to 125% of the chronographed one, past
+/// which the bullet may not reach the drops at all
+const VELOCITY_SEARCH_SPAN: f64 = 1.25;
+/// Stop once the bracket is this narrow, relative to the value
+const TOLERANCE: f64 = 1e-5;
+/// Solve this far past the longest observed range, metres
+const RANGE_MARGIN_M: f64 = 10.0;
+
+/// An input `fit_drops` or `true` varies
+#[derive(Clone, Copy)]
+enum Fit {
+    Bc,
+    DragScaleFactor,
+    MuzzleVelocity,
+}
+
+impl Fit {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bc" => Some(Self::Bc),
+            "drag_scale_factor" => Some(Self::DragScaleFactor),
+            "muzzle_velocity" => Some(Self::MuzzleVelocity),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bc => "bc",
+            Self::DragScaleFactor => "drag_scale_factor",
+            Self::MuzzleVelocity => "muzzle_velocity",
+        }
+    }
+
+    /// Ratio of the search's ends to the starting value
+    fn span(self) -> f64 {
+        match self {
+            Self::Bc | Self::DragScaleFactor => SEARCH_SPAN,
+            Self::MuzzleVelocity => VELOCITY_SEARCH_SPAN,
+        }
+    }
+
+    /// Record why `inputs` cannot have this value fitted, if they can't
+    fn check(self, inputs: &ImperialInputs, problems: &mut Problems) {
+        match self {
+            Self::Bc => problems.check(
+                "fit",
+                inputs.bc_bands.is_none() && inputs.drag_curve.is_none() && inputs.form_factor.is_none(),
+                "bc needs a single bc (fit drag_scale_factor with bc bands, a drag curve or a form factor)",
+            ),
+            Self::DragScaleFactor => problems.check(
+                "fit",
+                inputs.drag_scale_by_mach.is_none(),
+                "drag_scale_factor needs a single drag_scale_factor, not one by Mach",
+            ),
+            Self::MuzzleVelocity => {}
+        }
+    }
+
+    fn value(self, inputs: &ImperialInputs) -> f64 {
+        match self {
+            Self::Bc => inputs.bc,
+            Self::DragScaleFactor => inputs.drag_scale_factor,
+            Self::MuzzleVelocity => inputs.muzzle_velocity_fps,
+        }
+    }
+
+    fn with_value(self, inputs: &ImperialInputs, value: f64) -> ImperialInputs {
+        let mut inputs = inputs.clone();
+        match self {
+            Self::Bc => inputs.bc = value,
+            Self::DragScaleFactor => inputs.drag_scale_factor = value,
+            Self::MuzzleVelocity => inputs.muzzle_velocity_fps = value,
+        }
+        inputs
+    }
+}
+
+/// An observed drop: `(range_yards, drop)`, in its `Measure`
+type Drop = (f64, f64);
+
+/// How observed drops are given
+#[derive(Clone, Copy)]
+enum Measure {
+    /// Inches from the line of sight, as `fit_drops` takes them
+    Inches,
+    /// Angle from the line of sight, MOA, as `true` takes them
+    Moa,
+}
+
+impl Measure {
+    /// A drop of `drop_inches` at `range_yards`, in this measure
+    fn of(self, range_yards: f64, drop_inches: f64) -> f64 {
+        match self {
+            Self::Inches => drop_inches,
+            Self::Moa => drop_inches.atan2(range_yards * YARDS_TO_METERS / INCHES_TO_METERS) * RADIANS_TO_MOA,
+        }
+    }
+}
+
+/// Read `field`, an array of `{range_yards:, <drop_key>:}` hashes
+fn read_drops(value: Value, field: &str, drop_key: &str, problems: &mut Problems) -> Vec<Drop> {
+    let Some(drops) = RArray::from_value(value) else {
+        problems.add(field, format!("{} is invalid (expected an array of {{range_yards:, {}:}}, got {})", field, drop_key, value.inspect()));
+        return Vec::new();
+    };
+    let mut read = Vec::with_capacity(drops.len());
+    for (index, drop) in drops.into_iter().enumerate() {
+        let entry = format!("{}[{}]", field, index);
+        let Some(drop) = RHash::from_value(drop) else {
+            problems.add(&entry, format!("{} is invalid (expected {{range_yards:, {}:}}, got {})", entry, drop_key, drop.inspect()));
+            continue;
+        };
+        let range_yards: f64 = problems.fetch(drop, &format!("{}.range_yards", entry), "range_yards");
+        let observed: f64 = problems.fetch(drop, &format!("{}.{}", entry, drop_key), drop_key);
+        problems.check_positive(&format!("{}.range_yards", entry), range_yards);
+        read.push((range_yards, observed));
+    }
+    problems.check(field, !read.is_empty(), "must have at least one drop");
+    read
+}
+
+/// A fit's name, as a String or Symbol
+fn fit_name(value: Value) -> Result<String, Error> {
+    match Symbol::from_value(value) {
+        Some(_) => value.funcall("to_s", ()),
+        None => String::try_convert(value),
+    }
+}
+
+/// Read `fit:`, `"bc"` (the default), `"drag_scale_factor"` or `"muzzle_velocity"`, as a String or Symbol
+fn read_fit(value: Option<Value>, problems: &mut Problems) -> Result<Fit, Error> {
+    let name = match value {
+        Some(value) if Symbol::from_value(value).is_some() => value.funcall::<_, _, String>("to_s", ())?,
+        value => problems.optional("fit", value, "bc".to_string()),
+    };
+    Ok(Fit::from_name(&name).unwrap_or_else(|| {
+        problems.add("fit", format!("fit {:?} is not supported (supported: bc, drag_scale_factor, muzzle_velocity)", name));
+        Fit::Bc
+    }))
+}
+
+/// Read `true`'s `fit:`, `muzzle_velocity`, `drag_scale_factor` or an array of
+/// both (the default), muzzle velocity first
+fn read_true_fits(value: Option<Value>, problems: &mut Problems) -> Result<Vec<Fit>, Error> {
+    let names = match value.filter(|value| !value.is_nil()) {
+        None => return Ok(vec![Fit::MuzzleVelocity, Fit::DragScaleFactor]),
+        Some(value) => match RArray::from_value(value) {
+            Some(names) => names.into_iter().map(fit_name).collect::<Result<Vec<_>, Error>>(),
+            None => fit_name(value).map(|name| vec![name]),
+        },
+    };
+    let Ok(names) = names else {
+        problems.add("fit", "fit must be muzzle_velocity, drag_scale_factor or an array of them".to_string());
+        return Ok(Vec::new());
+    };
+    let mut fits = Vec::new();
+    for name in names {
+        match Fit::from_name(&name) {
+            Some(fit @ (Fit::MuzzleVelocity | Fit::DragScaleFactor)) => fits.push(fit),
+            _ => problems.add("fit", format!("fit {:?} is not supported (supported: muzzle_velocity, drag_scale_factor)", name)),
+        }
+    }
+    problems.check("fit", !fits.is_empty(), "must name muzzle_velocity, drag_scale_factor or both");
+    fits.sort_by_key(|fit| matches!(fit, Fit::DragScaleFactor));
+    fits.dedup_by_key(|fit| fit.name());
+    Ok(fits)
+}
+
+/// Everything a candidate is solved against
+struct Observations<'a> {
+    drops: &'a [Drop],
+    measure: Measure,
+    wind: WindField,
+    atmosphere: AtmosphericConditions,
+}
+
+impl Observations<'_> {
+    /// Predicted drop at each observed range, in the observations' measure
+    fn predict(&self, ruby: &Ruby, inputs: &ImperialInputs) -> Result<Vec<f64>, Error> {
+        let solver = Solver::from_inputs(inputs.clone());
+        let longest_yards = self.drops.iter().map(|(range_yards, _)| *range_yards).fold(0.0, f64::max);
+        let options = SolveOptions { max_range_m: Some(longest_yards * YARDS_TO_METERS + RANGE_MARGIN_M), ..SolveOptions::default() };
+        let (result, solved_inputs, ..) = solver.solve_engine(ruby, self.wind.clone(), &self.atmosphere, options)?;
+        let spin = Spin::from_inputs(&solved_inputs, &self.atmosphere);
+        let speed_of_sound_mps = result::speed_of_sound_mps(&self.atmosphere);
+        // Only the drops are read, so the yaw of repose and wind band are left out
+        let points = result
+            .points
+            .iter()
+            .map(|point| TrajectoryPoint::from_engine(point, solver.sight_height_m(), speed_of_sound_mps, spin, (0.0, 0.0), (0.0, 0.0)))
+            .collect::<Vec<_>>();
+
+        let inches_per_yard = YARDS_TO_METERS / INCHES_TO_METERS;
+        self.drops
+            .iter()
+            .map(|(range_yards, _)| {
+                interpolation::interpolate(&points, TrajectoryPoint::x, *range_yards, Method::Linear)
+                    .map(|point| self.measure.of(*range_yards, point.y() * inches_per_yard))
+                    .ok_or_else(|| {
+                        Error::new(
+                            error::solver_error(ruby),
+                            format!("drop at {} yards is past the end of the trajectory", range_yards),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Sum of squared residuals, in the observations' measure squared
+    fn cost(&self, ruby: &Ruby, inputs: &ImperialInputs) -> Result<f64, Error> {
+        let predicted = self.predict(ruby, inputs)?;
+        Ok(self.drops.iter().zip(predicted).map(|((_, observed), predicted)| (observed - predicted).powi(2)).sum())
+    }
+
+    /// The value of `fit` minimizing `cost` of `inputs` with it, and the cost
+    /// there, by golden-section search from its value in `inputs`
+    fn minimize(
+        &self,
+        ruby: &Ruby,
+        inputs: &ImperialInputs,
+        fit: Fit,
+        mut cost: impl FnMut(&ImperialInputs) -> Result<f64, Error>,
+    ) -> Result<(f64, f64), Error> {
+        let (start, span) = (fit.value(inputs), fit.span());
+        let mut cost_at = |log_value: f64| cost(&fit.with_value(inputs, log_value.exp()));
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = ((start / span).ln(), (start * span).ln());
+        let mut a = high - ratio * (high - low);
+        let mut b = low + ratio * (high - low);
+        let (mut cost_a, mut cost_b) = (cost_at(a)?, cost_at(b)?);
+        while high - low > TOLERANCE {
+            if cost_a < cost_b {
+                (high, b, cost_b) = (b, a, cost_a);
+                a = high - ratio * (high - low);
+                cost_a = cost_at(a)?;
+            } else {
+                (low, a, cost_a) = (a, b, cost_b);
+                b = low + ratio * (high - low);
+                cost_b = cost_at(b)?;
+            }
+        }
+
+        let (first, last) = ((start / span).ln(), (start * span).ln());
+        if low - first < TOLERANCE || last - high < TOLERANCE {
+            return Err(Error::new(
+                error::convergence_error(ruby),
+                format!(
+                    "best {} is outside {:.4}..{:.4}; check the drops, zero and starting {}",
+                    fit.name(),
+                    first.exp(),
+                    last.exp(),
+                    fit.name()
+                ),
+            ));
+        }
+        Ok((((low + high) / 2.0).exp(), cost_a.min(cost_b)))
+    }
+
+    /// `fits` minimizing the cost, from their values in `inputs`: each fit's
+    /// value is searched with the ones after it minimized for every candidate
+    fn fit(&self, ruby: &Ruby, inputs: &ImperialInputs, fits: &[Fit]) -> Result<(ImperialInputs, f64), Error> {
+        let Some((&fit, rest)) = fits.split_first() else {
+            return Ok((inputs.clone(), self.cost(ruby, inputs)?));
+        };
+        let (value, _) = self.minimize(ruby, inputs, fit, |inputs| self.fit(ruby, inputs, rest).map(|(_, cost)| cost))?;
+        self.fit(ruby, &fit.with_value(inputs, value), rest)
+    }
+
+    /// `{range_yards:, observed_<unit>:, predicted_<unit>:, residual_<unit>:}` per observation
+    fn residuals(&self, ruby: &Ruby, predicted: &[f64], unit: &str) -> Result<RArray, Error> {
+        let residuals = ruby.ary_new_capa(self.drops.len());
+        for ((range_yards, observed), predicted) in self.drops.iter().zip(predicted) {
+            let residual = ruby.hash_new();
+            residual.aset(ruby.to_symbol("range_yards"), *range_yards)?;
+            residual.aset(ruby.to_symbol(format!("observed_{}", unit)), *observed)?;
+            residual.aset(ruby.to_symbol(format!("predicted_{}", unit)), *predicted)?;
+            residual.aset(ruby.to_symbol(format!("residual_{}", unit)), observed - predicted)?;
+            residuals.push(residual)?;
+        }
+        Ok(residuals)
+    }
+
+    /// Root-mean-square residual of `predicted`
+    fn rms(&self, predicted: &[f64]) -> f64 {
+        let sum_of_squares: f64 = self.drops.iter().zip(predicted).map(|((_, observed), predicted)| (observed - predicted).powi(2)).sum();
+        (sum_of_squares / self.drops.len() as f64).sqrt()
+    }
+}
+
+/// BallisticsEngine.fit_drops(inputs, drops:, fit: :bc, environment: nil, wind: nil, atmosphere: nil)
+///
+/// Solves for the `bc` (or `drag_scale_factor`, or `muzzle_velocity`) that
+/// best reproduces drops measured at several ranges, with the inputs' zero.
+/// The inputs' own value is the starting guess.
+fn fit_drops(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let drops = kw.delete::<_, Option<Value>>(ruby.to_symbol("drops"))?;
+    let fit = kw.delete::<_, Option<Value>>(ruby.to_symbol("fit"))?;
+    let (wind, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let inputs = ImperialInputs::from_value(inputs, &mut problems)?;
+    let fit = read_fit(fit, &mut problems)?;
+    let drops = match drops {
+        Some(drops) => read_drops(drops, "drops", "drop_inches", &mut problems),
+        None => {
+            problems.add("drops", "drops is missing".to_string());
+            Vec::new()
+        }
+    };
+    fit.check(&inputs, &mut problems);
+    problems.into_result(ruby)?;
+
+    let observations = Observations { drops: &drops, measure: Measure::Inches, wind, atmosphere };
+    let (value, _) = observations.minimize(ruby, &inputs, fit, |inputs| observations.cost(ruby, inputs))?;
+    let predicted = observations.predict(ruby, &fit.with_value(&inputs, value))?;
+
+    let fitted = ruby.hash_new();
+    fitted.aset(ruby.to_symbol("fit"), fit.name())?;
+    fitted.aset(ruby.to_symbol("value"), value)?;
+    fitted.aset(ruby.to_symbol("rms_in"), observations.rms(&predicted))?;
+    fitted.aset(ruby.to_symbol("residuals"), observations.residuals(ruby, &predicted, "in")?)?;
+    Ok(fitted)
+}
+
+/// BallisticsEngine.true(profile, observations:, fit: [:muzzle_velocity, :drag_scale_factor], environment: nil, wind: nil, atmosphere: nil)
+///
+/// Trues `profile` to field DOPE, drops in MOA at several ranges: the muzzle
+/// velocity and/or drag scale factor that best reproduce them, and the
+/// profile with them as a `Projectile`.
+fn true_profile(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (profile,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let drops = kw.delete::<_, Option<Value>>(ruby.to_symbol("observations"))?;
+    let fits = kw.delete::<_, Option<Value>>(ruby.to_symbol("fit"))?;
+    let (wind, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let inputs = ImperialInputs::from_value(profile, &mut problems)?;
+    let fits = read_true_fits(fits, &mut problems)?;
+    let drops = match drops {
+        Some(drops) => read_drops(drops, "observations", "measured_drop_moa", &mut problems),
+        None => {
+            problems.add("observations", "observations is missing".to_string());
+            Vec::new()
+        }
+    };
+    for fit in &fits {
+        fit.check(&inputs, &mut problems);
+    }
+    problems.check(
+        "observations",
+        fits.len() < 2 || drops.is_empty() || drops.len() >= 2,
+        "must have at least two drops to fit both muzzle_velocity and drag_scale_factor",
+    );
+    problems.into_result(ruby)?;
+
+    let observations = Observations { drops: &drops, measure: Measure::Moa, wind, atmosphere };
+    let (trued, _) = observations.fit(ruby, &inputs, &fits)?;
+    let predicted = observations.predict(ruby, &trued)?;
+
+    let fitted = ruby.hash_new();
+    fitted.aset(ruby.to_symbol("fit"), fits.iter().map(|fit| fit.name()).collect::<Vec<_>>())?;
+    fitted.aset(ruby.to_symbol("muzzle_velocity_fps"), trued.muzzle_velocity_fps)?;
+    fitted.aset(ruby.to_symbol("drag_scale_factor"), trued.drag_scale_factor)?;
+    fitted.aset(ruby.to_symbol("rms_moa"), observations.rms(&predicted))?;
+    fitted.aset(ruby.to_symbol("residuals"), observations.residuals(ruby, &predicted, "moa")?)?;
+    fitted.aset(ruby.to_symbol("profile"), Projectile::from_inputs(trued))?;
+    Ok(fitted)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("fit_drops", function!(fit_drops, -1))?;
+    module.define_module_function("true", function!(true_profile, -1))?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::scan_args};
+use ballistics_engine::TrajectoryPoint as EngineTrajectoryPoint;
+
+use crate::error;
+use crate::projectile::ImperialInputs;
+use crate::solver::{self, SolveOptions, Solver};
+use crate::units::*;
+use crate::validation::{self, Problems};
+
+// Uphill and downhill fire. Gravity pulls across a tipped line of sight by the
+// cosine of its inclination, so the bullet falls less below it than over the
+// same range on the level, up the slope or down. Rifleman's rule holds for the
+// horizontal distance, the slant range times the cosine; the improved rule
+// takes the level path at the slant range with the bullet's fall from the bore
+// line scaled by the cosine. `BallisticsEngine.incline` solves the shot itself,
+// the bore zeroed on the level and tipped with the line of sight, and sets
+// both rules beside it. The angle is the inputs' `shooting_angle_degrees`, or
+// worked out from `target_elevation_change_feet`, the height of the target
+// above the shooter (negative below) at the slant range.
+
+/// Range solved to past the slant range, as a fraction of it
+const RANGE_MARGIN: f64 = 1.05;
+
+/// The path where it is `range_m` along the line of sight through `sight`
+/// (downrange, up) tipped `angle` radians, interpolated between the solved
+/// points, and its height above the line there, metres
+pub fn at_sight_range(points: &[EngineTrajectoryPoint], sight: (f64, f64), angle: f64, range_m: f64) -> Option<(EngineTrajectoryPoint, f64)> {
+    let frame = |point: &EngineTrajectoryPoint| {
+        let (downrange, up) = (point.position.z - sight.0, point.position.y - sight.1);
+        (downrange * angle.cos() + up * angle.sin(), up * angle.cos() - downrange * angle.sin())
+    };
+    points.windows(2).find_map(|pair| {
+        let ((before, height_before), (after, height_after)) = (frame(&pair[0]), frame(&pair[1]));
+        (before <= range_m && after >= range_m && after > before).then(|| {
+            let fraction = (range_m - before) / (after - before);
+            let lerp = |from: f64, to: f64| from + (to - from) * fraction;
+            let point = EngineTrajectoryPoint {
+                time: lerp(pair[0].time, pair[1].time),
+                position: pair[0].position.lerp(&pair[1].position, fraction),
+                velocity_magnitude: lerp(pair[0].velocity_magnitude, pair[1].velocity_magnitude),
+                kinetic_energy: lerp(pair[0].kinetic_energy, pair[1].kinetic_energy),
+            };
+            (point, lerp(height_before, height_after))
+        })
+    })
+}
+
+/// Sight of a trajectory, tipped `angle` radians, from its first point: `sight_height_m` across the bore (downrange, up)
+pub fn sight(points: &[EngineTrajectoryPoint], sight_height_m: f64, angle: f64) -> (f64, f64) {
+    let (downrange, up) = points.first().map_or((0.0, 0.0), |point| (point.position.z, point.position.y));
+    (downrange - sight_height_m * angle.sin(), up + sight_height_m * angle.cos())
+}
+
+/// Level range where the path falls through `height` metres from the line of sight at `sight_m` for the last time, metres
+fn level_range(points: &[EngineTrajectoryPoint], sight_m: f64, height: f64) -> Option<f64> {
+    points.windows(2).rev().find_map(|pair| {
+        let (before, after) = (pair[0].position.y - sight_m - height, pair[1].position.y - sight_m - height);
+        (before >= 0.0 && after < 0.0).then(|| pair[0].position.z + (pair[1].position.z - pair[0].position.z) * before / (before - after))
+    })
+}
+
+/// BallisticsEngine.incline(inputs, slant_range_yards:, target_elevation_change_feet: nil, environment: nil, wind: nil, atmosphere: nil)
+///
+/// The drop at `slant_range_yards` along the line of sight and the level range
+/// with the same drop, solved and by both rifleman's rules.
+fn incline(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let slant_range = kw.delete::<_, Option<Value>>(ruby.to_symbol("slant_range_yards"))?;
+    let elevation_change = kw.delete::<_, Option<Value>>(ruby.to_symbol("target_elevation_change_feet"))?;
+    let (wind, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let solver = Solver::from_inputs(ImperialInputs::from_value(inputs, &mut problems)?);
+    let slant_range_yards: f64 = problems.required("slant_range_yards", slant_range);
+    problems.check_positive("slant_range_yards", slant_range_yards);
+    let slant_range_m = slant_range_yards * YARDS_TO_METERS;
+    let elevation_change_m = elevation_change
+        .filter(|value| !value.is_nil())
+        .map(|value| problems.required::<f64>("target_elevation_change_feet", Some(value)) * FEET_TO_METERS);
+    if let Some(elevation_change_m) = elevation_change_m {
+        problems.check("target_elevation_change_feet", elevation_change_m.abs() <= slant_range_m, "must not exceed slant_range_yards");
+    }
+    problems.into_result(ruby)?;
+
+    let angle = elevation_change_m.map_or(solver.shooting_angle(), |elevation_change_m| (elevation_change_m / slant_range_m).asin());
+    let sight_height_m = solver.sight_height_m();
+    let options = SolveOptions { max_range_m: Some(slant_range_m * RANGE_MARGIN), ..SolveOptions::default() };
+    let (level, ..) = solver.solve_engine(ruby, wind.clone(), &atmosphere, options)?;
+    let depth_m = (-angle.sin()).max(0.0) * slant_range_m * RANGE_MARGIN;
+    let (inclined, ..) = solver.solve_inclined(ruby, wind, &atmosphere, angle, depth_m, options)?;
+
+    let (level_sight, inclined_sight) = (sight(&level.points, sight_height_m, 0.0), sight(&inclined.points, sight_height_m, angle));
+    let short = || {
+        Error::new(
+            error::solver_error(ruby),
+            format!("the trajectory ends short of slant_range_yards {:.1}", slant_range_yards),
+        )
+    };
+    let height_at = |points: &[EngineTrajectoryPoint], sight: (f64, f64), angle: f64, range_m: f64| {
+        at_sight_range(points, sight, angle, range_m).map(|(_, height)| height).ok_or_else(short)
+    };
+    let level_drop_m = height_at(&level.points, level_sight, 0.0, slant_range_m)?;
+    let drop_m = height_at(&inclined.points, inclined_sight, angle, slant_range_m)?;
+
+    let horizontal_m = slant_range_m * angle.cos();
+    let riflemans_drop_m = height_at(&level.points, level_sight, 0.0, horizontal_m)?;
+    // The bore line is the sight height below the line of sight at the muzzle and rises at the zero angle
+    let bore_m = slant_range_m * solver.bore_angle(ruby, &atmosphere, &options)?.tan() - sight_height_m;
+    let improved_drop_m = bore_m - (bore_m - level_drop_m) * angle.cos();
+
+    let rule = |equivalent_m: Option<f64>, rule_drop_m: f64| -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("equivalent_range_yards"), equivalent_m.map(|metres| metres / YARDS_TO_METERS))?;
+        hash.aset(ruby.to_symbol("drop_in"), rule_drop_m / INCHES_TO_METERS)?;
+        hash.aset(ruby.to_symbol("error_in"), (rule_drop_m - drop_m) / INCHES_TO_METERS)?;
+        Ok(hash)
+    };
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("slant_range_yards"), slant_range_yards)?;
+    hash.aset(ruby.to_symbol("shooting_angle_degrees"), angle / DEGREES_TO_RADIANS)?;
+    hash.aset(ruby.to_symbol("horizontal_range_yards"), horizontal_m / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("target_elevation_change_feet"), slant_range_m * angle.sin() / FEET_TO_METERS)?;
+    hash.aset(ruby.to_symbol("equivalent_range_yards"), level_range(&level.points, level_sight.1, drop_m).map(|metres| metres / YARDS_TO_METERS))?;
+    hash.aset(ruby.to_symbol("drop_in"), drop_m / INCHES_TO_METERS)?;
+    hash.aset(ruby.to_symbol("drop_moa"), drop_m.atan2(slant_range_m) * RADIANS_TO_MOA)?;
+    hash.aset(ruby.to_symbol("drop_mil"), drop_m.atan2(slant_range_m) * RADIANS_TO_MILS)?;
+    hash.aset(ruby.to_symbol("level_drop_in"), level_drop_m / INCHES_TO_METERS)?;
+    hash.aset(ruby.to_symbol("riflemans_rule"), rule(Some(horizontal_m), riflemans_drop_m)?)?;
+    hash.aset(ruby.to_symbol("improved_riflemans_rule"), rule(level_range(&level.points, level_sight.1, improved_drop_m), improved_drop_m)?)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("incline", function!(incline, -1))?;
+    Ok(())
+}
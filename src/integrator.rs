@@ -0,0 +1,333 @@
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult};
+use nalgebra::Vector3;
+
+use crate::analytic::Drag;
+use crate::earth;
+use crate::spin::YawOfRepose;
+use crate::units::*;
+use crate::wind_field::WindField;
+
+// Integrators the extension steps itself, for the `solver_options` the engine's
+// cannot serve. The engine has an Euler integrator of its own, but it takes the
+// drag table's coefficient as the bullet's own, without the BC, and logs every
+// hundredth step to stderr, so `integrator: "euler"` is stepped here: each step
+// moves the velocity by the acceleration at its start and the position by the
+// new velocity. The engine's RK4 stops as soon as the bullet falls below the
+// bore, where its RK45 carries on to the ground threshold, so `integrator:
+// "rk4"` is stepped here as well, in classic fourth-order steps that stop where
+// the others do. The engine's adaptive RK45 works to a fixed tolerance, so
+// `rkf45` with a `relative_tolerance` or `absolute_tolerance` is stepped here
+// too, in Dormand-Prince 5(4) steps. A step is kept when the difference between
+// its fifth- and fourth-order results is within the tolerance of every position
+// and velocity component, and retaken shorter when it is not; the next step
+// grows or shrinks with the error, so steps are long where the drag changes
+// slowly and short through the transonic region where it changes fast. Both use
+// the drag and gravity of the engine's Runge-Kutta integrators, in the wind at
+// the bullet's range, so wind zones, whose headwind the engine's uniform wind
+// cannot follow, and a vertical wind are stepped here too, as is the modified
+// point-mass model, whose yaw of repose adds its lift and drag to every stage.
+
+/// Time of flight the engine's integrators give up at, seconds
+const MAX_TIME_S: f64 = 100.0;
+/// First adaptive step, seconds, as the engine's RK45 takes
+const INITIAL_STEP_S: f64 = 0.001;
+/// Shortest adaptive step, seconds, taken whatever its error
+const MIN_STEP_S: f64 = 1e-6;
+/// Longest adaptive step, seconds
+pub const MAX_STEP_S: f64 = 0.1;
+
+/// Tolerance of an adaptive step: a component's error may be up to
+/// `absolute + relative * |value|` (metres for positions, m/s for velocities)
+#[derive(Clone, Copy)]
+pub struct Tolerance {
+    pub relative: f64,
+    pub absolute: f64,
+}
+
+/// Dormand-Prince stage coefficients, row `s` for stage `s + 2`; the last row is
+/// the fifth-order solution, whose velocity is the seventh stage's
+const DORMAND_PRINCE: [[f64; 6]; 6] = [
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+/// Fraction of the step each stage is taken at
+const DORMAND_PRINCE_NODES: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+/// Fifth- less fourth-order weights of the seven stages, for the error estimate
+const DORMAND_PRINCE_ERROR: [f64; 7] =
+    [71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0];
+
+/// Drag and gravity on the bullet, in the wind at its range, and for the
+/// modified point-mass model the force of its yaw of repose
+struct Flight<'a> {
+    drag: Drag<'a>,
+    wind: &'a WindField,
+    repose: Option<&'a YawOfRepose>,
+}
+
+impl Flight<'_> {
+    fn acceleration(&self, time: f64, position: Vector3<f64>, velocity: Vector3<f64>) -> Vector3<f64> {
+        let relative = velocity - self.wind.air_velocity(position.z);
+        let relative_speed = relative.magnitude();
+        let mut acceleration = Vector3::new(0.0, -earth::SOLVER_GRAVITY, 0.0);
+        if relative_speed > 0.001 {
+            // v² / F, with F the retardation distance
+            let retardation_m = self.drag.retardation_ft(relative_speed / FPS_TO_MPS) * FEET_TO_METERS;
+            acceleration -= relative * (relative_speed / retardation_m);
+            if let Some(repose) = self.repose {
+                acceleration += repose.acceleration(time, relative, acceleration);
+            }
+        }
+        acceleration
+    }
+}
+
+/// Solve `inputs`, already zeroed and prepared by `drag::apply`, to `max_range_m` in Euler steps of `time_step` seconds
+///
+/// Each point is passed to `on_step` as it is stepped to; the trajectory ends
+/// at the first it returns false for.
+pub fn euler(
+    inputs: &BallisticInputs,
+    wind: &WindField,
+    repose: Option<&YawOfRepose>,
+    atmosphere: &AtmosphericConditions,
+    max_range_m: f64,
+    time_step: f64,
+    on_step: impl FnMut(&EngineTrajectoryPoint) -> bool,
+) -> Result<EngineTrajectoryResult, String> {
+    integrate(inputs, wind, repose, atmosphere, max_range_m, on_step, |flight, time, position, velocity| {
+        *velocity += flight.acceleration(time, *position, *velocity) * time_step;
+        *position += *velocity * time_step;
+        time_step
+    })
+}
+
+/// Solve `inputs`, already zeroed and prepared by `drag::apply`, to `max_range_m` in fourth-order
+/// Runge-Kutta steps of `time_step` seconds, passing each point to `on_step` as `euler` does
+pub fn rk4(
+    inputs: &BallisticInputs,
+    wind: &WindField,
+    repose: Option<&YawOfRepose>,
+    atmosphere: &AtmosphericConditions,
+    max_range_m: f64,
+    time_step: f64,
+    on_step: impl FnMut(&EngineTrajectoryPoint) -> bool,
+) -> Result<EngineTrajectoryResult, String> {
+    integrate(inputs, wind, repose, atmosphere, max_range_m, on_step, |flight, time, position, velocity| {
+        let half = time_step / 2.0;
+        // Each stage's rates: its velocity for the position, its acceleration for the velocity
+        let (v1, a1) = (*velocity, flight.acceleration(time, *position, *velocity));
+        let v2 = *velocity + a1 * half;
+        let a2 = flight.acceleration(time + half, *position + v1 * half, v2);
+        let v3 = *velocity + a2 * half;
+        let a3 = flight.acceleration(time + half, *position + v2 * half, v3);
+        let v4 = *velocity + a3 * time_step;
+        let a4 = flight.acceleration(time + time_step, *position + v3 * time_step, v4);
+        *position += (v1 + 2.0 * v2 + 2.0 * v3 + v4) * (time_step / 6.0);
+        *velocity += (a1 + 2.0 * a2 + 2.0 * a3 + a4) * (time_step / 6.0);
+        time_step
+    })
+}
+
+/// Solve `inputs`, already zeroed and prepared by `drag::apply`, to `max_range_m` in adaptive steps within `tolerance`,
+/// passing each point to `on_step` as `euler` does
+pub fn adaptive(
+    inputs: &BallisticInputs,
+    wind: &WindField,
+    repose: Option<&YawOfRepose>,
+    atmosphere: &AtmosphericConditions,
+    max_range_m: f64,
+    tolerance: Tolerance,
+    on_step: impl FnMut(&EngineTrajectoryPoint) -> bool,
+) -> Result<EngineTrajectoryResult, String> {
+    let mut step = INITIAL_STEP_S;
+    integrate(inputs, wind, repose, atmosphere, max_range_m, on_step, |flight, time, position, velocity| loop {
+        let (next_position, next_velocity, error) = dormand_prince(flight, time, *position, *velocity, step, tolerance);
+        let taken = step;
+        // 0.9 err^(-1/5), the usual safety factor and the order of the error estimate
+        let factor = if error > 0.0 { (0.9 * error.powf(-0.2)).clamp(0.2, 5.0) } else { 5.0 };
+        step = (step * factor).clamp(MIN_STEP_S, MAX_STEP_S);
+        if error <= 1.0 || taken <= MIN_STEP_S {
+            (*position, *velocity) = (next_position, next_velocity);
+            return taken;
+        }
+    })
+}
+
+/// One Dormand-Prince step of `dt` from `time`: the fifth-order position and
+/// velocity, and the largest component error as a fraction of its tolerance
+fn dormand_prince(
+    flight: &Flight,
+    time: f64,
+    position: Vector3<f64>,
+    velocity: Vector3<f64>,
+    dt: f64,
+    tolerance: Tolerance,
+) -> (Vector3<f64>, Vector3<f64>, f64) {
+    // Stage velocities (the position's rates) and accelerations
+    let mut rates = [Vector3::zeros(); 7];
+    let mut accelerations = [Vector3::zeros(); 7];
+    (rates[0], accelerations[0]) = (velocity, flight.acceleration(time, position, velocity));
+    let mut next_position = position;
+    for (stage, weights) in DORMAND_PRINCE.iter().enumerate() {
+        let (mut stage_position, mut stage_velocity) = (position, velocity);
+        for (index, weight) in weights.iter().enumerate().take(stage + 1) {
+            stage_position += dt * weight * rates[index];
+            stage_velocity += dt * weight * accelerations[index];
+        }
+        let stage_time = time + dt * DORMAND_PRINCE_NODES[stage + 1];
+        (rates[stage + 1], accelerations[stage + 1]) = (stage_velocity, flight.acceleration(stage_time, stage_position, stage_velocity));
+        next_position = stage_position;
+    }
+    let next_velocity = rates[6];
+
+    let (mut position_error, mut velocity_error) = (Vector3::<f64>::zeros(), Vector3::<f64>::zeros());
+    for (index, weight) in DORMAND_PRINCE_ERROR.iter().enumerate() {
+        position_error += dt * weight * rates[index];
+        velocity_error += dt * weight * accelerations[index];
+    }
+    let scaled = |error: Vector3<f64>, from: Vector3<f64>, to: Vector3<f64>| {
+        (0..3).map(|axis| error[axis].abs() / (tolerance.absolute + tolerance.relative * from[axis].abs().max(to[axis].abs()))).fold(0.0, f64::max)
+    };
+    let error = scaled(position_error, position, next_position).max(scaled(velocity_error, velocity, next_velocity));
+    (next_position, next_velocity, error)
+}
+
+/// Step from the muzzle until `max_range_m`, 100 s, the bullet falling to
+/// `inputs.ground_threshold` (metres, 100 below the bore unless set) or a point
+/// `on_step` returns false for, with `step` advancing the position and velocity
+/// from the time of flight and returning the time it took
+fn integrate(
+    inputs: &BallisticInputs,
+    wind: &WindField,
+    repose: Option<&YawOfRepose>,
+    atmosphere: &AtmosphericConditions,
+    max_range_m: f64,
+    mut on_step: impl FnMut(&EngineTrajectoryPoint) -> bool,
+    mut step: impl FnMut(&Flight, f64, &mut Vector3<f64>, &mut Vector3<f64>) -> f64,
+) -> Result<EngineTrajectoryResult, String> {
+    let flight = Flight { drag: Drag::new(inputs, atmosphere), wind, repose };
+    let horizontal_velocity = inputs.muzzle_velocity * inputs.muzzle_angle.cos();
+    let mut velocity = Vector3::new(
+        horizontal_velocity * inputs.azimuth_angle.sin(),
+        inputs.muzzle_velocity * inputs.muzzle_angle.sin(),
+        horizontal_velocity * inputs.azimuth_angle.cos(),
+    );
+    let (mut position, mut time) = (Vector3::new(0.0, inputs.muzzle_height, 0.0), 0.0);
+
+    let mut points = Vec::new();
+    while position.z < max_range_m && position.y > inputs.ground_threshold && time < MAX_TIME_S {
+        let speed = velocity.magnitude();
+        let point = EngineTrajectoryPoint { time, position, velocity_magnitude: speed, kinetic_energy: 0.5 * inputs.bullet_mass * speed * speed };
+        let more = on_step(&point);
+        points.push(point);
+        if !more {
+            break;
+        }
+        time += step(&flight, time, &mut position, &mut velocity);
+    }
+
+    let last = points.last().ok_or("No trajectory points generated")?;
+    Ok(EngineTrajectoryResult {
+        max_range: last.position.z,
+        max_height: points.iter().map(|point| point.position.y).fold(f64::MIN, f64::max),
+        time_of_flight: last.time,
+        impact_velocity: last.velocity_magnitude,
+        impact_energy: last.kinetic_energy,
+        points,
+        sampled_points: None,
+        min_pitch_damping: None,
+        transonic_mach: None,
+        angular_state: None,
+        max_yaw_angle: None,
+        max_precession_angle: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ballistics_engine::WindConditions;
+
+    use super::*;
+    use crate::wind_field::Zone;
+
+    /// The engine's default bullet, prepared for the extension's integrators
+    fn prepared() -> (BallisticInputs, AtmosphericConditions) {
+        let (mut inputs, atmosphere) = (BallisticInputs::default(), AtmosphericConditions::default());
+        crate::drag::apply(&mut inputs, &atmosphere);
+        (inputs, atmosphere)
+    }
+
+    #[test]
+    fn rk4_carries_on_below_the_bore_to_the_ground_threshold() {
+        let (inputs, atmosphere) = prepared();
+        let fired = BallisticInputs { ground_threshold: -5.0, ..inputs };
+        let result = rk4(&fired, &WindField::default(), None, &atmosphere, 2000.0, 0.001, |_| true).unwrap();
+        let last = result.points.last().unwrap();
+        // The last point is the one stepped from below it
+        assert!(last.position.y > -5.0 && last.position.y < -4.5, "stopped at {} m", last.position.y);
+    }
+
+    #[test]
+    fn rk4_lands_where_a_tight_adaptive_solve_does() {
+        let (inputs, atmosphere) = prepared();
+        let tolerance = Tolerance { relative: 1e-9, absolute: 1e-9 };
+        let fixed = rk4(&inputs, &WindField::default(), None, &atmosphere, 500.0, 0.001, |_| true).unwrap();
+        let adaptive = adaptive(&inputs, &WindField::default(), None, &atmosphere, 500.0, tolerance, |_| true).unwrap();
+        let at_400_m = |points: &[EngineTrajectoryPoint]| crate::interpolation::height_at(points, 400.0).unwrap();
+        // Within a millimetre, the rest being the straight line between the adaptive points
+        assert!((at_400_m(&fixed.points) - at_400_m(&adaptive.points)).abs() < 1e-3);
+    }
+
+    /// `(lateral position m, speed m/s)` 400 m downrange in a wind blowing to `direction` from 200 m on
+    fn beyond_200_m(direction: f64) -> (f64, f64) {
+        let (inputs, atmosphere) = prepared();
+        let zone = Zone {
+            from_m: 200.0,
+            to_m: f64::INFINITY,
+            measured: WindConditions { speed: 5.0, direction },
+            gradient: None,
+            vertical: 0.0,
+            speed_uncertainty: 0.0,
+            direction_uncertainty: 0.0,
+        };
+        let tolerance = Tolerance { relative: 1e-9, absolute: 1e-9 };
+        let result = adaptive(&inputs, &WindField::new(vec![zone]), None, &atmosphere, 500.0, tolerance, |_| true).unwrap();
+        crate::wind::tests::at_400_m(&result.points)
+    }
+
+    #[test]
+    fn steps_through_the_wind_of_each_zone() {
+        let (still_drift, still) = beyond_200_m(0.0);
+        let ((_, headwind), (drift, _)) = (beyond_200_m(std::f64::consts::PI), beyond_200_m(std::f64::consts::FRAC_PI_2));
+        // Still air at the muzzle, a headwind slowing the bullet beyond 200 m
+        assert!(still_drift.abs() < 1e-9 && headwind < still - 0.5, "{} against {} m/s", headwind, still);
+        assert!(drift > 0.05, "drifted {} m", drift);
+    }
+
+    /// `(lateral position m, speed m/s)` 400 m downrange, with a 10 inch twist's yaw of repose
+    fn with_repose(is_twist_right: bool) -> (f64, f64) {
+        let (inputs, atmosphere) = prepared();
+        let spinning = BallisticInputs { twist_rate: 10.0, is_twist_right, ..inputs };
+        let repose = YawOfRepose::new(crate::spin::Spin::from_inputs(&spinning, &atmosphere), &spinning, &atmosphere).unwrap();
+        let tolerance = Tolerance { relative: 1e-9, absolute: 1e-9 };
+        let result = adaptive(&spinning, &WindField::default(), Some(&repose), &atmosphere, 500.0, tolerance, |_| true).unwrap();
+        crate::wind::tests::at_400_m(&result.points)
+    }
+
+    #[test]
+    fn the_yaw_of_repose_lifts_the_bullet_the_way_it_spins() {
+        let (inputs, atmosphere) = prepared();
+        let point_mass = adaptive(&inputs, &WindField::default(), None, &atmosphere, 500.0, Tolerance { relative: 1e-9, absolute: 1e-9 }, |_| true).unwrap();
+        let (_, point_mass_speed) = crate::wind::tests::at_400_m(&point_mass.points);
+        let ((right, speed), (left, _)) = (with_repose(true), with_repose(false));
+        // Centimetres at 400 m, mirrored by the twist; the drag of a stable bullet's
+        // yaw of a few hundredths of a degree takes next to no speed
+        assert!(right > 0.01 && right < 0.1, "drifted {} m", right);
+        assert!((right + left).abs() < 1e-6);
+        assert!((speed - point_mass_speed).abs() < 1e-3, "{} against {} m/s", speed, point_mass_speed);
+    }
+}
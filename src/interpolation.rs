@@ -0,0 +1,165 @@
+use ballistics_engine::{TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult};
+
+use crate::point::TrajectoryPoint;
+
+/// How to fill in values between stored trajectory points
+#[derive(Clone, Copy)]
+pub enum Method {
+    Linear,
+    /// Cubic Hermite with finite-difference slopes, exact at the stored points
+    Cubic,
+}
+
+impl Method {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "cubic" => Some(Self::Cubic),
+            _ => None,
+        }
+    }
+}
+
+/// Interpolate every field of `points` at `at`, where `key` increases along the points
+///
+/// Returns `None` when `at` lies outside the first and last keys.
+pub fn interpolate(
+    points: &[TrajectoryPoint],
+    key: impl Fn(&TrajectoryPoint) -> f64,
+    at: f64,
+    method: Method,
+) -> Option<TrajectoryPoint> {
+    let keys: Vec<f64> = points.iter().map(&key).collect();
+    let (first, last) = (*keys.first()?, *keys.last()?);
+    if !(at >= first && at <= last) {
+        return None;
+    }
+    if keys.len() == 1 {
+        return Some(points[0]);
+    }
+
+    // Segment [i, i + 1] containing `at`
+    let i = keys.partition_point(|&k| k <= at).clamp(1, keys.len() - 1) - 1;
+    let span = keys[i + 1] - keys[i];
+    if span <= 0.0 {
+        return Some(points[i]);
+    }
+    let t = (at - keys[i]) / span;
+    let (a, b) = (points[i].fields(), points[i + 1].fields());
+
+    let mut fields = [0.0; TrajectoryPoint::FIELD_COUNT];
+    for (field, value) in fields.iter_mut().enumerate() {
+        *value = match method {
+            Method::Linear => a[field] + (b[field] - a[field]) * t,
+            Method::Cubic => {
+                let slope = |j: usize| slope(points, &keys, j, field);
+                hermite(a[field], b[field], slope(i) * span, slope(i + 1) * span, t)
+            }
+        };
+    }
+    Some(TrajectoryPoint::from_fields(fields))
+}
+
+/// Height of `points` at `range_m` downrange, metres, by the cubic of `Method::Cubic`
+///
+/// Returns `None` when the points do not reach it.
+pub fn height_at(points: &[EngineTrajectoryPoint], range_m: f64) -> Option<f64> {
+    let i = points.iter().position(|point| point.position.z >= range_m)?.max(1) - 1;
+    let (a, b) = (&points[i].position, &points.get(i + 1)?.position);
+    let span = b.z - a.z;
+    if span <= 0.0 {
+        return Some(a.y);
+    }
+    let secant = |from: usize, to: usize| (points[to].position.y - points[from].position.y) / (points[to].position.z - points[from].position.z);
+    let slope = |j: usize| match j {
+        0 => secant(0, 1),
+        _ if j == points.len() - 1 => secant(j - 1, j),
+        _ => (secant(j - 1, j) + secant(j, j + 1)) / 2.0,
+    };
+    Some(hermite(a.y, b.y, slope(i) * span, slope(i + 1) * span, (range_m - a.z) / span))
+}
+
+/// d(field)/d(key) at point `j`, averaging the neighbouring secants
+fn slope(points: &[TrajectoryPoint], keys: &[f64], j: usize, field: usize) -> f64 {
+    let secant = |from: usize, to: usize| {
+        let span = keys[to] - keys[from];
+        if span > 0.0 {
+            (points[to].fields()[field] - points[from].fields()[field]) / span
+        } else {
+            0.0
+        }
+    };
+    if j == 0 {
+        secant(0, 1)
+    } else if j == points.len() - 1 {
+        secant(j - 1, j)
+    } else {
+        (secant(j - 1, j) + secant(j, j + 1)) / 2.0
+    }
+}
+
+fn hermite(p0: f64, p1: f64, m0: f64, m1: f64, t: f64) -> f64 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * p0 + (t3 - 2.0 * t2 + t) * m0 + (-2.0 * t3 + 3.0 * t2) * p1 + (t3 - t2) * m1
+}
+
+/// Where an output point falls among the solved ones: `fraction` of the way from point `index` to the next
+#[derive(Clone, Copy)]
+pub struct Sample {
+    index: usize,
+    fraction: f64,
+}
+
+/// Samples every `step` of `key` from the first point, where `key` increases
+/// along the points, and one at the last point
+pub fn samples(points: &[EngineTrajectoryPoint], key: impl Fn(&EngineTrajectoryPoint) -> f64, step: f64) -> Vec<Sample> {
+    let keys: Vec<f64> = points.iter().map(&key).collect();
+    let (Some(&first), Some(&last)) = (keys.first(), keys.last()) else {
+        return Vec::new();
+    };
+    let mut samples = Vec::new();
+    let mut index = 0;
+    // Multiples of the step, so they do not drift as a running sum would
+    for at in (0..).map(|multiple| first + multiple as f64 * step).take_while(|at| *at < last) {
+        while keys[index + 1] <= at {
+            index += 1;
+        }
+        let span = keys[index + 1] - keys[index];
+        samples.push(Sample { index, fraction: if span > 0.0 { (at - keys[index]) / span } else { 0.0 } });
+    }
+    samples.push(Sample { index: keys.len() - 1, fraction: 0.0 });
+    samples
+}
+
+/// `values`, one per solved point, linearly interpolated at `samples`
+pub fn resample(values: &[f64], samples: &[Sample]) -> Vec<f64> {
+    samples
+        .iter()
+        .map(|sample| match (values.get(sample.index), values.get(sample.index + 1)) {
+            (Some(from), Some(to)) => from + (to - from) * sample.fraction,
+            (from, _) => from.copied().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Replace the points of `result` with ones linearly interpolated at `samples`
+pub fn resample_points(result: &mut EngineTrajectoryResult, samples: &[Sample]) {
+    let points = &result.points;
+    result.points = samples
+        .iter()
+        .map(|sample| {
+            let from = &points[sample.index];
+            let Some(to) = points.get(sample.index + 1).filter(|_| sample.fraction > 0.0) else {
+                return from.clone();
+            };
+            let lerp = |from: f64, to: f64| from + (to - from) * sample.fraction;
+            EngineTrajectoryPoint {
+                time: lerp(from.time, to.time),
+                position: from.position.lerp(&to.position, sample.fraction),
+                velocity_magnitude: lerp(from.velocity_magnitude, to.velocity_magnitude),
+                kinetic_energy: lerp(from.kinetic_energy, to.kinetic_energy),
+            }
+        })
+        .collect();
+}
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::atmosphere::{self, Given, ImperialAtmosphere};
+use crate::bullets;
+use crate::cant;
+use crate::config;
+use crate::drag;
+use crate::earth;
+use crate::environment::{self, Environment};
+use crate::output_units::OutputUnits;
+use crate::powder::{self, Sensitivity};
+use crate::projectile::{self, DEFAULT_IS_RIGHT_TWIST, DEFAULT_SHOOTING_ANGLE_DEGREES, DEFAULT_SPIN_DRIFT, DEFAULT_TWIST_RATE_INCHES, ImperialInputs};
+use crate::solver::{self, SolveOptions};
+use crate::spin;
+use crate::units::{DEGREES_TO_RADIANS, FPS_TO_MPS, JOULES_TO_FTLBS, YARDS_TO_METERS};
+use crate::validation::Problems;
+use crate::wind::{self, ImperialWind};
+use crate::wind_field::{self, WindField};
+
+// JSON input documents (`solve('{"bc": 0.223, ...}')`), deserialized straight
+// into Rust values without building Ruby hashes. Keys are the imperial hash
+// keys; `null` means the default, as `nil` does in a hash.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    bullet: Option<String>,
+    bc: Option<BcDocument>,
+    form_factor: Option<f64>,
+    bullet_weight_grains: Option<f64>,
+    muzzle_velocity_fps: Option<f64>,
+    bullet_diameter_inches: Option<f64>,
+    bullet_length_inches: Option<f64>,
+    sight_height_inches: Option<f64>,
+    zero_distance_yards: Option<f64>,
+    shooting_angle_degrees: Option<f64>,
+    twist_rate_inches: Option<f64>,
+    is_right_twist: Option<bool>,
+    drag_model: Option<String>,
+    drag_curve: Option<Vec<(f64, f64)>>,
+    drag_scale_factor: Option<DragScaleDocument>,
+    spin_drift: Option<bool>,
+    magnus: Option<MagnusDocument>,
+    solver: Option<String>,
+    standard: Option<String>,
+    powder_temp_f: Option<f64>,
+    powder_sensitivity: Option<SensitivityDocument>,
+    powder_reference_temp_f: Option<f64>,
+    latitude_degrees: Option<f64>,
+    azimuth_degrees: Option<f64>,
+    cant_angle_degrees: Option<f64>,
+    earth_curvature: Option<bool>,
+    wgs84_gravity: Option<bool>,
+    environment: Option<String>,
+    wind: Option<WindsDocument>,
+    atmosphere: Option<AtmosphereDocument>,
+    output_units: Option<BTreeMap<String, String>>,
+    solver_options: Option<SolverOptionsDocument>,
+}
+
+/// `solver_options`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolverOptionsDocument {
+    integrator: Option<String>,
+    time_step: Option<f64>,
+    relative_tolerance: Option<f64>,
+    absolute_tolerance: Option<f64>,
+    max_range_yards: Option<f64>,
+    max_time_s: Option<f64>,
+    stop_at_velocity_fps: Option<f64>,
+    stop_at_energy_ftlbs: Option<f64>,
+    target_range_yards: Option<f64>,
+    output_step_yards: Option<f64>,
+    output_step_meters: Option<f64>,
+    output_step_seconds: Option<f64>,
+    launch_angle_degrees: Option<f64>,
+    zero_angle_degrees: Option<f64>,
+}
+
+/// `bc`: a number or `[{"velocity_fps": ..., "bc": ...}, ...]` bands
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BcDocument {
+    Single(f64),
+    Bands(Vec<BandDocument>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BandDocument {
+    velocity_fps: f64,
+    bc: f64,
+}
+
+/// `magnus`: on/off, a bullet class name or a coefficient
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MagnusDocument {
+    Enabled(bool),
+    Class(String),
+    Coefficient(f64),
+}
+
+/// `drag_scale_factor`: a number or `[[mach, factor], ...]` points
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DragScaleDocument {
+    Single(f64),
+    ByMach(Vec<(f64, f64)>),
+}
+
+/// `powder_sensitivity`: fps per °F or `[[temp_f, muzzle_velocity_fps], ...]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SensitivityDocument {
+    PerDegree(f64),
+    Table(Vec<(f64, f64)>),
+}
+
+/// `wind`: a wind or `[{"from_yards": ..., "to_yards": ..., ...}, ...]` zones
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WindsDocument {
+    Single(WindDocument),
+    Zones(Vec<WindDocument>),
+}
+
+/// `direction_clock`: hours or `"H:MM"`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClockDocument {
+    Hours(f64),
+    Position(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindDocument {
+    from_yards: Option<f64>,
+    to_yards: Option<f64>,
+    speed_mph: Option<f64>,
+    direction_degrees: Option<f64>,
+    direction_clock: Option<ClockDocument>,
+    vertical_speed_mph: Option<f64>,
+    shear: Option<String>,
+    shear_exponent: Option<f64>,
+    roughness_length_feet: Option<f64>,
+    reference_height_feet: Option<f64>,
+    muzzle_height_feet: Option<f64>,
+    speed_uncertainty_mph: Option<f64>,
+    direction_uncertainty_degrees: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtmosphereDocument {
+    temperature_f: Option<f64>,
+    pressure_inhg: Option<f64>,
+    humidity_percent: Option<f64>,
+    altitude_feet: Option<f64>,
+    pressure_is_absolute: Option<bool>,
+    dew_point_f: Option<f64>,
+    density_altitude_feet: Option<f64>,
+    air_density_kg_m3: Option<f64>,
+    speed_of_sound_fps: Option<f64>,
+}
+
+/// Everything a JSON document describes
+pub struct Inputs {
+    pub inputs: ImperialInputs,
+    pub wind: WindField,
+    pub atmosphere: ImperialAtmosphere,
+    pub units: OutputUnits,
+    pub options: SolveOptions,
+}
+
+/// A wind entry, with keys left out taken from the configured default
+fn read_wind(wind: WindDocument, problems: &mut Problems) -> ImperialWind {
+    let defaults = config::defaults().wind;
+    let clock_degrees = wind.direction_clock.and_then(|clock| {
+        let hours = match clock {
+            ClockDocument::Hours(hours) => Ok(hours),
+            ClockDocument::Position(position) => wind::parse_clock(&position),
+        };
+        wind::clock_direction(hours, wind.direction_degrees.is_some(), problems)
+    });
+    let wind = ImperialWind {
+        speed_mph: wind.speed_mph.unwrap_or(defaults.speed_mph),
+        direction_degrees: clock_degrees.or(wind.direction_degrees).unwrap_or(defaults.direction_degrees),
+        vertical_speed_mph: wind.vertical_speed_mph.unwrap_or(defaults.vertical_speed_mph),
+        shear: match wind.shear {
+            Some(name) => wind_field::read_shear(Some(name), problems),
+            None => defaults.shear,
+        },
+        shear_exponent: wind.shear_exponent.unwrap_or(defaults.shear_exponent),
+        roughness_length_feet: wind.roughness_length_feet.unwrap_or(defaults.roughness_length_feet),
+        reference_height_feet: wind.reference_height_feet.or(defaults.reference_height_feet),
+        muzzle_height_feet: wind.muzzle_height_feet.unwrap_or(defaults.muzzle_height_feet),
+        speed_uncertainty_mph: wind.speed_uncertainty_mph.unwrap_or(defaults.speed_uncertainty_mph),
+        direction_uncertainty_degrees: wind.direction_uncertainty_degrees.unwrap_or(defaults.direction_uncertainty_degrees),
+    };
+    wind.check(problems);
+    wind
+}
+
+fn required(field: &str, value: Option<f64>, problems: &mut Problems) -> f64 {
+    value.unwrap_or_else(|| {
+        problems.add(field, format!("{} is missing", field));
+        0.0
+    })
+}
+
+/// Read a JSON input document, recording every missing or invalid value
+///
+/// A document that is not valid JSON, or has unknown keys or wrongly typed
+/// values, is recorded as a single problem and `None` is returned.
+pub fn read(json: &str, problems: &mut Problems) -> Option<Inputs> {
+    let mut document: Document = match serde_json::from_str(json) {
+        Ok(document) => document,
+        Err(e) => {
+            problems.add("json", format!("json is invalid ({})", e));
+            return None;
+        }
+    };
+    let defaults = config::defaults();
+    let custom = projectile::is_custom(document.drag_model.as_deref());
+    if let Some(bullet) = document.bullet.as_deref().and_then(|name| bullets::named(name, problems)) {
+        document.bullet_weight_grains.get_or_insert(bullet.weight_grains);
+        document.bullet_diameter_inches.get_or_insert(bullet.diameter_inches);
+        document.bullet_length_inches.get_or_insert(bullet.length_inches);
+        if document.bc.is_none() && document.form_factor.is_none() && document.drag_curve.is_none() && !custom {
+            document.bc = Some(BcDocument::Single(bullet.bc(document.drag_model.as_deref(), problems)));
+            document.drag_model.get_or_insert_with(|| "G7".to_string());
+        }
+    }
+    let (drag_model, drag_curve) = projectile::read_drag(document.drag_model, document.drag_curve, problems);
+
+    if document.form_factor.is_some() && document.bc.is_some() {
+        problems.add("form_factor", "form_factor cannot be combined with bc (the BC is derived from it)".to_string());
+    }
+    let (bc, bc_bands) = match document.bc {
+        Some(BcDocument::Single(bc)) => (bc, None),
+        Some(BcDocument::Bands(bands)) => drag::banded(bands.into_iter().map(|band| (band.velocity_fps, band.bc)).collect()),
+        None if custom || document.form_factor.is_some() => (0.0, None),
+        None => (required("bc", None, problems), None),
+    };
+    let (drag_scale_factor, drag_scale_by_mach) = match document.drag_scale_factor {
+        Some(DragScaleDocument::Single(factor)) => (factor, None),
+        Some(DragScaleDocument::ByMach(points)) => drag::scaled(points),
+        None => (1.0, None),
+    };
+    let magnus = match document.magnus {
+        Some(MagnusDocument::Enabled(true)) => Some(spin::default_magnus_coefficient()),
+        Some(MagnusDocument::Enabled(false)) | None => None,
+        Some(MagnusDocument::Class(class)) => spin::magnus_coefficient(&class, problems),
+        Some(MagnusDocument::Coefficient(coefficient)) => Some(coefficient),
+    };
+
+    let inputs = ImperialInputs {
+        bc,
+        bc_bands,
+        form_factor: document.form_factor,
+        bullet_weight_grains: required("bullet_weight_grains", document.bullet_weight_grains, problems),
+        muzzle_velocity_fps: required("muzzle_velocity_fps", document.muzzle_velocity_fps, problems),
+        bullet_diameter_inches: required("bullet_diameter_inches", document.bullet_diameter_inches, problems),
+        bullet_length_inches: required("bullet_length_inches", document.bullet_length_inches, problems),
+        sight_height_inches: required("sight_height_inches", document.sight_height_inches, problems),
+        zero_distance_yards: required("zero_distance_yards", document.zero_distance_yards, problems),
+        shooting_angle_degrees: document.shooting_angle_degrees.unwrap_or(DEFAULT_SHOOTING_ANGLE_DEGREES),
+        twist_rate_inches: document.twist_rate_inches.unwrap_or(DEFAULT_TWIST_RATE_INCHES),
+        is_right_twist: document.is_right_twist.unwrap_or(DEFAULT_IS_RIGHT_TWIST),
+        drag_model,
+        drag_curve,
+        drag_scale_factor,
+        drag_scale_by_mach,
+        spin_drift: document.spin_drift.unwrap_or(DEFAULT_SPIN_DRIFT),
+        magnus,
+        solver: solver::read_model(document.solver, problems),
+        standard: atmosphere::read_standard(document.standard, problems),
+        powder_temp_f: document.powder_temp_f,
+        powder_sensitivity: document.powder_sensitivity.map(|sensitivity| match sensitivity {
+            SensitivityDocument::PerDegree(fps_per_f) => Sensitivity::PerDegree(fps_per_f),
+            SensitivityDocument::Table(points) => powder::table(points),
+        }),
+        powder_reference_temp_f: document.powder_reference_temp_f.unwrap_or(powder::DEFAULT_REFERENCE_TEMP_F),
+        latitude_degrees: document.latitude_degrees,
+        azimuth_degrees: document.azimuth_degrees.unwrap_or(earth::DEFAULT_AZIMUTH_DEGREES),
+        cant_angle_degrees: document.cant_angle_degrees.unwrap_or(cant::DEFAULT_CANT_ANGLE_DEGREES),
+        earth_curvature: document.earth_curvature.unwrap_or(earth::DEFAULT_EARTH_CURVATURE),
+        wgs84_gravity: document.wgs84_gravity.unwrap_or(earth::DEFAULT_WGS84_GRAVITY),
+    };
+    inputs.check(problems);
+
+    let environment = match document.environment {
+        Some(name) => environment::named(&name, problems),
+        None => Environment::default(),
+    };
+
+    let wind = match document.wind {
+        Some(WindsDocument::Single(wind)) => {
+            if wind.from_yards.is_some() || wind.to_yards.is_some() {
+                problems.add("wind", "wind from_yards and to_yards are only for an array of wind zones".to_string());
+            }
+            read_wind(wind, problems).to_conditions()
+        }
+        Some(WindsDocument::Zones(zones)) => {
+            let zones = zones.into_iter().map(|zone| (zone.from_yards, zone.to_yards, read_wind(zone, problems))).collect();
+            wind::zones_field(zones, problems)
+        }
+        None => {
+            let wind = environment.wind_or_default();
+            wind.check(problems);
+            wind.to_conditions()
+        }
+    };
+
+    let atmosphere = match document.atmosphere {
+        Some(atmosphere) => {
+            let given = Given {
+                temperature: atmosphere.temperature_f.is_some(),
+                pressure: atmosphere.pressure_inhg.is_some(),
+                humidity: atmosphere.humidity_percent.is_some() || atmosphere.dew_point_f.is_some(),
+            };
+            ImperialAtmosphere {
+                temperature_f: atmosphere.temperature_f.unwrap_or(defaults.atmosphere.temperature_f),
+                pressure_inhg: atmosphere.pressure_inhg.unwrap_or(defaults.atmosphere.pressure_inhg),
+                humidity_percent: atmosphere.humidity_percent.unwrap_or(defaults.atmosphere.humidity_percent),
+                altitude_feet: atmosphere.altitude_feet.unwrap_or(defaults.atmosphere.altitude_feet),
+            }
+            .with_dew_point(atmosphere.dew_point_f, atmosphere.humidity_percent.is_some(), problems)
+            .with_pressure_reference(atmosphere.pressure_is_absolute.unwrap_or(true))
+            .with_density_altitude(atmosphere.density_altitude_feet, given, problems)
+            .with_air_density(atmosphere.air_density_kg_m3, atmosphere.density_altitude_feet.is_some(), given, problems)
+            .with_speed_of_sound(atmosphere.speed_of_sound_fps, given, problems)
+        }
+        None => environment.atmosphere_or_default(),
+    };
+    atmosphere.check(problems);
+
+    let units = match document.output_units {
+        Some(names) => OutputUnits::from_names(names, problems),
+        None => defaults.output_units,
+    };
+
+    let options = match document.solver_options {
+        Some(options) => SolveOptions {
+            time_step: options.time_step,
+            integrator: solver::read_integrator(options.integrator, problems),
+            max_range_m: options.max_range_yards.map(|yards| yards * YARDS_TO_METERS),
+            max_time_s: options.max_time_s,
+            stop_velocity_mps: options.stop_at_velocity_fps.map(|fps| fps * FPS_TO_MPS),
+            stop_energy_j: options.stop_at_energy_ftlbs.map(|ftlbs| ftlbs / JOULES_TO_FTLBS),
+            target_range_m: options.target_range_yards.map(|yards| yards * YARDS_TO_METERS),
+            relative_tolerance: options.relative_tolerance,
+            absolute_tolerance: options.absolute_tolerance,
+            output_step: solver::read_output_step([options.output_step_yards, options.output_step_meters, options.output_step_seconds], problems),
+            launch_angle: options.launch_angle_degrees.map(|degrees| degrees * DEGREES_TO_RADIANS),
+            zero_angle: options.zero_angle_degrees.map(|degrees| degrees * DEGREES_TO_RADIANS),
+            ..SolveOptions::default()
+        },
+        None => SolveOptions::default(),
+    };
+    options.check(problems);
+
+    Some(Inputs { inputs, wind, atmosphere, units, options })
+}
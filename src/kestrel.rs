@@ -0,0 +1,309 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::{get_kwargs, scan_args}};
+
+use crate::cdm;
+use crate::coerce;
+use crate::error;
+use crate::units::*;
+use crate::validation::Problems;
+
+// Kestrel weather meter exports (Kestrel LiNK CSV logs): `name,value` device
+// lines, then a header row of measurement names, a row of their units and one
+// row per logged reading. `BallisticsEngine.parse_kestrel` turns the latest
+// reading into the `atmosphere` and `wind` hashes the solvers take.
+
+/// Header name that marks the measurement header row
+const TEMPERATURE: &str = "Temperature";
+
+/// Measurement names and units of an export
+struct Header {
+    names: Vec<String>,
+    units: Vec<String>,
+}
+
+impl Header {
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|column| column.eq_ignore_ascii_case(name))
+    }
+
+    /// The timestamp column, e.g. `FORMATTED DATE_TIME`
+    fn time_index(&self) -> Option<usize> {
+        self.names.iter().position(|column| column.to_lowercase().contains("date"))
+    }
+
+    /// The reading's value in column `name`, converted by its unit; None when
+    /// the column is missing or blank (`--` when the sensor had no reading)
+    fn value(&self, row: &[String], name: &str, convert: fn(&str, f64) -> Option<f64>, problems: &mut Problems) -> Option<f64> {
+        let index = self.index(name)?;
+        let value = row.get(index)?.parse::<f64>().ok()?;
+        let unit = self.units.get(index).map_or("", |unit| unit.as_str());
+        let converted = convert(&unit.to_lowercase(), value);
+        if converted.is_none() {
+            problems.add("kestrel", format!("kestrel {} unit {:?} is not supported", name, unit));
+        }
+        converted
+    }
+}
+
+// Unit conversions to the imperial keys, with Kestrel's imperial units (or
+// none, for exports without a units row) passed through
+
+fn temperature_f(unit: &str, value: f64) -> Option<f64> {
+    match unit.trim_start_matches(|c: char| !c.is_ascii_alphabetic()) {
+        "" | "f" => Some(value),
+        "c" => Some(value * 9.0 / 5.0 + 32.0),
+        _ => None,
+    }
+}
+
+fn pressure_inhg(unit: &str, value: f64) -> Option<f64> {
+    match unit {
+        "" | "inhg" => Some(value),
+        "hpa" | "mb" | "mbar" => Some(value / INHG_TO_HPA),
+        "psi" => Some(value * 2.036_02),
+        "mmhg" => Some(value / 25.4),
+        _ => None,
+    }
+}
+
+fn feet(unit: &str, value: f64) -> Option<f64> {
+    match unit {
+        "" | "ft" => Some(value),
+        "m" => Some(value / FEET_TO_METERS),
+        _ => None,
+    }
+}
+
+fn mph(unit: &str, value: f64) -> Option<f64> {
+    match unit {
+        "" | "mph" => Some(value),
+        "km/h" | "kph" => Some(value / 3.6 / MPH_TO_MPS),
+        "m/s" => Some(value / MPH_TO_MPS),
+        "kt" | "kts" | "kn" | "knots" => Some(value * 1.150_779),
+        "ft/min" | "fpm" => Some(value / 88.0),
+        _ => None,
+    }
+}
+
+fn percent(unit: &str, value: f64) -> Option<f64> {
+    matches!(unit, "" | "%").then_some(value)
+}
+
+fn degrees(_unit: &str, value: f64) -> Option<f64> {
+    Some(value)
+}
+
+/// The latest reading of an export, in the imperial keys
+#[derive(Default)]
+struct Reading {
+    recorded_at: Option<String>,
+    temperature_f: Option<f64>,
+    humidity_percent: Option<f64>,
+    dew_point_f: Option<f64>,
+    station_pressure_inhg: Option<f64>,
+    /// The altimeter setting
+    barometric_pressure_inhg: Option<f64>,
+    altitude_feet: Option<f64>,
+    density_altitude_feet: Option<f64>,
+    wind_speed_mph: Option<f64>,
+    headwind_mph: Option<f64>,
+    /// Positive from the right
+    crosswind_mph: Option<f64>,
+    /// Compass direction the wind blows from, degrees true
+    wind_from_degrees: Option<f64>,
+}
+
+/// Read the latest reading from export text, recording an unreadable export
+fn parse(text: &str, problems: &mut Problems) -> Reading {
+    let rows = text.lines().map(str::trim).filter(|line| !line.is_empty()).map(cdm::fields).collect::<Vec<_>>();
+    let Some(start) = rows.iter().position(|row| row.iter().any(|name| name.eq_ignore_ascii_case(TEMPERATURE))) else {
+        problems.add("kestrel", format!("kestrel export has no header row (no {:?} column)", TEMPERATURE));
+        return Reading::default();
+    };
+    let mut header = Header { names: rows[start].clone(), units: Vec::new() };
+    let temperature = header.index(TEMPERATURE).unwrap_or_default();
+    let is_reading = |row: &Vec<String>| row.get(temperature).is_some_and(|value| value.parse::<f64>().is_ok());
+    let mut readings = &rows[start + 1..];
+    if let Some((units, rest)) = readings.split_first().filter(|(units, _)| !is_reading(units)) {
+        header.units = units.clone();
+        readings = rest;
+    }
+    let Some(row) = readings.iter().rev().find(|row| is_reading(row)) else {
+        problems.add("kestrel", "kestrel export has no readings".to_string());
+        return Reading::default();
+    };
+
+    Reading {
+        recorded_at: header.time_index().and_then(|index| row.get(index)).cloned(),
+        temperature_f: header.value(row, TEMPERATURE, temperature_f, problems),
+        humidity_percent: header.value(row, "Relative Humidity", percent, problems),
+        dew_point_f: header.value(row, "Dew Point", temperature_f, problems),
+        station_pressure_inhg: header.value(row, "Station Pressure", pressure_inhg, problems),
+        barometric_pressure_inhg: header.value(row, "Barometric Pressure", pressure_inhg, problems),
+        altitude_feet: header.value(row, "Altitude", feet, problems),
+        density_altitude_feet: header.value(row, "Density Altitude", feet, problems),
+        wind_speed_mph: header.value(row, "Wind Speed", mph, problems),
+        headwind_mph: header.value(row, "Headwind", mph, problems),
+        crosswind_mph: header.value(row, "Crosswind", mph, problems),
+        wind_from_degrees: header.value(row, "Compass True Direction", degrees, problems),
+    }
+}
+
+impl Reading {
+    /// The `atmosphere` hash: station pressure if logged, else the altimeter
+    /// setting, else the density altitude; the humidity, else the dew point
+    fn atmosphere(&self, ruby: &Ruby) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        let set = |key: &str, value: Option<f64>| value.map_or(Ok(()), |value| hash.aset(ruby.to_symbol(key), value));
+        set("temperature_f", self.temperature_f)?;
+        set("altitude_feet", self.altitude_feet)?;
+        match (self.station_pressure_inhg, self.barometric_pressure_inhg) {
+            (Some(pressure), _) => set("pressure_inhg", Some(pressure))?,
+            (None, Some(pressure)) => {
+                set("pressure_inhg", Some(pressure))?;
+                hash.aset(ruby.to_symbol("pressure_is_absolute"), false)?;
+            }
+            // A density altitude stands for the humidity too
+            (None, None) if self.density_altitude_feet.is_some() => {
+                set("density_altitude_feet", self.density_altitude_feet)?;
+                return Ok(hash);
+            }
+            (None, None) => {}
+        }
+        match self.humidity_percent {
+            Some(humidity) => set("humidity_percent", Some(humidity))?,
+            None => set("dew_point_f", self.dew_point_f)?,
+        }
+        Ok(hash)
+    }
+
+    /// The wind's speed and `direction_degrees`, where it blows from
+    /// clockwise from the line of fire: from the headwind and crosswind the
+    /// Kestrel worked out for its target direction, else from the wind's
+    /// compass direction and `shot_direction_degrees`; None when neither
+    /// gives the direction
+    fn speed_and_direction(&self, shot_direction_degrees: Option<f64>) -> Option<(f64, f64)> {
+        let (speed_mph, direction_degrees) = match (self.headwind_mph, self.crosswind_mph, self.wind_speed_mph, self.wind_from_degrees, shot_direction_degrees) {
+            (Some(headwind), Some(crosswind), ..) => (headwind.hypot(crosswind), crosswind.atan2(headwind).to_degrees()),
+            (_, _, Some(speed), Some(from), Some(shot)) => (speed, from - shot),
+            _ => return None,
+        };
+        Some((speed_mph, direction_degrees.rem_euclid(360.0)))
+    }
+
+    /// The `wind` hash, None when the export does not give its direction
+    fn wind(&self, ruby: &Ruby, shot_direction_degrees: Option<f64>) -> Result<Option<RHash>, Error> {
+        let Some((speed_mph, direction_degrees)) = self.speed_and_direction(shot_direction_degrees) else {
+            return Ok(None);
+        };
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("speed_mph"), speed_mph)?;
+        hash.aset(ruby.to_symbol("direction_degrees"), direction_degrees)?;
+        Ok(Some(hash))
+    }
+}
+
+/// BallisticsEngine.parse_kestrel(path_or_string, shot_direction_degrees: nil)
+///
+/// `{recorded_at:, atmosphere:, wind:}` for the export's latest reading;
+/// `wind` is nil when the export does not give its direction.
+fn parse_kestrel(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let kwargs = get_kwargs::<_, (), (Option<Value>,), ()>(args.keywords, &[], &["shot_direction_degrees"])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let text = coerce::text_or_file(ruby, args.required.0, "kestrel")?;
+
+    let mut problems = Problems::default();
+    let shot_direction_degrees = kwargs
+        .optional
+        .0
+        .filter(|value| !value.is_nil())
+        .map(|value| problems.required::<f64>("shot_direction_degrees", Some(value)));
+    let reading = parse(&text, &mut problems);
+    problems.into_result(ruby)?;
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("recorded_at"), reading.recorded_at.clone())?;
+    hash.aset(ruby.to_symbol("atmosphere"), reading.atmosphere(ruby)?)?;
+    hash.aset(ruby.to_symbol("wind"), reading.wind(ruby, shot_direction_degrees)?)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("parse_kestrel", function!(parse_kestrel, -1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind::tests::{at_400_m, solved_in};
+    use crate::wind::ImperialWind;
+
+    const EXPORT: &str = "\
+Device Name,Kestrel 5700 Elite
+Serial Number,2412345
+FORMATTED DATE_TIME,Temperature,Relative Humidity,Station Pressure,Wind Speed,Compass True Direction
+yyyy-MM-dd hh:mm:ss a,°F,%,inHg,mph,Deg
+2026-06-01 10:41:07 AM,70.9,32.0,25.31,7.5,340
+2026-06-01 10:42:07 AM,71.6,31.0,25.30,8.2,345
+";
+
+    fn read(text: &str) -> (Reading, Problems) {
+        let mut problems = Problems::default();
+        (parse(text, &mut problems), problems)
+    }
+
+    #[test]
+    fn reads_the_latest_reading() {
+        let (reading, problems) = read(EXPORT);
+        assert!(problems.is_empty());
+        assert_eq!(reading.recorded_at.as_deref(), Some("2026-06-01 10:42:07 AM"));
+        assert_eq!(reading.temperature_f, Some(71.6));
+        assert_eq!(reading.humidity_percent, Some(31.0));
+        assert_eq!(reading.station_pressure_inhg, Some(25.3));
+        assert_eq!((reading.wind_speed_mph, reading.wind_from_degrees), (Some(8.2), Some(345.0)));
+        assert_eq!(reading.barometric_pressure_inhg, None);
+    }
+
+    #[test]
+    fn converts_metric_units() {
+        let (reading, problems) = read("Temperature,Barometric Pressure,Altitude,Wind Speed\n°C,hPa,m,m/s\n20.0,1013.25,100,2.0\n");
+        assert!(problems.is_empty());
+        assert!((reading.temperature_f.unwrap() - 68.0).abs() < 1e-9);
+        assert!((reading.barometric_pressure_inhg.unwrap() - 29.921).abs() < 0.001);
+        assert!((reading.altitude_feet.unwrap() - 328.084).abs() < 0.001);
+        assert!((reading.wind_speed_mph.unwrap() - 4.4739).abs() < 0.001);
+    }
+
+    #[test]
+    fn reads_an_export_without_a_units_row() {
+        let (reading, problems) = read("Temperature,Crosswind,Headwind\n65,3,-4\n");
+        assert!(problems.is_empty());
+        assert_eq!((reading.temperature_f, reading.crosswind_mph, reading.headwind_mph), (Some(65.0), Some(3.0), Some(-4.0)));
+    }
+
+    /// The lateral position 400 m downrange in the reading's wind
+    fn drift(reading: &Reading, shot_direction_degrees: Option<f64>) -> f64 {
+        let (speed_mph, direction_degrees) = reading.speed_and_direction(shot_direction_degrees).unwrap();
+        let wind = ImperialWind { speed_mph, direction_degrees, ..ImperialWind::default() };
+        at_400_m(&solved_in(&wind.to_conditions())).0
+    }
+
+    #[test]
+    fn a_wind_from_the_right_drifts_the_bullet_left() {
+        let (reading, _) = read(EXPORT);
+        assert_eq!(reading.speed_and_direction(Some(270.0)), Some((8.2, 75.0)));
+        assert!(drift(&reading, Some(270.0)) < -0.1);
+        assert_eq!(reading.speed_and_direction(None), None);
+        let (components, _) = read("Temperature,Crosswind,Headwind\n65,5,0\n");
+        assert_eq!(components.speed_and_direction(None), Some((5.0, 90.0)));
+        assert!(drift(&components, None) < -0.1);
+    }
+
+    #[test]
+    fn records_unreadable_exports() {
+        assert_eq!(read("Wind Speed\n5\n").1.messages().len(), 1);
+        assert_eq!(read("Temperature\n°F\n").1.messages().len(), 1);
+        assert_eq!(read("Temperature\nK\n290\n").1.messages().len(), 1);
+    }
+}
@@ -0,0 +1,71 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, scan_args::scan_args};
+
+use crate::error;
+use crate::projectile::ImperialInputs;
+use crate::solver::{self, SolveOptions, Solver};
+use crate::units::*;
+use crate::validation::{self, Problems};
+
+// Lead on a moving target: how far ahead of it to aim, the distance it covers
+// across the line of fire while the bullet is in flight. A target crossing at
+// `crossing_angle_degrees` to the line of fire (90 straight across, 0 straight
+// towards or away) moves across it at its speed times the sine of the angle.
+// The time of flight is the solved one, so the lead grows faster than the
+// range as the bullet slows.
+
+/// Range solved to past the target, as a fraction of it
+const RANGE_MARGIN: f64 = 1.05;
+
+/// BallisticsEngine.lead(inputs, target_speed_mph:, crossing_angle_degrees:, range_yards:, environment: nil, wind: nil, atmosphere: nil)
+///
+/// `{range_yards:, time_of_flight:, lead_feet:, lead_moa:, lead_mil:}` for a
+/// target moving at `target_speed_mph` at `range_yards`.
+fn lead(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let target_speed = kw.delete::<_, Option<Value>>(ruby.to_symbol("target_speed_mph"))?;
+    let crossing_angle = kw.delete::<_, Option<Value>>(ruby.to_symbol("crossing_angle_degrees"))?;
+    let range = kw.delete::<_, Option<Value>>(ruby.to_symbol("range_yards"))?;
+    let (wind, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let solver = Solver::from_inputs(ImperialInputs::from_value(inputs, &mut problems)?);
+    let target_speed_mph: f64 = problems.required("target_speed_mph", target_speed);
+    let crossing_angle_degrees: f64 = problems.required("crossing_angle_degrees", crossing_angle);
+    let range_yards: f64 = problems.required("range_yards", range);
+    problems.check_non_negative("target_speed_mph", target_speed_mph);
+    problems.check_range("crossing_angle_degrees", crossing_angle_degrees, 0.0, 180.0);
+    problems.check_positive("range_yards", range_yards);
+    problems.into_result(ruby)?;
+
+    let range_m = range_yards * YARDS_TO_METERS;
+    let options = SolveOptions { max_range_m: Some(range_m * RANGE_MARGIN), ..SolveOptions::default() };
+    let (result, ..) = solver.solve_engine(ruby, wind, &atmosphere, options)?;
+    let time_of_flight = result
+        .points
+        .windows(2)
+        .find(|pair| pair[0].position.z <= range_m && pair[1].position.z >= range_m && pair[1].position.z > pair[0].position.z)
+        .map(|pair| pair[0].time + (pair[1].time - pair[0].time) * (range_m - pair[0].position.z) / (pair[1].position.z - pair[0].position.z))
+        .ok_or_else(|| {
+            let reached_m = result.points.last().map_or(0.0, |point| point.position.z);
+            Error::new(
+                error::solver_error(ruby),
+                format!("the trajectory ends at {:.1} yards, short of range_yards {:.1}", reached_m / YARDS_TO_METERS, range_yards),
+            )
+        })?;
+
+    let lead_m = target_speed_mph * MPH_TO_MPS * (crossing_angle_degrees * DEGREES_TO_RADIANS).sin() * time_of_flight;
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("range_yards"), range_yards)?;
+    hash.aset(ruby.to_symbol("time_of_flight"), time_of_flight)?;
+    hash.aset(ruby.to_symbol("lead_feet"), lead_m / FEET_TO_METERS)?;
+    hash.aset(ruby.to_symbol("lead_moa"), lead_m.atan2(range_m) * RADIANS_TO_MOA)?;
+    hash.aset(ruby.to_symbol("lead_mil"), lead_m.atan2(range_m) * RADIANS_TO_MILS)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("lead", function!(lead, -1))?;
+    Ok(())
+}
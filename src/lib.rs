@@ -1,143 +1,376 @@
-use magnus::{Error, RHash, function};
-use ballistics_engine::{
-    DragModel, BallisticInputs, WindConditions, AtmosphericConditions, TrajectorySolver,
-};
-
-// Unit conversion constants
-const GRAINS_TO_KG: f64 = 0.00006479891;
-const FPS_TO_MPS: f64 = 0.3048;
-const YARDS_TO_METERS: f64 = 0.9144;
-const INCHES_TO_METERS: f64 = 0.0254;
-const MPH_TO_MPS: f64 = 0.44704;
-const DEGREES_TO_RADIANS: f64 = std::f64::consts::PI / 180.0;
-
-/// Calculate trajectory from Ruby hash input
-fn solve_trajectory(ruby: &magnus::Ruby, inputs_hash: RHash) -> Result<RHash, Error> {
-    // Extract required values from Ruby hash
-    let bc: f64 = inputs_hash.fetch("bc")?;
-    let bullet_weight_grains: f64 = inputs_hash.fetch("bullet_weight_grains")?;
-    let muzzle_velocity_fps: f64 = inputs_hash.fetch("muzzle_velocity_fps")?;
-    let bullet_diameter_inches: f64 = inputs_hash.fetch("bullet_diameter_inches")?;
-    let bullet_length_inches: f64 = inputs_hash.fetch("bullet_length_inches")?;
-    let sight_height_inches: f64 = inputs_hash.fetch("sight_height_inches")?;
-    let zero_distance_yards: f64 = inputs_hash.fetch("zero_distance_yards")?;
-
-    // Optional values with defaults
-    let shooting_angle_degrees: f64 = inputs_hash.lookup2("shooting_angle_degrees", 0.0)?;
-    let twist_rate_inches: f64 = inputs_hash.lookup2("twist_rate_inches", 10.0)?;
-    let is_right_twist: bool = inputs_hash.lookup2("is_right_twist", true)?;
-
-    // Drag model (default to G7)
-    let drag_model_str: String = inputs_hash.lookup2("drag_model", "G7")?;
-    let drag_model = match drag_model_str.to_uppercase().as_str() {
-        "G1" => DragModel::G1,
-        "G7" => DragModel::G7,
-        "G8" => DragModel::G8,
-        _ => return Err(Error::new(ruby.exception_arg_error(), "Invalid drag_model, must be G1, G7, or G8")),
-    };
+use magnus::{Error, RArray, RHash, RString, Value, function, scan_args::scan_args, typed_data::Obj};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs};
 
-    // Create ballistic inputs using defaults and override specific fields
-    let ballistic_inputs = BallisticInputs {
-        bc_type: drag_model,
-        bc_value: bc,
-        bullet_diameter: bullet_diameter_inches * INCHES_TO_METERS,
-        bullet_mass: bullet_weight_grains * GRAINS_TO_KG,
-        bullet_length: bullet_length_inches * INCHES_TO_METERS,
-        muzzle_velocity: muzzle_velocity_fps * FPS_TO_MPS,
-        sight_height: sight_height_inches * INCHES_TO_METERS,
-        target_distance: zero_distance_yards * YARDS_TO_METERS,
-        shooting_angle: shooting_angle_degrees * DEGREES_TO_RADIANS,
-        twist_rate: twist_rate_inches,  // Already in inches per the struct definition
-        is_twist_right: is_right_twist,
-        caliber_inches: bullet_diameter_inches,
-        weight_grains: bullet_weight_grains,
-        ..Default::default()
-    };
+/// Generate Ruby getter/setter pairs for plain `Copy` fields of a wrapped `RefCell`
+///
+/// Setters coerce their argument like the constructors do and raise `InputError` naming the field.
+/// Optional fields (`field / setter: type = default`) are reset to the default when set to nil.
+macro_rules! field_accessors {
+    ($ty:ident { $($field:ident / $setter:ident: $t:ty $(= $default:expr)?),* $(,)? }) => {
+        impl $ty {
+            $(
+                fn $field(&self) -> $t {
+                    self.0.borrow().$field
+                }
 
-    // Optional wind conditions (default to no wind)
-    let wind = if let Some(wind_hash) = inputs_hash.lookup::<_, Option<RHash>>("wind")? {
-        let speed_mph: f64 = wind_hash.lookup2("speed_mph", 0.0)?;
-        let direction_deg: f64 = wind_hash.lookup2("direction_degrees", 0.0)?;
+                fn $setter(ruby: &magnus::Ruby, rb_self: &Self, value: magnus::Value) -> Result<(), magnus::Error> {
+                    $(
+                        if magnus::value::ReprValue::is_nil(value) {
+                            rb_self.0.borrow_mut().$field = $default;
+                            return Ok(());
+                        }
+                    )?
+                    let value = <$t as $crate::coerce::Coerce>::coerce(value).map_err(|reason| {
+                        magnus::Error::new(
+                            $crate::error::input_error(ruby),
+                            $crate::validation::invalid_message(stringify!($field), &reason),
+                        )
+                    })?;
+                    rb_self.0.borrow_mut().$field = value;
+                    Ok(())
+                }
+            )*
 
-        WindConditions {
-            speed: speed_mph * MPH_TO_MPS,
-            direction: direction_deg * DEGREES_TO_RADIANS,
-        }
-    } else {
-        WindConditions {
-            speed: 0.0,
-            direction: 0.0,
+            fn define_field_accessors(class: magnus::RClass) -> Result<(), magnus::Error> {
+                $(
+                    class.define_method(stringify!($field), magnus::method!($ty::$field, 0))?;
+                    class.define_method(concat!(stringify!($field), "="), magnus::method!($ty::$setter, 1))?;
+                )*
+                Ok(())
+            }
         }
     };
+}
 
-    // Optional atmospheric conditions (default to standard conditions)
-    let atmosphere = if let Some(atm_hash) = inputs_hash.lookup::<_, Option<RHash>>("atmosphere")? {
-        let temp_f: f64 = atm_hash.lookup2("temperature_f", 59.0)?;
-        let pressure_inhg: f64 = atm_hash.lookup2("pressure_inhg", 29.92)?;
-        let humidity: f64 = atm_hash.lookup2("humidity_percent", 50.0)?;
-        let altitude_ft: f64 = atm_hash.lookup2("altitude_feet", 0.0)?;
-
-        // Convert to Celsius and other SI units
-        let temp_c = (temp_f - 32.0) * 5.0 / 9.0;
-        let pressure_pa = pressure_inhg * 3386.389;
-        let altitude_m = altitude_ft * 0.3048;
-
-        AtmosphericConditions {
-            temperature: temp_c,
-            pressure: pressure_pa,
-            humidity,
-            altitude: altitude_m,
-        }
+mod analytic;
+mod atmosphere;
+mod bc;
+mod bullets;
+mod cant;
+mod capabilities;
+mod cdm;
+mod coerce;
+mod config;
+mod drag;
+mod earth;
+mod environment;
+mod error;
+mod firing_solution;
+mod fit;
+mod incline;
+mod integrator;
+mod interpolation;
+mod json;
+mod kestrel;
+mod lead;
+mod metar;
+mod mcdrag;
+mod metric;
+mod output_units;
+mod pejsa;
+mod point;
+mod point_blank;
+mod powder;
+mod projectile;
+mod result;
+mod si;
+mod siacci;
+mod solver;
+mod spin;
+mod stability;
+mod units;
+mod v2;
+mod validation;
+mod warnings;
+mod wind;
+mod wind_field;
+
+use atmosphere::{Atmosphere, ImperialAtmosphere};
+use output_units::OutputUnits;
+use projectile::ImperialInputs;
+use result::TrajectoryResult;
+use solver::{SolveOptions, Solver};
+use units::{DEGREES_TO_RADIANS, RADIANS_TO_MILS, RADIANS_TO_MOA, YARDS_TO_METERS};
+use validation::Problems;
+use wind::{ImperialWind, Wind};
+use wind_field::WindField;
+
+/// Everything `solve` and `validate` read from their arguments, in engine units
+struct SolveArgs {
+    solver: Solver,
+    wind: WindField,
+    atmosphere: AtmosphericConditions,
+    units: OutputUnits,
+    options: SolveOptions,
+    problems: Problems,
+}
+
+/// Hash keys read by `solve` itself rather than the inputs readers
+const SOLVE_HASH_KEYS: &[&str] = &["environment", "wind", "atmosphere", "output_units", "si", "solver_options"];
+
+/// Read `(inputs, wind = nil, atmosphere = nil)` as accepted by `solve` and `validate`
+///
+/// Wind and Atmosphere arguments take precedence over `"wind"`/`"atmosphere"` hash keys.
+/// A hash with `"si" => true` is read as raw SI inputs.
+fn read_solve_args(args: &[Value]) -> Result<SolveArgs, Error> {
+    let args = scan_args::<(Value,), (Option<&Wind>, Option<&Atmosphere>), (), (), (), ()>(args)?;
+    let (inputs,) = args.required;
+    let (wind_arg, atmosphere_arg) = args.optional;
+    read_solve_values(inputs, wind_arg, atmosphere_arg)
+}
+
+/// `read_solve_args` once the positional arguments are split out
+fn read_solve_values(inputs: Value, wind_arg: Option<&Wind>, atmosphere_arg: Option<&Atmosphere>) -> Result<SolveArgs, Error> {
+    let mut problems = Problems::default();
+
+    let (solver, mut wind, mut atmosphere, units, options) = if let Some(json) = RString::from_value(inputs) {
+        read_json(json, &mut problems)?
     } else {
-        // Standard ICAO atmosphere
-        AtmosphericConditions {
-            temperature: 15.0,  // 15°C (59°F)
-            pressure: 101325.0, // 1 atm in Pa
-            humidity: 50.0,
-            altitude: 0.0,
-        }
+        read_inputs(inputs, &mut problems)?
     };
 
-    // Solve trajectory - handle Result properly
-    let solver = TrajectorySolver::new(ballistic_inputs, wind, atmosphere);
-    let result = solver.solve()
-        .map_err(|e| Error::new(ruby.exception_runtime_error(), e.to_string()))?;
+    if let Some(wind_arg) = wind_arg {
+        let imperial = wind_arg.wind();
+        imperial.check(&mut problems);
+        wind = imperial.to_conditions();
+    }
+    if let Some(atmosphere_arg) = atmosphere_arg {
+        let imperial = atmosphere_arg.atmosphere();
+        imperial.check(&mut problems);
+        atmosphere = imperial.to_conditions();
+    }
+
+    Ok(SolveArgs { solver, wind, atmosphere, units, options, problems })
+}
+
+/// Solver, wind, atmosphere, output units and solver options read from `solve`'s first argument
+type Conditions = (Solver, WindField, AtmosphericConditions, OutputUnits, SolveOptions);
+
+/// `Conditions` from a JSON input document
+fn read_json(json: RString, problems: &mut Problems) -> Result<Conditions, Error> {
+    let defaults = config::defaults();
+    Ok(match json::read(&json.to_string()?, problems) {
+        Some(document) => (
+            Solver::from_inputs(document.inputs),
+            document.wind,
+            document.atmosphere.to_conditions(),
+            document.units,
+            document.options,
+        ),
+        // Only reported, never solved
+        None => (
+            Solver::from_ballistic_inputs(BallisticInputs::default()),
+            defaults.wind.to_conditions(),
+            defaults.atmosphere.to_conditions(),
+            defaults.output_units,
+            SolveOptions::default(),
+        ),
+    })
+}
+
+/// `Conditions` from an inputs hash (imperial, metric or SI) or a `Projectile`
+fn read_inputs(inputs: Value, problems: &mut Problems) -> Result<Conditions, Error> {
+    Ok(match projectile::inputs_hash(inputs)? {
+        Some(inputs_hash) if problems.lookup(inputs_hash, "si", "si", false) => {
+            let environment = environment::read(validation::entry(inputs_hash, "environment"), problems)?;
+            (
+                Solver::from_ballistic_inputs(si::inputs_from_hash(inputs_hash, SOLVE_HASH_KEYS, problems)?),
+                environment.si_wind_from_option(validation::entry(inputs_hash, "wind"), problems)?,
+                environment.si_atmosphere_from_option(validation::entry(inputs_hash, "atmosphere"), problems)?,
+                OutputUnits::si(),
+                SolveOptions::from_option(validation::entry(inputs_hash, "solver_options"), problems)?,
+            )
+        }
+        Some(inputs_hash) => {
+            let environment = environment::read(validation::entry(inputs_hash, "environment"), problems)?;
+
+            // Optional wind conditions or zones (default to the environment, or no wind)
+            let wind = match validation::entry(inputs_hash, "wind").and_then(RArray::from_value) {
+                Some(zones) => wind::read_zones(zones, problems)?,
+                None => match problems.lookup::<Option<RHash>>(inputs_hash, "wind", "wind", None) {
+                    Some(wind_hash) => ImperialWind::from_hash(wind_hash, problems)?.to_conditions(),
+                    None => environment.wind_or_default().to_conditions(),
+                },
+            };
 
-    // Convert result to Ruby hash using Ruby context methods
-    let result_hash = ruby.hash_new();
+            // Optional atmospheric conditions (default to the environment, or standard conditions)
+            let mut atmosphere = environment.atmosphere_or_default();
+            if let Some(atm_hash) = problems.lookup::<Option<RHash>>(inputs_hash, "atmosphere", "atmosphere", None) {
+                atmosphere = ImperialAtmosphere::from_hash(atm_hash, problems)?;
+            }
 
-    result_hash.aset("max_range_yards", result.max_range / YARDS_TO_METERS)?;
-    result_hash.aset("max_height_yards", result.max_height / YARDS_TO_METERS)?;
-    result_hash.aset("time_of_flight", result.time_of_flight)?;
-    result_hash.aset("impact_velocity_fps", result.impact_velocity / FPS_TO_MPS)?;
-    result_hash.aset("impact_energy_ftlbs", result.impact_energy * 0.737562)?; // J to ft-lbs
+            (
+                Solver::from_inputs(ImperialInputs::from_hash(inputs_hash, problems)?),
+                wind,
+                atmosphere.to_conditions(),
+                OutputUnits::from_option(validation::entry(inputs_hash, "output_units"), problems)?,
+                SolveOptions::from_option(validation::entry(inputs_hash, "solver_options"), problems)?,
+            )
+        }
+        None => (
+            Solver::from_inputs(ImperialInputs::from_value(inputs, problems)?),
+            config::defaults().wind.to_conditions(),
+            config::defaults().atmosphere.to_conditions(),
+            config::defaults().output_units,
+            SolveOptions::default(),
+        ),
+    })
+}
 
-    // Convert trajectory points to array of hashes - use correct field name "points"
-    let points = ruby.ary_new();
-    for point in result.points {
-        let point_hash = ruby.hash_new();
-        point_hash.aset("time", point.time)?;
-        point_hash.aset("x", point.position.x / YARDS_TO_METERS)?;
-        point_hash.aset("y", point.position.y / YARDS_TO_METERS)?;
-        point_hash.aset("z", point.position.z / YARDS_TO_METERS)?;
+/// Calculate trajectory from a Ruby hash, or from Projectile/Wind/Atmosphere objects
+///
+/// Every invalid input is reported in a single `InputError`.
+fn solve_trajectory(ruby: &magnus::Ruby, args: &[Value]) -> Result<Obj<TrajectoryResult>, Error> {
+    let args = read_solve_args(args)?;
+    args.problems.into_result(ruby)?;
 
-        // Use the velocity_magnitude field directly
-        point_hash.aset("velocity_fps", point.velocity_magnitude / FPS_TO_MPS)?;
+    args.solver.solve_with(ruby, args.wind, args.atmosphere, args.units, args.options)
+}
 
-        // Use the kinetic_energy field directly
-        point_hash.aset("energy_ftlbs", point.kinetic_energy * 0.737562)?;
+/// Calculate trajectory from keyword arguments
+///
+/// Unknown or missing keywords raise `InputError` before anything is solved.
+/// `si: true` switches every keyword, and the result hash, to raw SI values.
+fn solve_trajectory_kw(ruby: &magnus::Ruby, args: &[Value]) -> Result<Obj<TrajectoryResult>, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let kw = validation::copy(args.keywords)?;
+    let mut problems = Problems::default();
 
-        points.push(point_hash)?;
+    let si = problems.optional("si", kw.delete(ruby.to_symbol("si"))?, false);
+    let environment = environment::read(kw.delete(ruby.to_symbol("environment"))?, &mut problems)?;
+    let wind = kw.delete(ruby.to_symbol("wind"))?;
+    let atmosphere = kw.delete(ruby.to_symbol("atmosphere"))?;
+    let units = kw.delete(ruby.to_symbol("output_units"))?;
+    let options = SolveOptions::from_option(kw.delete(ruby.to_symbol("solver_options"))?, &mut problems)?;
+    if si {
+        let wind = environment.si_wind_from_option(wind, &mut problems)?;
+        let atmosphere = environment.si_atmosphere_from_option(atmosphere, &mut problems)?;
+        let inputs = si::inputs_from_hash(kw, &[], &mut problems)?;
+        problems.into_result(ruby)?;
+        return Solver::from_ballistic_inputs(inputs).solve_with(ruby, wind, atmosphere, OutputUnits::si(), options);
     }
 
-    result_hash.aset("points", points)?;
+    // Nested wind/atmosphere hashes are keyword-checked the same way
+    let wind = environment.wind_field_from_option(wind, &mut problems)?;
+    let atmosphere = environment.atmosphere_from_option(atmosphere, &mut problems)?;
+    let units = OutputUnits::from_option(units, &mut problems)?;
+    let inputs = ImperialInputs::from_kwargs(kw, &mut problems)?;
+    problems.into_result(ruby)?;
+
+    Solver::from_inputs(inputs).solve_with(ruby, wind, atmosphere.to_conditions(), units, options)
+}
+
+/// BallisticsEngine.range_card(inputs, wind = nil, atmosphere = nil, start:, stop:, step:)
+///
+/// Takes the same inputs as `solve`; the trajectory is solved out to `stop` yards.
+fn range_card(ruby: &magnus::Ruby, args: &[Value]) -> Result<RArray, Error> {
+    let args = scan_args::<(Value,), (Option<&Wind>, Option<&Atmosphere>), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let (wind_arg, atmosphere_arg) = args.optional;
+    let mut solve_args = read_solve_values(inputs, wind_arg, atmosphere_arg)?;
+    let card = result::RangeCard::from_kwargs(ruby, args.keywords, &mut solve_args.problems)?;
+    solve_args.problems.into_result(ruby)?;
+
+    let options = SolveOptions { max_range_m: Some(card.max_range_m()), output_step: None, ..solve_args.options };
+    let result = solve_args.solver.solve_with(ruby, solve_args.wind, solve_args.atmosphere, solve_args.units, options)?;
+    result.range_card(ruby, card)
+}
+
+/// BallisticsEngine.max_range(inputs, wind = nil, atmosphere = nil)
+///
+/// Takes the same inputs as `solve`, zero aside; searches the launch angle for
+/// the longest range to the muzzle's height and solves the trajectory there.
+fn max_range(ruby: &magnus::Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = read_solve_args(args)?;
+    args.problems.into_result(ruby)?;
 
-    Ok(result_hash)
+    let launch_angle = args.solver.max_range_angle(ruby, &args.wind, &args.atmosphere, args.options)?;
+    let options = SolveOptions { launch_angle: Some(launch_angle), max_range_m: None, max_time_s: None, stop_velocity_mps: None, stop_energy_j: None, target_range_m: None, ..args.options };
+    let result = args.solver.solve_with(ruby, args.wind, args.atmosphere, args.units, options)?;
+    TrajectoryResult::max_range_hash(ruby, result, launch_angle)
+}
+
+/// BallisticsEngine.zero_angle(inputs, wind = nil, atmosphere = nil)
+///
+/// Takes the same inputs as `solve`; the bore elevation that zeroes them, in
+/// still air, without solving the trajectory.
+fn zero_angle(ruby: &magnus::Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = read_solve_args(args)?;
+    args.problems.into_result(ruby)?;
+
+    let angle = args.solver.bore_angle(ruby, &args.atmosphere, &args.options)?;
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("zero_distance_yards"), args.solver.zero_distance_m() / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("angle_degrees"), angle / DEGREES_TO_RADIANS)?;
+    hash.aset(ruby.to_symbol("angle_moa"), angle * RADIANS_TO_MOA)?;
+    hash.aset(ruby.to_symbol("angle_mil"), angle * RADIANS_TO_MILS)?;
+    Ok(hash)
+}
+
+/// BallisticsEngine.try_solve(inputs, wind = nil, atmosphere = nil) => [result, errors]
+///
+/// `solve` without raising: `[result, []]` on success, `[nil, messages]` when
+/// the inputs are invalid (one message per problem) or the engine fails.
+/// Errors other than `BallisticsEngine::Error` are still raised.
+fn try_solve(ruby: &magnus::Ruby, args: &[Value]) -> Result<(Option<Obj<TrajectoryResult>>, Vec<String>), Error> {
+    let solved = read_solve_args(args).and_then(|args| {
+        if !args.problems.is_empty() {
+            return Ok(Err(args.problems.messages()));
+        }
+        args.solver.solve_with(ruby, args.wind, args.atmosphere, args.units, args.options).map(Ok)
+    });
+    match solved {
+        Ok(Ok(result)) => Ok((Some(result), Vec::new())),
+        Ok(Err(messages)) => Ok((None, messages)),
+        Err(e) if e.is_kind_of(error::base_error(ruby)) => Ok((None, vec![error::message(&e)])),
+        Err(e) => Err(e),
+    }
+}
+
+/// List every problem with the inputs without solving (an empty array means valid)
+fn validate(args: &[Value]) -> Result<Vec<String>, Error> {
+    Ok(read_solve_args(args)?.problems.messages())
 }
 
 #[magnus::init]
 fn init(ruby: &magnus::Ruby) -> Result<(), Error> {
+    // Methods only touch their own receiver, arguments and immutable engine data,
+    // so they may be called from any Ractor
+    unsafe { rb_sys::rb_ext_ractor_safe(true) };
+
     let module = ruby.define_module("BallisticsEngine")?;
-    module.define_module_function("solve", function!(solve_trajectory, 1))?;
+    error::init(ruby, module)?;
+    config::init(ruby, module)?;
+    environment::init(module)?;
+    capabilities::init(module)?;
+    cdm::init(module)?;
+    kestrel::init(module)?;
+    metar::init(module)?;
+    bc::init(module)?;
+    mcdrag::init(module)?;
+    bullets::init(module)?;
+    fit::init(module)?;
+    point_blank::init(module)?;
+    incline::init(module)?;
+    lead::init(module)?;
+    firing_solution::init(module)?;
+    stability::init(module)?;
+
+    module.define_module_function("solve", function!(solve_trajectory, -1))?;
+    module.define_module_function("solve!", function!(solve_trajectory, -1))?;
+    module.define_module_function("try_solve", function!(try_solve, -1))?;
+    module.define_module_function("solve_kw", function!(solve_trajectory_kw, -1))?;
+    module.define_module_function("validate", function!(validate, -1))?;
+    module.define_module_function("range_card", function!(range_card, -1))?;
+    module.define_module_function("max_range", function!(max_range, -1))?;
+    module.define_module_function("zero_angle", function!(zero_angle, -1))?;
+
+    projectile::init(ruby, module)?;
+    wind::init(ruby, module)?;
+    atmosphere::init(ruby, module)?;
+    result::init(ruby, module)?;
+    point::init(ruby, module)?;
+    solver::init(ruby, module)?;
+    v2::init(module)?;
     Ok(())
 }
@@ -0,0 +1,210 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::{get_kwargs, scan_args}};
+use ballistics_engine::DragModel;
+use ballistics_engine::drag::get_drag_coefficient;
+
+use crate::bc;
+use crate::error;
+use crate::metric::{self, Alternative, alternative};
+use crate::projectile;
+use crate::units::*;
+use crate::validation::Problems;
+
+// Drag estimated from a bullet's shape, for wildcat and cast bullets with no
+// published BC. The drag is built up from components the way McCoy's McDrag
+// does, in calibers and against the standard sea-level atmosphere:
+//
+//   Cd = skin friction + meplat + nose wave + boat-tail + base
+//
+// It is an estimate: expect the form factor to be within about 10% of a
+// measured one for ordinary spitzer shapes, and worse for unusual ones.
+
+/// Keys in millimetres or grams accepted in place of the inch and grain ones
+const GEOMETRY: &[Alternative] = &[
+    alternative("bullet_diameter_mm", "bullet_diameter_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("bullet_length_mm", "bullet_length_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("nose_length_mm", "nose_length_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("meplat_diameter_mm", "meplat_diameter_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("boat_tail_length_mm", "boat_tail_length_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("bullet_weight_grams", "bullet_weight_grains", |grams| grams / 1000.0 / GRAINS_TO_KG),
+];
+
+/// Mach numbers of the estimated curve: 0.1 to 5.0 in steps of 0.05
+const CURVE_MACH_STEP: f64 = 0.05;
+const CURVE_POINTS: usize = 99;
+/// The form factor is the mean drag ratio over this Mach range, where rifle
+/// bullets spend most of their flight
+const FORM_FACTOR_MACH: (f64, f64) = (1.2, 3.0);
+
+/// Wave drag starts to build from this Mach number and is fully formed at Mach 1
+const DRAG_RISE_MACH: f64 = 0.9;
+/// Share of the stagnation pressure acting on a supersonic meplat
+const MEPLAT_PRESSURE: f64 = 0.85;
+
+/// Bullet shape in calibers
+#[derive(Clone, Copy)]
+struct Geometry {
+    /// Bullet diameter, mm (for the Reynolds number)
+    diameter_mm: f64,
+    length: f64,
+    nose_length: f64,
+    meplat_diameter: f64,
+    boat_tail_length: f64,
+    /// Boat-tail half-angle, radians
+    boat_tail_angle: f64,
+    /// Tangent ogive radius over the actual one: 1 for a tangent ogive, towards 0 for a cone
+    ogive_radius_ratio: f64,
+}
+
+impl Geometry {
+    /// Base diameter left by the boat-tail
+    fn base_diameter(self) -> f64 {
+        1.0 - 2.0 * self.boat_tail_length * self.boat_tail_angle.tan()
+    }
+
+    /// Drag coefficient at `mach`, referenced to the bullet's cross-section
+    fn drag_coefficient(self, mach: f64) -> f64 {
+        let m2 = mach * mach;
+        let base = self.base_diameter();
+        // Linear supersonic theory, held near Mach 1 where it does not apply
+        let beta = (m2 - 1.0).max(1.0).sqrt();
+        let drag_rise = ((mach - DRAG_RISE_MACH) / (1.0 - DRAG_RISE_MACH)).clamp(0.0, 1.0);
+
+        // Turbulent flat-plate friction over the nose and body
+        let reynolds = 23296.3 * mach * self.length * self.diameter_mm;
+        let friction = 0.455 / reynolds.log10().powf(2.58) * (1.0 + 0.21 * m2).powf(-0.32);
+        let nose_area = std::f64::consts::FRAC_PI_2 * self.nose_length * (1.0 + 1.0 / (8.0 * self.nose_length.powi(2)));
+        let body_area = std::f64::consts::PI * (self.length - self.nose_length);
+        let skin_friction = 4.0 / std::f64::consts::PI * friction * (nose_area + body_area);
+
+        // Stagnation pressure on the meplat: isentropic below Mach 1, behind a normal shock above
+        let pitot = if mach <= 1.0 {
+            (1.0 + 0.2 * m2).powf(3.5)
+        } else {
+            (1.2 * m2).powf(3.5) * (6.0 / (7.0 * m2 - 1.0)).powf(2.5)
+        };
+        let stagnation = 1.122 * (pitot - 1.0) * self.meplat_diameter.powi(2) / m2;
+        let meplat = match mach {
+            mach if mach <= 0.91 => 0.0,
+            mach if mach >= 1.41 => MEPLAT_PRESSURE * stagnation,
+            _ => (0.254 + 2.88 * (m2 - 1.0) / (2.4 * m2)) * stagnation,
+        };
+
+        // Cone wave drag at the nose's mean half-angle, less for an ogive
+        let half_angle = ((1.0 - self.meplat_diameter) / (2.0 * self.nose_length)).atan().to_degrees();
+        let ratio = self.ogive_radius_ratio;
+        let ogive = 1.0 - 1.2 * ratio + 0.85 * ratio * ratio;
+        let nose_wave = drag_rise * (0.083 + 0.096 / m2.max(1.0)) * (half_angle / 10.0).powf(1.69) * ogive;
+
+        // Suction on the boat-tail's rearward-facing surface
+        let boat_tail = drag_rise * 2.0 * self.boat_tail_angle / beta * (1.0 - base * base);
+
+        // Base pressure, raised by the boat-tail and body length
+        let pressure = if mach < 1.0 {
+            1.0 / (1.0 + 0.1875 * m2 + 0.0531 * m2 * m2)
+        } else {
+            1.0 / (1.0 + 0.2477 * m2 + 0.0345 * m2 * m2)
+        };
+        let recovery = (1.0 + 0.09 * m2 * (1.0 - (self.nose_length - self.length).exp())) * (1.0 + 0.25 * m2 * (1.0 - base));
+        let base_drag = 1.4286 * (1.0 - pressure * recovery).max(0.0) * base * base / m2;
+
+        skin_friction + meplat + nose_wave + boat_tail + base_drag
+    }
+
+    /// `[[mach, cd], ...]` from Mach 0.1 to 5
+    fn curve(self) -> Vec<(f64, f64)> {
+        (0..CURVE_POINTS)
+            .map(|index| {
+                let mach = 0.1 + CURVE_MACH_STEP * index as f64;
+                (mach, self.drag_coefficient(mach))
+            })
+            .collect()
+    }
+
+    /// Mean ratio of the estimated drag to `drag_model`'s over `FORM_FACTOR_MACH`
+    fn form_factor(self, drag_model: &DragModel) -> f64 {
+        let ratios = self
+            .curve()
+            .into_iter()
+            .filter(|(mach, _)| (FORM_FACTOR_MACH.0..=FORM_FACTOR_MACH.1).contains(mach))
+            .map(|(mach, cd)| cd / get_drag_coefficient(mach, drag_model))
+            .collect::<Vec<_>>();
+        ratios.iter().sum::<f64>() / ratios.len() as f64
+    }
+}
+
+/// BallisticsEngine.estimate_drag(bullet_diameter_inches:, bullet_length_inches:, nose_length_inches:, ...)
+///
+/// `{drag_curve: [[mach, cd], ...], drag_model:, form_factor:}` for the
+/// bullet's shape, with `bc:` too when `bullet_weight_grains` is given.
+/// `meplat_diameter_inches`, `boat_tail_length_inches` and
+/// `boat_tail_angle_deg` default to 0 (a pointed, flat-based bullet) and
+/// `ogive_radius_ratio` to 1 (a tangent ogive).
+fn estimate_drag(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let mut problems = Problems::default();
+    let kw = metric::to_imperial(args.keywords, GEOMETRY, "", &mut problems)?;
+    type Optional = (Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>);
+    let kwargs = get_kwargs::<_, (Value, Value, Value), Optional, ()>(
+        kw,
+        &["bullet_diameter_inches", "bullet_length_inches", "nose_length_inches"],
+        &[
+            "meplat_diameter_inches",
+            "boat_tail_length_inches",
+            "boat_tail_angle_deg",
+            "ogive_radius_ratio",
+            "drag_model",
+            "bullet_weight_grains",
+        ],
+    )
+    .map_err(|e| error::as_input_error(ruby, e))?;
+    let (diameter, length, nose_length) = kwargs.required;
+    let (meplat, boat_tail_length, boat_tail_angle, ogive_radius_ratio, drag_model, weight) = kwargs.optional;
+
+    let diameter: f64 = problems.required("bullet_diameter_inches", Some(diameter));
+    let length: f64 = problems.required("bullet_length_inches", Some(length));
+    let nose_length: f64 = problems.required("nose_length_inches", Some(nose_length));
+    let meplat: f64 = problems.optional("meplat_diameter_inches", meplat, 0.0);
+    let boat_tail_length: f64 = problems.optional("boat_tail_length_inches", boat_tail_length, 0.0);
+    let boat_tail_angle: f64 = problems.optional("boat_tail_angle_deg", boat_tail_angle, 0.0);
+    let ogive_radius_ratio: f64 = problems.optional("ogive_radius_ratio", ogive_radius_ratio, 1.0);
+    let drag_model = projectile::drag_model_or_default(problems.optional("drag_model", drag_model, None), &mut problems);
+    let weight = weight.filter(|weight| !weight.is_nil()).map(|weight| problems.required::<f64>("bullet_weight_grains", Some(weight)));
+    problems.check_positive("bullet_diameter_inches", diameter);
+    problems.check_positive("nose_length_inches", nose_length);
+    problems.check("bullet_length_inches", length >= nose_length + boat_tail_length, "must be at least the nose and boat-tail lengths");
+    problems.check("meplat_diameter_inches", (0.0..diameter).contains(&meplat), "must be at least 0 and less than the bullet diameter");
+    problems.check_non_negative("boat_tail_length_inches", boat_tail_length);
+    problems.check_range("boat_tail_angle_deg", boat_tail_angle, 0.0, 45.0);
+    problems.check_range("ogive_radius_ratio", ogive_radius_ratio, 0.0, 1.0);
+    if let Some(weight) = weight {
+        problems.check_positive("bullet_weight_grains", weight);
+    }
+
+    let geometry = Geometry {
+        diameter_mm: diameter * INCHES_TO_METERS * 1000.0,
+        length: length / diameter,
+        nose_length: nose_length / diameter,
+        meplat_diameter: meplat / diameter,
+        boat_tail_length: boat_tail_length / diameter,
+        boat_tail_angle: boat_tail_angle.to_radians(),
+        ogive_radius_ratio,
+    };
+    problems.check("boat_tail_angle_deg", geometry.base_diameter() > 0.0, "must leave a base at boat_tail_length_inches");
+    problems.into_result(ruby)?;
+
+    let form_factor = geometry.form_factor(&drag_model);
+    let hash = ruby.hash_new();
+    let curve = geometry.curve().into_iter().map(|(mach, cd)| vec![mach, cd]).collect::<Vec<_>>();
+    hash.aset(ruby.to_symbol("drag_curve"), curve)?;
+    hash.aset(ruby.to_symbol("drag_model"), drag_model.to_string())?;
+    hash.aset(ruby.to_symbol("form_factor"), form_factor)?;
+    if let Some(weight) = weight {
+        hash.aset(ruby.to_symbol("bc"), bc::sectional_density(weight, diameter) / form_factor)?;
+    }
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("estimate_drag", function!(estimate_drag, -1))?;
+    Ok(())
+}
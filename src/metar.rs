@@ -0,0 +1,244 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::{get_kwargs, scan_args}};
+
+use crate::error;
+use crate::units::*;
+use crate::validation::Problems;
+
+// METAR weather reports, e.g. `KMSP 121753Z 18008KT 10SM FEW250 24/12 A3002`.
+// `BallisticsEngine.atmosphere_from_metar` reads the wind, temperature and dew
+// point, and altimeter groups into the `atmosphere` and `wind` hashes the
+// solvers take; the other groups (visibility, weather, clouds) are skipped.
+
+/// Knots to mph
+const KNOTS_TO_MPH: f64 = 1.150_779;
+
+/// The groups of a report the solvers use
+#[derive(Default)]
+struct Report {
+    /// Compass direction the wind blows from, degrees true; None when variable
+    wind_from_degrees: Option<f64>,
+    wind_speed_mph: Option<f64>,
+    /// Half the spread of a `dddVddd` variable direction group, degrees
+    wind_variation_degrees: Option<f64>,
+    temperature_c: Option<f64>,
+    dew_point_c: Option<f64>,
+    altimeter_inhg: Option<f64>,
+}
+
+/// `24` or `M05` in °C
+fn celsius(group: &str) -> Option<f64> {
+    let (sign, digits) = group.strip_prefix('M').map_or((1.0, group), |digits| (-1.0, digits));
+    digits.parse::<f64>().ok().filter(|_| digits.len() == 2).map(|value| sign * value)
+}
+
+/// `18008KT`, `18008G15KT`, `VRB03MPS`: `(from_degrees, speed_mph)`, the gust ignored
+fn wind(group: &str) -> Option<(Option<f64>, f64)> {
+    let (group, to_mph) = [("KT", KNOTS_TO_MPH), ("MPS", 1.0 / MPH_TO_MPS), ("KMH", 1.0 / 3.6 / MPH_TO_MPS)]
+        .into_iter()
+        .find_map(|(unit, to_mph)| group.strip_suffix(unit).map(|group| (group, to_mph)))?;
+    let group = group.split_once('G').map_or(group, |(sustained, _)| sustained);
+    let (direction, speed) = (group.get(..3)?, group.get(3..)?);
+    let from = match direction {
+        "VRB" => None,
+        _ => Some(direction.parse::<f64>().ok()?),
+    };
+    let speed = speed.parse::<f64>().ok().filter(|_| (2..=3).contains(&speed.len()))?;
+    Some((from, speed * to_mph))
+}
+
+/// `180V240`: half the spread of the direction, degrees
+fn variation(group: &str) -> Option<f64> {
+    let (from, to) = group.split_once('V')?;
+    let (from, to) = (from.parse::<f64>().ok().filter(|_| from.len() == 3)?, to.parse::<f64>().ok().filter(|_| to.len() == 3)?);
+    Some((to - from).rem_euclid(360.0) / 2.0)
+}
+
+/// `T02440122` in the remarks: temperature and dew point to a tenth of a °C
+fn precise_temperatures(group: &str) -> Option<(f64, Option<f64>)> {
+    let digits = group.strip_prefix('T').filter(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))?;
+    let tenths = |reading: &str| {
+        let value = reading[1..].parse::<f64>().ok()? / 10.0;
+        match &reading[..1] {
+            "0" => Some(value),
+            "1" => Some(-value),
+            _ => None,
+        }
+    };
+    match digits.len() {
+        4 => Some((tenths(digits)?, None)),
+        8 => Some((tenths(&digits[..4])?, Some(tenths(&digits[4..])?))),
+        _ => None,
+    }
+}
+
+/// Read the groups of a report, recording one with neither a temperature nor an altimeter group
+fn parse(text: &str, problems: &mut Problems) -> Report {
+    let mut report = Report::default();
+    let mut groups = text.split_whitespace().map(|group| group.trim_end_matches('='));
+    for group in groups.by_ref() {
+        if group == "RMK" {
+            break;
+        }
+        if let Some((from, speed)) = wind(group) {
+            (report.wind_from_degrees, report.wind_speed_mph) = (from, Some(speed));
+        } else if let Some(half_width) = variation(group) {
+            report.wind_variation_degrees = Some(half_width);
+        } else if let Some((temperature, dew_point)) = group.split_once('/').filter(|_| report.temperature_c.is_none()) {
+            // Visibility in statute miles (`1/2SM`) has the slash too, but not °C on both sides
+            if let Some(temperature) = celsius(temperature) {
+                report.temperature_c = Some(temperature);
+                report.dew_point_c = celsius(dew_point);
+            }
+        } else if let Some(hundredths) = group.strip_prefix('A').filter(|digits| digits.len() == 4).and_then(|digits| digits.parse::<f64>().ok()) {
+            report.altimeter_inhg = Some(hundredths / 100.0);
+        } else if let Some(hpa) = group.strip_prefix('Q').filter(|digits| digits.len() == 4).and_then(|digits| digits.parse::<f64>().ok()) {
+            report.altimeter_inhg = Some(hpa / INHG_TO_HPA);
+        }
+    }
+    if let Some((temperature, dew_point)) = groups.find_map(precise_temperatures) {
+        report.temperature_c = Some(temperature);
+        report.dew_point_c = dew_point.or(report.dew_point_c);
+    }
+    if report.temperature_c.is_none() && report.altimeter_inhg.is_none() {
+        problems.add("metar", format!("metar report {:?} has no temperature or altimeter group", text.trim()));
+    }
+    report
+}
+
+impl Report {
+    /// The `atmosphere` hash: the altimeter setting as a sea-level pressure
+    /// (`pressure_is_absolute: false`) at `altitude_feet`, and the dew point
+    /// in place of the humidity
+    fn atmosphere(&self, ruby: &Ruby, altitude_feet: Option<f64>) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        let set = |key: &str, value: Option<f64>| value.map_or(Ok(()), |value| hash.aset(ruby.to_symbol(key), value));
+        set("temperature_f", self.temperature_c.map(|celsius| celsius * 9.0 / 5.0 + 32.0))?;
+        set("altitude_feet", altitude_feet)?;
+        if let Some(altimeter) = self.altimeter_inhg {
+            set("pressure_inhg", Some(altimeter))?;
+            hash.aset(ruby.to_symbol("pressure_is_absolute"), false)?;
+        }
+        set("dew_point_f", self.dew_point_c.map(|celsius| celsius * 9.0 / 5.0 + 32.0))?;
+        Ok(hash)
+    }
+
+    /// The wind's `direction_degrees`, where it blows from clockwise from
+    /// the line of fire, from its compass direction and
+    /// `shot_direction_degrees`; None without both, unless the wind is calm
+    fn direction_degrees(&self, shot_direction_degrees: Option<f64>) -> Option<f64> {
+        match (self.wind_from_degrees, shot_direction_degrees) {
+            _ if self.wind_speed_mph == Some(0.0) => Some(0.0),
+            (Some(from), Some(shot)) => Some((from - shot).rem_euclid(360.0)),
+            _ => None,
+        }
+    }
+
+    /// The `wind` hash, None without a speed and `direction_degrees`
+    fn wind(&self, ruby: &Ruby, shot_direction_degrees: Option<f64>) -> Result<Option<RHash>, Error> {
+        let (Some(speed_mph), Some(direction_degrees)) = (self.wind_speed_mph, self.direction_degrees(shot_direction_degrees)) else {
+            return Ok(None);
+        };
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("speed_mph"), speed_mph)?;
+        hash.aset(ruby.to_symbol("direction_degrees"), direction_degrees)?;
+        if let Some(variation) = self.wind_variation_degrees.filter(|_| speed_mph > 0.0) {
+            hash.aset(ruby.to_symbol("direction_uncertainty_degrees"), variation)?;
+        }
+        Ok(Some(hash))
+    }
+}
+
+/// BallisticsEngine.atmosphere_from_metar(report, shot_direction_degrees: nil, altitude_feet: nil)
+///
+/// `{atmosphere:, wind:}` for the report; `wind` is nil when the report's
+/// wind is variable or `shot_direction_degrees` is not given.
+fn atmosphere_from_metar(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(String,), (), (), (), RHash, ()>(args)?;
+    let kwargs = get_kwargs::<_, (), (Option<Value>, Option<Value>), ()>(args.keywords, &[], &["shot_direction_degrees", "altitude_feet"])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+
+    let mut problems = Problems::default();
+    let number = |key: &'static str, value: Option<Value>, problems: &mut Problems| {
+        value.filter(|value| !value.is_nil()).map(|value| problems.required::<f64>(key, Some(value)))
+    };
+    let shot_direction_degrees = number("shot_direction_degrees", kwargs.optional.0, &mut problems);
+    let altitude_feet = number("altitude_feet", kwargs.optional.1, &mut problems);
+    let report = parse(&args.required.0, &mut problems);
+    problems.into_result(ruby)?;
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("atmosphere"), report.atmosphere(ruby, altitude_feet)?)?;
+    hash.aset(ruby.to_symbol("wind"), report.wind(ruby, shot_direction_degrees)?)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("atmosphere_from_metar", function!(atmosphere_from_metar, -1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind::tests::{at_400_m, solved_in};
+    use crate::wind::ImperialWind;
+
+    fn read(text: &str) -> (Report, Problems) {
+        let mut problems = Problems::default();
+        (parse(text, &mut problems), problems)
+    }
+
+    #[test]
+    fn reads_an_inhg_altimeter_report() {
+        let (report, problems) = read("KMSP 121753Z 18008KT 10SM FEW250 24/12 A2992");
+        assert!(problems.is_empty());
+        assert_eq!(report.wind_from_degrees, Some(180.0));
+        assert!((report.wind_speed_mph.unwrap() - 8.0 * KNOTS_TO_MPH).abs() < 1e-9);
+        assert_eq!((report.temperature_c, report.dew_point_c), (Some(24.0), Some(12.0)));
+        assert!((report.altimeter_inhg.unwrap() - 29.92).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reads_a_hpa_altimeter_report_below_freezing() {
+        let (report, problems) = read("EGLL 121750Z VRB03MPS 1/2SM M05/M08 Q1013=");
+        assert!(problems.is_empty());
+        assert_eq!(report.wind_from_degrees, None);
+        assert!((report.wind_speed_mph.unwrap() - 3.0 / MPH_TO_MPS).abs() < 1e-9);
+        assert_eq!((report.temperature_c, report.dew_point_c), (Some(-5.0), Some(-8.0)));
+        assert!((report.altimeter_inhg.unwrap() - 1013.0 / INHG_TO_HPA).abs() < 1e-9);
+    }
+
+    #[test]
+    fn prefers_the_remarks_temperatures() {
+        let (report, _) = read("KMSP 121753Z 18008KT 24/12 A3002 RMK AO2 T02440122");
+        assert_eq!((report.temperature_c, report.dew_point_c), (Some(24.4), Some(12.2)));
+        assert_eq!(precise_temperatures("T1005"), Some((-0.5, None)));
+    }
+
+    #[test]
+    fn reads_gusts_and_variable_directions() {
+        let (from, speed) = wind("27015G25KT").unwrap();
+        assert_eq!(from, Some(270.0));
+        assert!((speed - 15.0 * KNOTS_TO_MPH).abs() < 1e-9);
+        assert_eq!(variation("150V210"), Some(30.0));
+        assert_eq!(variation("330V030"), Some(30.0));
+        assert_eq!(wind("10SM"), None);
+    }
+
+    #[test]
+    fn a_west_wind_on_a_northward_shot_drifts_the_bullet_right() {
+        let (report, _) = read("KMSP 121753Z 27010KT 10SM 24/12 A2992");
+        let direction_degrees = report.direction_degrees(Some(0.0)).unwrap();
+        assert_eq!(direction_degrees, 270.0);
+        let wind = ImperialWind { speed_mph: report.wind_speed_mph.unwrap(), direction_degrees, ..ImperialWind::default() };
+        let (drift, _) = at_400_m(&solved_in(&wind.to_conditions()));
+        assert!(drift > 0.1, "drifted {} m", drift);
+        assert_eq!(report.direction_degrees(None), None);
+    }
+
+    #[test]
+    fn records_a_report_without_temperature_or_altimeter() {
+        let (_, problems) = read("KMSP 121753Z 18008KT 10SM FEW250");
+        assert_eq!(problems.messages().len(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use magnus::{Error, RHash, Ruby, Symbol, Value, prelude::*};
+
+use crate::coerce::Coerce;
+use crate::units::*;
+use crate::validation::{Problems, invalid_message};
+
+/// A metric key accepted in place of an imperial one
+pub struct Alternative {
+    pub metric: &'static str,
+    pub imperial: &'static str,
+    pub to_imperial: fn(f64) -> f64,
+}
+
+pub const fn alternative(metric: &'static str, imperial: &'static str, to_imperial: fn(f64) -> f64) -> Alternative {
+    Alternative { metric, imperial, to_imperial }
+}
+
+pub const PROJECTILE: &[Alternative] = &[
+    alternative("bullet_weight_grams", "bullet_weight_grains", |grams| grams / 1000.0 / GRAINS_TO_KG),
+    alternative("muzzle_velocity_mps", "muzzle_velocity_fps", |mps| mps / FPS_TO_MPS),
+    alternative("bullet_diameter_mm", "bullet_diameter_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("bullet_length_mm", "bullet_length_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("sight_height_mm", "sight_height_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("zero_distance_meters", "zero_distance_yards", |meters| meters / YARDS_TO_METERS),
+    alternative("twist_rate_mm", "twist_rate_inches", |mm| mm / 1000.0 / INCHES_TO_METERS),
+    alternative("powder_temp_c", "powder_temp_f", |celsius| celsius * 9.0 / 5.0 + 32.0),
+    alternative("powder_reference_temp_c", "powder_reference_temp_f", |celsius| celsius * 9.0 / 5.0 + 32.0),
+];
+
+pub const WIND: &[Alternative] = &[
+    alternative("speed_mps", "speed_mph", |mps| mps / MPH_TO_MPS),
+    alternative("speed_kph", "speed_mph", |kph| kph / 3.6 / MPH_TO_MPS),
+    alternative("vertical_speed_mps", "vertical_speed_mph", |mps| mps / MPH_TO_MPS),
+    alternative("vertical_speed_kph", "vertical_speed_mph", |kph| kph / 3.6 / MPH_TO_MPS),
+    alternative("roughness_length_meters", "roughness_length_feet", |meters| meters / FEET_TO_METERS),
+    alternative("reference_height_meters", "reference_height_feet", |meters| meters / FEET_TO_METERS),
+    alternative("muzzle_height_meters", "muzzle_height_feet", |meters| meters / FEET_TO_METERS),
+    alternative("speed_uncertainty_mps", "speed_uncertainty_mph", |mps| mps / MPH_TO_MPS),
+    alternative("speed_uncertainty_kph", "speed_uncertainty_mph", |kph| kph / 3.6 / MPH_TO_MPS),
+    alternative("from_meters", "from_yards", |meters| meters / YARDS_TO_METERS),
+    alternative("to_meters", "to_yards", |meters| meters / YARDS_TO_METERS),
+];
+
+pub const ATMOSPHERE: &[Alternative] = &[
+    alternative("temperature_c", "temperature_f", |celsius| celsius * 9.0 / 5.0 + 32.0),
+    alternative("pressure_hpa", "pressure_inhg", |hpa| hpa / INHG_TO_HPA),
+    alternative("dew_point_c", "dew_point_f", |celsius| celsius * 9.0 / 5.0 + 32.0),
+    alternative("altitude_meters", "altitude_feet", |meters| meters / FEET_TO_METERS),
+    alternative("density_altitude_meters", "density_altitude_feet", |meters| meters / FEET_TO_METERS),
+    alternative("speed_of_sound_mps", "speed_of_sound_fps", |mps| mps / FPS_TO_MPS),
+];
+
+/// Copy `hash`, replacing metric keys by their imperial equivalents
+///
+/// String and Symbol keys are both handled and keep their kind. Giving both
+/// the metric and imperial key, or an unconvertible metric value, is recorded
+/// as a problem under `prefix` + key. The result is always a new hash, so the
+/// keyword readers can pass it to `get_kwargs` without touching the caller's.
+pub fn to_imperial(hash: RHash, alternatives: &[Alternative], prefix: &str, problems: &mut Problems) -> Result<RHash, Error> {
+    let ruby = Ruby::get_with(hash);
+    let entries = hash.to_vec::<Value, Value>()?;
+    let names = entries
+        .iter()
+        .map(|(key, _)| key.funcall::<_, _, String>("to_s", ()))
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    let converted = ruby.hash_new();
+    for ((key, value), name) in entries.into_iter().zip(&names) {
+        let Some(alternative) = alternatives.iter().find(|alternative| alternative.metric == name.as_str()) else {
+            converted.aset(key, value)?;
+            continue;
+        };
+
+        let field = format!("{}{}", prefix, alternative.metric);
+        if names.iter().any(|name| name == alternative.imperial) {
+            problems.add(&field, format!("{} and {}{} cannot both be given", field, prefix, alternative.imperial));
+            continue;
+        }
+        if value.is_nil() {
+            continue;
+        }
+        match f64::coerce(value) {
+            Ok(metric) => {
+                let imperial_key = if Symbol::from_value(key).is_some() {
+                    ruby.to_symbol(alternative.imperial).as_value()
+                } else {
+                    ruby.str_new(alternative.imperial).as_value()
+                };
+                converted.aset(imperial_key, (alternative.to_imperial)(metric))?;
+            }
+            Err(reason) => problems.add(&field, invalid_message(&field, &reason)),
+        }
+    }
+    Ok(converted)
+}
@@ -0,0 +1,238 @@
+use magnus::{Error, RHash, Ruby, Value, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::units::*;
+use crate::validation::Problems;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Length {
+    Yards,
+    Feet,
+    Inches,
+    Meters,
+    Centimeters,
+    Millimeters,
+}
+
+impl Length {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "yd" | "yards" => Some(Self::Yards),
+            "ft" | "feet" => Some(Self::Feet),
+            "in" | "inches" => Some(Self::Inches),
+            "m" | "meters" => Some(Self::Meters),
+            "cm" => Some(Self::Centimeters),
+            "mm" => Some(Self::Millimeters),
+            _ => None,
+        }
+    }
+
+    /// Convert from yards
+    pub fn convert_yards(self, yards: f64) -> f64 {
+        let meters = yards * YARDS_TO_METERS;
+        match self {
+            Self::Yards => yards,
+            Self::Feet => meters / FEET_TO_METERS,
+            Self::Inches => meters / INCHES_TO_METERS,
+            Self::Meters => meters,
+            Self::Centimeters => meters * 100.0,
+            Self::Millimeters => meters * 1000.0,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Yards => "yards",
+            Self::Feet => "feet",
+            Self::Inches => "inches",
+            Self::Meters => "meters",
+            Self::Centimeters => "cm",
+            Self::Millimeters => "mm",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Velocity {
+    Fps,
+    Mps,
+}
+
+impl Velocity {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fps" => Some(Self::Fps),
+            "mps" => Some(Self::Mps),
+            _ => None,
+        }
+    }
+
+    /// Convert from feet per second
+    pub fn convert_fps(self, fps: f64) -> f64 {
+        match self {
+            Self::Fps => fps,
+            Self::Mps => fps * FPS_TO_MPS,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Fps => "fps",
+            Self::Mps => "mps",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Energy {
+    FootPounds,
+    Joules,
+}
+
+impl Energy {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ftlbs" => Some(Self::FootPounds),
+            "j" | "joules" => Some(Self::Joules),
+            _ => None,
+        }
+    }
+
+    /// Convert from foot-pounds
+    pub fn convert_ftlbs(self, ftlbs: f64) -> f64 {
+        match self {
+            Self::FootPounds => ftlbs,
+            Self::Joules => ftlbs / JOULES_TO_FTLBS,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::FootPounds => "ftlbs",
+            Self::Joules => "joules",
+        }
+    }
+}
+
+/// Angular unit for the optional per-point `drop_*`/`windage_*` hash entries
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Angle {
+    Moa,
+    Mil,
+}
+
+impl Angle {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "moa" => Some(Self::Moa),
+            "mil" | "mrad" => Some(Self::Mil),
+            _ => None,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Moa => "moa",
+            Self::Mil => "mil",
+        }
+    }
+}
+
+/// Unit names accepted by `OutputUnits::from_names`, by kind
+pub const UNIT_NAMES: &[(&str, &[&str])] = &[
+    ("distance", &["yd", "yards", "ft", "feet", "in", "inches", "m", "meters", "cm", "mm"]),
+    ("drop", &["yd", "yards", "ft", "feet", "in", "inches", "m", "meters", "cm", "mm"]),
+    ("velocity", &["fps", "mps"]),
+    ("energy", &["ftlbs", "j", "joules"]),
+    ("angle", &["moa", "mil", "mrad"]),
+];
+
+/// Units used when building result hashes
+///
+/// `distance` covers downrange values and maximum height, `drop` covers the
+/// per-point `y`/`z` offsets. Unit-suffixed keys take the chosen unit's suffix.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutputUnits {
+    pub distance: Length,
+    pub drop: Length,
+    pub velocity: Velocity,
+    pub energy: Energy,
+    /// Adds angular drop and windage to each point when set
+    pub angle: Option<Angle>,
+    /// Report raw engine SI values instead, skipping the imperial conversions
+    pub si: bool,
+}
+
+impl Default for OutputUnits {
+    fn default() -> Self {
+        Self {
+            distance: Length::Yards,
+            drop: Length::Yards,
+            velocity: Velocity::Fps,
+            energy: Energy::FootPounds,
+            angle: None,
+            si: false,
+        }
+    }
+}
+
+impl OutputUnits {
+    pub fn si() -> Self {
+        Self { si: true, ..Self::default() }
+    }
+
+    /// Read `{distance:, drop:, velocity:, energy:, angle:}` (string or symbol keys and values)
+    ///
+    /// Kinds not given keep their configured unit.
+    pub fn from_hash(hash: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let names = hash
+            .to_vec::<Value, Value>()?
+            .into_iter()
+            .map(|(key, value)| Ok((key.funcall("to_s", ())?, value.funcall("to_s", ())?)))
+            .collect::<Result<Vec<(String, String)>, Error>>()?;
+        Ok(Self::from_names(names, problems))
+    }
+
+    /// `from_hash` for `(kind, unit)` pairs already converted to strings
+    pub fn from_names(names: impl IntoIterator<Item = (String, String)>, problems: &mut Problems) -> Self {
+        let mut units = config::defaults().output_units;
+        for (key, name) in names {
+            let field = format!("output_units.{}", key);
+            let known = match key.as_str() {
+                "distance" => Length::from_name(&name).map(|unit| units.distance = unit),
+                "drop" => Length::from_name(&name).map(|unit| units.drop = unit),
+                "velocity" => Velocity::from_name(&name).map(|unit| units.velocity = unit),
+                "energy" => Energy::from_name(&name).map(|unit| units.energy = unit),
+                "angle" => Angle::from_name(&name).map(|unit| units.angle = Some(unit)),
+                _ => {
+                    problems.add(&field, format!("{} is not a known unit kind (expected distance, drop, velocity, energy or angle)", field));
+                    continue;
+                }
+            };
+            if known.is_none() {
+                problems.add(&field, format!("{} has unknown unit {:?}", field, name));
+            }
+        }
+        units
+    }
+
+    /// Read an optional `output_units` value; nil means the configured units
+    pub fn from_option(value: Option<Value>, problems: &mut Problems) -> Result<Self, Error> {
+        match problems.optional::<Option<RHash>>("output_units", value, None) {
+            Some(hash) => Self::from_hash(hash, problems),
+            None => Ok(config::defaults().output_units),
+        }
+    }
+
+    /// Symbol-keyed hash accepted by `from_hash`
+    pub fn to_h(self, ruby: &Ruby) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("distance"), self.distance.suffix())?;
+        hash.aset(ruby.to_symbol("drop"), self.drop.suffix())?;
+        hash.aset(ruby.to_symbol("velocity"), self.velocity.suffix())?;
+        hash.aset(ruby.to_symbol("energy"), self.energy.suffix())?;
+        hash.aset(ruby.to_symbol("angle"), self.angle.map(Angle::suffix))?;
+        Ok(hash)
+    }
+}
@@ -0,0 +1,116 @@
+use ballistics_engine::{AtmosphericConditions, BallisticInputs};
+
+use crate::analytic::{self, Drag, FlatFire};
+use crate::units::*;
+
+// Pejsa's closed-form flat-fire solution, for `solver: "pejsa"`. Pejsa takes
+// the retardation distance F = v / |dv/dx| as a power of the velocity,
+// F = F0 · (v / V0)^N, which makes it fall linearly with range, F = F0 - N·x,
+// and gives the velocity, time and drop in closed form:
+//
+//   v(x) = V0 · (1 - k·x)^(1/N),  k = N / F0
+//
+// One N cannot follow a G table from the muzzle through the transonic region,
+// so the trajectory is split where the velocity has fallen by a tenth, and N
+// is fitted to the drag over each piece.
+
+/// Velocity ratio across each piece
+const PIECE_VELOCITY_RATIO: f64 = 0.9;
+/// Pieces stop below this velocity, fps; the drag tables' subsonic end
+const MIN_VELOCITY_FPS: f64 = 100.0;
+/// Smallest |N| kept apart from 0, where the power law becomes an exponential
+const MIN_EXPONENT: f64 = 1e-3;
+
+/// One fitted piece, from its starting state
+#[derive(Clone, Copy)]
+struct Piece {
+    /// Start of the piece, feet
+    range: f64,
+    /// Length, feet
+    length: f64,
+    velocity: f64,
+    time: f64,
+    /// Drop below the bore line (feet) and its slope
+    drop: f64,
+    slope: f64,
+    exponent: f64,
+    k: f64,
+}
+
+/// Piecewise Pejsa trajectory for one load and atmosphere
+pub struct Pejsa {
+    pieces: Vec<Piece>,
+}
+
+/// ∫₀ᵘ (1 - k·s)^e ds
+fn power_integral(k: f64, e: f64, u: f64) -> f64 {
+    if (e + 1.0).abs() < 1e-9 {
+        -(1.0 - k * u).ln() / k
+    } else {
+        (1.0 - (1.0 - k * u).powf(e + 1.0)) / (k * (e + 1.0))
+    }
+}
+
+/// ∫₀ᵘ ∫₀ˢ (1 - k·r)^e dr ds
+fn double_power_integral(k: f64, e: f64, u: f64) -> f64 {
+    if (e + 1.0).abs() < 1e-9 {
+        ((1.0 - k * u) * (1.0 - k * u).ln() + k * u) / (k * k)
+    } else {
+        (u - power_integral(k, e + 1.0, u)) / (k * (e + 1.0))
+    }
+}
+
+impl Piece {
+    /// `(velocity fps, time s, drop ft, drop slope)` a distance `u` feet into the piece
+    fn at(&self, u: f64) -> (f64, f64, f64, f64) {
+        let gravity = analytic::gravity_fps2();
+        let acceleration = gravity / self.velocity.powi(2);
+        let e = -2.0 / self.exponent;
+        (
+            self.velocity * (1.0 - self.k * u).powf(1.0 / self.exponent),
+            self.time + power_integral(self.k, -1.0 / self.exponent, u) / self.velocity,
+            self.drop + self.slope * u + acceleration * double_power_integral(self.k, e, u),
+            self.slope + acceleration * power_integral(self.k, e, u),
+        )
+    }
+}
+
+impl Pejsa {
+    /// Fit the pieces out to `max_range_m` for `inputs` already prepared by `drag::apply`
+    pub fn new(inputs: &BallisticInputs, atmosphere: &AtmosphericConditions, max_range_m: f64) -> Self {
+        let drag = Drag::new(inputs, atmosphere);
+        let max_range = max_range_m / FEET_TO_METERS;
+        let mut pieces = Vec::new();
+        let (mut range, mut velocity, mut time, mut drop, mut slope) = (0.0, inputs.muzzle_velocity / FPS_TO_MPS, 0.0, 0.0, 0.0);
+        while range < max_range && velocity > MIN_VELOCITY_FPS {
+            let end_velocity = velocity * PIECE_VELOCITY_RATIO;
+            let retardation = drag.retardation_ft(velocity);
+            let fitted = (drag.retardation_ft(end_velocity) / retardation).ln() / PIECE_VELOCITY_RATIO.ln();
+            let exponent = if fitted.abs() < MIN_EXPONENT { MIN_EXPONENT } else { fitted };
+            let k = exponent / retardation;
+            let piece = Piece {
+                range,
+                length: (1.0 - PIECE_VELOCITY_RATIO.powf(exponent)) / k,
+                velocity,
+                time,
+                drop,
+                slope,
+                exponent,
+                k,
+            };
+            (velocity, time, drop, slope) = piece.at(piece.length);
+            range += piece.length;
+            pieces.push(piece);
+        }
+        Self { pieces }
+    }
+}
+
+impl FlatFire for Pejsa {
+    fn at(&self, range_m: f64) -> Option<(f64, f64, f64)> {
+        let range = range_m / FEET_TO_METERS;
+        let piece = self.pieces.iter().find(|piece| range <= piece.range + piece.length)?;
+        let (velocity, time, drop, _) = piece.at(range - piece.range);
+        Some((velocity * FPS_TO_MPS, time, drop * FEET_TO_METERS))
+    }
+}
@@ -0,0 +1,348 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, method, prelude::*, typed_data::Obj};
+use ballistics_engine::TrajectoryPoint as EngineTrajectoryPoint;
+use serde::Serialize;
+
+use crate::output_units::{Angle, OutputUnits};
+use crate::spin::Spin;
+use crate::units::*;
+
+/// Single trajectory point in imperial units
+///
+/// `x` is downrange, `y` is height relative to the line of sight and `z` is
+/// lateral drift (positive to the right), including `spin_drift`.
+/// `wind_drift_min`/`wind_drift_max` bound `wind_drift` within the wind's
+/// uncertainty. JSON keys carry their units.
+/// Instances are frozen and can be shared between Ractors.
+#[derive(Clone, Copy, Serialize)]
+#[magnus::wrap(class = "BallisticsEngine::TrajectoryPoint", free_immediately, size, frozen_shareable)]
+pub struct TrajectoryPoint {
+    #[serde(rename = "time_s")]
+    time: f64,
+    #[serde(rename = "x_yards")]
+    x: f64,
+    #[serde(rename = "y_yards")]
+    y: f64,
+    #[serde(rename = "z_yards")]
+    z: f64,
+    velocity_fps: f64,
+    energy_ftlbs: f64,
+    #[serde(rename = "spin_drift_yards")]
+    spin_drift: f64,
+    #[serde(rename = "wind_drift_min_yards")]
+    wind_drift_min: f64,
+    #[serde(rename = "wind_drift_max_yards")]
+    wind_drift_max: f64,
+    spin_rpm: f64,
+    #[serde(rename = "yaw_of_repose_deg")]
+    yaw_of_repose: f64,
+    mach: f64,
+}
+
+/// Columns available to `TrajectoryResult#to_csv`
+pub const COLUMNS: &[&str] = &[
+    "time",
+    "x_yards",
+    "y_yards",
+    "z_yards",
+    "drop_in",
+    "windage_in",
+    "velocity_fps",
+    "energy_ftlbs",
+    "drop_moa",
+    "drop_mil",
+    "windage_moa",
+    "windage_mil",
+    "spin_drift_in",
+    "wind_drift_in",
+    "wind_drift_min_in",
+    "wind_drift_max_in",
+    "spin_rpm",
+    "yaw_of_repose_deg",
+    "mach",
+];
+
+impl TrajectoryPoint {
+    /// Convert an engine point (X lateral, Y vertical, Z downrange, SI units)
+    ///
+    /// The engine's lateral position already includes `spin_drift`, which is
+    /// Litz's or, with `(yaw radians, drift metres)` of repose, the modified
+    /// point-mass drift. Mach is against the atmosphere's speed of sound.
+    /// `(least, greatest)` metres of wind band offset the drift bounds.
+    pub fn from_engine(
+        point: &EngineTrajectoryPoint,
+        sight_height_m: f64,
+        speed_of_sound_mps: f64,
+        spin: Spin,
+        (repose_yaw, repose_drift): (f64, f64),
+        (band_least, band_greatest): (f64, f64),
+    ) -> Self {
+        let spin_drift_m = spin.drift_at(point.time) + repose_drift;
+        Self {
+            time: point.time,
+            x: point.position.z / YARDS_TO_METERS,
+            y: (point.position.y - sight_height_m) / YARDS_TO_METERS,
+            z: point.position.x / YARDS_TO_METERS,
+            velocity_fps: point.velocity_magnitude / FPS_TO_MPS,
+            energy_ftlbs: point.kinetic_energy * JOULES_TO_FTLBS,
+            spin_drift: spin_drift_m / YARDS_TO_METERS,
+            wind_drift_min: (point.position.x - spin_drift_m + band_least) / YARDS_TO_METERS,
+            wind_drift_max: (point.position.x - spin_drift_m + band_greatest) / YARDS_TO_METERS,
+            spin_rpm: spin.rpm_at(point.time),
+            yaw_of_repose: repose_yaw / DEGREES_TO_RADIANS,
+            mach: point.velocity_magnitude / speed_of_sound_mps,
+        }
+    }
+
+    /// Wrap as a frozen Ruby object
+    pub fn into_frozen(self, ruby: &Ruby) -> Obj<Self> {
+        let point = ruby.obj_wrap(self);
+        point.freeze();
+        point
+    }
+
+    pub const FIELD_COUNT: usize = 12;
+
+    /// All fields in declaration order, for interpolation
+    pub fn fields(&self) -> [f64; Self::FIELD_COUNT] {
+        [
+            self.time,
+            self.x,
+            self.y,
+            self.z,
+            self.velocity_fps,
+            self.energy_ftlbs,
+            self.spin_drift,
+            self.wind_drift_min,
+            self.wind_drift_max,
+            self.spin_rpm,
+            self.yaw_of_repose,
+            self.mach,
+        ]
+    }
+
+    pub fn from_fields(fields: [f64; Self::FIELD_COUNT]) -> Self {
+        let [time, x, y, z, velocity_fps, energy_ftlbs, spin_drift, wind_drift_min, wind_drift_max, spin_rpm, yaw_of_repose, mach] = fields;
+        Self { time, x, y, z, velocity_fps, energy_ftlbs, spin_drift, wind_drift_min, wind_drift_max, spin_rpm, yaw_of_repose, mach }
+    }
+
+    /// Value of one of `COLUMNS`; `drop_in`/`windage_in` are `y`/`z` in inches
+    pub fn column(&self, name: &str) -> Option<f64> {
+        let inches_per_yard = YARDS_TO_METERS / INCHES_TO_METERS;
+        match name {
+            "time" => Some(self.time),
+            "x_yards" => Some(self.x),
+            "y_yards" => Some(self.y),
+            "z_yards" => Some(self.z),
+            "drop_in" => Some(self.y * inches_per_yard),
+            "windage_in" => Some(self.z * inches_per_yard),
+            "velocity_fps" => Some(self.velocity_fps),
+            "energy_ftlbs" => Some(self.energy_ftlbs),
+            "drop_moa" => Some(self.drop_moa()),
+            "drop_mil" => Some(self.drop_mil()),
+            "windage_moa" => Some(self.windage_moa()),
+            "windage_mil" => Some(self.windage_mil()),
+            "spin_drift_in" => Some(self.spin_drift * inches_per_yard),
+            "wind_drift_in" => Some(self.wind_drift() * inches_per_yard),
+            "wind_drift_min_in" => Some(self.wind_drift_min * inches_per_yard),
+            "wind_drift_max_in" => Some(self.wind_drift_max * inches_per_yard),
+            "spin_rpm" => Some(self.spin_rpm),
+            "yaw_of_repose_deg" => Some(self.yaw_of_repose),
+            "mach" => Some(self.mach),
+            _ => None,
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+
+    pub fn velocity_fps(&self) -> f64 {
+        self.velocity_fps
+    }
+
+    pub fn energy_ftlbs(&self) -> f64 {
+        self.energy_ftlbs
+    }
+
+    /// Velocity over the speed of sound in the atmosphere solved in
+    pub fn mach(&self) -> f64 {
+        self.mach
+    }
+
+    /// Part of `z` from spin drift, yards (0 unless solved with `spin_drift: true` or `solver: "mpm"`)
+    pub fn spin_drift(&self) -> f64 {
+        self.spin_drift
+    }
+
+    /// Spin rate, rpm, decaying from the muzzle rate
+    pub fn spin_rpm(&self) -> f64 {
+        self.spin_rpm
+    }
+
+    /// Yaw of repose, degrees (positive is right; 0 unless solved with `solver: "mpm"`)
+    pub fn yaw_of_repose(&self) -> f64 {
+        self.yaw_of_repose
+    }
+
+    /// Part of `z` from everything but spin drift, yards
+    pub fn wind_drift(&self) -> f64 {
+        self.z - self.spin_drift
+    }
+
+    /// Least `wind_drift` within the wind's uncertainty, yards (`wind_drift` without one)
+    pub fn wind_drift_min(&self) -> f64 {
+        self.wind_drift_min
+    }
+
+    /// Greatest `wind_drift` within the wind's uncertainty, yards (`wind_drift` without one)
+    pub fn wind_drift_max(&self) -> f64 {
+        self.wind_drift_max
+    }
+
+    /// Angle of `offset` yards seen from the shooter at this range, in radians
+    fn angle(&self, offset: f64) -> f64 {
+        if self.x > 0.0 { offset.atan2(self.x) } else { 0.0 }
+    }
+
+    /// `y` as an angle from the line of sight, in MOA (negative is below)
+    pub fn drop_moa(&self) -> f64 {
+        self.angle(self.y) * RADIANS_TO_MOA
+    }
+
+    /// `y` as an angle from the line of sight, in milliradians (negative is below)
+    pub fn drop_mil(&self) -> f64 {
+        self.angle(self.y) * RADIANS_TO_MILS
+    }
+
+    /// `z` as an angle from the line of sight, in MOA (positive is right)
+    pub fn windage_moa(&self) -> f64 {
+        self.angle(self.z) * RADIANS_TO_MOA
+    }
+
+    /// `z` as an angle from the line of sight, in milliradians (positive is right)
+    pub fn windage_mil(&self) -> f64 {
+        self.angle(self.z) * RADIANS_TO_MILS
+    }
+
+    /// `#<BallisticsEngine::TrajectoryPoint time=0.512s x=400.0yards y=-0.42yards z=0.00yards velocity=2100.3fps energy=1645.2ftlbs>`
+    fn inspect(&self) -> String {
+        format!(
+            "#<BallisticsEngine::TrajectoryPoint time={:.3}s x={:.1}yards y={:.2}yards z={:.2}yards velocity={:.1}fps energy={:.1}ftlbs>",
+            self.time, self.x, self.y, self.z, self.velocity_fps, self.energy_ftlbs
+        )
+    }
+
+    /// `pp` prints the `inspect` line rather than the object's internals
+    fn pretty_print(&self, q: Value) -> Result<(), Error> {
+        let _: Value = q.funcall("text", (self.inspect(),))?;
+        Ok(())
+    }
+
+    /// String-keyed hash of the point
+    fn to_h(ruby: &Ruby, rb_self: &Self) -> Result<RHash, Error> {
+        rb_self.to_h_in(ruby, OutputUnits::default())
+    }
+
+    /// String-keyed hash of the point in `units`
+    pub fn to_h_in(self, ruby: &Ruby, units: OutputUnits) -> Result<RHash, Error> {
+        let point_hash = ruby.hash_new();
+        point_hash.aset("time", self.time)?;
+        point_hash.aset("x", units.distance.convert_yards(self.x))?;
+        point_hash.aset("y", units.drop.convert_yards(self.y))?;
+        point_hash.aset("z", units.drop.convert_yards(self.z))?;
+        point_hash.aset("spin_drift", units.drop.convert_yards(self.spin_drift))?;
+        point_hash.aset("wind_drift_min", units.drop.convert_yards(self.wind_drift_min))?;
+        point_hash.aset("wind_drift_max", units.drop.convert_yards(self.wind_drift_max))?;
+        point_hash.aset(format!("velocity_{}", units.velocity.suffix()), units.velocity.convert_fps(self.velocity_fps))?;
+        point_hash.aset(format!("energy_{}", units.energy.suffix()), units.energy.convert_ftlbs(self.energy_ftlbs))?;
+        point_hash.aset("mach", self.mach)?;
+        point_hash.aset("spin_rpm", self.spin_rpm)?;
+        point_hash.aset("yaw_of_repose_deg", self.yaw_of_repose)?;
+        match units.angle {
+            Some(Angle::Moa) => {
+                point_hash.aset("drop_moa", self.drop_moa())?;
+                point_hash.aset("windage_moa", self.windage_moa())?;
+            }
+            Some(Angle::Mil) => {
+                point_hash.aset("drop_mil", self.drop_mil())?;
+                point_hash.aset("windage_mil", self.windage_mil())?;
+            }
+            None => {}
+        }
+        Ok(point_hash)
+    }
+
+    /// JSON object of the point; arguments from `JSON.generate` are ignored
+    fn to_json(ruby: &Ruby, rb_self: &Self, _args: &[Value]) -> Result<String, Error> {
+        serde_json::to_string(rb_self).map_err(|e| Error::new(ruby.exception_runtime_error(), e.to_string()))
+    }
+}
+
+/// String-keyed hash of an engine point in raw SI units (`si: true`)
+///
+/// Same axes as `TrajectoryPoint`; only the line-of-sight offset is applied.
+pub fn engine_point_to_si_h(
+    ruby: &Ruby,
+    point: &EngineTrajectoryPoint,
+    sight_height_m: f64,
+    speed_of_sound_mps: f64,
+    spin: Spin,
+    (repose_yaw, repose_drift): (f64, f64),
+    (band_least, band_greatest): (f64, f64),
+) -> Result<RHash, Error> {
+    let spin_drift_m = spin.drift_at(point.time) + repose_drift;
+    let point_hash = ruby.hash_new();
+    point_hash.aset("time_s", point.time)?;
+    point_hash.aset("x_m", point.position.z)?;
+    point_hash.aset("y_m", point.position.y - sight_height_m)?;
+    point_hash.aset("z_m", point.position.x)?;
+    point_hash.aset("spin_drift_m", spin_drift_m)?;
+    point_hash.aset("wind_drift_min_m", point.position.x - spin_drift_m + band_least)?;
+    point_hash.aset("wind_drift_max_m", point.position.x - spin_drift_m + band_greatest)?;
+    point_hash.aset("velocity_mps", point.velocity_magnitude)?;
+    point_hash.aset("energy_j", point.kinetic_energy)?;
+    point_hash.aset("mach", point.velocity_magnitude / speed_of_sound_mps)?;
+    point_hash.aset("spin_rad_s", spin.rpm_at(point.time) * std::f64::consts::TAU / 60.0)?;
+    point_hash.aset("yaw_of_repose_rad", repose_yaw)?;
+    Ok(point_hash)
+}
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("TrajectoryPoint", ruby.class_object())?;
+    class.undef_default_alloc_func();
+
+    class.define_method("time", method!(TrajectoryPoint::time, 0))?;
+    class.define_method("x", method!(TrajectoryPoint::x, 0))?;
+    class.define_method("y", method!(TrajectoryPoint::y, 0))?;
+    class.define_method("z", method!(TrajectoryPoint::z, 0))?;
+    class.define_method("velocity_fps", method!(TrajectoryPoint::velocity_fps, 0))?;
+    class.define_method("energy_ftlbs", method!(TrajectoryPoint::energy_ftlbs, 0))?;
+    class.define_method("mach", method!(TrajectoryPoint::mach, 0))?;
+    class.define_method("spin_drift", method!(TrajectoryPoint::spin_drift, 0))?;
+    class.define_method("wind_drift", method!(TrajectoryPoint::wind_drift, 0))?;
+    class.define_method("wind_drift_min", method!(TrajectoryPoint::wind_drift_min, 0))?;
+    class.define_method("wind_drift_max", method!(TrajectoryPoint::wind_drift_max, 0))?;
+    class.define_method("spin_rpm", method!(TrajectoryPoint::spin_rpm, 0))?;
+    class.define_method("yaw_of_repose", method!(TrajectoryPoint::yaw_of_repose, 0))?;
+    class.define_method("drop_moa", method!(TrajectoryPoint::drop_moa, 0))?;
+    class.define_method("drop_mil", method!(TrajectoryPoint::drop_mil, 0))?;
+    class.define_method("windage_moa", method!(TrajectoryPoint::windage_moa, 0))?;
+    class.define_method("windage_mil", method!(TrajectoryPoint::windage_mil, 0))?;
+    class.define_method("to_h", method!(TrajectoryPoint::to_h, 0))?;
+    class.define_method("inspect", method!(TrajectoryPoint::inspect, 0))?;
+    class.define_method("pretty_print", method!(TrajectoryPoint::pretty_print, 1))?;
+    class.define_method("to_json", method!(TrajectoryPoint::to_json, -1))?;
+    Ok(())
+}
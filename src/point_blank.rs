@@ -0,0 +1,106 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, scan_args::scan_args};
+use ballistics_engine::TrajectoryPoint as EngineTrajectoryPoint;
+
+use crate::error;
+use crate::projectile::ImperialInputs;
+use crate::solver::{self, SolveOptions, Solver};
+use crate::units::*;
+use crate::validation::{self, Problems};
+
+// Maximum point-blank range: the zero that keeps the bullet within half the
+// vital zone of the line of sight for as far as possible. The further the
+// bore is elevated the higher the trajectory peaks and the further it stays
+// above the bottom of the zone, so the longest range comes with the peak at
+// the top of the zone. That bore angle is found by bisection, and the
+// trajectory solved at it (as with `solver_options.zero_angle_degrees`)
+// gives the zero and the limits.
+
+/// Bore angles searched between, radians: level, and far above any peak of interest
+const ANGLES: (f64, f64) = (0.0, 5.0 * DEGREES_TO_RADIANS);
+/// Stop once the bracket is this narrow, radians
+const TOLERANCE: f64 = 1e-7;
+/// Range every candidate is solved to, metres
+const SEARCH_RANGE_M: f64 = 2000.0 * YARDS_TO_METERS;
+
+/// Height above the line of sight, metres
+fn height(point: &EngineTrajectoryPoint, sight_height_m: f64) -> f64 {
+    point.position.y - sight_height_m
+}
+
+/// Range where the height above the line of sight first crosses `level`
+/// going up (`rising`) or down, metres, from point `from` on
+fn crossing(points: &[EngineTrajectoryPoint], sight_height_m: f64, level: f64, rising: bool, from: usize) -> Option<f64> {
+    points.windows(2).skip(from).find_map(|pair| {
+        let (before, after) = (height(&pair[0], sight_height_m) - level, height(&pair[1], sight_height_m) - level);
+        let crosses = if rising { before < 0.0 && after >= 0.0 } else { before >= 0.0 && after < 0.0 };
+        crosses.then(|| pair[0].position.z + (pair[1].position.z - pair[0].position.z) * before / (before - after))
+    })
+}
+
+/// BallisticsEngine.mpbr(inputs, vital_zone_inches:, environment: nil, wind: nil, atmosphere: nil)
+///
+/// The zero, near and far limits and peak of the maximum point-blank range
+/// for a vital zone of `vital_zone_inches`; the inputs' own zero is ignored.
+fn mpbr(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let vital_zone = kw.delete::<_, Option<Value>>(ruby.to_symbol("vital_zone_inches"))?;
+    let (wind, atmosphere, units) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let solver = Solver::from_inputs(ImperialInputs::from_value(inputs, &mut problems)?);
+    let vital_zone_inches: f64 = problems.required("vital_zone_inches", vital_zone);
+    problems.check_positive("vital_zone_inches", vital_zone_inches);
+    problems.into_result(ruby)?;
+
+    let half_zone_m = vital_zone_inches * INCHES_TO_METERS / 2.0;
+    let sight_height_m = solver.sight_height_m();
+    let options = |zero_angle: f64| SolveOptions { zero_angle: Some(zero_angle), max_range_m: Some(SEARCH_RANGE_M), ..SolveOptions::default() };
+    let (mut low, mut high) = ANGLES;
+    while high - low > TOLERANCE {
+        let angle = (low + high) / 2.0;
+        let (result, ..) = solver.solve_engine(ruby, wind.clone(), &atmosphere, options(angle))?;
+        let peak = result.points.iter().map(|point| height(point, sight_height_m)).fold(f64::MIN, f64::max);
+        if peak > half_zone_m {
+            high = angle;
+        } else {
+            low = angle;
+        }
+    }
+
+    let (result, ..) = solver.solve_engine(ruby, wind.clone(), &atmosphere, options(low))?;
+    let points = &result.points;
+    let apex = points.iter().enumerate().fold(0, |apex, (index, point)| if point.position.y > points[apex].position.y { index } else { apex });
+    let near_limit_m = match points.first() {
+        Some(muzzle) if height(muzzle, sight_height_m) >= -half_zone_m => Some(0.0),
+        _ => crossing(points, sight_height_m, -half_zone_m, true, 0),
+    };
+    let (Some(near_limit_m), Some(zero_m), Some(far_limit_m)) = (
+        near_limit_m,
+        crossing(points, sight_height_m, 0.0, false, apex),
+        crossing(points, sight_height_m, -half_zone_m, false, apex),
+    ) else {
+        return Err(Error::new(
+            error::convergence_error(ruby),
+            format!("no point-blank range for a {} inch vital zone within {:.0} yards", vital_zone_inches, SEARCH_RANGE_M / YARDS_TO_METERS),
+        ));
+    };
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("vital_zone_inches"), vital_zone_inches)?;
+    hash.aset(ruby.to_symbol("zero_yards"), zero_m / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("near_limit_yards"), near_limit_m / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("far_limit_yards"), far_limit_m / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("peak_height_in"), height(&points[apex], sight_height_m) / INCHES_TO_METERS)?;
+    hash.aset(ruby.to_symbol("peak_range_yards"), points[apex].position.z / YARDS_TO_METERS)?;
+    hash.aset(ruby.to_symbol("zero_angle_degrees"), low / DEGREES_TO_RADIANS)?;
+    let options = SolveOptions { max_range_m: Some(far_limit_m), ..options(low) };
+    hash.aset(ruby.to_symbol("result"), solver.solve_with(ruby, wind, atmosphere, units, options)?)?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("mpbr", function!(mpbr, -1))?;
+    Ok(())
+}
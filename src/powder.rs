@@ -0,0 +1,95 @@
+use magnus::{RArray, Value, prelude::*};
+
+use crate::coerce::Coerce;
+use crate::validation::{Problems, invalid_message};
+
+// Powder temperature sensitivity: the muzzle velocity was chronographed at one
+// powder temperature, and `powder_temp_f` with `powder_sensitivity` moves it to
+// the temperature of the day before solving. The sensitivity is fps per °F
+// from `powder_reference_temp_f`, or a table of muzzle velocities measured at
+// several temperatures, which then replaces `muzzle_velocity_fps`.
+
+/// Powder temperature `muzzle_velocity_fps` is taken to be chronographed at, °F
+pub const DEFAULT_REFERENCE_TEMP_F: f64 = 59.0;
+
+/// How the muzzle velocity follows the powder temperature
+#[derive(Clone, PartialEq)]
+pub enum Sensitivity {
+    /// fps per °F
+    PerDegree(f64),
+    /// `(temp_f, muzzle_velocity_fps)` points, in increasing temperature order
+    Table(Vec<(f64, f64)>),
+}
+
+/// Read `powder_sensitivity`: a number, or an array of `[temp_f, muzzle_velocity_fps]` pairs
+///
+/// nil gives `None`.
+pub fn read_sensitivity(value: Option<Value>, problems: &mut Problems) -> Option<Sensitivity> {
+    let value = value.filter(|value| !value.is_nil())?;
+    let Some(pairs) = RArray::from_value(value) else {
+        return Some(Sensitivity::PerDegree(problems.required("powder_sensitivity", Some(value))));
+    };
+    let mut points = Vec::with_capacity(pairs.len());
+    for (index, pair) in pairs.into_iter().enumerate() {
+        let point = RArray::from_value(pair)
+            .filter(|values| values.len() == 2)
+            .ok_or_else(|| format!("expected [temp_f, muzzle_velocity_fps], got {}", pair.inspect()))
+            .and_then(|values| {
+                let value = |index| values.entry::<Value>(index).map_err(|e| e.to_string()).and_then(f64::coerce);
+                Ok((value(0)?, value(1)?))
+            });
+        match point {
+            Ok(point) => points.push(point),
+            Err(reason) => problems.add("powder_sensitivity", invalid_message(&format!("powder_sensitivity[{}]", index), &reason)),
+        }
+    }
+    Some(table(points))
+}
+
+/// A table sensitivity from points in any order
+pub fn table(mut points: Vec<(f64, f64)>) -> Sensitivity {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Sensitivity::Table(points)
+}
+
+/// The muzzle velocity at `powder_temp_f`, fps
+///
+/// Tables are interpolated between points and extrapolated along the end
+/// segments (a single point is used at any temperature).
+pub fn muzzle_velocity_fps(measured_fps: f64, powder_temp_f: f64, sensitivity: &Sensitivity, reference_temp_f: f64) -> f64 {
+    let points = match sensitivity {
+        Sensitivity::PerDegree(fps_per_f) => return measured_fps + fps_per_f * (powder_temp_f - reference_temp_f),
+        Sensitivity::Table(points) => points,
+    };
+    match points.as_slice() {
+        [] => measured_fps,
+        [(_, fps)] => *fps,
+        _ => {
+            let index = points.iter().position(|(temp, _)| *temp > powder_temp_f).unwrap_or(points.len()).clamp(1, points.len() - 1);
+            let ((temp_0, fps_0), (temp_1, fps_1)) = (points[index - 1], points[index]);
+            fps_0 + (fps_1 - fps_0) * (powder_temp_f - temp_0) / (temp_1 - temp_0)
+        }
+    }
+}
+
+/// Record every problem with the powder inputs
+pub fn check(powder_temp_f: Option<f64>, sensitivity: Option<&Sensitivity>, reference_temp_f: f64, problems: &mut Problems) {
+    match (powder_temp_f, sensitivity) {
+        (Some(_), None) => problems.add("powder_sensitivity", "powder_sensitivity is missing (required with powder_temp_f)".to_string()),
+        (None, Some(_)) => problems.add("powder_temp_f", "powder_temp_f is missing (required with powder_sensitivity)".to_string()),
+        _ => {}
+    }
+    if let Some(temp_f) = powder_temp_f {
+        problems.check("powder_temp_f", temp_f.is_finite() && temp_f > -459.67, "must be above absolute zero");
+    }
+    match sensitivity {
+        Some(Sensitivity::PerDegree(fps_per_f)) => problems.check("powder_sensitivity", fps_per_f.is_finite(), "must be finite"),
+        Some(Sensitivity::Table(points)) => {
+            problems.check("powder_sensitivity", !points.is_empty(), "must have at least one [temp_f, muzzle_velocity_fps] point");
+            problems.check("powder_sensitivity", points.iter().all(|(_, fps)| *fps > 0.0), "must have positive muzzle velocities");
+            problems.check("powder_sensitivity", points.windows(2).all(|pair| pair[0].0 != pair[1].0), "must not repeat a temperature");
+        }
+        None => {}
+    }
+    problems.check("powder_reference_temp_f", reference_temp_f.is_finite() && reference_temp_f > -459.67, "must be above absolute zero");
+}
@@ -0,0 +1,747 @@
+use std::cell::RefCell;
+
+use magnus::{Error, RHash, RModule, Ruby, Value, function, method, prelude::*, scan_args::{get_kwargs, scan_args}};
+use ballistics_engine::{BallisticInputs, DragModel};
+
+use crate::atmosphere::{self, Standard};
+use crate::bc;
+use crate::bullets;
+use crate::cant;
+use crate::cdm;
+use crate::coerce;
+use crate::config;
+use crate::drag;
+use crate::earth;
+use crate::error;
+use crate::metric;
+use crate::powder::{self, Sensitivity};
+use crate::solver::{self, Model};
+use crate::spin;
+use crate::units::*;
+use crate::validation::{self, Problems};
+
+// Documented defaults for optional fields, used when a field is missing or nil
+pub const DEFAULT_SHOOTING_ANGLE_DEGREES: f64 = 0.0;
+pub const DEFAULT_TWIST_RATE_INCHES: f64 = 10.0;
+pub const DEFAULT_IS_RIGHT_TWIST: bool = true;
+pub const DEFAULT_SPIN_DRIFT: bool = false;
+
+const FORM_FACTOR_WITH_CURVE: &str = "form_factor needs a standard drag_model, not a drag_curve";
+
+/// Projectile and sight parameters in the imperial units accepted from Ruby
+#[derive(Clone)]
+pub struct ImperialInputs {
+    /// Unused when `form_factor` is set
+    pub bc: f64,
+    /// `(velocity_fps, bc)` bands, fastest first, when `bc` was given as bands
+    pub bc_bands: Option<Vec<(f64, f64)>>,
+    /// Form factor against `drag_model`, given instead of `bc`
+    pub form_factor: Option<f64>,
+    pub bullet_weight_grains: f64,
+    pub muzzle_velocity_fps: f64,
+    pub bullet_diameter_inches: f64,
+    pub bullet_length_inches: f64,
+    pub sight_height_inches: f64,
+    pub zero_distance_yards: f64,
+    pub shooting_angle_degrees: f64,
+    pub twist_rate_inches: f64,
+    pub is_right_twist: bool,
+    pub drag_model: DragModel,
+    /// Measured `(mach, cd)` pairs for `drag_model: "CUSTOM"`, which replace `bc` and the table
+    pub drag_curve: Option<Vec<(f64, f64)>>,
+    /// Multiplies the drag, for truing to field data
+    pub drag_scale_factor: f64,
+    /// `(mach, factor)` points, in increasing Mach order, when the factor was given by Mach
+    pub drag_scale_by_mach: Option<Vec<(f64, f64)>>,
+    /// Add gyroscopic spin drift to the lateral positions
+    pub spin_drift: bool,
+    /// Magnus force coefficient, when a crosswind's Magnus lift is added
+    pub magnus: Option<f64>,
+    /// Trajectory model to solve with
+    pub solver: Model,
+    /// Reference atmosphere `bc` (or the form factor) was published against
+    pub standard: Standard,
+    /// Powder temperature of the day, which moves the muzzle velocity by `powder_sensitivity`
+    pub powder_temp_f: Option<f64>,
+    pub powder_sensitivity: Option<Sensitivity>,
+    /// Powder temperature `muzzle_velocity_fps` was chronographed at
+    pub powder_reference_temp_f: f64,
+    /// Firing point latitude, which turns on the Coriolis deflection
+    pub latitude_degrees: Option<f64>,
+    /// Direction of fire, clockwise from true north
+    pub azimuth_degrees: f64,
+    /// Roll of the rifle about the line of sight, clockwise as the shooter sees it
+    pub cant_angle_degrees: f64,
+    /// Follow the Earth's curvature instead of flat ground
+    pub earth_curvature: bool,
+    /// WGS-84 normal gravity instead of the solvers' fixed value
+    pub wgs84_gravity: bool,
+}
+
+impl ImperialInputs {
+    /// Read inputs from a string-keyed Ruby hash, recording every missing or invalid entry
+    pub fn from_hash(inputs_hash: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let inputs_hash = metric::to_imperial(inputs_hash, metric::PROJECTILE, "", problems)?;
+        bullets::fill(inputs_hash, problems)?;
+        let drag_model: Option<String> = problems.lookup(inputs_hash, "drag_model", "drag_model", None);
+        let custom = is_custom(drag_model.as_deref());
+        let drag_curve = drag::read_curve(validation::entry(inputs_hash, "drag_curve"), problems);
+        let (drag_model, drag_curve) = read_drag(drag_model, drag_curve, problems);
+        // Required unless a drag curve or form factor stands in for it
+        let (bc, bc_bands, form_factor) =
+            read_bc_or_form_factor(validation::entry(inputs_hash, "bc"), validation::entry(inputs_hash, "form_factor"), custom, problems);
+        let (drag_scale_factor, drag_scale_by_mach) = drag::read_scale(validation::entry(inputs_hash, "drag_scale_factor"), problems);
+
+        let inputs = Self {
+            // Required values
+            bc,
+            bc_bands,
+            form_factor,
+            bullet_weight_grains: problems.fetch(inputs_hash, "bullet_weight_grains", "bullet_weight_grains"),
+            muzzle_velocity_fps: problems.fetch(inputs_hash, "muzzle_velocity_fps", "muzzle_velocity_fps"),
+            bullet_diameter_inches: problems.fetch(inputs_hash, "bullet_diameter_inches", "bullet_diameter_inches"),
+            bullet_length_inches: problems.fetch(inputs_hash, "bullet_length_inches", "bullet_length_inches"),
+            sight_height_inches: problems.fetch(inputs_hash, "sight_height_inches", "sight_height_inches"),
+            zero_distance_yards: problems.fetch(inputs_hash, "zero_distance_yards", "zero_distance_yards"),
+
+            // Optional values with defaults
+            shooting_angle_degrees: problems.lookup(inputs_hash, "shooting_angle_degrees", "shooting_angle_degrees", DEFAULT_SHOOTING_ANGLE_DEGREES),
+            twist_rate_inches: problems.lookup(inputs_hash, "twist_rate_inches", "twist_rate_inches", DEFAULT_TWIST_RATE_INCHES),
+            is_right_twist: problems.lookup(inputs_hash, "is_right_twist", "is_right_twist", DEFAULT_IS_RIGHT_TWIST),
+            drag_model,
+            drag_curve,
+            drag_scale_factor,
+            drag_scale_by_mach,
+            spin_drift: problems.lookup(inputs_hash, "spin_drift", "spin_drift", DEFAULT_SPIN_DRIFT),
+            magnus: spin::read_magnus(validation::entry(inputs_hash, "magnus"), problems)?,
+            solver: solver::read_model(problems.lookup(inputs_hash, "solver", "solver", None), problems),
+            standard: atmosphere::read_standard(problems.lookup(inputs_hash, "standard", "standard", None), problems),
+            powder_temp_f: read_number("powder_temp_f", validation::entry(inputs_hash, "powder_temp_f"), problems),
+            powder_sensitivity: powder::read_sensitivity(validation::entry(inputs_hash, "powder_sensitivity"), problems),
+            powder_reference_temp_f: problems.lookup(
+                inputs_hash,
+                "powder_reference_temp_f",
+                "powder_reference_temp_f",
+                powder::DEFAULT_REFERENCE_TEMP_F,
+            ),
+            latitude_degrees: read_number("latitude_degrees", validation::entry(inputs_hash, "latitude_degrees"), problems),
+            azimuth_degrees: problems.lookup(inputs_hash, "azimuth_degrees", "azimuth_degrees", earth::DEFAULT_AZIMUTH_DEGREES),
+            cant_angle_degrees: problems.lookup(inputs_hash, "cant_angle_degrees", "cant_angle_degrees", cant::DEFAULT_CANT_ANGLE_DEGREES),
+            earth_curvature: problems.lookup(inputs_hash, "earth_curvature", "earth_curvature", earth::DEFAULT_EARTH_CURVATURE),
+            wgs84_gravity: problems.lookup(inputs_hash, "wgs84_gravity", "wgs84_gravity", earth::DEFAULT_WGS84_GRAVITY),
+        };
+        inputs.check(problems);
+        Ok(inputs)
+    }
+
+    /// Read inputs from a symbol-keyed keyword hash, rejecting unknown keys
+    ///
+    /// Missing or unknown keywords raise immediately; value problems are recorded.
+    pub fn from_kwargs(kw: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let kw = metric::to_imperial(kw, metric::PROJECTILE, "", problems)?;
+        bullets::fill(kw, problems)?;
+        // Effect switches, the model, the standard, the powder and the Earth
+        // are taken out first: get_kwargs reads at most nine optional keywords
+        let ruby = Ruby::get_with(kw);
+        let spin_drift = kw.delete::<_, Option<Value>>(ruby.to_symbol("spin_drift"))?;
+        let magnus = kw.delete::<_, Option<Value>>(ruby.to_symbol("magnus"))?;
+        let model = kw.delete::<_, Option<Value>>(ruby.to_symbol("solver"))?;
+        let standard = kw.delete::<_, Option<Value>>(ruby.to_symbol("standard"))?;
+        let powder_temp_f = kw.delete::<_, Option<Value>>(ruby.to_symbol("powder_temp_f"))?;
+        let powder_sensitivity = kw.delete::<_, Option<Value>>(ruby.to_symbol("powder_sensitivity"))?;
+        let powder_reference_temp_f = kw.delete::<_, Option<Value>>(ruby.to_symbol("powder_reference_temp_f"))?;
+        let latitude_degrees = kw.delete::<_, Option<Value>>(ruby.to_symbol("latitude_degrees"))?;
+        let azimuth_degrees = kw.delete::<_, Option<Value>>(ruby.to_symbol("azimuth_degrees"))?;
+        let cant_angle_degrees = kw.delete::<_, Option<Value>>(ruby.to_symbol("cant_angle_degrees"))?;
+        let earth_curvature = kw.delete::<_, Option<Value>>(ruby.to_symbol("earth_curvature"))?;
+        let wgs84_gravity = kw.delete::<_, Option<Value>>(ruby.to_symbol("wgs84_gravity"))?;
+        let kwargs = get_kwargs::<
+            _,
+            (Value, Value, Value, Value, Value, Value),
+            (Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>),
+            (),
+        >(
+            kw,
+            &[
+                "bullet_weight_grains",
+                "muzzle_velocity_fps",
+                "bullet_diameter_inches",
+                "bullet_length_inches",
+                "sight_height_inches",
+                "zero_distance_yards",
+            ],
+            // `bc` is required unless there is a drag curve or form factor
+            &["bc", "form_factor", "shooting_angle_degrees", "twist_rate_inches", "is_right_twist", "drag_model", "drag_curve", "drag_scale_factor"],
+        )
+        .map_err(|e| error::as_input_error(&ruby, e))?;
+        let (bullet_weight_grains, muzzle_velocity_fps, bullet_diameter_inches, bullet_length_inches, sight_height_inches, zero_distance_yards) = kwargs.required;
+        let (bc, form_factor, shooting_angle_degrees, twist_rate_inches, is_right_twist, drag_model, drag_curve, drag_scale_factor) = kwargs.optional;
+        let drag_model: Option<String> = problems.optional("drag_model", drag_model, None);
+        let custom = is_custom(drag_model.as_deref());
+        let drag_curve = drag::read_curve(drag_curve, problems);
+        let (drag_model, drag_curve) = read_drag(drag_model, drag_curve, problems);
+        let (bc, bc_bands, form_factor) = read_bc_or_form_factor(bc, form_factor, custom, problems);
+        let (drag_scale_factor, drag_scale_by_mach) = drag::read_scale(drag_scale_factor, problems);
+
+        let inputs = Self {
+            bc,
+            bc_bands,
+            form_factor,
+            bullet_weight_grains: problems.required("bullet_weight_grains", Some(bullet_weight_grains)),
+            muzzle_velocity_fps: problems.required("muzzle_velocity_fps", Some(muzzle_velocity_fps)),
+            bullet_diameter_inches: problems.required("bullet_diameter_inches", Some(bullet_diameter_inches)),
+            bullet_length_inches: problems.required("bullet_length_inches", Some(bullet_length_inches)),
+            sight_height_inches: problems.required("sight_height_inches", Some(sight_height_inches)),
+            zero_distance_yards: problems.required("zero_distance_yards", Some(zero_distance_yards)),
+            shooting_angle_degrees: problems.optional("shooting_angle_degrees", shooting_angle_degrees, DEFAULT_SHOOTING_ANGLE_DEGREES),
+            twist_rate_inches: problems.optional("twist_rate_inches", twist_rate_inches, DEFAULT_TWIST_RATE_INCHES),
+            is_right_twist: problems.optional("is_right_twist", is_right_twist, DEFAULT_IS_RIGHT_TWIST),
+            drag_model,
+            drag_curve,
+            drag_scale_factor,
+            drag_scale_by_mach,
+            spin_drift: problems.optional("spin_drift", spin_drift, DEFAULT_SPIN_DRIFT),
+            magnus: spin::read_magnus(magnus, problems)?,
+            solver: solver::read_model(problems.optional("solver", model, None), problems),
+            standard: atmosphere::read_standard(problems.optional("standard", standard, None), problems),
+            powder_temp_f: read_number("powder_temp_f", powder_temp_f, problems),
+            powder_sensitivity: powder::read_sensitivity(powder_sensitivity, problems),
+            powder_reference_temp_f: problems.optional("powder_reference_temp_f", powder_reference_temp_f, powder::DEFAULT_REFERENCE_TEMP_F),
+            latitude_degrees: read_number("latitude_degrees", latitude_degrees, problems),
+            azimuth_degrees: problems.optional("azimuth_degrees", azimuth_degrees, earth::DEFAULT_AZIMUTH_DEGREES),
+            cant_angle_degrees: problems.optional("cant_angle_degrees", cant_angle_degrees, cant::DEFAULT_CANT_ANGLE_DEGREES),
+            earth_curvature: problems.optional("earth_curvature", earth_curvature, earth::DEFAULT_EARTH_CURVATURE),
+            wgs84_gravity: problems.optional("wgs84_gravity", wgs84_gravity, earth::DEFAULT_WGS84_GRAVITY),
+        };
+        inputs.check(problems);
+        Ok(inputs)
+    }
+
+    /// Read inputs from a hash (or anything with `to_h`) or a `Projectile`
+    pub fn from_value(value: Value, problems: &mut Problems) -> Result<Self, Error> {
+        match inputs_hash(value)? {
+            Some(inputs_hash) => Self::from_hash(inputs_hash, problems),
+            None => {
+                let inputs = <&Projectile>::try_convert(value)?.inputs();
+                inputs.check(problems);
+                Ok(inputs)
+            }
+        }
+    }
+
+    /// Record every out-of-range value
+    pub fn check(&self, problems: &mut Problems) {
+        match (&self.drag_curve, &self.bc_bands) {
+            (Some(_), Some(_)) => problems.add("bc", "bc bands cannot be combined with a drag_curve".to_string()),
+            (Some(curve), None) => drag::check_curve(curve, problems),
+            (None, Some(bands)) => drag::check_bands(bands, problems),
+            (None, None) => match self.form_factor {
+                Some(form_factor) => problems.check_positive("form_factor", form_factor),
+                None => problems.check_positive("bc", self.bc),
+            },
+        }
+        if self.form_factor.is_some() && self.drag_curve.is_some() {
+            problems.add("form_factor", FORM_FACTOR_WITH_CURVE.to_string());
+        }
+        drag::check_scale(self.drag_scale_factor, self.drag_scale_by_mach.as_deref(), problems);
+        if self.bc_bands.is_some() && self.drag_scale_by_mach.is_some() {
+            problems.add("drag_scale_factor", drag::SCALE_BY_MACH_WITH_BANDS.to_string());
+        }
+        problems.check_positive("bullet_weight_grains", self.bullet_weight_grains);
+        problems.check_positive("muzzle_velocity_fps", self.muzzle_velocity_fps);
+        problems.check_positive("bullet_diameter_inches", self.bullet_diameter_inches);
+        problems.check_positive("bullet_length_inches", self.bullet_length_inches);
+        problems.check_non_negative("sight_height_inches", self.sight_height_inches);
+        problems.check_non_negative("zero_distance_yards", self.zero_distance_yards);
+        problems.check_range("shooting_angle_degrees", self.shooting_angle_degrees, -90.0, 90.0);
+        problems.check_range("twist_rate_inches", self.twist_rate_inches, 1.0, 100.0);
+        if let Some(magnus) = self.magnus {
+            problems.check_positive("magnus", magnus);
+        }
+        powder::check(self.powder_temp_f, self.powder_sensitivity.as_ref(), self.powder_reference_temp_f, problems);
+        earth::check(self.latitude_degrees, self.azimuth_degrees, problems);
+        cant::check(self.cant_angle_degrees, problems);
+    }
+
+    /// The muzzle velocity at the powder temperature, fps
+    pub fn adjusted_muzzle_velocity_fps(&self) -> f64 {
+        match (self.powder_temp_f, &self.powder_sensitivity) {
+            (Some(temp_f), Some(sensitivity)) => {
+                powder::muzzle_velocity_fps(self.muzzle_velocity_fps, temp_f, sensitivity, self.powder_reference_temp_f)
+            }
+            _ => self.muzzle_velocity_fps,
+        }
+    }
+
+    /// Sectional density, lb/in²
+    pub fn sectional_density(&self) -> f64 {
+        bc::sectional_density(self.bullet_weight_grains, self.bullet_diameter_inches)
+    }
+
+    /// The BC, derived from the form factor and sectional density when given that way
+    pub fn effective_bc(&self) -> f64 {
+        match self.form_factor {
+            Some(form_factor) => self.sectional_density() / form_factor,
+            None => self.bc,
+        }
+    }
+
+    /// Convert to the SI-based engine inputs
+    ///
+    /// The drag scale factor is folded into the BC (or the curve's drag), and
+    /// so is the standard the BC was published against. The muzzle velocity
+    /// is the one at the powder temperature.
+    pub fn to_ballistic_inputs(&self) -> BallisticInputs {
+        let factor = self.drag_scale_factor;
+        let muzzle_velocity_fps = self.adjusted_muzzle_velocity_fps();
+        let bc_factor = self.standard.bc_factor();
+        let bc_bands =
+            self.bc_bands.as_ref().map(|bands| bands.iter().map(|(velocity_fps, bc)| (*velocity_fps, bc * bc_factor / factor)).collect::<Vec<_>>());
+        let drag_curve = self.drag_curve.as_deref().map(|curve| match &self.drag_scale_by_mach {
+            Some(points) => drag::scale_curve(curve, points),
+            None => curve.iter().map(|(mach, cd)| (*mach, cd * factor)).collect(),
+        });
+        let bc_segments = match (&self.drag_scale_by_mach, &self.drag_curve) {
+            (Some(points), None) => Some(drag::scaled_bc_segments(self.effective_bc() * bc_factor, points)),
+            _ => None,
+        };
+
+        // Create ballistic inputs using defaults and override specific fields
+        BallisticInputs {
+            bc_type: self.drag_model,
+            bc_value: bc_bands.as_deref().and_then(|bands| drag::band_bc(bands, muzzle_velocity_fps)).unwrap_or(self.effective_bc() * bc_factor / factor),
+            bullet_diameter: self.bullet_diameter_inches * INCHES_TO_METERS,
+            bullet_mass: self.bullet_weight_grains * GRAINS_TO_KG,
+            bullet_length: self.bullet_length_inches * INCHES_TO_METERS,
+            muzzle_velocity: muzzle_velocity_fps * FPS_TO_MPS,
+            sight_height: self.sight_height_inches * INCHES_TO_METERS,
+            target_distance: self.zero_distance_yards * YARDS_TO_METERS,
+            shooting_angle: self.shooting_angle_degrees * DEGREES_TO_RADIANS,
+            twist_rate: self.twist_rate_inches,  // Already in inches per the struct definition
+            is_twist_right: self.is_right_twist,
+            caliber_inches: self.bullet_diameter_inches,
+            weight_grains: self.bullet_weight_grains,
+            bc_segments,
+            bc_segments_data: bc_bands.as_deref().map(drag::segments),
+            custom_drag_table: drag_curve.as_deref().map(drag::table),
+            use_enhanced_spin_drift: self.spin_drift,
+            ..Default::default()
+        }
+    }
+}
+
+/// `(velocity_fps, bc)` bands, fastest first
+type Bands = Vec<(f64, f64)>;
+
+/// An optional number such as `powder_temp_f`, None if nil or not given
+fn read_number(field: &str, value: Option<Value>, problems: &mut Problems) -> Option<f64> {
+    value.filter(|value| !value.is_nil()).map(|value| problems.required(field, Some(value)))
+}
+
+/// BC, bands and form factor from the `bc` and `form_factor` values
+///
+/// `bc` may be left out for a drag curve (`custom`) and must be left out
+/// with a form factor.
+fn read_bc_or_form_factor(
+    bc: Option<Value>,
+    form_factor: Option<Value>,
+    custom: bool,
+    problems: &mut Problems,
+) -> (f64, Option<Bands>, Option<f64>) {
+    let form_factor = form_factor.filter(|value| !value.is_nil()).map(|value| problems.required::<f64>("form_factor", Some(value)));
+    if form_factor.is_some() && bc.is_some_and(|bc| !bc.is_nil()) {
+        problems.add("form_factor", "form_factor cannot be combined with bc (the BC is derived from it)".to_string());
+    }
+    let (bc, bc_bands) = drag::read_bc(bc, custom || form_factor.is_some(), problems);
+    (bc, bc_bands, form_factor)
+}
+
+/// The inputs hash for `value`: a Hash, or the `to_h` of a Struct, OpenStruct, etc.
+///
+/// `None` for a `Projectile` (or anything without `to_h`).
+pub fn inputs_hash(value: Value) -> Result<Option<RHash>, Error> {
+    if <&Projectile>::try_convert(value).is_ok() {
+        return Ok(None);
+    }
+    coerce::hash_like(value)
+}
+
+/// Drag models accepted as `drag_model`, by name: those the engine has drag tables for
+pub const DRAG_MODELS: &[(&str, DragModel)] = &[
+    ("G1", DragModel::G1),
+    ("G6", DragModel::G6),
+    ("G7", DragModel::G7),
+    ("G8", DragModel::G8),
+];
+
+/// Standard drag functions the engine names but has no table for
+///
+/// The engine would silently solve these with the G1 table, so they are rejected.
+const DRAG_MODELS_WITHOUT_TABLES: &[&str] = &["G2", "G5", "GI", "GL", "GS"];
+
+/// Look up a supported drag model by name
+pub fn parse_drag_model_name(name: &str) -> Option<DragModel> {
+    let name = name.to_uppercase();
+    DRAG_MODELS.iter().find(|(model_name, _)| *model_name == name).map(|(_, model)| *model)
+}
+
+/// Whether `name` is taken by a standard drag function, `"CUSTOM"` or the CDM handles
+pub fn is_reserved_drag_model_name(name: &str) -> bool {
+    parse_drag_model_name(name).is_some()
+        || DRAG_MODELS_WITHOUT_TABLES.contains(&name.to_uppercase().as_str())
+        || name.eq_ignore_ascii_case(drag::CUSTOM)
+        || cdm::is_handle(name)
+}
+
+/// Why `name` was not accepted as a drag model, listing the valid names
+pub fn invalid_drag_model_message(name: &str) -> String {
+    let valid = DRAG_MODELS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+    if cdm::is_handle(name) {
+        format!("drag_model {} is not a loaded CDM (see BallisticsEngine.load_cdm)", name)
+    } else if DRAG_MODELS_WITHOUT_TABLES.contains(&name.to_uppercase().as_str()) {
+        format!("drag_model {} has no drag table in this build (supported: {})", name.to_uppercase(), valid)
+    } else {
+        format!("drag_model {:?} is not supported (supported: {})", name, valid)
+    }
+}
+
+/// Parse a drag model name, recording a problem (and falling back to G7) if unsupported
+pub fn drag_model_from_name(name: &str, problems: &mut Problems) -> DragModel {
+    parse_drag_model_name(name).unwrap_or_else(|| {
+        problems.add("drag_model", invalid_drag_model_message(name));
+        DragModel::G7
+    })
+}
+
+/// Whether a `drag_model` name selects a measured drag curve: `"CUSTOM"`, a loaded CDM or a registered model
+pub fn is_custom(name: Option<&str>) -> bool {
+    name.is_some_and(|name| name.eq_ignore_ascii_case(drag::CUSTOM) || cdm::curve(name).is_some())
+}
+
+/// Drag model and curve from `drag_model` and an already read `drag_curve`
+///
+/// `"CUSTOM"` needs a curve, and a curve needs `"CUSTOM"`. A loaded CDM
+/// handle brings its own curve.
+pub fn read_drag(name: Option<String>, curve: Option<Vec<(f64, f64)>>, problems: &mut Problems) -> (DragModel, Option<Vec<(f64, f64)>>) {
+    if let Some(loaded) = name.as_deref().and_then(cdm::curve) {
+        if curve.is_some() {
+            problems.add("drag_curve", format!("drag_curve cannot be combined with a loaded CDM ({})", name.unwrap_or_default()));
+        }
+        return (drag::CURVE_REFERENCE, Some(loaded));
+    }
+    match (is_custom(name.as_deref()), curve) {
+        (true, Some(curve)) => (drag::CURVE_REFERENCE, Some(curve)),
+        (true, None) => {
+            problems.add("drag_curve", "drag_curve is missing (required with drag_model CUSTOM)".to_string());
+            (drag::CURVE_REFERENCE, None)
+        }
+        (false, Some(_)) => {
+            problems.add("drag_curve", format!("drag_curve is only used with drag_model {:?}", drag::CUSTOM));
+            (drag_model_or_default(name, problems), None)
+        }
+        (false, None) => (drag_model_or_default(name, problems), None),
+    }
+}
+
+/// Parse an optional drag model name, falling back to the configured default
+pub fn drag_model_or_default(name: Option<String>, problems: &mut Problems) -> DragModel {
+    match name {
+        Some(name) => drag_model_from_name(&name, problems),
+        None => config::defaults().drag_model,
+    }
+}
+
+/// Reusable projectile and sight description
+#[magnus::wrap(class = "BallisticsEngine::Projectile", free_immediately, size)]
+pub struct Projectile(RefCell<ImperialInputs>);
+
+impl Projectile {
+    /// Projectile.new(bc:, bullet_weight_grains:, ...) with the same keywords as solve_kw
+    fn new(ruby: &Ruby, args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let inputs = ImperialInputs::from_kwargs(args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+        Ok(Self(RefCell::new(inputs)))
+    }
+
+    /// A projectile with `inputs`, as fitted or derived ones are returned
+    pub fn from_inputs(inputs: ImperialInputs) -> Self {
+        Self(RefCell::new(inputs))
+    }
+
+    /// Snapshot of the current values
+    pub fn inputs(&self) -> ImperialInputs {
+        self.0.borrow().clone()
+    }
+
+    fn drag_model(&self) -> String {
+        let inputs = self.0.borrow();
+        match inputs.drag_curve {
+            Some(_) => drag::CUSTOM.to_string(),
+            None => inputs.drag_model.to_string(),
+        }
+    }
+
+    /// nil restores the configured default drag model
+    ///
+    /// `"CUSTOM"` keeps the current `drag_curve`, which must already be set,
+    /// and a loaded CDM handle replaces it; any other model drops it.
+    fn set_drag_model(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        if let Some(curve) = name.as_deref().and_then(cdm::curve) {
+            let mut inputs = rb_self.0.borrow_mut();
+            inputs.drag_model = drag::CURVE_REFERENCE;
+            inputs.drag_curve = Some(curve);
+            return Ok(());
+        }
+        if is_custom(name.as_deref()) {
+            if rb_self.0.borrow().drag_curve.is_none() {
+                return Err(Error::new(error::input_error(ruby), "Invalid inputs: set drag_curve before drag_model CUSTOM"));
+            }
+            return Ok(());
+        }
+        let drag_model = match name {
+            Some(name) => parse_drag_model_name(&name)
+                .ok_or_else(|| Error::new(error::unsupported_drag_model_error(ruby), invalid_drag_model_message(&name)))?,
+            None => config::defaults().drag_model,
+        };
+        let mut inputs = rb_self.0.borrow_mut();
+        inputs.drag_model = drag_model;
+        inputs.drag_curve = None;
+        Ok(())
+    }
+
+    /// The BC, or its `{velocity_fps:, bc:}` bands
+    fn bc(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        let inputs = rb_self.0.borrow();
+        Ok(match &inputs.bc_bands {
+            Some(bands) => drag::bands_to_a(ruby, bands)?.as_value(),
+            None => ruby.into_value(inputs.effective_bc()),
+        })
+    }
+
+    /// A number, or an array of `{velocity_fps:, bc:}` bands
+    fn set_bc(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let (bc, bc_bands) = drag::read_bc(Some(value), false, &mut problems);
+        match &bc_bands {
+            Some(bands) => drag::check_bands(bands, &mut problems),
+            None => problems.check_positive("bc", bc),
+        }
+        if bc_bands.is_some() && rb_self.0.borrow().drag_scale_by_mach.is_some() {
+            problems.add("drag_scale_factor", drag::SCALE_BY_MACH_WITH_BANDS.to_string());
+        }
+        problems.into_result(ruby)?;
+        let mut inputs = rb_self.0.borrow_mut();
+        inputs.bc = bc;
+        inputs.bc_bands = bc_bands;
+        inputs.form_factor = None;
+        Ok(())
+    }
+
+    fn form_factor(&self) -> Option<f64> {
+        self.0.borrow().form_factor
+    }
+
+    /// Sectional density, lb/in²
+    fn sectional_density(&self) -> f64 {
+        self.0.borrow().sectional_density()
+    }
+
+    /// Replaces `bc` (and any bands); nil keeps the BC it gives, as a plain `bc`
+    fn set_form_factor(ruby: &Ruby, rb_self: &Self, value: Option<Value>) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let form_factor = value.filter(|value| !value.is_nil()).map(|value| problems.required::<f64>("form_factor", Some(value)));
+        if let Some(form_factor) = form_factor {
+            problems.check_positive("form_factor", form_factor);
+        }
+        if form_factor.is_some() && rb_self.0.borrow().drag_curve.is_some() {
+            problems.add("form_factor", FORM_FACTOR_WITH_CURVE.to_string());
+        }
+        problems.into_result(ruby)?;
+        let mut inputs = rb_self.0.borrow_mut();
+        match form_factor {
+            Some(_) => inputs.bc_bands = None,
+            None => inputs.bc = inputs.effective_bc(),
+        }
+        inputs.form_factor = form_factor;
+        Ok(())
+    }
+
+    fn drag_curve(&self) -> Option<Vec<(f64, f64)>> {
+        self.0.borrow().drag_curve.clone()
+    }
+
+    /// A `[[mach, cd], ...]` array switches to drag_model CUSTOM; nil restores the configured default model
+    fn set_drag_curve(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let curve = drag::read_curve(Some(value), &mut problems);
+        if let Some(curve) = &curve {
+            drag::check_curve(curve, &mut problems);
+        }
+        problems.into_result(ruby)?;
+        let mut inputs = rb_self.0.borrow_mut();
+        inputs.drag_model = if curve.is_some() { drag::CURVE_REFERENCE } else { config::defaults().drag_model };
+        inputs.drag_curve = curve;
+        Ok(())
+    }
+
+    /// The drag scale factor, or its `[mach, factor]` points
+    fn drag_scale_factor(ruby: &Ruby, rb_self: &Self) -> Value {
+        let inputs = rb_self.0.borrow();
+        match &inputs.drag_scale_by_mach {
+            Some(points) => ruby.into_value(points.clone()),
+            None => ruby.into_value(inputs.drag_scale_factor),
+        }
+    }
+
+    /// A number or an array of `[mach, factor]` points; nil restores 1.0
+    fn set_drag_scale_factor(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let (factor, points) = drag::read_scale(Some(value), &mut problems);
+        drag::check_scale(factor, points.as_deref(), &mut problems);
+        if points.is_some() && rb_self.0.borrow().bc_bands.is_some() {
+            problems.add("drag_scale_factor", drag::SCALE_BY_MACH_WITH_BANDS.to_string());
+        }
+        problems.into_result(ruby)?;
+        let mut inputs = rb_self.0.borrow_mut();
+        inputs.drag_scale_factor = factor;
+        inputs.drag_scale_by_mach = points;
+        Ok(())
+    }
+
+    /// The Magnus force coefficient, or nil when the Magnus force is not modeled
+    fn magnus(&self) -> Option<f64> {
+        self.0.borrow().magnus
+    }
+
+    /// true, a bullet class name or a coefficient; false or nil turns it off
+    fn set_magnus(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let magnus = spin::read_magnus(Some(value), &mut problems)?;
+        if let Some(magnus) = magnus {
+            problems.check_positive("magnus", magnus);
+        }
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().magnus = magnus;
+        Ok(())
+    }
+
+    fn solver(&self) -> &'static str {
+        self.0.borrow().solver.name()
+    }
+
+    /// nil restores point mass
+    fn set_solver(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let model = solver::read_model(name, &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().solver = model;
+        Ok(())
+    }
+
+    fn standard(&self) -> &'static str {
+        self.0.borrow().standard.name()
+    }
+
+    fn powder_temp_f(&self) -> Option<f64> {
+        self.0.borrow().powder_temp_f
+    }
+
+    /// nil turns the adjustment off
+    fn set_powder_temp_f(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let temp_f = read_number("powder_temp_f", Some(value), &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().powder_temp_f = temp_f;
+        Ok(())
+    }
+
+    /// fps per °F, or the `[[temp_f, muzzle_velocity_fps], ...]` table
+    fn powder_sensitivity(ruby: &Ruby, rb_self: &Self) -> Value {
+        match &rb_self.0.borrow().powder_sensitivity {
+            Some(Sensitivity::PerDegree(fps_per_f)) => ruby.into_value(*fps_per_f),
+            Some(Sensitivity::Table(points)) => ruby.into_value(points.clone()),
+            None => ruby.qnil().as_value(),
+        }
+    }
+
+    /// nil turns the adjustment off
+    fn set_powder_sensitivity(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let sensitivity = powder::read_sensitivity(Some(value), &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().powder_sensitivity = sensitivity;
+        Ok(())
+    }
+
+    fn latitude_degrees(&self) -> Option<f64> {
+        self.0.borrow().latitude_degrees
+    }
+
+    /// nil turns the Coriolis deflection off
+    fn set_latitude_degrees(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let latitude = read_number("latitude_degrees", Some(value), &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().latitude_degrees = latitude;
+        Ok(())
+    }
+
+    /// nil restores the configured standard
+    fn set_standard(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let standard = atmosphere::read_standard(name, &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().standard = standard;
+        Ok(())
+    }
+}
+
+field_accessors!(Projectile {
+    bullet_weight_grains / set_bullet_weight_grains: f64,
+    muzzle_velocity_fps / set_muzzle_velocity_fps: f64,
+    bullet_diameter_inches / set_bullet_diameter_inches: f64,
+    bullet_length_inches / set_bullet_length_inches: f64,
+    sight_height_inches / set_sight_height_inches: f64,
+    zero_distance_yards / set_zero_distance_yards: f64,
+    shooting_angle_degrees / set_shooting_angle_degrees: f64 = DEFAULT_SHOOTING_ANGLE_DEGREES,
+    twist_rate_inches / set_twist_rate_inches: f64 = DEFAULT_TWIST_RATE_INCHES,
+    is_right_twist / set_is_right_twist: bool = DEFAULT_IS_RIGHT_TWIST,
+    spin_drift / set_spin_drift: bool = DEFAULT_SPIN_DRIFT,
+    powder_reference_temp_f / set_powder_reference_temp_f: f64 = powder::DEFAULT_REFERENCE_TEMP_F,
+    azimuth_degrees / set_azimuth_degrees: f64 = earth::DEFAULT_AZIMUTH_DEGREES,
+    cant_angle_degrees / set_cant_angle_degrees: f64 = cant::DEFAULT_CANT_ANGLE_DEGREES,
+    earth_curvature / set_earth_curvature: bool = earth::DEFAULT_EARTH_CURVATURE,
+    wgs84_gravity / set_wgs84_gravity: bool = earth::DEFAULT_WGS84_GRAVITY,
+});
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("Projectile", ruby.class_object())?;
+    class.define_singleton_method("new", function!(Projectile::new, -1))?;
+    Projectile::define_field_accessors(class)?;
+    class.define_method("drag_model", method!(Projectile::drag_model, 0))?;
+    class.define_method("drag_model=", method!(Projectile::set_drag_model, 1))?;
+    class.define_method("bc", method!(Projectile::bc, 0))?;
+    class.define_method("bc=", method!(Projectile::set_bc, 1))?;
+    class.define_method("form_factor", method!(Projectile::form_factor, 0))?;
+    class.define_method("form_factor=", method!(Projectile::set_form_factor, 1))?;
+    class.define_method("sectional_density", method!(Projectile::sectional_density, 0))?;
+    class.define_method("drag_curve", method!(Projectile::drag_curve, 0))?;
+    class.define_method("drag_curve=", method!(Projectile::set_drag_curve, 1))?;
+    class.define_method("drag_scale_factor", method!(Projectile::drag_scale_factor, 0))?;
+    class.define_method("drag_scale_factor=", method!(Projectile::set_drag_scale_factor, 1))?;
+    class.define_method("magnus", method!(Projectile::magnus, 0))?;
+    class.define_method("magnus=", method!(Projectile::set_magnus, 1))?;
+    class.define_method("solver", method!(Projectile::solver, 0))?;
+    class.define_method("solver=", method!(Projectile::set_solver, 1))?;
+    class.define_method("standard", method!(Projectile::standard, 0))?;
+    class.define_method("standard=", method!(Projectile::set_standard, 1))?;
+    class.define_method("powder_temp_f", method!(Projectile::powder_temp_f, 0))?;
+    class.define_method("powder_temp_f=", method!(Projectile::set_powder_temp_f, 1))?;
+    class.define_method("powder_sensitivity", method!(Projectile::powder_sensitivity, 0))?;
+    class.define_method("powder_sensitivity=", method!(Projectile::set_powder_sensitivity, 1))?;
+    class.define_method("latitude_degrees", method!(Projectile::latitude_degrees, 0))?;
+    class.define_method("latitude_degrees=", method!(Projectile::set_latitude_degrees, 1))?;
+    Ok(())
+}
@@ -0,0 +1,1435 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+use magnus::{Error, IntoValue, RArray, RHash, RModule, Ruby, Value, function, method, prelude::*, scan_args::{get_kwargs, scan_args}, typed_data::Obj};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::atmosphere;
+use crate::bc;
+use crate::cant::Cant;
+use crate::earth::{self, Coriolis, Curvature, Gravity};
+use crate::drag;
+use crate::error;
+use crate::interpolation::{self, Method};
+use crate::output_units::{Energy, Length, OutputUnits, Velocity};
+use crate::point::{self, TrajectoryPoint};
+use crate::solver::{Integrator, Stop};
+use crate::spin::{Repose, Spin};
+use crate::units::*;
+use crate::validation::Problems;
+use crate::warnings::{self, Warning};
+use crate::wind_field::WindBand;
+
+const DEFAULT_CSV_COLUMNS: &[&str] = &["time", "x_yards", "drop_in", "windage_in", "velocity_fps", "energy_ftlbs"];
+
+/// Point columns in each range card row, after `range_yards`
+const RANGE_CARD_COLUMNS: &[&str] = &[
+    "drop_in",
+    "drop_moa",
+    "drop_mil",
+    "windage_in",
+    "windage_moa",
+    "windage_mil",
+    "wind_drift_min_in",
+    "wind_drift_max_in",
+    "velocity_fps",
+    "energy_ftlbs",
+    "time",
+];
+
+/// Point columns of `at_target`, after `range_yards`
+const TARGET_COLUMNS: &[&str] = &[
+    "drop_in",
+    "drop_moa",
+    "drop_mil",
+    "windage_in",
+    "windage_moa",
+    "windage_mil",
+    "spin_drift_in",
+    "wind_drift_in",
+    "wind_drift_min_in",
+    "wind_drift_max_in",
+    "velocity_fps",
+    "energy_ftlbs",
+    "mach",
+    "time",
+];
+
+/// Layout of trajectory points in `points`/`to_h`
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// One object (or hash) per point
+    Objects,
+    /// One flat Float array per field
+    Columns,
+}
+
+/// Ranges for a range card, in yards
+#[derive(Clone, Copy)]
+pub struct RangeCard {
+    start: f64,
+    stop: f64,
+    step: f64,
+}
+
+impl RangeCard {
+    /// Read `start:`, `stop:` and `step:` keywords, recording invalid values
+    pub fn from_kwargs(ruby: &Ruby, kw: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let kwargs = get_kwargs::<_, (Value, Value, Value), (), ()>(kw, &["start", "stop", "step"], &[])
+            .map_err(|e| error::as_input_error(ruby, e))?;
+        let (start, stop, step) = kwargs.required;
+        let card = Self {
+            start: problems.required("start", Some(start)),
+            stop: problems.required("stop", Some(stop)),
+            step: problems.required("step", Some(step)),
+        };
+        problems.check_non_negative("start", card.start);
+        problems.check("stop", card.stop >= card.start, "must not be less than start");
+        problems.check_positive("step", card.step);
+        Ok(card)
+    }
+
+    /// Range to integrate to, with room for the last stored point to pass `stop`
+    pub fn max_range_m(self) -> f64 {
+        self.stop * YARDS_TO_METERS * 1.05
+    }
+
+    fn ranges(self) -> impl Iterator<Item = f64> {
+        let count = ((self.stop - self.start) / self.step + 1e-9).floor() as usize;
+        (0..=count).map(move |index| self.start + self.step * index as f64)
+    }
+}
+
+/// Trajectory calculation results, backed by the engine result
+///
+/// Immutable: instances are frozen and can be shared between Ractors.
+#[magnus::wrap(class = "BallisticsEngine::TrajectoryResult", free_immediately, size, frozen_shareable)]
+pub struct TrajectoryResult {
+    inner: EngineTrajectoryResult,
+    derived: Derived,
+    sight_height_m: f64,
+    // In the atmosphere solved in, for the supersonic range
+    speed_of_sound_mps: f64,
+    // Units for `to_h`/`[]`
+    units: OutputUnits,
+    warnings: Vec<Warning>,
+    // The points in imperial units, converted on first use; the result never changes
+    converted: OnceLock<Vec<TrajectoryPoint>>,
+}
+
+/// A `to_h` summary key and the getter behind it, run only when read
+type SummaryField<'a> = (String, Box<dyn Fn(&Ruby) -> Value + 'a>);
+
+fn field<'a, T: IntoValue>(name: impl Into<String>, value: impl Fn() -> T + 'a) -> SummaryField<'a> {
+    (name.into(), Box::new(move |ruby: &Ruby| ruby.into_value(value())))
+}
+
+/// Speed of sound in `atmosphere`, m/s: the solver's, so each point's Mach is
+/// the one its drag was looked up at
+pub fn speed_of_sound_mps(atmosphere: &AtmosphericConditions) -> f64 {
+    drag::solver_speed_of_sound_mps(atmosphere)
+}
+
+/// Values worked out from the solved inputs rather than integrated
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Derived {
+    /// lb/in²
+    pub sectional_density: f64,
+    /// Against the drag model; only for a single BC
+    pub form_factor: Option<f64>,
+    /// Spin rate, and the spin drift already included in the points' lateral positions
+    #[serde(default)]
+    pub spin: Spin,
+    /// Miller gyroscopic stability factor at the muzzle
+    #[serde(default)]
+    pub stability_factor: f64,
+    /// Air density the solver worked out, kg/m³
+    #[serde(default = "default_air_density")]
+    pub air_density: f64,
+    /// Yaw of repose and its drift at each point, for `solver: "mpm"`
+    #[serde(default)]
+    pub repose: Option<Repose>,
+    /// Lateral spread of each point within the wind's uncertainty
+    #[serde(default)]
+    pub wind_band: Option<WindBand>,
+    /// Coriolis deflection at each point, given a latitude
+    #[serde(default)]
+    pub coriolis: Option<Coriolis>,
+    /// Deflection by the Earth's curvature at each point, with `earth_curvature`
+    #[serde(default)]
+    pub curvature: Option<Curvature>,
+    /// Deflection by the WGS-84 normal gravity at each point, with `wgs84_gravity`
+    #[serde(default)]
+    pub gravity: Option<Gravity>,
+    /// Method the trajectory was integrated with; None for the flat-fire models
+    #[serde(default)]
+    pub integrator: Option<Integrator>,
+    /// `solver_options.target_range_yards`, metres: the range of the last point
+    #[serde(default)]
+    pub target_range_m: Option<f64>,
+    /// The `solver_options` threshold the bullet slowed to, if the trajectory ends there
+    #[serde(default)]
+    pub stop: Option<Stop>,
+    /// The rifle's cant and the bore line it turns, with `cant_angle_degrees`
+    #[serde(default)]
+    pub cant: Option<Cant>,
+}
+
+/// Sectional density of the bullet `inputs` fire, lb/in²
+pub fn sectional_density(inputs: &BallisticInputs) -> f64 {
+    bc::sectional_density(inputs.bullet_mass / GRAINS_TO_KG, inputs.bullet_diameter / INCHES_TO_METERS)
+}
+
+impl Derived {
+    /// From the engine inputs and atmosphere a trajectory was solved with, and
+    /// the form factor of the BC as given
+    pub fn from_inputs(inputs: &BallisticInputs, form_factor: Option<f64>, atmosphere: &AtmosphericConditions) -> Self {
+        Self {
+            sectional_density: sectional_density(inputs),
+            form_factor,
+            spin: Spin::from_inputs(inputs, atmosphere),
+            stability_factor: warnings::stability(inputs, atmosphere),
+            air_density: drag::solver_air_density(atmosphere),
+            repose: None,
+            wind_band: None,
+            coriolis: None,
+            curvature: None,
+            gravity: None,
+            integrator: None,
+            target_range_m: None,
+            stop: None,
+            cant: None,
+        }
+    }
+
+    /// `(yaw radians, drift metres)` of repose at a point's index; zero without `solver: "mpm"`
+    pub fn repose_at(&self, index: usize) -> (f64, f64) {
+        self.repose.as_ref().map_or((0.0, 0.0), |repose| repose.at(index))
+    }
+
+    /// `(least, greatest)` lateral offset within the wind's uncertainty at a point's index, metres
+    pub fn wind_band_at(&self, index: usize) -> (f64, f64) {
+        self.wind_band.as_ref().map_or((0.0, 0.0), |band| band.at(index))
+    }
+}
+
+// Dumps written before the speed of sound was stored were solved in whatever
+// atmosphere the caller gave; the standard one is the best guess
+fn default_speed_of_sound_mps() -> f64 {
+    speed_of_sound_mps(&AtmosphericConditions::default())
+}
+
+fn default_air_density() -> f64 {
+    drag::solver_air_density(&AtmosphericConditions::default())
+}
+
+/// JSON layout of a result, every key suffixed with its unit
+#[derive(Serialize)]
+struct ResultJson<'a> {
+    max_range_yards: f64,
+    max_height_yards: f64,
+    time_of_flight_s: f64,
+    impact_velocity_fps: f64,
+    impact_energy_ftlbs: f64,
+    spin_drift_yards: f64,
+    speed_of_sound_fps: f64,
+    air_density_kg_m3: f64,
+    density_altitude_feet: f64,
+    integrator: Option<&'static str>,
+    apex_range_yards: f64,
+    apex_time_s: f64,
+    descent_angle_degrees: f64,
+    near_zero_yards: Option<f64>,
+    near_zero_time_s: Option<f64>,
+    far_zero_yards: Option<f64>,
+    far_zero_time_s: Option<f64>,
+    at_target: Option<BTreeMap<&'static str, f64>>,
+    points: &'a [TrajectoryPoint],
+}
+
+/// Marshal format (`_dump`/`_load`), engine units
+///
+/// Only what the binding reads is kept; the engine's optional extras
+/// (sampled points, angular state) are not dumped.
+#[derive(Serialize, Deserialize)]
+struct Dump {
+    version: u32,
+    max_range: f64,
+    max_height: f64,
+    time_of_flight: f64,
+    impact_velocity: f64,
+    impact_energy: f64,
+    // [time, position x, y, z, velocity, energy]
+    points: Vec<[f64; 6]>,
+    sight_height_m: f64,
+    #[serde(default = "default_speed_of_sound_mps")]
+    speed_of_sound_mps: f64,
+    units: OutputUnits,
+    #[serde(default, deserialize_with = "warnings::deserialize_dumped")]
+    warnings: Vec<Warning>,
+    #[serde(default)]
+    derived: Derived,
+}
+
+const DUMP_VERSION: u32 = 1;
+
+impl TrajectoryResult {
+    /// Wrap an engine result as a frozen Ruby object
+    pub fn new(
+        ruby: &Ruby,
+        inner: EngineTrajectoryResult,
+        sight_height_m: f64,
+        speed_of_sound_mps: f64,
+        units: OutputUnits,
+        warnings: Vec<Warning>,
+        derived: Derived,
+    ) -> Obj<Self> {
+        let result = ruby.obj_wrap(Self { inner, derived, sight_height_m, speed_of_sound_mps, units, warnings, converted: OnceLock::new() });
+        result.freeze();
+        result
+    }
+
+    /// `BallisticsEngine.max_range`'s hash for this trajectory, fired at `launch_angle` radians
+    pub fn max_range_hash(ruby: &Ruby, rb_self: Obj<Self>, launch_angle: f64) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("launch_angle_degrees"), launch_angle / DEGREES_TO_RADIANS)?;
+        hash.aset(ruby.to_symbol("max_range_yards"), rb_self.max_range_yards())?;
+        hash.aset(ruby.to_symbol("max_height_yards"), rb_self.max_height_yards())?;
+        hash.aset(ruby.to_symbol("time_of_flight"), rb_self.time_of_flight())?;
+        hash.aset(ruby.to_symbol("impact_velocity_fps"), rb_self.impact_velocity_fps())?;
+        hash.aset(ruby.to_symbol("impact_energy_ftlbs"), rb_self.impact_energy_ftlbs())?;
+        hash.aset(ruby.to_symbol("descent_angle_degrees"), rb_self.descent_angle_degrees())?;
+        hash.aset(ruby.to_symbol("result"), rb_self)?;
+        Ok(hash)
+    }
+
+    fn point(&self, index: usize) -> TrajectoryPoint {
+        TrajectoryPoint::from_engine(
+            &self.inner.points[index],
+            self.sight_height_m,
+            self.speed_of_sound_mps,
+            self.derived.spin,
+            self.derived.repose_at(index),
+            self.derived.wind_band_at(index),
+        )
+    }
+
+    fn converted_points(&self) -> &[TrajectoryPoint] {
+        self.converted.get_or_init(|| (0..self.inner.points.len()).map(|index| self.point(index)).collect())
+    }
+
+    fn max_range_yards(&self) -> f64 {
+        self.inner.max_range / YARDS_TO_METERS
+    }
+
+    fn max_height_yards(&self) -> f64 {
+        self.inner.max_height / YARDS_TO_METERS
+    }
+
+    fn time_of_flight(&self) -> f64 {
+        self.inner.time_of_flight
+    }
+
+    /// Muzzle velocity solved with, after any powder temperature adjustment, fps
+    fn muzzle_velocity_fps(&self) -> f64 {
+        self.inner.points.first().map_or(0.0, |point| point.velocity_magnitude) / FPS_TO_MPS
+    }
+
+    fn impact_velocity_fps(&self) -> f64 {
+        self.inner.impact_velocity / FPS_TO_MPS
+    }
+
+    fn impact_energy_ftlbs(&self) -> f64 {
+        self.inner.impact_energy * JOULES_TO_FTLBS
+    }
+
+    /// Index of the highest point, the first of any as high
+    fn apex_index(&self) -> usize {
+        self.inner.points.iter().enumerate().fold(0, |apex, (index, point)| {
+            if point.position.y > self.inner.points[apex].position.y { index } else { apex }
+        })
+    }
+
+    /// Range of the highest point, yards
+    fn apex_range_yards(&self) -> f64 {
+        self.inner.points.get(self.apex_index()).map_or(0.0, |point| point.position.z) / YARDS_TO_METERS
+    }
+
+    /// Time of flight to the highest point, seconds
+    fn apex_time(&self) -> f64 {
+        self.inner.points.get(self.apex_index()).map_or(0.0, |point| point.time)
+    }
+
+    /// The points from the highest on, a frozen array of `TrajectoryPoint`
+    fn descending_points(ruby: &Ruby, rb_self: &Self) -> Result<RArray, Error> {
+        let apex = rb_self.apex_index();
+        let points = ruby.ary_new_capa(rb_self.inner.points.len().saturating_sub(apex));
+        for index in apex..rb_self.inner.points.len() {
+            points.push(rb_self.point(index).into_frozen(ruby))?;
+        }
+        points.freeze();
+        Ok(points)
+    }
+
+    /// Angle the bullet is falling at over the last two points, below the horizontal, radians
+    fn descent_angle(&self) -> f64 {
+        match self.inner.points[..] {
+            [.., ref before, ref last] => (before.position.y - last.position.y).atan2(last.position.z - before.position.z),
+            _ => 0.0,
+        }
+    }
+
+    /// Angle the bullet is falling at where the trajectory ends, degrees below the horizontal
+    fn descent_angle_degrees(&self) -> f64 {
+        self.descent_angle() / DEGREES_TO_RADIANS
+    }
+
+    /// Air density the solver worked out from the atmosphere, kg/m³
+    fn air_density_kg_m3(&self) -> f64 {
+        self.derived.air_density
+    }
+
+    /// Density altitude of the air density solved in, feet
+    fn density_altitude_feet(&self) -> f64 {
+        atmosphere::density_altitude_feet(self.derived.air_density)
+    }
+
+    /// `(column, value)` pairs of the last point, solved with `solver_options.target_range_yards`
+    fn target_columns(&self) -> Option<Vec<(&'static str, f64)>> {
+        let target_range_m = self.derived.target_range_m?;
+        let point = self.point(self.inner.points.len().checked_sub(1)?);
+        let columns = TARGET_COLUMNS.iter().map(|column| (*column, point.column(column).unwrap_or_default()));
+        Some(std::iter::once(("range_yards", target_range_m / YARDS_TO_METERS)).chain(columns).collect())
+    }
+
+    /// The solution at `solver_options.target_range_yards` as a string-keyed
+    /// hash, a range card row with the spin drift, wind drift and Mach too; nil without one
+    fn at_target(ruby: &Ruby, rb_self: &Self) -> Result<Option<RHash>, Error> {
+        rb_self.target_columns().map(|columns| target_hash(ruby, columns)).transpose()
+    }
+
+    /// Integration method the trajectory was solved with (`"euler"`, `"rk4"` or `"rkf45"`); nil for `pejsa` and `siacci`
+    fn integrator(&self) -> Option<&'static str> {
+        self.derived.integrator.map(Integrator::name)
+    }
+
+    /// Speed of sound in the atmosphere solved in, which the Mach numbers and
+    /// supersonic range are taken against, fps
+    fn speed_of_sound_fps(&self) -> f64 {
+        self.speed_of_sound_mps / FPS_TO_MPS
+    }
+
+    /// Spin drift at the end of the trajectory, metres
+    fn spin_drift_m(&self) -> f64 {
+        let last = self.inner.points.len().saturating_sub(1);
+        self.inner.points.last().map_or(0.0, |point| self.derived.spin.drift_at(point.time) + self.derived.repose_at(last).1)
+    }
+
+    /// Spin drift at the end of the trajectory, yards (0 unless solved with `spin_drift: true` or `solver: "mpm"`)
+    fn spin_drift_yards(&self) -> f64 {
+        self.spin_drift_m() / YARDS_TO_METERS
+    }
+
+    /// `{min:, nominal:, max:}` wind drift at the end of the trajectory within
+    /// the wind's uncertainty, yards
+    fn wind_drift_band(ruby: &Ruby, rb_self: &Self) -> Result<RHash, Error> {
+        let band = ruby.hash_new();
+        if let Some(last) = rb_self.inner.points.len().checked_sub(1) {
+            let point = rb_self.point(last);
+            band.aset(ruby.to_symbol("min"), point.wind_drift_min())?;
+            band.aset(ruby.to_symbol("nominal"), point.wind_drift())?;
+            band.aset(ruby.to_symbol("max"), point.wind_drift_max())?;
+        }
+        Ok(band)
+    }
+
+    /// `(horizontal, Eötvös)` deflection by the Earth's rotation at the end of the trajectory, metres
+    fn coriolis_m(&self) -> (f64, f64) {
+        let last = self.inner.points.len().saturating_sub(1);
+        self.derived.coriolis.as_ref().map_or((0.0, 0.0), |coriolis| coriolis.at(last))
+    }
+
+    /// Coriolis deflection at the end of the trajectory, yards, positive right (0 without `latitude_degrees`)
+    fn coriolis_horizontal_yards(&self) -> f64 {
+        self.coriolis_m().0 / YARDS_TO_METERS
+    }
+
+    /// Eötvös deflection, the vertical part of the Coriolis deflection, at the
+    /// end of the trajectory, yards, positive up (0 without `latitude_degrees`)
+    fn eotvos_yards(&self) -> f64 {
+        self.coriolis_m().1 / YARDS_TO_METERS
+    }
+
+    /// Fall of the level surface below the flat ground plane at the end of
+    /// the trajectory, yards (0 without `earth_curvature`)
+    fn curvature_drop_yards(&self) -> f64 {
+        match (self.derived.curvature.as_ref(), self.inner.points.first(), self.inner.points.last()) {
+            (Some(_), Some(first), Some(last)) => earth::level_drop(last.position.z - first.position.z) / YARDS_TO_METERS,
+            _ => 0.0,
+        }
+    }
+
+    /// Change in height at the end of the trajectory from gravity following
+    /// the Earth's curvature, yards, positive up (0 without `earth_curvature`)
+    fn curvature_correction_yards(&self) -> f64 {
+        let Some(curvature) = self.derived.curvature.as_ref() else {
+            return 0.0;
+        };
+        let points = &self.inner.points;
+        let (rise, range) = curvature.at(points.len().saturating_sub(1));
+        // The range moved back is made up along the path's slope at the end
+        let slope = match points.as_slice() {
+            [.., before, last] if last.position.z > before.position.z => {
+                (last.position.y - before.position.y) / (last.position.z - before.position.z)
+            }
+            _ => 0.0,
+        };
+        (rise - slope * range) / YARDS_TO_METERS
+    }
+
+    /// Change in height at the end of the trajectory from the WGS-84 normal
+    /// gravity, yards, positive up (0 without `wgs84_gravity`)
+    fn gravity_correction_yards(&self) -> f64 {
+        let last = self.inner.points.len().saturating_sub(1);
+        self.derived.gravity.as_ref().map_or(0.0, |gravity| gravity.at(last)) / YARDS_TO_METERS
+    }
+
+    /// `(horizontal, vertical)` deflection by the rifle's cant at the end of the trajectory, metres
+    fn cant_m(&self) -> (f64, f64) {
+        match (self.derived.cant.as_ref(), self.inner.points.first(), self.inner.points.last()) {
+            (Some(cant), Some(first), Some(last)) => cant.at(last.position.z - first.position.z),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Deflection by the rifle's cant at the end of the trajectory, yards,
+    /// positive right (0 without `cant_angle_degrees`)
+    fn cant_horizontal_yards(&self) -> f64 {
+        self.cant_m().0 / YARDS_TO_METERS
+    }
+
+    /// Deflection by the rifle's cant at the end of the trajectory, yards,
+    /// positive up (0 without `cant_angle_degrees`)
+    fn cant_vertical_yards(&self) -> f64 {
+        self.cant_m().1 / YARDS_TO_METERS
+    }
+
+    /// Output units with SI mode spelled out as metric units
+    fn display_units(&self) -> OutputUnits {
+        if self.units.si {
+            OutputUnits {
+                distance: Length::Meters,
+                drop: Length::Meters,
+                velocity: Velocity::Mps,
+                energy: Energy::Joules,
+                ..self.units
+            }
+        } else {
+            self.units
+        }
+    }
+
+    /// Non-fatal findings about the solve (marginal stability, transonic flight, ...)
+    fn warnings(&self) -> Vec<String> {
+        self.warnings.iter().map(|warning| warning.message.clone()).collect()
+    }
+
+    /// `[{code:, message:, range_yards:}, ...]`, in the order of `warnings`
+    ///
+    /// `range_yards` is where the finding applies from, or nil if it applies throughout.
+    fn warning_details(ruby: &Ruby, rb_self: &Self) -> Result<RArray, Error> {
+        let details = ruby.ary_new_capa(rb_self.warnings.len());
+        for warning in &rb_self.warnings {
+            let detail = ruby.hash_new();
+            detail.aset(ruby.to_symbol("code"), ruby.to_symbol(&warning.code))?;
+            detail.aset(ruby.to_symbol("message"), warning.message.as_str())?;
+            detail.aset(ruby.to_symbol("range_yards"), warning.range_m.map(|range_m| range_m / YARDS_TO_METERS))?;
+            details.push(detail)?;
+        }
+        Ok(details)
+    }
+
+    /// Range where the bullet slows below Mach 1.2 into the transonic region, yards
+    fn transonic_range_yards(&self) -> Option<f64> {
+        self.slows_below(warnings::TRANSONIC_MACH).map(|(range_m, _)| range_m / YARDS_TO_METERS)
+    }
+
+    /// Time of flight to Mach 1.2, seconds
+    fn transonic_time(&self) -> Option<f64> {
+        self.slows_below(warnings::TRANSONIC_MACH).map(|(_, time)| time)
+    }
+
+    /// Range where the bullet slowed to `solver_options.stop_at_velocity_fps`
+    /// or `stop_at_energy_ftlbs` and the trajectory ends, yards; nil if it
+    /// ended before then
+    fn stop_range_yards(&self) -> Option<f64> {
+        self.derived.stop.map(|_| self.max_range_yards())
+    }
+
+    /// Time of flight to that point, seconds
+    fn stop_time(&self) -> Option<f64> {
+        self.derived.stop.map(|_| self.time_of_flight())
+    }
+
+    /// Which threshold ended the trajectory, `"velocity"` or `"energy"`; nil if neither did
+    fn stop_reason(&self) -> Option<&'static str> {
+        self.derived.stop.map(Stop::name)
+    }
+
+    /// Range where the bullet slows below the speed of sound, yards
+    fn subsonic_range_yards(&self) -> Option<f64> {
+        self.slows_below(1.0).map(|(range_m, _)| range_m / YARDS_TO_METERS)
+    }
+
+    /// Time of flight to Mach 1.0, seconds
+    fn subsonic_time(&self) -> Option<f64> {
+        self.slows_below(1.0).map(|(_, time)| time)
+    }
+
+    /// `(range metres, time)` where the bullet first slows below `mach`, in the atmosphere solved in
+    fn slows_below(&self, mach: f64) -> Option<(f64, f64)> {
+        warnings::slows_below(&self.inner, mach * self.speed_of_sound_mps)
+    }
+
+    /// Sectional density of the bullet, lb/in²
+    fn sectional_density(&self) -> f64 {
+        self.derived.sectional_density
+    }
+
+    /// Form factor implied by the BC against the drag model (nil for banded BCs and drag curves)
+    fn form_factor(&self) -> Option<f64> {
+        self.derived.form_factor
+    }
+
+    /// Miller gyroscopic stability factor at the muzzle, in the atmosphere solved in
+    fn stability_factor(&self) -> f64 {
+        self.derived.stability_factor
+    }
+
+    /// result.points(format: :objects)
+    ///
+    /// A frozen array of `TrajectoryPoint`, or with `format: :columns` a hash of
+    /// flat Float arrays in the result's output units.
+    fn points(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Value, Error> {
+        if read_format(ruby, args)? == Format::Columns {
+            return Ok(rb_self.columns(ruby)?.as_value());
+        }
+        let points = ruby.ary_new_capa(rb_self.inner.points.len());
+        for point in rb_self.converted_points() {
+            points.push(point.into_frozen(ruby))?;
+        }
+        points.freeze();
+        Ok(points.as_value())
+    }
+
+    /// `{times:, x:, y:, z:, spin_drift:, wind_drift_min:, wind_drift_max:, velocity:, energy:, mach:, spin_rpm:,
+    /// yaw_of_repose:}`, one Float array per field
+    ///
+    /// Built straight from the engine points, without a Ruby object per point.
+    fn columns(&self, ruby: &Ruby) -> Result<RHash, Error> {
+        let units = self.display_units();
+        let points = self.converted_points();
+        let column = |value: &dyn Fn(&TrajectoryPoint) -> f64| ruby.ary_from_iter(points.iter().map(value));
+
+        let columns = ruby.hash_new();
+        columns.aset(ruby.to_symbol("times"), column(&TrajectoryPoint::time))?;
+        columns.aset(ruby.to_symbol("x"), column(&|p| units.distance.convert_yards(p.x())))?;
+        columns.aset(ruby.to_symbol("y"), column(&|p| units.drop.convert_yards(p.y())))?;
+        columns.aset(ruby.to_symbol("z"), column(&|p| units.drop.convert_yards(p.z())))?;
+        columns.aset(ruby.to_symbol("spin_drift"), column(&|p| units.drop.convert_yards(p.spin_drift())))?;
+        columns.aset(ruby.to_symbol("wind_drift_min"), column(&|p| units.drop.convert_yards(p.wind_drift_min())))?;
+        columns.aset(ruby.to_symbol("wind_drift_max"), column(&|p| units.drop.convert_yards(p.wind_drift_max())))?;
+        columns.aset(ruby.to_symbol("velocity"), column(&|p| units.velocity.convert_fps(p.velocity_fps())))?;
+        columns.aset(ruby.to_symbol("energy"), column(&|p| units.energy.convert_ftlbs(p.energy_ftlbs())))?;
+        columns.aset(ruby.to_symbol("mach"), column(&TrajectoryPoint::mach))?;
+        columns.aset(ruby.to_symbol("spin_rpm"), column(&TrajectoryPoint::spin_rpm))?;
+        columns.aset(ruby.to_symbol("yaw_of_repose"), column(&TrajectoryPoint::yaw_of_repose))?;
+        Ok(columns)
+    }
+
+    /// Yield each trajectory point to the current block, in time order
+    pub fn yield_points(&self, ruby: &Ruby) -> Result<(), Error> {
+        for point in self.converted_points() {
+            let _: Value = ruby.yield_value(point.into_frozen(ruby))?;
+        }
+        Ok(())
+    }
+
+    /// `Enumerator::Lazy` over the points; each `TrajectoryPoint` is built only when reached
+    fn lazy_points(rb_self: Obj<Self>) -> Result<Value, Error> {
+        rb_self.enumeratorize("each_point", ()).funcall("lazy", ())
+    }
+
+    /// Number of trajectory points, without converting them
+    fn point_count(&self) -> usize {
+        self.inner.points.len()
+    }
+
+    /// Yield each trajectory point, or return an Enumerator without a block
+    fn each_point(ruby: &Ruby, rb_self: Obj<Self>) -> Result<Value, Error> {
+        if !ruby.block_given() {
+            return Ok(rb_self.enumeratorize("each_point", ()).as_value());
+        }
+        rb_self.yield_points(ruby)?;
+        Ok(rb_self.as_value())
+    }
+
+    /// result.to_h(format: :objects)
+    ///
+    /// The hash returned by earlier versions of `BallisticsEngine.solve`. Values
+    /// and unit suffixes follow the `output_units` given to the solve; with
+    /// `format: :columns`, `"points"` is the `points(format: :columns)` hash.
+    fn to_h(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<RHash, Error> {
+        let format = read_format(ruby, args)?;
+        rb_self.hash(ruby, format)
+    }
+
+    fn hash(&self, ruby: &Ruby, format: Format) -> Result<RHash, Error> {
+        let result_hash = ruby.hash_new();
+        for (name, value) in self.summary_fields() {
+            result_hash.aset(name, value(ruby))?;
+        }
+        result_hash.aset("at_target", self.target_columns().map(|columns| target_hash(ruby, columns)).transpose()?)?;
+        result_hash.aset("points", self.point_hashes(ruby, format)?)?;
+        Ok(result_hash)
+    }
+
+    /// `"points"` of `to_h`
+    fn point_hashes(&self, ruby: &Ruby, format: Format) -> Result<Value, Error> {
+        let units = self.units;
+        Ok(match format {
+            Format::Columns => self.columns(ruby)?.as_value(),
+            // Points stay plain hashes here so hash consumers keep working
+            Format::Objects if units.si => ruby
+                .ary_try_from_iter(self.inner.points.iter().enumerate().map(|(index, point)| {
+                    let (repose, band) = (self.derived.repose_at(index), self.derived.wind_band_at(index));
+                    point::engine_point_to_si_h(ruby, point, self.sight_height_m, self.speed_of_sound_mps, self.derived.spin, repose, band)
+                }))?
+                .as_value(),
+            Format::Objects => ruby
+                .ary_try_from_iter(self.converted_points().iter().map(|point| point.to_h_in(ruby, units)))?
+                .as_value(),
+        })
+    }
+
+    /// Summary keys of `to_h`, in the output units, or as-is (metres, seconds,
+    /// m/s and joules) for an SI solve
+    fn summary_fields(&self) -> Vec<SummaryField<'_>> {
+        let units = self.units;
+        let near_zero = move || Self::near_zero(self.converted_points());
+        let far_zero = move || Self::far_zero(self.converted_points());
+        if units.si {
+            return vec![
+                field("max_range_m", || self.inner.max_range),
+                field("max_height_m", || self.inner.max_height),
+                field("time_of_flight_s", || self.inner.time_of_flight),
+                field("impact_velocity_mps", || self.inner.impact_velocity),
+                field("impact_energy_j", || self.inner.impact_energy),
+                field("spin_drift_m", || self.spin_drift_m()),
+                field("speed_of_sound_mps", || self.speed_of_sound_mps),
+                field("air_density_kg_m3", || self.air_density_kg_m3()),
+                field("density_altitude_m", || self.density_altitude_feet() * FEET_TO_METERS),
+                field("integrator", || self.integrator()),
+                field("apex_range_m", || self.apex_range_yards() * YARDS_TO_METERS),
+                field("apex_time_s", || self.apex_time()),
+                field("descent_angle_rad", || self.descent_angle()),
+                field("near_zero_m", move || near_zero().map(|(yards, _)| yards * YARDS_TO_METERS)),
+                field("near_zero_time_s", move || near_zero().map(|(_, time)| time)),
+                field("far_zero_m", move || far_zero().map(|(yards, _)| yards * YARDS_TO_METERS)),
+                field("far_zero_time_s", move || far_zero().map(|(_, time)| time)),
+            ];
+        }
+        let distance = units.distance.suffix();
+        vec![
+            field(format!("max_range_{}", distance), move || units.distance.convert_yards(self.max_range_yards())),
+            field(format!("max_height_{}", distance), move || units.distance.convert_yards(self.max_height_yards())),
+            field("time_of_flight", || self.time_of_flight()),
+            field(format!("impact_velocity_{}", units.velocity.suffix()), move || units.velocity.convert_fps(self.impact_velocity_fps())),
+            field(format!("impact_energy_{}", units.energy.suffix()), move || units.energy.convert_ftlbs(self.impact_energy_ftlbs())),
+            field(format!("spin_drift_{}", units.drop.suffix()), move || units.drop.convert_yards(self.spin_drift_yards())),
+            field(format!("speed_of_sound_{}", units.velocity.suffix()), move || units.velocity.convert_fps(self.speed_of_sound_fps())),
+            field("air_density_kg_m3", || self.air_density_kg_m3()),
+            field("density_altitude_feet", || self.density_altitude_feet()),
+            field("integrator", || self.integrator()),
+            field(format!("apex_range_{}", distance), move || units.distance.convert_yards(self.apex_range_yards())),
+            field("apex_time", || self.apex_time()),
+            field("descent_angle_degrees", || self.descent_angle_degrees()),
+            field(format!("near_zero_{}", distance), move || near_zero().map(|(yards, _)| units.distance.convert_yards(yards))),
+            field("near_zero_time", move || near_zero().map(|(_, time)| time)),
+            field(format!("far_zero_{}", distance), move || far_zero().map(|(yards, _)| units.distance.convert_yards(yards))),
+            field("far_zero_time", move || far_zero().map(|(_, time)| time)),
+        ]
+    }
+
+    /// The full trajectory as a JSON string, serialized without building Ruby hashes
+    ///
+    /// Arguments passed by `JSON.generate`/`to_json(state)` are ignored.
+    fn to_json(ruby: &Ruby, rb_self: &Self, _args: &[Value]) -> Result<String, Error> {
+        let json = ResultJson {
+            max_range_yards: rb_self.max_range_yards(),
+            max_height_yards: rb_self.max_height_yards(),
+            time_of_flight_s: rb_self.time_of_flight(),
+            impact_velocity_fps: rb_self.impact_velocity_fps(),
+            impact_energy_ftlbs: rb_self.impact_energy_ftlbs(),
+            spin_drift_yards: rb_self.spin_drift_yards(),
+            speed_of_sound_fps: rb_self.speed_of_sound_fps(),
+            air_density_kg_m3: rb_self.air_density_kg_m3(),
+            density_altitude_feet: rb_self.density_altitude_feet(),
+            integrator: rb_self.integrator(),
+            apex_range_yards: rb_self.apex_range_yards(),
+            apex_time_s: rb_self.apex_time(),
+            descent_angle_degrees: rb_self.descent_angle_degrees(),
+            near_zero_yards: rb_self.near_zero_yards(),
+            near_zero_time_s: rb_self.near_zero_time(),
+            far_zero_yards: rb_self.far_zero_yards(),
+            far_zero_time_s: rb_self.far_zero_time(),
+            at_target: rb_self.target_columns().map(BTreeMap::from_iter),
+            points: rb_self.converted_points(),
+        };
+        serde_json::to_string(&json).map_err(|e| Error::new(ruby.exception_runtime_error(), e.to_string()))
+    }
+
+    /// result.at_range(yards, interpolation: :linear)
+    ///
+    /// Interpolated `TrajectoryPoint` at a downrange distance between the stored points.
+    fn at_range(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Obj<TrajectoryPoint>, Error> {
+        let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let yards: f64 = problems.required("yards", Some(args.required.0));
+        let method = read_method(ruby, args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+
+        let points = rb_self.converted_points();
+        interpolation::interpolate(points, TrajectoryPoint::x, yards, method)
+            .map(|point| point.into_frozen(ruby))
+            .ok_or_else(|| out_of_range(ruby, "yards", yards, points, TrajectoryPoint::x))
+    }
+
+    /// result.at_time(seconds, interpolation: :linear)
+    ///
+    /// Interpolated `TrajectoryPoint` at a time of flight, sharing `at_range`'s machinery.
+    fn at_time(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Obj<TrajectoryPoint>, Error> {
+        let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let seconds: f64 = problems.required("seconds", Some(args.required.0));
+        let method = read_method(ruby, args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+
+        let points = rb_self.converted_points();
+        interpolation::interpolate(points, TrajectoryPoint::time, seconds, method)
+            .map(|point| point.into_frozen(ruby))
+            .ok_or_else(|| out_of_range(ruby, "seconds", seconds, points, TrajectoryPoint::time))
+    }
+
+    /// result.to_csv(columns: nil, headers: true)
+    ///
+    /// One row per trajectory point. `columns` is an array of names (strings or
+    /// symbols) from `TrajectoryPoint::COLUMNS`.
+    fn to_csv(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<String, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let kwargs = get_kwargs::<_, (), (Option<Vec<Value>>, Option<bool>), ()>(
+            args.keywords,
+            &[],
+            &["columns", "headers"],
+        )
+        .map_err(|e| error::as_input_error(ruby, e))?;
+        let (columns, headers) = kwargs.optional;
+
+        let columns = match columns {
+            Some(columns) => columns
+                .into_iter()
+                .map(|column| column.funcall("to_s", ()))
+                .collect::<Result<Vec<String>, Error>>()?,
+            None => DEFAULT_CSV_COLUMNS.iter().map(|column| column.to_string()).collect(),
+        };
+        let mut problems = Problems::default();
+        for column in columns.iter().filter(|column| !point::COLUMNS.contains(&column.as_str())) {
+            problems.add(
+                "columns",
+                format!("columns has unknown column {:?} (expected one of {})", column, point::COLUMNS.join(", ")),
+            );
+        }
+        problems.check("columns", !columns.is_empty(), "must not be empty");
+        problems.into_result(ruby)?;
+
+        let mut csv = String::new();
+        if headers.unwrap_or(true) {
+            csv.push_str(&columns.join(","));
+            csv.push('\n');
+        }
+        for index in 0..rb_self.inner.points.len() {
+            let point = rb_self.point(index);
+            for (position, column) in columns.iter().enumerate() {
+                if position > 0 {
+                    csv.push(',');
+                }
+                write!(csv, "{}", point.column(column).unwrap_or_default()).unwrap();
+            }
+            csv.push('\n');
+        }
+        Ok(csv)
+    }
+
+    /// `(range yards, time)` where the bullet first rises through the line of sight
+    fn near_zero(points: &[TrajectoryPoint]) -> Option<(f64, f64)> {
+        points.windows(2).find(|pair| pair[0].y() < 0.0 && pair[1].y() >= 0.0).map(|pair| sight_crossing(&pair[0], &pair[1]))
+    }
+
+    /// `(range yards, time)` where the bullet falls back through the line of sight
+    fn far_zero(points: &[TrajectoryPoint]) -> Option<(f64, f64)> {
+        points.windows(2).rev().find(|pair| pair[0].y() >= 0.0 && pair[1].y() < 0.0).map(|pair| sight_crossing(&pair[0], &pair[1]))
+    }
+
+    /// Range of the near zero, yards; nil if the bullet never rises through the line of sight
+    fn near_zero_yards(&self) -> Option<f64> {
+        Self::near_zero(self.converted_points()).map(|(yards, _)| yards)
+    }
+
+    /// Time of flight to the near zero, seconds
+    fn near_zero_time(&self) -> Option<f64> {
+        Self::near_zero(self.converted_points()).map(|(_, time)| time)
+    }
+
+    /// Range of the far zero, yards; nil if the bullet never falls back through the line of sight
+    fn far_zero_yards(&self) -> Option<f64> {
+        Self::far_zero(self.converted_points()).map(|(yards, _)| yards)
+    }
+
+    /// Time of flight to the far zero, seconds
+    fn far_zero_time(&self) -> Option<f64> {
+        Self::far_zero(self.converted_points()).map(|(_, time)| time)
+    }
+
+    /// result.danger_space(yards, target_height_inches:)
+    ///
+    /// `{near_limit_yards:, far_limit_yards:, danger_space_yards:}` over which a
+    /// bullet aimed at the centre of a target `target_height_inches` tall at
+    /// `yards` stays within its height. The aim passes through the bullet at
+    /// `yards`, as if held for that range; the near limit is the first point's
+    /// range if the bullet is within the height all the way from the muzzle, and
+    /// the far limit (and so the danger space) nil if it still is where the
+    /// trajectory ends.
+    fn danger_space(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<RHash, Error> {
+        let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+        let kwargs = get_kwargs::<_, (Value,), (), ()>(args.keywords, &["target_height_inches"], &[])
+            .map_err(|e| error::as_input_error(ruby, e))?;
+        let mut problems = Problems::default();
+        let yards: f64 = problems.required("yards", Some(args.required.0));
+        let height_inches: f64 = problems.required("target_height_inches", Some(kwargs.required.0));
+        problems.check_positive("yards", yards);
+        problems.check_positive("target_height_inches", height_inches);
+        problems.into_result(ruby)?;
+
+        let points = rb_self.converted_points();
+        let aim = interpolation::interpolate(points, TrajectoryPoint::x, yards, Method::Linear)
+            .ok_or_else(|| out_of_range(ruby, "yards", yards, points, TrajectoryPoint::x))?;
+        // Distance outside the target from its centre line through the aim, yards; negative inside
+        let half_height = height_inches / 2.0 * INCHES_TO_METERS / YARDS_TO_METERS;
+        let outside = |point: &TrajectoryPoint| (point.y() - aim.y() * point.x() / yards).abs() - half_height;
+        let limit = |side: &mut dyn Iterator<Item = &TrajectoryPoint>| {
+            let mut inside = &aim;
+            for point in side {
+                if outside(point) > 0.0 {
+                    return Some(crossing(inside, point, outside(inside), outside(point)));
+                }
+                inside = point;
+            }
+            None
+        };
+        let split = points.partition_point(|point| point.x() < yards);
+        let near = limit(&mut points[..split].iter().rev()).unwrap_or_else(|| points[0].x());
+        let far = limit(&mut points[split..].iter());
+
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("near_limit_yards"), near)?;
+        hash.aset(ruby.to_symbol("far_limit_yards"), far)?;
+        hash.aset(ruby.to_symbol("danger_space_yards"), far.map(|far| far - near))?;
+        Ok(hash)
+    }
+
+    /// result.events(ranges_yards: [], velocities_fps: [], energies_ftlbs: [])
+    ///
+    /// `[{event:, threshold:, range_yards:, time:, point:}, ...]` in time
+    /// order: the apex, near and far zeros, Mach 1.2 (`:transonic`) and 1.0
+    /// (`:subsonic`), the end of the trajectory (`:impact`), and where the
+    /// bullet passes each of `ranges_yards` (`:range`) and slows to each of
+    /// `velocities_fps` (`:velocity`) and `energies_ftlbs` (`:energy`), with
+    /// that value as `threshold` (nil for the others). `point` is the
+    /// `TrajectoryPoint` interpolated there; events the trajectory does not
+    /// reach are left out.
+    fn events(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<RArray, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let kwargs = get_kwargs::<_, (), (Option<Vec<f64>>, Option<Vec<f64>>, Option<Vec<f64>>), ()>(
+            args.keywords,
+            &[],
+            &["ranges_yards", "velocities_fps", "energies_ftlbs"],
+        )
+        .map_err(|e| error::as_input_error(ruby, e))?;
+        let (ranges, velocities, energies) = kwargs.optional;
+        let (ranges, velocities, energies) = (ranges.unwrap_or_default(), velocities.unwrap_or_default(), energies.unwrap_or_default());
+        let mut problems = Problems::default();
+        for (field, values) in [("ranges_yards", &ranges), ("velocities_fps", &velocities), ("energies_ftlbs", &energies)] {
+            for (index, value) in values.iter().enumerate() {
+                problems.check_positive(&format!("{}[{}]", field, index), *value);
+            }
+        }
+        problems.into_result(ruby)?;
+
+        let points = rb_self.converted_points();
+        // (event, threshold, time)
+        let mut events: Vec<(&str, Option<f64>, f64)> = Vec::new();
+        let apex = rb_self.apex_index();
+        if apex > 0 {
+            events.push(("apex", None, points[apex].time()));
+        }
+        events.extend(Self::near_zero(points).map(|(_, time)| ("near_zero", None, time)));
+        events.extend(Self::far_zero(points).map(|(_, time)| ("far_zero", None, time)));
+        events.extend(rb_self.slows_below(warnings::TRANSONIC_MACH).map(|(_, time)| ("transonic", None, time)));
+        events.extend(rb_self.slows_below(1.0).map(|(_, time)| ("subsonic", None, time)));
+        events.extend(points.last().map(|point| ("impact", None, point.time())));
+        for yards in ranges {
+            let point = interpolation::interpolate(points, TrajectoryPoint::x, yards, Method::Linear);
+            events.extend(point.map(|point| ("range", Some(yards), point.time())));
+        }
+        for fps in velocities {
+            events.extend(warnings::slows_below(&rb_self.inner, fps * FPS_TO_MPS).map(|(_, time)| ("velocity", Some(fps), time)));
+        }
+        for ftlbs in energies {
+            let crossing = points.windows(2).find(|pair| pair[0].energy_ftlbs() >= ftlbs && pair[1].energy_ftlbs() < ftlbs);
+            events.extend(crossing.map(|pair| {
+                let fraction = (pair[0].energy_ftlbs() - ftlbs) / (pair[0].energy_ftlbs() - pair[1].energy_ftlbs());
+                ("energy", Some(ftlbs), pair[0].time() + (pair[1].time() - pair[0].time()) * fraction)
+            }));
+        }
+        events.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+        let array = ruby.ary_new_capa(events.len());
+        for (event, threshold, time) in events {
+            let Some(point) = interpolation::interpolate(points, TrajectoryPoint::time, time, Method::Linear) else {
+                continue;
+            };
+            let hash = ruby.hash_new();
+            hash.aset(ruby.to_symbol("event"), ruby.to_symbol(event))?;
+            hash.aset(ruby.to_symbol("threshold"), threshold)?;
+            hash.aset(ruby.to_symbol("range_yards"), point.x())?;
+            hash.aset(ruby.to_symbol("time"), time)?;
+            hash.aset(ruby.to_symbol("point"), point.into_frozen(ruby))?;
+            array.push(hash)?;
+        }
+        Ok(array)
+    }
+
+    /// result.max_ordinate(yards)
+    ///
+    /// `{height_in:, range_yards:}` of the bullet's highest point above the line
+    /// of sight between the muzzle and `yards`, for clearing berms, vehicles and
+    /// positions short of the target. The height at `yards` itself counts, so it
+    /// is the highest point there if the bullet is still rising.
+    fn max_ordinate(ruby: &Ruby, rb_self: &Self, yards: f64) -> Result<RHash, Error> {
+        let mut problems = Problems::default();
+        problems.check_positive("yards", yards);
+        problems.into_result(ruby)?;
+
+        let points = rb_self.converted_points();
+        let end = interpolation::interpolate(points, TrajectoryPoint::x, yards, Method::Linear)
+            .ok_or_else(|| out_of_range(ruby, "yards", yards, points, TrajectoryPoint::x))?;
+        let highest = points
+            .iter()
+            .take_while(|point| point.x() < yards)
+            .fold(&end, |highest, point| if point.y() > highest.y() { point } else { highest });
+
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("height_in"), highest.y() * YARDS_TO_METERS / INCHES_TO_METERS)?;
+        hash.aset(ruby.to_symbol("range_yards"), highest.x())?;
+        Ok(hash)
+    }
+
+    /// Range where the bullet drops below the speed of sound, in yards
+    fn supersonic_yards(&self, points: &[TrajectoryPoint]) -> Option<f64> {
+        let sound_fps = self.speed_of_sound_mps / FPS_TO_MPS;
+        points
+            .windows(2)
+            .find(|pair| pair[0].velocity_fps() >= sound_fps && pair[1].velocity_fps() < sound_fps)
+            .map(|pair| crossing(&pair[0], &pair[1], pair[0].velocity_fps() - sound_fps, pair[1].velocity_fps() - sound_fps))
+    }
+
+    /// result.summary
+    ///
+    /// Multi-line overview for logs and CLI output, in the result's output units.
+    fn summary(&self) -> String {
+        let units = self.display_units();
+        let distance = |yards: f64| format!("{:.1} {}", units.distance.convert_yards(yards), units.distance.suffix());
+        let points = self.converted_points();
+
+        let mut summary = String::new();
+        match Self::far_zero(points).map(|(yards, _)| yards) {
+            Some(zero) => writeln!(summary, "Zero: {}", distance(zero)),
+            None => writeln!(summary, "Zero: none (never crosses the line of sight)"),
+        }
+        .unwrap();
+        if let Some(apex) = points.iter().max_by(|a, b| a.y().total_cmp(&b.y())) {
+            writeln!(
+                summary,
+                "Max ordinate: {:.2} {} at {}",
+                units.drop.convert_yards(apex.y()),
+                units.drop.suffix(),
+                distance(apex.x()),
+            )
+            .unwrap();
+        }
+        match self.supersonic_yards(points) {
+            Some(transonic) => writeln!(summary, "Supersonic range: {}", distance(transonic)),
+            None if self.impact_velocity_fps() * FPS_TO_MPS >= self.speed_of_sound_mps => {
+                writeln!(summary, "Supersonic range: beyond {}", distance(self.max_range_yards()))
+            }
+            None => writeln!(summary, "Supersonic range: none (subsonic at the muzzle)"),
+        }
+        .unwrap();
+        writeln!(
+            summary,
+            "Impact: {:.1} {}, {:.1} {} at {}",
+            units.velocity.convert_fps(self.impact_velocity_fps()),
+            units.velocity.suffix(),
+            units.energy.convert_ftlbs(self.impact_energy_ftlbs()),
+            units.energy.suffix(),
+            distance(self.max_range_yards()),
+        )
+        .unwrap();
+        if self.derived.coriolis.is_some() {
+            let (horizontal, eotvos) = (self.coriolis_horizontal_yards(), self.eotvos_yards());
+            writeln!(
+                summary,
+                "Coriolis: {:.2} {} {}, Eötvös: {:.2} {} {}",
+                units.drop.convert_yards(horizontal.abs()),
+                units.drop.suffix(),
+                if horizontal < 0.0 { "left" } else { "right" },
+                units.drop.convert_yards(eotvos.abs()),
+                units.drop.suffix(),
+                if eotvos < 0.0 { "down" } else { "up" },
+            )
+            .unwrap();
+        }
+        if self.derived.cant.is_some() {
+            let (horizontal, vertical) = (self.cant_horizontal_yards(), self.cant_vertical_yards());
+            writeln!(
+                summary,
+                "Cant: {:.2} {} {}, {:.2} {} {}",
+                units.drop.convert_yards(horizontal.abs()),
+                units.drop.suffix(),
+                if horizontal < 0.0 { "left" } else { "right" },
+                units.drop.convert_yards(vertical.abs()),
+                units.drop.suffix(),
+                if vertical < 0.0 { "down" } else { "up" },
+            )
+            .unwrap();
+        }
+        if self.derived.curvature.is_some() {
+            let correction = self.curvature_correction_yards();
+            writeln!(
+                summary,
+                "Earth curvature: level falls {:.2} {} below flat, trajectory {:.2} {} {}",
+                units.drop.convert_yards(self.curvature_drop_yards()),
+                units.drop.suffix(),
+                units.drop.convert_yards(correction.abs()),
+                units.drop.suffix(),
+                if correction < 0.0 { "down" } else { "up" },
+            )
+            .unwrap();
+        }
+        if self.derived.gravity.is_some() {
+            let correction = self.gravity_correction_yards();
+            writeln!(
+                summary,
+                "WGS-84 gravity: {:.2} {} {}",
+                units.drop.convert_yards(correction.abs()),
+                units.drop.suffix(),
+                if correction < 0.0 { "down" } else { "up" },
+            )
+            .unwrap();
+        }
+        let (density_altitude, altitude_unit) = match units.distance {
+            Length::Yards | Length::Feet | Length::Inches => (self.density_altitude_feet(), "feet"),
+            _ => (self.density_altitude_feet() * FEET_TO_METERS, "meters"),
+        };
+        writeln!(summary, "Air density: {:.4} kg/m³ (density altitude {:.0} {})", self.air_density_kg_m3(), density_altitude, altitude_unit).unwrap();
+        write!(summary, "Time of flight: {:.3} s", self.time_of_flight()).unwrap();
+        summary
+    }
+
+    /// `#<BallisticsEngine::TrajectoryResult range=1092.2yards tof=2.172s impact=969.3fps points=221>`
+    ///
+    /// In the result's output units; the warning count is added when there are any.
+    fn inspect(&self) -> String {
+        let units = self.display_units();
+        let mut inspect = format!(
+            "#<BallisticsEngine::TrajectoryResult range={:.1}{} tof={:.3}s impact={:.1}{} points={}",
+            units.distance.convert_yards(self.max_range_yards()),
+            units.distance.suffix(),
+            self.time_of_flight(),
+            units.velocity.convert_fps(self.impact_velocity_fps()),
+            units.velocity.suffix(),
+            self.point_count(),
+        );
+        if !self.warnings.is_empty() {
+            write!(inspect, " warnings={}", self.warnings.len()).unwrap();
+        }
+        inspect.push('>');
+        inspect
+    }
+
+    /// `pp` prints the `inspect` line rather than the object's internals
+    fn pretty_print(&self, q: Value) -> Result<(), Error> {
+        let _: Value = q.funcall("text", (self.inspect(),))?;
+        Ok(())
+    }
+
+    /// One row per range from `card.start` to `card.stop`, interpolated between points
+    pub fn range_card(&self, ruby: &Ruby, card: RangeCard) -> Result<RArray, Error> {
+        let points = self.converted_points();
+        let rows = ruby.ary_new();
+        for yards in card.ranges() {
+            let point = interpolation::interpolate(points, TrajectoryPoint::x, yards, Method::Linear)
+                .ok_or_else(|| out_of_range(ruby, "range", yards, points, TrajectoryPoint::x))?;
+            let row = ruby.hash_new();
+            row.aset("range_yards", yards)?;
+            for column in RANGE_CARD_COLUMNS {
+                row.aset(*column, point.column(column))?;
+            }
+            if let Some(cant) = &self.derived.cant {
+                let (horizontal, vertical) = cant.at(yards * YARDS_TO_METERS);
+                row.aset("cant_horizontal_in", horizontal / INCHES_TO_METERS)?;
+                row.aset("cant_vertical_in", vertical / INCHES_TO_METERS)?;
+            }
+            rows.push(row)?;
+        }
+        Ok(rows)
+    }
+
+    /// result.range_card(start:, stop:, step:) over the solved trajectory
+    fn range_card_kw(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<RArray, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let card = RangeCard::from_kwargs(ruby, args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+        rb_self.range_card(ruby, card)
+    }
+
+    /// Marshal support: `Marshal.dump(result)`
+    fn dump(ruby: &Ruby, rb_self: &Self, _level: i32) -> Result<String, Error> {
+        let inner = &rb_self.inner;
+        let dump = Dump {
+            version: DUMP_VERSION,
+            max_range: inner.max_range,
+            max_height: inner.max_height,
+            time_of_flight: inner.time_of_flight,
+            impact_velocity: inner.impact_velocity,
+            impact_energy: inner.impact_energy,
+            points: inner
+                .points
+                .iter()
+                .map(|point| [point.time, point.position.x, point.position.y, point.position.z, point.velocity_magnitude, point.kinetic_energy])
+                .collect(),
+            sight_height_m: rb_self.sight_height_m,
+            speed_of_sound_mps: rb_self.speed_of_sound_mps,
+            units: rb_self.units,
+            warnings: rb_self.warnings.clone(),
+            derived: rb_self.derived.clone(),
+        };
+        serde_json::to_string(&dump).map_err(|e| Error::new(ruby.exception_runtime_error(), e.to_string()))
+    }
+
+    /// Marshal support: rebuild a result from `_dump` output
+    fn load(ruby: &Ruby, data: String) -> Result<Obj<Self>, Error> {
+        let dump: Dump = serde_json::from_str(&data)
+            .map_err(|e| Error::new(error::input_error(ruby), format!("Invalid TrajectoryResult dump: {}", e)))?;
+        if dump.version != DUMP_VERSION {
+            return Err(Error::new(
+                error::input_error(ruby),
+                format!("Unsupported TrajectoryResult dump version {}", dump.version),
+            ));
+        }
+
+        let inner = EngineTrajectoryResult {
+            max_range: dump.max_range,
+            max_height: dump.max_height,
+            time_of_flight: dump.time_of_flight,
+            impact_velocity: dump.impact_velocity,
+            impact_energy: dump.impact_energy,
+            points: dump
+                .points
+                .into_iter()
+                .map(|[time, x, y, z, velocity_magnitude, kinetic_energy]| EngineTrajectoryPoint {
+                    time,
+                    position: Vector3::new(x, y, z),
+                    velocity_magnitude,
+                    kinetic_energy,
+                })
+                .collect(),
+            sampled_points: None,
+            min_pitch_damping: None,
+            transonic_mach: None,
+            angular_state: None,
+            max_yaw_angle: None,
+            max_precession_angle: None,
+        };
+        Ok(Self::new(ruby, inner, dump.sight_height_m, dump.speed_of_sound_mps, dump.units, dump.warnings, dump.derived))
+    }
+
+    /// Hash-style access (`result["max_range_yards"]` or `result[:max_range_yards]`)
+    ///
+    /// Reads the one key, through its getter; only `"points"` builds the point hashes.
+    fn aref(ruby: &Ruby, rb_self: &Self, key: Value) -> Result<Value, Error> {
+        let key: String = key.funcall("to_s", ())?;
+        match key.as_str() {
+            "points" => rb_self.point_hashes(ruby, Format::Objects),
+            "at_target" => Ok(ruby.into_value(rb_self.target_columns().map(|columns| target_hash(ruby, columns)).transpose()?)),
+            _ => Ok(rb_self
+                .summary_fields()
+                .into_iter()
+                .find(|(name, _)| *name == key)
+                .map_or_else(|| ruby.qnil().as_value(), |(_, value)| value(ruby))),
+        }
+    }
+}
+
+/// The `interpolation:` keyword, `:linear` (default) or `:cubic`
+fn read_method(ruby: &Ruby, kw: RHash, problems: &mut Problems) -> Result<Method, Error> {
+    let kwargs = get_kwargs::<_, (), (Option<Value>,), ()>(kw, &[], &["interpolation"])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let name = match kwargs.optional.0 {
+        Some(name) if !name.is_nil() => name.funcall::<_, _, String>("to_s", ())?,
+        _ => return Ok(Method::Linear),
+    };
+    Ok(Method::from_name(&name).unwrap_or_else(|| {
+        problems.add("interpolation", "interpolation must be linear or cubic".to_string());
+        Method::Linear
+    }))
+}
+
+/// The `format:` keyword, `:objects` (default) or `:columns`
+fn read_format(ruby: &Ruby, args: &[Value]) -> Result<Format, Error> {
+    let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+    let kwargs = get_kwargs::<_, (), (Option<Value>,), ()>(args.keywords, &[], &["format"])
+        .map_err(|e| error::as_input_error(ruby, e))?;
+    let name = match kwargs.optional.0 {
+        Some(name) if !name.is_nil() => name.funcall::<_, _, String>("to_s", ())?,
+        _ => return Ok(Format::Objects),
+    };
+    match name.as_str() {
+        "objects" => Ok(Format::Objects),
+        "columns" => Ok(Format::Columns),
+        _ => Err(Error::new(error::input_error(ruby), "Invalid inputs: format must be objects or columns")),
+    }
+}
+
+/// Downrange distance between two points where a value linear in range crosses zero
+fn crossing(before: &TrajectoryPoint, after: &TrajectoryPoint, value_before: f64, value_after: f64) -> f64 {
+    let fraction = value_before / (value_before - value_after);
+    before.x() + (after.x() - before.x()) * fraction
+}
+
+/// `(range yards, time)` between two points where the bullet crosses the line of sight
+fn sight_crossing(before: &TrajectoryPoint, after: &TrajectoryPoint) -> (f64, f64) {
+    let fraction = before.y() / (before.y() - after.y());
+    (before.x() + (after.x() - before.x()) * fraction, before.time() + (after.time() - before.time()) * fraction)
+}
+
+/// String-keyed hash of `at_target`'s columns
+fn target_hash(ruby: &Ruby, columns: Vec<(&str, f64)>) -> Result<RHash, Error> {
+    let hash = ruby.hash_new();
+    for (column, value) in columns {
+        hash.aset(column, value)?;
+    }
+    Ok(hash)
+}
+
+/// `InputError` for a lookup outside the stored points
+fn out_of_range(ruby: &Ruby, field: &str, value: f64, points: &[TrajectoryPoint], key: impl Fn(&TrajectoryPoint) -> f64) -> Error {
+    let first = points.first().map(&key).unwrap_or_default();
+    let last = points.last().map(&key).unwrap_or_default();
+    Error::new(
+        error::input_error(ruby),
+        format!("Invalid inputs: {} ({}) must be between {} and {}", field, value, first, last),
+    )
+}
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("TrajectoryResult", ruby.class_object())?;
+    class.undef_default_alloc_func();
+
+    class.define_method("max_range_yards", method!(TrajectoryResult::max_range_yards, 0))?;
+    class.define_method("max_height_yards", method!(TrajectoryResult::max_height_yards, 0))?;
+    class.define_method("time_of_flight", method!(TrajectoryResult::time_of_flight, 0))?;
+    class.define_method("muzzle_velocity_fps", method!(TrajectoryResult::muzzle_velocity_fps, 0))?;
+    class.define_method("impact_velocity_fps", method!(TrajectoryResult::impact_velocity_fps, 0))?;
+    class.define_method("impact_energy_ftlbs", method!(TrajectoryResult::impact_energy_ftlbs, 0))?;
+    class.define_method("spin_drift_yards", method!(TrajectoryResult::spin_drift_yards, 0))?;
+    class.define_method("speed_of_sound_fps", method!(TrajectoryResult::speed_of_sound_fps, 0))?;
+    class.define_method("air_density_kg_m3", method!(TrajectoryResult::air_density_kg_m3, 0))?;
+    class.define_method("density_altitude_feet", method!(TrajectoryResult::density_altitude_feet, 0))?;
+    class.define_method("integrator", method!(TrajectoryResult::integrator, 0))?;
+    class.define_method("at_target", method!(TrajectoryResult::at_target, 0))?;
+    class.define_method("apex_range_yards", method!(TrajectoryResult::apex_range_yards, 0))?;
+    class.define_method("apex_time", method!(TrajectoryResult::apex_time, 0))?;
+    class.define_method("descending_points", method!(TrajectoryResult::descending_points, 0))?;
+    class.define_method("descent_angle_degrees", method!(TrajectoryResult::descent_angle_degrees, 0))?;
+    class.define_method("near_zero_yards", method!(TrajectoryResult::near_zero_yards, 0))?;
+    class.define_method("near_zero_time", method!(TrajectoryResult::near_zero_time, 0))?;
+    class.define_method("far_zero_yards", method!(TrajectoryResult::far_zero_yards, 0))?;
+    class.define_method("far_zero_time", method!(TrajectoryResult::far_zero_time, 0))?;
+    class.define_method("danger_space", method!(TrajectoryResult::danger_space, -1))?;
+    class.define_method("max_ordinate", method!(TrajectoryResult::max_ordinate, 1))?;
+    class.define_method("events", method!(TrajectoryResult::events, -1))?;
+    class.define_method("wind_drift_band", method!(TrajectoryResult::wind_drift_band, 0))?;
+    class.define_method("coriolis_horizontal_yards", method!(TrajectoryResult::coriolis_horizontal_yards, 0))?;
+    class.define_method("eotvos_yards", method!(TrajectoryResult::eotvos_yards, 0))?;
+    class.define_alias("coriolis_vertical_yards", "eotvos_yards")?;
+    class.define_method("curvature_drop_yards", method!(TrajectoryResult::curvature_drop_yards, 0))?;
+    class.define_method("curvature_correction_yards", method!(TrajectoryResult::curvature_correction_yards, 0))?;
+    class.define_method("gravity_correction_yards", method!(TrajectoryResult::gravity_correction_yards, 0))?;
+    class.define_method("cant_horizontal_yards", method!(TrajectoryResult::cant_horizontal_yards, 0))?;
+    class.define_method("cant_vertical_yards", method!(TrajectoryResult::cant_vertical_yards, 0))?;
+
+    // Short names, same units as the long ones
+    class.define_alias("max_range", "max_range_yards")?;
+    class.define_alias("max_height", "max_height_yards")?;
+    class.define_alias("impact_velocity", "impact_velocity_fps")?;
+    class.define_alias("impact_energy", "impact_energy_ftlbs")?;
+
+    class.define_method("warnings", method!(TrajectoryResult::warnings, 0))?;
+    class.define_method("warning_details", method!(TrajectoryResult::warning_details, 0))?;
+    class.define_method("transonic_range_yards", method!(TrajectoryResult::transonic_range_yards, 0))?;
+    class.define_method("transonic_time", method!(TrajectoryResult::transonic_time, 0))?;
+    class.define_method("stop_range_yards", method!(TrajectoryResult::stop_range_yards, 0))?;
+    class.define_method("stop_time", method!(TrajectoryResult::stop_time, 0))?;
+    class.define_method("stop_reason", method!(TrajectoryResult::stop_reason, 0))?;
+    class.define_method("subsonic_range_yards", method!(TrajectoryResult::subsonic_range_yards, 0))?;
+    class.define_method("subsonic_time", method!(TrajectoryResult::subsonic_time, 0))?;
+    class.define_alias("transonic_range", "transonic_range_yards")?;
+    class.define_alias("mach_1_2_range", "transonic_range_yards")?;
+    class.define_alias("mach_1_2_time", "transonic_time")?;
+    class.define_alias("subsonic_range", "subsonic_range_yards")?;
+    class.define_method("sectional_density", method!(TrajectoryResult::sectional_density, 0))?;
+    class.define_method("form_factor", method!(TrajectoryResult::form_factor, 0))?;
+    class.define_method("stability_factor", method!(TrajectoryResult::stability_factor, 0))?;
+    class.define_method("points", method!(TrajectoryResult::points, -1))?;
+    class.define_method("each_point", method!(TrajectoryResult::each_point, 0))?;
+    class.define_method("lazy_points", method!(TrajectoryResult::lazy_points, 0))?;
+    class.define_method("point_count", method!(TrajectoryResult::point_count, 0))?;
+    class.define_method("to_h", method!(TrajectoryResult::to_h, -1))?;
+    class.define_method("to_json", method!(TrajectoryResult::to_json, -1))?;
+    class.define_method("to_csv", method!(TrajectoryResult::to_csv, -1))?;
+    class.define_method("at_range", method!(TrajectoryResult::at_range, -1))?;
+    class.define_method("at_time", method!(TrajectoryResult::at_time, -1))?;
+    class.define_method("summary", method!(TrajectoryResult::summary, 0))?;
+    class.define_method("inspect", method!(TrajectoryResult::inspect, 0))?;
+    class.define_method("pretty_print", method!(TrajectoryResult::pretty_print, 1))?;
+    class.define_method("range_card", method!(TrajectoryResult::range_card_kw, -1))?;
+    class.define_method("[]", method!(TrajectoryResult::aref, 1))?;
+    class.define_method("_dump", method!(TrajectoryResult::dump, 1))?;
+    class.define_singleton_method("_load", function!(TrajectoryResult::load, 1))?;
+    Ok(())
+}
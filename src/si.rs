@@ -0,0 +1,122 @@
+use magnus::{Error, RHash, Ruby, Value, prelude::*};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, WindConditions};
+
+use crate::projectile::{DEFAULT_IS_RIGHT_TWIST, DEFAULT_SHOOTING_ANGLE_DEGREES, DEFAULT_SPIN_DRIFT, DEFAULT_TWIST_RATE_INCHES, drag_model_from_name};
+use crate::units::*;
+use crate::validation::Problems;
+use crate::wind;
+
+// Raw SI inputs (`si: true`): metres, m/s, kg, radians, kelvin and pascals,
+// handed to the engine without passing through the imperial structs. Only the
+// engine's own non-SI fields (°C, hPa, twist in inches) and its wind
+// direction, where the air moves to rather than where it blows from, are
+// derived here.
+
+const INPUT_KEYS: &[&str] = &[
+    "bc",
+    "bullet_mass_kg",
+    "muzzle_velocity_mps",
+    "bullet_diameter_m",
+    "bullet_length_m",
+    "sight_height_m",
+    "zero_distance_m",
+    "shooting_angle_rad",
+    "twist_rate_m",
+    "is_right_twist",
+    "drag_model",
+    "spin_drift",
+];
+const WIND_KEYS: &[&str] = &["speed_mps", "direction_rad"];
+const ATMOSPHERE_KEYS: &[&str] = &["temperature_k", "pressure_pa", "humidity_percent", "altitude_m"];
+
+/// Copy `hash` with string keys, recording keys outside `known` and `ignored`
+fn string_keyed(hash: RHash, known: &[&str], ignored: &[&str], prefix: &str, problems: &mut Problems) -> Result<RHash, Error> {
+    let converted = Ruby::get_with(hash).hash_new();
+    for (key, value) in hash.to_vec::<Value, Value>()? {
+        let key: String = key.funcall("to_s", ())?;
+        if known.contains(&key.as_str()) {
+            converted.aset(key, value)?;
+        } else if !ignored.contains(&key.as_str()) {
+            let field = format!("{}{}", prefix, key);
+            problems.add(&field, format!("{} is not an SI input (expected one of {})", field, known.join(", ")));
+        }
+    }
+    Ok(converted)
+}
+
+/// Engine inputs from an SI hash or keywords; `ignored` keys belong to the caller
+pub fn inputs_from_hash(hash: RHash, ignored: &[&str], problems: &mut Problems) -> Result<BallisticInputs, Error> {
+    let hash = string_keyed(hash, INPUT_KEYS, ignored, "", problems)?;
+    let bullet_diameter: f64 = problems.fetch(hash, "bullet_diameter_m", "bullet_diameter_m");
+    let bullet_mass: f64 = problems.fetch(hash, "bullet_mass_kg", "bullet_mass_kg");
+    let twist_rate: f64 = problems.lookup(hash, "twist_rate_m", "twist_rate_m", DEFAULT_TWIST_RATE_INCHES * INCHES_TO_METERS);
+    let drag_model: String = problems.lookup(hash, "drag_model", "drag_model", "G7".to_string());
+
+    let inputs = BallisticInputs {
+        bc_type: drag_model_from_name(&drag_model, problems),
+        bc_value: problems.fetch(hash, "bc", "bc"),
+        bullet_diameter,
+        bullet_mass,
+        bullet_length: problems.fetch(hash, "bullet_length_m", "bullet_length_m"),
+        muzzle_velocity: problems.fetch(hash, "muzzle_velocity_mps", "muzzle_velocity_mps"),
+        sight_height: problems.fetch(hash, "sight_height_m", "sight_height_m"),
+        target_distance: problems.fetch(hash, "zero_distance_m", "zero_distance_m"),
+        shooting_angle: problems.lookup(hash, "shooting_angle_rad", "shooting_angle_rad", DEFAULT_SHOOTING_ANGLE_DEGREES * DEGREES_TO_RADIANS),
+        twist_rate: twist_rate / INCHES_TO_METERS, // The engine takes inches per turn
+        is_twist_right: problems.lookup(hash, "is_right_twist", "is_right_twist", DEFAULT_IS_RIGHT_TWIST),
+        caliber_inches: bullet_diameter / INCHES_TO_METERS,
+        weight_grains: bullet_mass / GRAINS_TO_KG,
+        use_enhanced_spin_drift: problems.lookup(hash, "spin_drift", "spin_drift", DEFAULT_SPIN_DRIFT),
+        ..Default::default()
+    };
+
+    problems.check_positive("bc", inputs.bc_value);
+    problems.check_positive("bullet_mass_kg", inputs.bullet_mass);
+    problems.check_positive("muzzle_velocity_mps", inputs.muzzle_velocity);
+    problems.check_positive("bullet_diameter_m", inputs.bullet_diameter);
+    problems.check_positive("bullet_length_m", inputs.bullet_length);
+    problems.check_non_negative("sight_height_m", inputs.sight_height);
+    problems.check_non_negative("zero_distance_m", inputs.target_distance);
+    problems.check_range("shooting_angle_rad", inputs.shooting_angle, -std::f64::consts::FRAC_PI_2, std::f64::consts::FRAC_PI_2);
+    problems.check_positive("twist_rate_m", twist_rate);
+    Ok(inputs)
+}
+
+/// Engine wind from an optional SI hash (`speed_mps`, `direction_rad`, where
+/// it blows from as `direction_degrees` is)
+pub fn wind_from_option(value: Option<Value>, problems: &mut Problems) -> Result<WindConditions, Error> {
+    let Some(hash) = problems.optional::<Option<RHash>>("wind", value, None) else {
+        return Ok(WindConditions::default());
+    };
+    let hash = string_keyed(hash, WIND_KEYS, &[], "wind.", problems)?;
+    let wind = WindConditions {
+        speed: problems.lookup(hash, "wind.speed_mps", "speed_mps", 0.0),
+        direction: problems.lookup(hash, "wind.direction_rad", "direction_rad", 0.0),
+    };
+    problems.check("wind.direction_rad", wind.direction.is_finite(), "must be finite");
+    let wind = WindConditions { direction: wind::engine_direction(wind.direction), ..wind };
+    problems.check_non_negative("wind.speed_mps", wind.speed);
+    Ok(wind)
+}
+
+/// Engine atmosphere from an optional SI hash (`temperature_k`, `pressure_pa`, ...)
+pub fn atmosphere_from_option(value: Option<Value>, problems: &mut Problems) -> Result<AtmosphericConditions, Error> {
+    let Some(hash) = problems.optional::<Option<RHash>>("atmosphere", value, None) else {
+        return Ok(AtmosphericConditions::default());
+    };
+    let hash = string_keyed(hash, ATMOSPHERE_KEYS, &[], "atmosphere.", problems)?;
+    let default = AtmosphericConditions::default();
+    let temperature_k: f64 = problems.lookup(hash, "atmosphere.temperature_k", "temperature_k", default.temperature + 273.15);
+    let pressure_pa: f64 = problems.lookup(hash, "atmosphere.pressure_pa", "pressure_pa", default.pressure * 100.0);
+    let atmosphere = AtmosphericConditions {
+        temperature: temperature_k - 273.15,
+        pressure: pressure_pa / 100.0,
+        humidity: problems.lookup(hash, "atmosphere.humidity_percent", "humidity_percent", default.humidity),
+        altitude: problems.lookup(hash, "atmosphere.altitude_m", "altitude_m", default.altitude),
+    };
+    problems.check_positive("atmosphere.temperature_k", temperature_k);
+    problems.check_positive("atmosphere.pressure_pa", pressure_pa);
+    problems.check_range("atmosphere.humidity_percent", atmosphere.humidity, 0.0, 100.0);
+    problems.check("atmosphere.altitude_m", atmosphere.altitude.is_finite(), "must be finite");
+    Ok(atmosphere)
+}
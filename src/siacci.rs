@@ -0,0 +1,86 @@
+use ballistics_engine::{AtmosphericConditions, BallisticInputs};
+
+use crate::analytic::{self, Drag, FlatFire};
+use crate::units::*;
+
+// The Siacci method, for `solver: "siacci"`: flat fire from tables of the
+// primary functions of the drag model, as range tables were computed before
+// trajectories could be integrated. With F(u) = u / |du/dx| the retardation
+// distance (so the BC and air density are folded in), the functions of the
+// velocity u are
+//
+//   space        S(u) = ∫ F(w) / w  dw        time     T(u) = ∫ F(w) / w²  dw
+//   inclination  I(u) = ∫ 2g F(w) / w³ dw     altitude A(u) = ∫ I(w) F(w) / w  dw
+//
+// each from u up to the muzzle velocity V, and at the range x = S(u) - S(V)
+// the time is T(u) - T(V) and the drop below the bore line
+//
+//   ½ · (A(u) - A(V) - I(V) · (S(u) - S(V)))
+//
+// The tables are built for each load and atmosphere, by the trapezoid rule.
+
+/// Velocity step of the tables, fps
+const TABLE_STEP_FPS: f64 = 1.0;
+/// The tables stop at this velocity, fps; the drag tables' subsonic end
+const MIN_VELOCITY_FPS: f64 = 100.0;
+
+/// One row of the primary functions, feet and seconds
+#[derive(Clone, Copy)]
+struct Row {
+    velocity: f64,
+    space: f64,
+    time: f64,
+    inclination: f64,
+    altitude: f64,
+}
+
+/// Siacci tables for one load and atmosphere, from the muzzle velocity down
+pub struct Siacci {
+    rows: Vec<Row>,
+}
+
+impl Siacci {
+    /// Tabulate the primary functions for `inputs` already prepared by `drag::apply`
+    pub fn new(inputs: &BallisticInputs, atmosphere: &AtmosphericConditions) -> Self {
+        let drag = Drag::new(inputs, atmosphere);
+        let gravity = analytic::gravity_fps2();
+        let muzzle_velocity = inputs.muzzle_velocity / FPS_TO_MPS;
+        let count = ((muzzle_velocity - MIN_VELOCITY_FPS) / TABLE_STEP_FPS).floor().max(0.0) as usize;
+
+        let mut rows = Vec::with_capacity(count + 1);
+        let mut row = Row { velocity: muzzle_velocity, space: 0.0, time: 0.0, inclination: 0.0, altitude: 0.0 };
+        let mut retardation = drag.retardation_ft(row.velocity);
+        rows.push(row);
+        for index in 1..=count {
+            let velocity = muzzle_velocity - index as f64 * TABLE_STEP_FPS;
+            let next = drag.retardation_ft(velocity);
+            let trapezoid = |f: &dyn Fn(f64, f64) -> f64| (f(row.velocity, retardation) + f(velocity, next)) / 2.0 * TABLE_STEP_FPS;
+            let inclination = row.inclination + trapezoid(&|w, f| 2.0 * gravity * f / w.powi(3));
+            let altitude = row.altitude
+                + (row.inclination * retardation / row.velocity + inclination * next / velocity) / 2.0 * TABLE_STEP_FPS;
+            row = Row {
+                velocity,
+                space: row.space + trapezoid(&|w, f| f / w),
+                time: row.time + trapezoid(&|w, f| f / (w * w)),
+                inclination,
+                altitude,
+            };
+            retardation = next;
+            rows.push(row);
+        }
+        Self { rows }
+    }
+}
+
+impl FlatFire for Siacci {
+    fn at(&self, range_m: f64) -> Option<(f64, f64, f64)> {
+        let range = range_m / FEET_TO_METERS;
+        let index = self.rows.partition_point(|row| row.space < range).max(1);
+        let (before, after) = (&self.rows[index - 1], self.rows.get(index)?);
+        let fraction = (range - before.space) / (after.space - before.space);
+        let lerp = |value: fn(&Row) -> f64| value(before) + (value(after) - value(before)) * fraction;
+        // The tables start at the muzzle, so S(V), A(V) and I(V) are 0
+        let drop = lerp(|row| row.altitude) / 2.0;
+        Some((lerp(|row| row.velocity) * FPS_TO_MPS, lerp(|row| row.time), drop * FEET_TO_METERS))
+    }
+}
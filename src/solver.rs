@@ -0,0 +1,1036 @@
+use std::cell::RefCell;
+
+use magnus::{Error, RHash, RModule, RString, Ruby, Value, function, method, prelude::*, scan_args::{get_kwargs, scan_args}, typed_data::Obj};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectorySolver, WindConditions, calculate_zero_angle_with_conditions};
+use ballistics_engine::{TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult};
+use ballistics_engine::constants::G_ACCEL_MPS2;
+use serde::{Deserialize, Serialize};
+
+use crate::analytic;
+use crate::cant::Cant;
+use crate::drag;
+use crate::earth::{self, Coriolis, Curvature, Earth, Gravity};
+use crate::environment;
+use crate::error;
+use crate::integrator::{self, Tolerance};
+use crate::interpolation;
+use crate::json;
+use crate::output_units::OutputUnits;
+use crate::pejsa::Pejsa;
+use crate::point::TrajectoryPoint;
+use crate::projectile::{self, ImperialInputs};
+use crate::result::{self, Derived, TrajectoryResult};
+use crate::si;
+use crate::siacci::Siacci;
+use crate::spin::{Repose, Spin, YawOfRepose};
+use crate::units::{DEGREES_TO_RADIANS, FPS_TO_MPS, JOULES_TO_FTLBS, YARDS_TO_METERS};
+use crate::validation::{self, Problems};
+use crate::warnings;
+use crate::wind_field::{WindBand, WindField};
+
+/// Integration settings beyond the inputs and conditions
+#[derive(Clone, Copy, Default)]
+pub struct SolveOptions {
+    /// Downrange distance to integrate to, metres (the engine stops at 1000 m otherwise)
+    pub max_range_m: Option<f64>,
+    /// Fixed integration step, seconds; the engine's adaptive RK45 picks its own otherwise
+    pub time_step: Option<f64>,
+    /// Integration method; RK4 given a `time_step`, the adaptive RK45 otherwise
+    pub integrator: Option<Integrator>,
+    /// Time of flight to stop at, seconds (the engine stops at 100 s otherwise)
+    pub max_time_s: Option<f64>,
+    /// Speed to stop at once the bullet slows to it, m/s
+    pub stop_velocity_mps: Option<f64>,
+    /// Kinetic energy to stop at once the bullet has shed down to it, joules
+    pub stop_energy_j: Option<f64>,
+    /// Downrange distance the last point is put at, metres
+    pub target_range_m: Option<f64>,
+    /// Adaptive step tolerances; the engine's RK45 works to its own without either
+    pub relative_tolerance: Option<f64>,
+    pub absolute_tolerance: Option<f64>,
+    /// Spacing of the points returned; those the integrator stepped to otherwise
+    pub output_step: Option<OutputStep>,
+    /// Bore elevation above the horizontal, radians, fired at in place of the zero's
+    pub launch_angle: Option<f64>,
+    /// Bore elevation of a known zero, radians, used without re-zeroing
+    pub zero_angle: Option<f64>,
+    /// Step the adaptive RK45 in the extension even without a tolerance, at
+    /// the engine's own, as a solve streaming its points or in a wind that
+    /// changes along the path needs
+    pub stepped: bool,
+}
+
+/// Spacing of the points a solve returns, from `solver_options.output_step_yards`,
+/// `output_step_meters` or `output_step_seconds`
+#[derive(Clone, Copy)]
+pub enum OutputStep {
+    /// Metres downrange
+    Range(f64),
+    /// Seconds of flight
+    Time(f64),
+}
+
+/// The `solver_options` keys of `OutputStep`, and metres per unit of the range ones
+const OUTPUT_STEPS: [(&str, Option<f64>); 3] =
+    [("output_step_yards", Some(YARDS_TO_METERS)), ("output_step_meters", Some(1.0)), ("output_step_seconds", None)];
+
+/// Read the `OUTPUT_STEPS` given, in that order, recording more than one or a step that is not positive
+pub fn read_output_step(steps: [Option<f64>; 3], problems: &mut Problems) -> Option<OutputStep> {
+    let given: Vec<_> = OUTPUT_STEPS.iter().zip(steps).filter_map(|(&(key, meters), step)| Some((key, meters, step?))).collect();
+    if let [(first, ..), (second, ..), ..] = given[..] {
+        problems.add(
+            &format!("solver_options.{}", second),
+            format!("solver_options.{} and solver_options.{} cannot both be given", first, second),
+        );
+    }
+    let &(key, meters, step) = given.first()?;
+    problems.check_positive(&format!("solver_options.{}", key), step);
+    Some(meters.map_or(OutputStep::Time(step), |meters| OutputStep::Range(step * meters)))
+}
+
+/// Integration method selected by `solver_options.integrator`
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Integrator {
+    /// First-order steps of `time_step`
+    Euler,
+    /// Fourth-order Runge-Kutta steps of `time_step`
+    Rk4,
+    /// The engine's adaptive Runge-Kutta-Fehlberg (Dormand-Prince) steps
+    Rkf45,
+}
+
+/// Threshold of `solver_options` that ended a trajectory
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Stop {
+    /// `stop_at_velocity_fps`
+    Velocity,
+    /// `stop_at_energy_ftlbs`
+    Energy,
+}
+
+impl Stop {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Velocity => "velocity",
+            Self::Energy => "energy",
+        }
+    }
+}
+
+/// Names accepted as `solver_options.integrator`, `"adaptive"` standing for `"rkf45"`
+pub const INTEGRATORS: &[(&str, Integrator)] = &[
+    ("euler", Integrator::Euler),
+    ("rk4", Integrator::Rk4),
+    ("rkf45", Integrator::Rkf45),
+    ("adaptive", Integrator::Rkf45),
+];
+
+impl Integrator {
+    pub fn name(self) -> &'static str {
+        INTEGRATORS.iter().find(|(_, integrator)| *integrator == self).map_or("", |(name, _)| name)
+    }
+}
+
+/// Stop `result` where `key` first passes `limit`
+///
+/// Points past it are dropped and one interpolated at it takes their place;
+/// the summary values are those of the new last point.
+fn end_at(result: &mut EngineTrajectoryResult, limit: f64, key: impl Fn(&EngineTrajectoryPoint) -> f64) {
+    let Some(index) = result.points.iter().position(|point| key(point) > limit).filter(|index| *index > 0) else {
+        return;
+    };
+    let (before, after) = (&result.points[index - 1], &result.points[index]);
+    let fraction = (limit - key(before)) / (key(after) - key(before));
+    let lerp = |from: f64, to: f64| from + (to - from) * fraction;
+    let end = EngineTrajectoryPoint {
+        time: lerp(before.time, after.time),
+        position: before.position.lerp(&after.position, fraction),
+        velocity_magnitude: lerp(before.velocity_magnitude, after.velocity_magnitude),
+        kinetic_energy: lerp(before.kinetic_energy, after.kinetic_energy),
+    };
+    result.points.truncate(index);
+    result.points.push(end);
+    summarize(result);
+}
+
+/// Set the range, greatest height, time of flight and impact velocity and
+/// energy from the points, as they stand after the corrections moved them
+fn summarize(result: &mut EngineTrajectoryResult) {
+    let Some(last) = result.points.last() else {
+        return;
+    };
+    result.max_range = last.position.z;
+    result.time_of_flight = last.time;
+    result.impact_velocity = last.velocity_magnitude;
+    result.impact_energy = last.kinetic_energy;
+    result.max_height = result.points.iter().map(|point| point.position.y).fold(f64::MIN, f64::max);
+}
+
+/// Time of flight where `key` first falls below `limit`, seconds, interpolated between the points
+fn falls_below(result: &EngineTrajectoryResult, limit: f64, key: impl Fn(&EngineTrajectoryPoint) -> f64) -> Option<f64> {
+    result.points.windows(2).find(|pair| key(&pair[0]) >= limit && key(&pair[1]) < limit).map(|pair| {
+        let fraction = (key(&pair[0]) - limit) / (key(&pair[0]) - key(&pair[1]));
+        pair[0].time + (pair[1].time - pair[0].time) * fraction
+    })
+}
+
+/// Read a `solver_options.integrator` name (nil is the default), recording an unsupported one
+pub fn read_integrator(name: Option<String>, problems: &mut Problems) -> Option<Integrator> {
+    let name = name?;
+    let integrator = INTEGRATORS.iter().find(|(integrator_name, _)| integrator_name.eq_ignore_ascii_case(&name)).map(|(_, integrator)| *integrator);
+    if integrator.is_none() {
+        let valid = INTEGRATORS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+        problems.add("solver_options.integrator", format!("solver_options.integrator {:?} is not supported (supported: {})", name, valid));
+    }
+    integrator
+}
+
+/// Bounds of `solver_options.time_step`, seconds: the adaptive RK45's smallest and largest steps
+const TIME_STEP_RANGE: (f64, f64) = (1e-6, 0.01);
+
+/// Time past `target_range_yards` integrated to, seconds at the muzzle
+/// velocity: twice the longest step of any integrator, so a point lies beyond it
+const TARGET_OVERSHOOT_S: f64 = 2.0 * integrator::MAX_STEP_S;
+
+/// Range integrated to at a `launch_angle_degrees` without `max_range_yards`,
+/// metres: beyond any small-arms or mortar trajectory, which lands or reaches
+/// the integrators' 100 s first
+const LAUNCH_ANGLE_MAX_RANGE_M: f64 = 100_000.0;
+
+/// Launch angles `max_range_angle` searches between, radians
+const MAX_RANGE_ANGLES: (f64, f64) = (1.0 * DEGREES_TO_RADIANS, 89.0 * DEGREES_TO_RADIANS);
+/// `max_range_angle` stops once the bracket is this narrow, radians
+const MAX_RANGE_ANGLE_TOLERANCE: f64 = 0.01 * DEGREES_TO_RADIANS;
+
+/// Launch angles `bisect_zero` searches between, radians: level to the 45
+/// degrees the engine's own search widens to
+const ZERO_ANGLES: (f64, f64) = (0.0, 0.785);
+/// `bisect_zero` stops once the bracket is this narrow, radians: a hundredth
+/// of the engine's, so the integrators' zeros can be told apart
+const ZERO_ANGLE_TOLERANCE: f64 = 1e-7;
+
+/// Tolerance of `solver_options.relative_tolerance`/`absolute_tolerance` when
+/// only the other is given, and of a `stepped` solve without either: the
+/// engine's RK45's
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+/// Fixed step without `solver_options.time_step`, seconds: the engine's
+const DEFAULT_TIME_STEP: f64 = 0.001;
+
+impl SolveOptions {
+    /// Read an optional `solver_options` hash (`{integrator: "rk4", time_step: 0.0005}`); nil means the defaults
+    pub fn from_option(value: Option<Value>, problems: &mut Problems) -> Result<Self, Error> {
+        let mut options = Self::default();
+        let Some(hash) = problems.optional::<Option<RHash>>("solver_options", value, None) else {
+            return Ok(options);
+        };
+        let mut output_steps = [None; 3];
+        for (key, value) in hash.to_vec::<Value, Value>()? {
+            let key: String = key.funcall("to_s", ())?;
+            let field = format!("solver_options.{}", key);
+            match key.as_str() {
+                "time_step" => options.time_step = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "integrator" => options.integrator = read_integrator(problems.optional(&field, Some(value), None), problems),
+                "max_range_yards" => {
+                    options.max_range_m = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) * YARDS_TO_METERS);
+                }
+                "max_time_s" => options.max_time_s = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "stop_at_velocity_fps" => {
+                    options.stop_velocity_mps = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) * FPS_TO_MPS);
+                }
+                "stop_at_energy_ftlbs" => {
+                    options.stop_energy_j = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) / JOULES_TO_FTLBS);
+                }
+                "target_range_yards" => {
+                    options.target_range_m = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) * YARDS_TO_METERS);
+                }
+                "relative_tolerance" => options.relative_tolerance = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "absolute_tolerance" => options.absolute_tolerance = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "output_step_yards" => output_steps[0] = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "output_step_meters" => output_steps[1] = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "output_step_seconds" => output_steps[2] = (!value.is_nil()).then(|| problems.required(&field, Some(value))),
+                "launch_angle_degrees" => {
+                    options.launch_angle = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) * DEGREES_TO_RADIANS);
+                }
+                "zero_angle_degrees" => {
+                    options.zero_angle = (!value.is_nil()).then(|| problems.required::<f64>(&field, Some(value)) * DEGREES_TO_RADIANS);
+                }
+                _ => problems.add(
+                    &field,
+                    format!(
+                        "{} is not a known solver option (expected integrator, time_step, relative_tolerance, absolute_tolerance, \
+                         max_range_yards, max_time_s, stop_at_velocity_fps, stop_at_energy_ftlbs, target_range_yards, \
+                         output_step_yards, output_step_meters, output_step_seconds, launch_angle_degrees or zero_angle_degrees)",
+                        field
+                    ),
+                ),
+            }
+        }
+        options.output_step = read_output_step(output_steps, problems);
+        options.check(problems);
+        Ok(options)
+    }
+
+    /// Record an out-of-range time step, or one given to the adaptive integrator,
+    /// tolerances given to a fixed-step one, a tolerance, range, time, speed
+    /// or energy to stop at that is not positive, and a launch or zero angle at or past
+    /// vertical, or both
+    pub fn check(&self, problems: &mut Problems) {
+        for (field, angle) in [("solver_options.launch_angle_degrees", self.launch_angle), ("solver_options.zero_angle_degrees", self.zero_angle)] {
+            if let Some(angle) = angle {
+                problems.check(field, angle.abs() < std::f64::consts::FRAC_PI_2, "must be between -90 and 90 degrees, exclusive");
+            }
+        }
+        if self.launch_angle.is_some() && self.zero_angle.is_some() {
+            problems.add(
+                "solver_options.zero_angle_degrees",
+                "solver_options.launch_angle_degrees and solver_options.zero_angle_degrees cannot both be given".to_string(),
+            );
+        }
+        for (field, tolerance) in [("solver_options.relative_tolerance", self.relative_tolerance), ("solver_options.absolute_tolerance", self.absolute_tolerance)] {
+            if let Some(tolerance) = tolerance {
+                problems.check_positive(field, tolerance);
+                problems.check(field, self.integrator() == Integrator::Rkf45, "only applies to the adaptive integrator (rkf45)");
+            }
+        }
+        if let Some(max_range_m) = self.max_range_m {
+            problems.check_positive("solver_options.max_range_yards", max_range_m);
+        }
+        if let Some(max_time_s) = self.max_time_s {
+            problems.check_positive("solver_options.max_time_s", max_time_s);
+        }
+        if let Some(stop_velocity_mps) = self.stop_velocity_mps {
+            problems.check_positive("solver_options.stop_at_velocity_fps", stop_velocity_mps);
+        }
+        if let Some(stop_energy_j) = self.stop_energy_j {
+            problems.check_positive("solver_options.stop_at_energy_ftlbs", stop_energy_j);
+        }
+        if let Some(target_range_m) = self.target_range_m {
+            problems.check_positive("solver_options.target_range_yards", target_range_m);
+            problems.check(
+                "solver_options.target_range_yards",
+                self.max_range_m.is_none_or(|max_range_m| target_range_m <= max_range_m),
+                "must not be beyond max_range_yards",
+            );
+        }
+        if let Some(time_step) = self.time_step {
+            problems.check_range("solver_options.time_step", time_step, TIME_STEP_RANGE.0, TIME_STEP_RANGE.1);
+            problems.check("solver_options.time_step", self.integrator != Some(Integrator::Rkf45), "only applies to the fixed-step integrators (euler, rk4)");
+        }
+    }
+
+    /// Range to integrate to, metres: past `target_range_m` at `muzzle_velocity`,
+    /// or `max_range_m`, or with a launch angle as far as it flies
+    fn integration_range_m(&self, muzzle_velocity: f64) -> Option<f64> {
+        self.target_range_m
+            .map(|target_range_m| target_range_m + TARGET_OVERSHOOT_S * muzzle_velocity)
+            .or(self.max_range_m)
+            .or(self.launch_angle.map(|_| LAUNCH_ANGLE_MAX_RANGE_M))
+    }
+
+    /// Tolerances of the extension's adaptive steps, if either is given or the solve is `stepped`
+    fn tolerance(&self) -> Option<Tolerance> {
+        (self.stepped || self.relative_tolerance.is_some() || self.absolute_tolerance.is_some()).then(|| Tolerance {
+            relative: self.relative_tolerance.unwrap_or(DEFAULT_TOLERANCE),
+            absolute: self.absolute_tolerance.unwrap_or(DEFAULT_TOLERANCE),
+        })
+    }
+
+    /// Integration method the engine's integrator is run with
+    pub fn integrator(&self) -> Integrator {
+        self.integrator.unwrap_or(if self.time_step.is_some() { Integrator::Rk4 } else { Integrator::Rkf45 })
+    }
+}
+
+/// Receives each point of `Solver#solve_streamed`, converted as the result will convert it
+pub type Stream<'a> = &'a mut dyn FnMut(TrajectoryPoint) -> Result<(), Error>;
+
+/// What `Solver#solve_engine` returns
+pub type Solved = (EngineTrajectoryResult, BallisticInputs, Option<Repose>, Option<WindBand>, Option<Coriolis>, Option<Curvature>, Option<Gravity>, Option<Stop>);
+
+/// Trajectory model selected by the `solver` input
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Model {
+    /// The engine's point-mass integrator
+    #[default]
+    PointMass,
+    /// Point mass plus the yaw of repose and its drift
+    ModifiedPointMass,
+    /// Pejsa's closed-form flat-fire solution
+    Pejsa,
+    /// Siacci's method, from tables of the drag model's primary functions
+    Siacci,
+}
+
+/// Names accepted as `solver`
+pub const MODELS: &[(&str, Model)] = &[
+    ("point_mass", Model::PointMass),
+    ("mpm", Model::ModifiedPointMass),
+    ("pejsa", Model::Pejsa),
+    ("siacci", Model::Siacci),
+];
+
+/// Models asked for by name that the engine has no integrator for
+const MODELS_WITHOUT_INTEGRATORS: &[(&str, &str)] = &[("6dof", "six-degree-of-freedom")];
+
+impl Model {
+    pub fn name(self) -> &'static str {
+        MODELS.iter().find(|(_, model)| *model == self).map_or("", |(name, _)| name)
+    }
+}
+
+/// Read a `solver` name (nil is point mass), recording an unsupported one
+pub fn read_model(name: Option<String>, problems: &mut Problems) -> Model {
+    let Some(name) = name else {
+        return Model::default();
+    };
+    if let Some((_, model)) = MODELS.iter().find(|(model_name, _)| model_name.eq_ignore_ascii_case(&name)) {
+        return *model;
+    }
+    let valid = MODELS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+    match MODELS_WITHOUT_INTEGRATORS.iter().find(|(model_name, _)| model_name.eq_ignore_ascii_case(&name)) {
+        Some((model_name, description)) => problems.add(
+            "solver",
+            format!("solver {} is not available: the engine has no {} integrator (supported: {})", model_name, description, valid),
+        ),
+        None => problems.add("solver", format!("solver {:?} is not supported (supported: {})", name, valid)),
+    }
+    Model::default()
+}
+
+/// Reusable solver for one projectile, converted to engine units once
+#[magnus::wrap(class = "BallisticsEngine::Solver", free_immediately, size)]
+pub struct Solver {
+    inputs: BallisticInputs,
+    // Magnus force coefficient, which the engine inputs have no field for
+    magnus: Option<f64>,
+    model: Model,
+    // Firing point for the Coriolis deflection, which the engine's solver leaves out
+    earth: Option<Earth>,
+    // Follow the Earth's curvature, where the engine's solver takes the ground flat
+    curvature: bool,
+    // Latitude of the WGS-84 normal gravity, where the engine's is standard
+    gravity_latitude: Option<f64>,
+    // Rifle cant, radians, which the engine's solver leaves out
+    cant: f64,
+    // Form factor of the BC as given, for a single BC; the engine inputs' has the drag scale factor and standard folded in
+    form_factor: Option<f64>,
+    // Line of sight above the horizontal for an inclined shot, radians, which the cant turns the bore about
+    sight_angle: f64,
+    // Zero angle for the last atmosphere and integration solved with; wind does not affect it
+    zero_cache: RefCell<Option<([f64; 8], f64)>>,
+}
+
+/// Cache key for a zero: the atmosphere (the engine type has no `PartialEq`)
+/// and the integrator, step and tolerances it was found with
+fn zero_key(atmosphere: &AtmosphericConditions, options: &SolveOptions) -> [f64; 8] {
+    let tolerance = options.tolerance().map_or((0.0, 0.0), |tolerance| (tolerance.relative, tolerance.absolute));
+    [
+        atmosphere.temperature,
+        atmosphere.pressure,
+        atmosphere.humidity,
+        atmosphere.altitude,
+        options.integrator() as u8 as f64,
+        options.time_step.unwrap_or(DEFAULT_TIME_STEP),
+        tolerance.0,
+        tolerance.1,
+    ]
+}
+
+/// Each point of a streamed solve as the integrator steps to it; `Ok(false)`
+/// ends the trajectory there
+type OnStep<'a> = &'a mut dyn FnMut(&EngineTrajectoryPoint) -> Result<bool, Error>;
+
+/// Integrate `inputs`, zeroed and with the drag applied and for `solver: "mpm"`
+/// flown with their yaw of repose, with the integrator, step and tolerances of
+/// `options`, to `range_m` or where the integrator stops
+///
+/// Only the extension's integrators can pass their points to `on_step`, follow
+/// a wind that is not uniform or fly a yaw of repose, so a solve needing any is
+/// `stepped`: the engine's RK45 reports no points until it has finished and
+/// solves point mass in the muzzle wind alone.
+fn integrate(
+    ruby: &Ruby,
+    (inputs, repose): (&BallisticInputs, Option<&YawOfRepose>),
+    wind: &WindField,
+    atmosphere: &AtmosphericConditions,
+    range_m: Option<f64>,
+    options: &SolveOptions,
+    on_step: Option<OnStep>,
+) -> Result<EngineTrajectoryResult, Error> {
+    let integrator = options.integrator();
+    let time_step = options.time_step.unwrap_or(DEFAULT_TIME_STEP);
+    let tolerance = options.tolerance().filter(|_| integrator == Integrator::Rkf45);
+    if integrator != Integrator::Rkf45 || tolerance.is_some() {
+        // A Ruby exception from `on_step` ends the trajectory and is raised once the integrator returns
+        let mut failed = None;
+        let mut on_step = on_step.map(|on_step| {
+            let failed = &mut failed;
+            move |point: &EngineTrajectoryPoint| on_step(point).unwrap_or_else(|e| {
+                *failed = Some(e);
+                false
+            })
+        });
+        let on_step = |point: &EngineTrajectoryPoint| on_step.as_mut().is_none_or(|on_step| on_step(point));
+        let max_range_m = analytic::max_range_m(range_m);
+        let result = match (integrator, tolerance) {
+            (_, Some(tolerance)) => integrator::adaptive(inputs, wind, repose, atmosphere, max_range_m, tolerance, on_step),
+            (Integrator::Rk4, None) => integrator::rk4(inputs, wind, repose, atmosphere, max_range_m, time_step, on_step),
+            _ => integrator::euler(inputs, wind, repose, atmosphere, max_range_m, time_step, on_step),
+        };
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        return result.map_err(|e| Error::new(error::solver_error(ruby), e));
+    }
+    let mut engine_inputs = inputs.clone();
+    engine_inputs.use_adaptive_rk45 = true;
+
+    // Solve trajectory - handle Result properly
+    let mut solver = TrajectorySolver::new(engine_inputs, wind.muzzle.clone(), atmosphere.clone());
+    if let Some(range_m) = range_m {
+        solver.set_max_range(range_m);
+    }
+    solver.set_time_step(time_step);
+    solver.solve()
+        .map_err(|e| Error::new(error::solver_error(ruby), e.to_string()))
+}
+
+/// Launch angle `inputs`, with the drag applied, zero at their target
+/// distance when integrated as `options` integrate them: bisected between
+/// `ZERO_ANGLES` on the height the trajectory crosses the zero distance at
+fn bisect_zero(
+    ruby: &Ruby,
+    inputs: &BallisticInputs,
+    repose: Option<&YawOfRepose>,
+    atmosphere: &AtmosphericConditions,
+    options: &SolveOptions,
+) -> Result<f64, Error> {
+    let zero_m = inputs.target_distance;
+    let range_m = zero_m + TARGET_OVERSHOOT_S * inputs.muzzle_velocity;
+    let sight_m = inputs.muzzle_height + inputs.sight_height;
+    // Whether the trajectory fired at `angle` passes above the line of sight at the zero distance
+    let above = |angle: f64| -> Result<bool, Error> {
+        let fired = BallisticInputs { muzzle_angle: angle, ..inputs.clone() };
+        let result = integrate(ruby, (&fired, repose), &WindField::default(), atmosphere, Some(range_m), options, None)?;
+        Ok(interpolation::height_at(&result.points, zero_m).is_some_and(|height| height > sight_m))
+    };
+    let (mut low, mut high) = ZERO_ANGLES;
+    if !above(high)? {
+        return Err(Error::new(
+            error::convergence_error(ruby),
+            format!(
+                "cannot zero at {:.1} yards: the trajectory passes below the line of sight there even at {:.0} degrees",
+                zero_m / YARDS_TO_METERS,
+                high / DEGREES_TO_RADIANS
+            ),
+        ));
+    }
+    while high - low > ZERO_ANGLE_TOLERANCE {
+        let angle = (low + high) / 2.0;
+        if above(angle)? {
+            high = angle;
+        } else {
+            low = angle;
+        }
+    }
+    Ok((low + high) / 2.0)
+}
+
+impl Solver {
+    pub fn from_inputs(inputs: ImperialInputs) -> Self {
+        Self {
+            magnus: inputs.magnus,
+            model: inputs.solver,
+            earth: inputs.latitude_degrees.map(|latitude| Earth::new(latitude, inputs.azimuth_degrees)),
+            curvature: inputs.earth_curvature,
+            gravity_latitude: inputs
+                .wgs84_gravity
+                .then(|| inputs.latitude_degrees.unwrap_or(earth::DEFAULT_GRAVITY_LATITUDE_DEGREES)),
+            cant: inputs.cant_angle_degrees * DEGREES_TO_RADIANS,
+            form_factor: (inputs.bc_bands.is_none() && inputs.drag_curve.is_none()).then(|| inputs.sectional_density() / inputs.effective_bc()),
+            ..Self::from_ballistic_inputs(inputs.to_ballistic_inputs())
+        }
+    }
+
+    pub fn from_ballistic_inputs(inputs: BallisticInputs) -> Self {
+        let single_bc = inputs.bc_segments_data.is_none() && inputs.custom_drag_table.is_none();
+        Self {
+            form_factor: single_bc.then(|| result::sectional_density(&inputs) / inputs.bc_value),
+            inputs,
+            magnus: None,
+            model: Model::default(),
+            earth: None,
+            curvature: false,
+            gravity_latitude: None,
+            cant: 0.0,
+            sight_angle: 0.0,
+            zero_cache: RefCell::new(None),
+        }
+    }
+
+    /// Solver.new(projectile), Solver.new(inputs_hash) or Solver.new(json)
+    ///
+    /// A hash with `"si" => true` is read as raw SI inputs. Conditions in a
+    /// JSON document are ignored, as the `wind`/`atmosphere` hash keys are.
+    pub fn new(ruby: &Ruby, inputs: Value) -> Result<Self, Error> {
+        let mut problems = Problems::default();
+        let solver = if let Some(json) = RString::from_value(inputs) {
+            match json::read(&json.to_string()?, &mut problems) {
+                Some(document) => Self::from_inputs(document.inputs),
+                // Only reported, never solved
+                None => Self::from_ballistic_inputs(BallisticInputs::default()),
+            }
+        } else {
+            match projectile::inputs_hash(inputs)? {
+                Some(inputs_hash) if problems.lookup(inputs_hash, "si", "si", false) => {
+                    Self::from_ballistic_inputs(si::inputs_from_hash(inputs_hash, &["si"], &mut problems)?)
+                }
+                _ => Self::from_inputs(ImperialInputs::from_value(inputs, &mut problems)?),
+            }
+        };
+        problems.into_result(ruby)?;
+        Ok(solver)
+    }
+
+    /// Launch angle that puts the trajectory on the line of sight at the zero
+    /// distance, integrated as `options` integrate the shot
+    fn zero_angle(&self, ruby: &Ruby, atmosphere: &AtmosphericConditions, options: &SolveOptions) -> Result<f64, Error> {
+        if self.inputs.target_distance <= 0.0 {
+            return Ok(0.0);
+        }
+        let options = &self.stepped(*options);
+        let key = zero_key(atmosphere, options);
+        if let Some((cached_key, angle)) = *self.zero_cache.borrow() {
+            if cached_key == key {
+                return Ok(angle);
+            }
+        }
+
+        // Zeroed in still air
+        let mut inputs = self.inputs.clone();
+        drag::apply(&mut inputs, atmosphere);
+        // The engine zeroes with its own adaptive RK45 and 1 ms step, the default integration
+        let angle = if options.integrator() == Integrator::Rkf45 && options.tolerance().is_none() {
+            calculate_zero_angle_with_conditions(
+                inputs,
+                self.inputs.target_distance,
+                self.inputs.sight_height,
+                WindConditions::default(),
+                atmosphere.clone(),
+            )
+            .map_err(|e| Error::new(error::convergence_error(ruby), e.to_string()))?
+        } else {
+            bisect_zero(ruby, &inputs, self.yaw_of_repose(&inputs, atmosphere).as_ref(), atmosphere, options)?
+        };
+
+        *self.zero_cache.borrow_mut() = Some((key, angle));
+        Ok(angle)
+    }
+
+    /// `options`, `stepped` for the modified point-mass model, whose yaw of
+    /// repose only the extension's integrators fly
+    fn stepped(&self, options: SolveOptions) -> SolveOptions {
+        SolveOptions { stepped: options.stepped || self.model == Model::ModifiedPointMass, ..options }
+    }
+
+    /// Yaw of repose `inputs`, with the drag applied, fly with for `solver: "mpm"`
+    fn yaw_of_repose(&self, inputs: &BallisticInputs, atmosphere: &AtmosphericConditions) -> Option<YawOfRepose> {
+        (self.model == Model::ModifiedPointMass).then(|| YawOfRepose::new(Spin::from_inputs(inputs, atmosphere), inputs, atmosphere)).flatten()
+    }
+
+    /// Bore elevation the model zeroes at the zero distance, in still air, radians
+    pub fn bore_angle(&self, ruby: &Ruby, atmosphere: &AtmosphericConditions, options: &SolveOptions) -> Result<f64, Error> {
+        if !matches!(self.model, Model::Pejsa | Model::Siacci) {
+            return self.zero_angle(ruby, atmosphere, options);
+        }
+        let mut inputs = self.inputs.clone();
+        drag::apply(&mut inputs, atmosphere);
+        let angle = if self.model == Model::Pejsa {
+            analytic::zero_angle(&Pejsa::new(&inputs, atmosphere, inputs.target_distance), &inputs)
+        } else {
+            analytic::zero_angle(&Siacci::new(&inputs, atmosphere), &inputs)
+        };
+        angle.map_err(|e| Error::new(error::convergence_error(ruby), e))
+    }
+
+    /// Sight height above the bore, metres
+    pub fn sight_height_m(&self) -> f64 {
+        self.inputs.sight_height
+    }
+
+    /// Distance the bore is zeroed at, metres
+    pub fn zero_distance_m(&self) -> f64 {
+        self.inputs.target_distance
+    }
+
+    /// Inclination of the line of sight, radians, positive uphill
+    pub fn shooting_angle(&self) -> f64 {
+        self.inputs.shooting_angle
+    }
+
+    /// The rifle's cant about the line of sight, for the bore `solved` was fired at; None upright
+    fn cant(&self, solved: &BallisticInputs) -> Option<Cant> {
+        (self.cant != 0.0).then(|| Cant::new(self.cant, solved.muzzle_angle - self.sight_angle, self.sight_angle, self.sight_height_m()))
+    }
+
+    /// Solve with the line of sight tipped `angle` radians, positive uphill, and
+    /// the bore zeroed for level fire tipped with it. The integrators carry on
+    /// `depth_m` further below the bore than the 100 m they stop at, so a shot
+    /// down the slope reaches its target first.
+    pub fn solve_inclined(
+        &self,
+        ruby: &Ruby,
+        wind: WindField,
+        atmosphere: &AtmosphericConditions,
+        angle: f64,
+        depth_m: f64,
+        options: SolveOptions,
+    ) -> Result<Solved, Error> {
+        if angle != 0.0 && matches!(self.model, Model::Pejsa | Model::Siacci) {
+            return Err(Error::new(
+                error::input_error(ruby),
+                format!("Invalid inputs: an inclined shot needs an integrated solver; {} is a flat-fire model", self.model.name()),
+            ));
+        }
+        let zero_angle = self.bore_angle(ruby, atmosphere, &options)? + angle;
+        let solver = Self {
+            inputs: BallisticInputs { ground_threshold: self.inputs.ground_threshold - depth_m, ..self.inputs.clone() },
+            sight_angle: angle,
+            zero_cache: RefCell::new(None),
+            ..*self
+        };
+        solver.solve_engine(ruby, wind, atmosphere, SolveOptions { zero_angle: Some(zero_angle), ..options })
+    }
+
+    /// Zero the bore and solve the trajectory, returning the engine result,
+    /// the inputs it was solved with, for `solver: "mpm"` the yaw of repose,
+    /// for an uncertain wind the band of drifts it allows, given a latitude
+    /// the Coriolis deflection, with `earth_curvature` the curvature's,
+    /// with `wgs84_gravity` the normal gravity's and, if the bullet slowed to
+    /// `stop_velocity_mps` or `stop_energy_j` and the trajectory was ended
+    /// there, which
+    pub fn solve_engine(
+        &self,
+        ruby: &Ruby,
+        wind: WindField,
+        atmosphere: &AtmosphericConditions,
+        options: SolveOptions,
+    ) -> Result<Solved, Error> {
+        self.solve_streamed(ruby, wind, atmosphere, options, None)
+    }
+
+    /// `solve_engine`, passing each point to `stream` as the integrator steps to it
+    ///
+    /// The streamed points carry the wind, less a gradient's, the spin drift
+    /// and for `solver: "mpm"` the yaw of repose's, which are known at every step; the corrections worked out from
+    /// the whole trajectory are only in the points returned. A trajectory ended at a
+    /// threshold stops integrating there, and the point interpolated at it is
+    /// streamed last.
+    pub fn solve_streamed(
+        &self,
+        ruby: &Ruby,
+        wind: WindField,
+        atmosphere: &AtmosphericConditions,
+        options: SolveOptions,
+        mut stream: Option<Stream>,
+    ) -> Result<Solved, Error> {
+        // Zeroed by the integrator that steps the points streamed, the wind along the path or the yaw of repose
+        let options = self.stepped(SolveOptions { stepped: options.stepped || stream.is_some() || !wind.is_uniform(), ..options });
+        let mut ballistic_inputs = self.inputs.clone();
+        let integration_range_m = options.integration_range_m(self.inputs.muzzle_velocity);
+        if options.launch_angle.is_some() && matches!(self.model, Model::Pejsa | Model::Siacci) {
+            return Err(Error::new(
+                error::input_error(ruby),
+                format!("Invalid inputs: solver_options.launch_angle_degrees needs an integrated solver; {} is a flat-fire model", self.model.name()),
+            ));
+        }
+        if stream.is_some() && matches!(self.model, Model::Pejsa | Model::Siacci) {
+            return Err(Error::new(
+                error::input_error(ruby),
+                format!("Invalid inputs: a block is passed each point as the integrator steps to it; {} is a flat-fire model and takes no steps", self.model.name()),
+            ));
+        }
+        let sight_height_m = self.sight_height_m();
+        let speed_of_sound_mps = result::speed_of_sound_mps(atmosphere);
+        // Streamed as `TrajectoryResult` converts its points, with the spin drift `Spin#apply` adds
+        let streamed = |point: &EngineTrajectoryPoint, spin: Spin| {
+            let mut point = point.clone();
+            point.position.x += spin.drift_at(point.time);
+            TrajectoryPoint::from_engine(&point, sight_height_m, speed_of_sound_mps, spin, (0.0, 0.0), (0.0, 0.0))
+        };
+        let mut streamed_to = f64::NEG_INFINITY;
+        let mut result = if matches!(self.model, Model::Pejsa | Model::Siacci) {
+            // Zeroed by the flat-fire model too, so no step of the solve is integrated
+            drag::apply(&mut ballistic_inputs, atmosphere);
+            let max_range_m = analytic::max_range_m(integration_range_m);
+            let solved = if self.model == Model::Pejsa {
+                let pejsa = Pejsa::new(&ballistic_inputs, atmosphere, max_range_m.max(ballistic_inputs.target_distance));
+                analytic::solve(&pejsa, &mut ballistic_inputs, &wind.muzzle, max_range_m, options.zero_angle)
+            } else {
+                let siacci = Siacci::new(&ballistic_inputs, atmosphere);
+                analytic::solve(&siacci, &mut ballistic_inputs, &wind.muzzle, max_range_m, options.zero_angle)
+            };
+            solved.map_err(|e| Error::new(error::solver_error(ruby), e))?
+        } else {
+            ballistic_inputs.muzzle_angle = match options.launch_angle.or(options.zero_angle) {
+                Some(muzzle_angle) => muzzle_angle,
+                None => self.zero_angle(ruby, atmosphere, &options)?,
+            };
+            drag::apply(&mut ballistic_inputs, atmosphere);
+            // The yaw of repose drift stands in for Litz's
+            if self.model == Model::ModifiedPointMass {
+                ballistic_inputs.use_enhanced_spin_drift = false;
+            }
+
+            let spin = Spin::from_inputs(&ballistic_inputs, atmosphere);
+            let repose = self.yaw_of_repose(&ballistic_inputs, atmosphere);
+            let muzzle_height = ballistic_inputs.muzzle_height;
+            let (mut risen, mut previous, streamed_to) = (false, None::<EngineTrajectoryPoint>, &mut streamed_to);
+            // The first point past a threshold below ends the integration unstreamed
+            let mut on_step = stream.as_mut().map(|stream| {
+                move |point: &EngineTrajectoryPoint| -> Result<bool, Error> {
+                    let fell_below = |limit: f64, key: fn(&EngineTrajectoryPoint) -> f64| {
+                        previous.as_ref().is_some_and(|previous| key(previous) >= limit) && key(point) < limit
+                    };
+                    let past = (options.launch_angle.is_some() && risen && point.position.y < muzzle_height)
+                        || options.max_time_s.is_some_and(|limit| point.time > limit)
+                        || options.stop_velocity_mps.is_some_and(|limit| fell_below(limit, |point| point.velocity_magnitude))
+                        || options.stop_energy_j.is_some_and(|limit| fell_below(limit, |point| point.kinetic_energy))
+                        || options.target_range_m.is_some_and(|limit| point.position.z > limit);
+                    if past {
+                        return Ok(false);
+                    }
+                    risen |= point.position.y > muzzle_height;
+                    previous = Some(point.clone());
+                    *streamed_to = point.time;
+                    stream(streamed(point, spin))?;
+                    Ok(true)
+                }
+            });
+            let on_step = on_step.as_mut().map(|on_step| on_step as OnStep);
+            integrate(ruby, (&ballistic_inputs, repose.as_ref()), &wind, atmosphere, integration_range_m, &options, on_step)?
+        };
+        // Fired up at an angle, the trajectory lands where it falls back to the muzzle's height
+        let muzzle_height = result.points.first().map_or(0.0, |point| point.position.y);
+        if options.launch_angle.is_some() && result.max_height > muzzle_height {
+            end_at(&mut result, -muzzle_height, |point| -point.position.y);
+        }
+        if let Some(max_time_s) = options.max_time_s {
+            end_at(&mut result, max_time_s, |point| point.time);
+        }
+        // Whichever threshold the bullet slows to first ends it
+        let stops = [
+            (Stop::Velocity, options.stop_velocity_mps.and_then(|limit| falls_below(&result, limit, |point| point.velocity_magnitude))),
+            (Stop::Energy, options.stop_energy_j.and_then(|limit| falls_below(&result, limit, |point| point.kinetic_energy))),
+        ];
+        let stop = stops.into_iter().filter_map(|(stop, time)| Some((stop, time?))).min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((_, time)) = stop {
+            end_at(&mut result, time, |point| point.time);
+        }
+        let stop = stop.map(|(stop, _)| stop);
+        // Only the curvature's deflection moves points along the range after this, by millimetres
+        if let Some(target_range_m) = options.target_range_m {
+            end_at(&mut result, target_range_m, |point| point.position.z);
+            let reached_m = result.points.last().map_or(0.0, |point| point.position.z);
+            if reached_m < target_range_m {
+                return Err(Error::new(
+                    error::solver_error(ruby),
+                    format!("the trajectory ends at {:.1} yards, short of target_range_yards {:.1}", reached_m / YARDS_TO_METERS, target_range_m / YARDS_TO_METERS),
+                ));
+            }
+        }
+        let spin = Spin::from_inputs(&ballistic_inputs, atmosphere);
+        if let (Some(stream), Some(end)) = (stream, result.points.last()) {
+            if end.time > streamed_to {
+                stream(streamed(end, spin))?;
+            }
+        }
+        wind.apply(&mut result, !matches!(self.model, Model::Pejsa | Model::Siacci));
+        let wind_band = wind.band(&result);
+        spin.apply(&mut result);
+        if let Some(magnus) = self.magnus {
+            spin.apply_magnus(&mut result, magnus, &ballistic_inputs, &wind.muzzle, atmosphere);
+        }
+        // The yaw of repose was flown; its drift is read back from the trajectory before it is corrected
+        let repose = (self.model == Model::ModifiedPointMass).then(|| Repose::from_result(self.yaw_of_repose(&ballistic_inputs, atmosphere), &result, &ballistic_inputs, atmosphere));
+        let coriolis = self.earth.map(|earth| earth.coriolis(&result));
+        if let Some(coriolis) = &coriolis {
+            coriolis.apply(&mut result);
+        }
+        let curvature = self.curvature.then(|| earth::curvature(&result));
+        if let Some(curvature) = &curvature {
+            curvature.apply(&mut result);
+        }
+        let solved_gravity = if matches!(self.model, Model::Pejsa | Model::Siacci) { G_ACCEL_MPS2 } else { earth::SOLVER_GRAVITY };
+        let gravity = self.gravity_latitude.map(|latitude| earth::gravity(&result, solved_gravity, latitude, atmosphere.altitude));
+        if let Some(gravity) = &gravity {
+            gravity.apply(&mut result);
+        }
+        if let Some(cant) = self.cant(&ballistic_inputs) {
+            cant.apply(&mut result);
+        }
+        summarize(&mut result);
+
+        // Corrected at the solved points, so only the returned ones are interpolated
+        let Some(output_step) = options.output_step else {
+            return Ok((result, ballistic_inputs, repose, wind_band, coriolis, curvature, gravity, stop));
+        };
+        let samples = match output_step {
+            OutputStep::Range(step) => interpolation::samples(&result.points, |point| point.position.z, step),
+            OutputStep::Time(step) => interpolation::samples(&result.points, |point| point.time, step),
+        };
+        interpolation::resample_points(&mut result, &samples);
+        Ok((
+            result,
+            ballistic_inputs,
+            repose.map(|repose| repose.resample(&samples)),
+            wind_band.map(|band| band.resample(&samples)),
+            coriolis.map(|coriolis| coriolis.resample(&samples)),
+            curvature.map(|curvature| curvature.resample(&samples)),
+            gravity.map(|gravity| gravity.resample(&samples)),
+            stop,
+        ))
+    }
+
+    /// Launch angle of the longest range, radians, with `options`' integration
+    /// settings; those ending the trajectory early are ignored
+    ///
+    /// Golden-section search over the angle, each candidate flown until it
+    /// falls back to the muzzle's height. The range rises with the angle to
+    /// the optimum and falls beyond it.
+    pub fn max_range_angle(&self, ruby: &Ruby, wind: &WindField, atmosphere: &AtmosphericConditions, options: SolveOptions) -> Result<f64, Error> {
+        let range_m = |launch_angle: f64| -> Result<f64, Error> {
+            let options = SolveOptions {
+                launch_angle: Some(launch_angle),
+                max_range_m: None,
+                max_time_s: None,
+                stop_velocity_mps: None,
+                stop_energy_j: None,
+                target_range_m: None,
+                output_step: None,
+                ..options
+            };
+            let (result, ..) = self.solve_engine(ruby, wind.clone(), atmosphere, options)?;
+            let (Some(first), Some(last)) = (result.points.first(), result.points.last()) else {
+                return Err(Error::new(error::solver_error(ruby), "No trajectory points generated"));
+            };
+            if last.position.y > first.position.y + 1.0 {
+                return Err(Error::new(
+                    error::solver_error(ruby),
+                    format!("fired at {:.2} degrees the bullet is still in flight after {:.0} s", launch_angle / DEGREES_TO_RADIANS, last.time),
+                ));
+            }
+            Ok(result.max_range)
+        };
+
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = MAX_RANGE_ANGLES;
+        let mut a = high - ratio * (high - low);
+        let mut b = low + ratio * (high - low);
+        let (mut range_a, mut range_b) = (range_m(a)?, range_m(b)?);
+        while high - low > MAX_RANGE_ANGLE_TOLERANCE {
+            if range_a > range_b {
+                (high, b, range_b) = (b, a, range_a);
+                a = high - ratio * (high - low);
+                range_a = range_m(a)?;
+            } else {
+                (low, a, range_a) = (a, b, range_b);
+                b = low + ratio * (high - low);
+                range_b = range_m(b)?;
+            }
+        }
+        Ok((low + high) / 2.0)
+    }
+
+    /// Zero the bore, solve the trajectory and wrap the engine result
+    ///
+    /// If the Ruby caller passed a block, each point is yielded to it as the
+    /// integrator steps to it (see `solve_streamed`).
+    pub fn solve_with(
+        &self,
+        ruby: &Ruby,
+        wind: WindField,
+        atmosphere: AtmosphericConditions,
+        units: OutputUnits,
+        options: SolveOptions,
+    ) -> Result<Obj<TrajectoryResult>, Error> {
+        let mut yield_point = |point: TrajectoryPoint| ruby.yield_value::<_, Value>(point.into_frozen(ruby)).map(|_| ());
+        let stream = ruby.block_given().then_some(&mut yield_point as Stream);
+        let (result, solved_inputs, repose, wind_band, coriolis, curvature, gravity, stop) = self.solve_streamed(ruby, wind, &atmosphere, options, stream)?;
+        let speed_of_sound_mps = result::speed_of_sound_mps(&atmosphere);
+        let warnings = warnings::collect(&solved_inputs, &atmosphere, &result, speed_of_sound_mps);
+        let integrator = (!matches!(self.model, Model::Pejsa | Model::Siacci)).then(|| options.integrator());
+        let derived = Derived {
+            repose,
+            wind_band,
+            coriolis,
+            curvature,
+            gravity,
+            integrator,
+            target_range_m: options.target_range_m,
+            stop,
+            cant: self.cant(&solved_inputs),
+            ..Derived::from_inputs(&solved_inputs, self.form_factor, &atmosphere)
+        };
+        Ok(TrajectoryResult::new(ruby, result, self.sight_height_m(), speed_of_sound_mps, units, warnings, derived))
+    }
+
+    /// solver.solve(environment: nil, wind: nil, atmosphere: nil, output_units: nil, si: false, solver_options: nil)
+    ///
+    /// `wind`/`atmosphere` may be `Wind`/`Atmosphere` objects or symbol-keyed hashes,
+    /// and replace those of a registered `environment`.
+    /// With `si: true` the hashes hold SI values and `to_h` reports SI values.
+    fn solve(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Obj<TrajectoryResult>, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let (wind, atmosphere, units, options) = read_solve_keywords(ruby, args.keywords)?;
+        rb_self.solve_with(ruby, wind, atmosphere, units, options)
+    }
+}
+
+/// The `wind:`, `atmosphere:`, `output_units:` and `si:` keywords of `Solver#solve`
+pub fn read_conditions(ruby: &Ruby, kw: RHash) -> Result<(WindField, AtmosphericConditions, OutputUnits), Error> {
+    let kwargs = get_kwargs::<_, (), (Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>), ()>(
+        kw,
+        &[],
+        &["environment", "wind", "atmosphere", "output_units", "si"],
+    )
+    .map_err(|e| error::as_input_error(ruby, e))?;
+    let (environment, wind, atmosphere, units, si) = kwargs.optional;
+
+    let mut problems = Problems::default();
+    let environment = environment::read(environment, &mut problems)?;
+    let (wind, atmosphere, units) = if problems.optional("si", si, false) {
+        (
+            environment.si_wind_from_option(wind, &mut problems)?,
+            environment.si_atmosphere_from_option(atmosphere, &mut problems)?,
+            OutputUnits::si(),
+        )
+    } else {
+        (
+            environment.wind_field_from_option(wind, &mut problems)?,
+            environment.atmosphere_from_option(atmosphere, &mut problems)?.to_conditions(),
+            OutputUnits::from_option(units, &mut problems)?,
+        )
+    };
+    problems.into_result(ruby)?;
+    Ok((wind, atmosphere, units))
+}
+
+/// `read_conditions` plus the `solver_options:` keyword
+pub fn read_solve_keywords(ruby: &Ruby, kw: RHash) -> Result<(WindField, AtmosphericConditions, OutputUnits, SolveOptions), Error> {
+    let kw = validation::copy(kw)?;
+    let options = kw.delete::<_, Option<Value>>(ruby.to_symbol("solver_options"))?;
+    let (wind, atmosphere, units) = read_conditions(ruby, kw)?;
+    let mut problems = Problems::default();
+    let options = SolveOptions::from_option(options, &mut problems)?;
+    problems.into_result(ruby)?;
+    Ok((wind, atmosphere, units, options))
+}
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("Solver", ruby.class_object())?;
+    class.define_singleton_method("new", function!(Solver::new, 1))?;
+    class.define_method("solve", method!(Solver::solve, -1))?;
+    Ok(())
+}
@@ -0,0 +1,413 @@
+use magnus::{Error, Ruby, TryConvert, Value, prelude::*};
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryResult as EngineTrajectoryResult, WindConditions};
+use ballistics_engine::pitch_damping::calculate_transverse_moment_of_inertia;
+use ballistics_engine::spin_decay::calculate_spin_decay_correction_factor;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::analytic::Drag;
+use crate::drag;
+use crate::earth;
+use crate::interpolation::{self, Sample};
+use crate::units::*;
+use crate::validation::Problems;
+use crate::warnings;
+
+// Bullet spin along the trajectory. The rate starts at the muzzle velocity
+// over the twist and decays by the engine's empirical model (a few percent a
+// second, set by the weight and muzzle velocity). Gyroscopic spin drift
+// (`spin_drift: true`) follows Litz's approximation, 1.25 * (Sg + 1.2) * t^1.83
+// inches, with Sg falling as the square of the remaining spin. The engine
+// solver has no spin drift of its own, so the drift is added to the lateral
+// position of each solved point.
+//
+// The Magnus force (`magnus:`) of a crosswind on the spinning bullet is added
+// the same way, to the height: 0.5 * rho * S * d * p * crosswind * C, integrated
+// twice over the solved points' times. It lifts a right-hand twist bullet the
+// wind pushes right and drops one it pushes left (mirrored for a left twist).
+//
+// The modified point-mass model (`solver: "mpm"`) replaces Litz's drift with
+// the one from the yaw of repose, the yaw at which the overturning moment
+// balances the turning of the air velocity v by the acceleration a:
+// 2 * Ix * p * (v x a) / (rho * S * d * CMa * V^4), to the right of a rising
+// or falling bullet and up or down of one turned by a crosswind. It enters
+// the integration, as in the NATO modified point-mass equations: the yaw's
+// lift, 0.5 * rho * S * V^2 * CLa times the yaw, pushes the bullet along it,
+// and its yaw drag, 0.5 * rho * S * V^2 * CDa2 times its square, slows it.
+// The engine reports no moment coefficients, so CMa is backed out of the
+// Miller stability factor at the muzzle and the inertias are estimated from
+// the bullet's dimensions. The coefficients are held at their supersonic
+// values; CMa really grows through the transonic region, so the drift there
+// comes out larger than measured drift.
+
+/// Exponent of the time of flight in Litz's approximation
+const TIME_EXPONENT: f64 = 1.83;
+/// Simpson's rule intervals for the drift under decaying spin (even)
+const DRIFT_STEPS: usize = 16;
+
+/// Magnus force coefficients by bullet class; blunter, rougher bullets turn more lift from spin
+const BULLET_CLASSES: &[(&str, f64)] = &[("match", 0.015), ("hunting", 0.020), ("fmj", 0.025), ("cast", 0.030)];
+/// Class used for `magnus: true`
+const DEFAULT_BULLET_CLASS: &str = "match";
+
+/// Lift force coefficient slope, per radian of yaw (typical of supersonic rifle bullets)
+const LIFT_SLOPE: f64 = 2.0;
+/// Yaw drag coefficient, per square radian of yaw (typical of supersonic rifle bullets)
+const YAW_DRAG_SLOPE: f64 = 4.0;
+/// Axial moment of inertia over that of a solid cylinder, for an ogive's lighter nose
+const AXIAL_INERTIA_FACTOR: f64 = 0.85;
+
+/// Read `magnus:`: false/nil (off), true, a bullet class name or a coefficient
+pub fn read_magnus(value: Option<Value>, problems: &mut Problems) -> Result<Option<f64>, Error> {
+    let Some(value) = value.filter(|value| value.to_bool()) else {
+        return Ok(None);
+    };
+    let ruby = Ruby::get_with(value);
+    if value.is_kind_of(ruby.class_numeric()) {
+        return Ok(Some(f64::try_convert(value)?));
+    }
+    let class = if value.is_kind_of(ruby.class_true_class()) {
+        DEFAULT_BULLET_CLASS.to_string()
+    } else {
+        value.funcall::<_, _, String>("to_s", ())?
+    };
+    Ok(magnus_coefficient(&class, problems))
+}
+
+/// Coefficient for a bullet class name, recording an unknown one
+pub fn magnus_coefficient(class: &str, problems: &mut Problems) -> Option<f64> {
+    let coefficient = BULLET_CLASSES.iter().find(|(name, _)| name.eq_ignore_ascii_case(class)).map(|(_, coefficient)| *coefficient);
+    if coefficient.is_none() {
+        let names = BULLET_CLASSES.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        problems.add("magnus", format!("magnus {:?} is not a bullet class (supported: {})", class, names.join(", ")));
+    }
+    coefficient
+}
+
+/// Coefficient for `magnus: true`
+pub fn default_magnus_coefficient() -> f64 {
+    BULLET_CLASSES.iter().find(|(name, _)| *name == DEFAULT_BULLET_CLASS).map_or(0.0, |(_, coefficient)| *coefficient)
+}
+
+/// Spin of a solved trajectory, carried with the result
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spin {
+    muzzle_rpm: f64,
+    bullet_weight_grains: f64,
+    muzzle_velocity_mps: f64,
+    /// Miller stability at the muzzle; 0 when spin drift is disabled
+    drift_stability: f64,
+    /// 1 for a right-hand twist, -1 for left
+    direction: f64,
+}
+
+impl Spin {
+    /// For the engine inputs (`use_enhanced_spin_drift`) and atmosphere solved in
+    ///
+    /// An unstable bullet (Sg at most 1) tumbles rather than drifts, so gets none.
+    pub fn from_inputs(inputs: &BallisticInputs, atmosphere: &AtmosphericConditions) -> Self {
+        let stability = if inputs.use_enhanced_spin_drift { warnings::stability(inputs, atmosphere) } else { 0.0 };
+        let twist_m = inputs.twist_rate * INCHES_TO_METERS;
+        Self {
+            muzzle_rpm: if twist_m > 0.0 { inputs.muzzle_velocity / twist_m * 60.0 } else { 0.0 },
+            bullet_weight_grains: inputs.bullet_mass / GRAINS_TO_KG,
+            muzzle_velocity_mps: inputs.muzzle_velocity,
+            drift_stability: if stability > 1.0 { stability } else { 0.0 },
+            direction: if inputs.is_twist_right { 1.0 } else { -1.0 },
+        }
+    }
+
+    /// Fraction of the muzzle spin left after `time` seconds
+    fn retained(self, time: f64) -> f64 {
+        calculate_spin_decay_correction_factor(time, self.muzzle_velocity_mps, 0.0, self.bullet_weight_grains, 0.0, 0.0, None)
+    }
+
+    /// Spin rate after `time` seconds of flight, rpm
+    pub fn rpm_at(self, time: f64) -> f64 {
+        self.muzzle_rpm * self.retained(time)
+    }
+
+    /// Spin rate after `time` seconds of flight, rad/s
+    fn spin_rate(self, time: f64) -> f64 {
+        self.rpm_at(time) * std::f64::consts::TAU / 60.0
+    }
+
+    /// Lateral drift after `time` seconds of flight, metres (positive is right)
+    ///
+    /// Litz's drift grows as 1.25 * (Sg + 1.2) per unit of t^1.83, so it is
+    /// integrated over u = t^1.83 with the decayed Sg.
+    pub fn drift_at(self, time: f64) -> f64 {
+        if self.drift_stability == 0.0 || time <= 0.0 {
+            return 0.0;
+        }
+        let end = time.powf(TIME_EXPONENT);
+        let step = end / DRIFT_STEPS as f64;
+        let rate = |u: f64| {
+            let retained = self.retained(u.powf(1.0 / TIME_EXPONENT));
+            1.25 * (self.drift_stability * retained * retained + 1.2)
+        };
+        let interior: f64 = (1..DRIFT_STEPS).map(|index| rate(step * index as f64) * if index % 2 == 1 { 4.0 } else { 2.0 }).sum();
+        let inches = (rate(0.0) + interior + rate(end)) * step / 3.0;
+        self.direction * inches * INCHES_TO_METERS
+    }
+
+    /// Add the Magnus lift of the crosswind in `wind` to the height of every point
+    pub fn apply_magnus(
+        self,
+        result: &mut EngineTrajectoryResult,
+        coefficient: f64,
+        inputs: &BallisticInputs,
+        wind: &WindConditions,
+        atmosphere: &AtmosphericConditions,
+    ) {
+        // The engine's air velocity, positive to the right
+        let crosswind = wind.speed * wind.direction.sin();
+        if crosswind == 0.0 || result.points.is_empty() {
+            return;
+        }
+        let area = std::f64::consts::FRAC_PI_4 * inputs.bullet_diameter.powi(2);
+        let per_spin = self.direction * 0.5 * drag::solver_air_density(atmosphere) * area * inputs.bullet_diameter * crosswind * coefficient
+            / inputs.bullet_mass;
+        let acceleration = |time: f64| per_spin * self.rpm_at(time) * std::f64::consts::TAU / 60.0;
+
+        let (mut velocity, mut lift) = (0.0, 0.0);
+        let mut previous = (result.points[0].time, acceleration(result.points[0].time));
+        for point in result.points.iter_mut().skip(1) {
+            let current = (point.time, acceleration(point.time));
+            let step = current.0 - previous.0;
+            let next_velocity = velocity + (previous.1 + current.1) / 2.0 * step;
+            lift += (velocity + next_velocity) / 2.0 * step;
+            velocity = next_velocity;
+            previous = current;
+            point.position.y += lift;
+        }
+    }
+
+    /// Add the drift to the lateral position of every point
+    pub fn apply(self, result: &mut EngineTrajectoryResult) {
+        if self.drift_stability == 0.0 {
+            return;
+        }
+        for point in &mut result.points {
+            point.position.x += self.drift_at(point.time);
+        }
+    }
+}
+
+/// Yaw of repose of a modified point-mass solve and the force it adds, as the
+/// extension's integrators step through it
+#[derive(Clone, Copy)]
+pub struct YawOfRepose {
+    spin: Spin,
+    mass: f64,
+    /// kg/m³, as the solver's drag takes it
+    density: f64,
+    /// Frontal area, m²
+    area: f64,
+    diameter: f64,
+    axial_inertia: f64,
+    /// Overturning moment coefficient slope CMa, per radian
+    overturning_slope: f64,
+}
+
+impl YawOfRepose {
+    /// For the engine inputs and atmosphere solved in; None for a bullet that
+    /// is not gyroscopically stable, which tumbles instead
+    pub fn new(spin: Spin, inputs: &BallisticInputs, atmosphere: &AtmosphericConditions) -> Option<Self> {
+        let stability = warnings::stability(inputs, atmosphere);
+        if stability <= warnings::UNSTABLE || spin.muzzle_rpm == 0.0 {
+            return None;
+        }
+        let (mass, diameter) = (inputs.bullet_mass, inputs.bullet_diameter);
+        let density = drag::solver_air_density(atmosphere);
+        let area = std::f64::consts::FRAC_PI_4 * diameter.powi(2);
+        let axial_inertia = AXIAL_INERTIA_FACTOR * mass * diameter.powi(2) / 8.0;
+        let transverse_inertia = calculate_transverse_moment_of_inertia(mass, diameter, inputs.bullet_length, "ogive");
+        // Miller's Sg is Ix^2 p^2 / (2 rho S d Iy CMa V^2); solved for CMa at the muzzle
+        let overturning_slope = (axial_inertia * spin.spin_rate(0.0)).powi(2)
+            / (2.0 * density * area * diameter * transverse_inertia * stability * spin.muzzle_velocity_mps.powi(2));
+        Some(Self { spin, mass, density, area, diameter, axial_inertia, overturning_slope })
+    }
+
+    /// Yaw, radians, as a vector across the air velocity `relative` the point-mass
+    /// `acceleration` turns, `time` seconds into the flight
+    pub fn yaw(&self, time: f64, relative: Vector3<f64>, acceleration: Vector3<f64>) -> Vector3<f64> {
+        let speed = relative.magnitude();
+        if speed <= 0.0 {
+            return Vector3::zeros();
+        }
+        relative.cross(&acceleration) * (self.spin.direction * 2.0 * self.axial_inertia * self.spin.spin_rate(time)
+            / (self.density * self.area * self.diameter * self.overturning_slope * speed.powi(4)))
+    }
+
+    /// Lift and yaw drag of the yaw of repose, m/s², on a bullet at the air
+    /// velocity `relative` the point-mass `acceleration` turns
+    pub fn acceleration(&self, time: f64, relative: Vector3<f64>, acceleration: Vector3<f64>) -> Vector3<f64> {
+        let yaw = self.yaw(time, relative, acceleration);
+        let per_yaw = 0.5 * self.density * self.area * relative.magnitude_squared() / self.mass;
+        yaw * (per_yaw * LIFT_SLOPE) - relative.normalize() * (per_yaw * YAW_DRAG_SLOPE * yaw.magnitude_squared())
+    }
+}
+
+/// Modified point-mass yaw of repose and the drift it causes, one entry per
+/// solved point
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Repose {
+    /// Radians, positive to the right
+    yaw: Vec<f64>,
+    /// Metres, positive to the right
+    drift: Vec<f64>,
+}
+
+impl Repose {
+    /// Along a trajectory `inputs` were integrated with `repose` to; all zero
+    /// for a bullet that is not gyroscopically stable
+    ///
+    /// The drift is already in the positions; this is its share, reported as
+    /// the spin drift: the lift's lateral push integrated twice, with the drag
+    /// slowing the sideways velocity it builds as it slows the bullet. The yaw
+    /// is taken in still air, the velocity turned by gravity alone.
+    pub fn from_result(
+        repose: Option<YawOfRepose>,
+        result: &EngineTrajectoryResult,
+        inputs: &BallisticInputs,
+        atmosphere: &AtmosphericConditions,
+    ) -> Self {
+        let count = result.points.len();
+        let Some(repose) = repose.filter(|_| count >= 2) else {
+            return Self { yaw: vec![0.0; count], drift: vec![0.0; count] };
+        };
+        let gravity = Vector3::new(0.0, -earth::SOLVER_GRAVITY, 0.0);
+        let velocity = |index: usize| {
+            let (before, after) = (&result.points[index.saturating_sub(1)], &result.points[(index + 1).min(count - 1)]);
+            (after.position - before.position).normalize() * result.points[index].velocity_magnitude
+        };
+        let yaw = (0..count).map(|index| repose.yaw(result.points[index].time, velocity(index), gravity)).collect::<Vec<_>>();
+        let lateral = |index: usize| repose.acceleration(result.points[index].time, velocity(index), gravity).x;
+        // Drag deceleration per m/s of velocity, V / F with F the retardation distance
+        let drag = Drag::new(inputs, atmosphere);
+        let damping = |index: usize| {
+            let speed = result.points[index].velocity_magnitude;
+            speed / (drag.retardation_ft(speed / FPS_TO_MPS) * FEET_TO_METERS)
+        };
+
+        let mut drift = vec![0.0; count];
+        let mut drift_velocity = 0.0;
+        for index in 1..count {
+            let step = result.points[index].time - result.points[index - 1].time;
+            let (push, damping) = ((lateral(index - 1) + lateral(index)) / 2.0, (damping(index - 1) + damping(index)) / 2.0);
+            // Trapezoidal in the damping too, which holds for steps as long as the adaptive ones
+            let next_velocity = (drift_velocity * (1.0 - damping * step / 2.0) + push * step) / (1.0 + damping * step / 2.0);
+            drift[index] = drift[index - 1] + (drift_velocity + next_velocity) / 2.0 * step;
+            drift_velocity = next_velocity;
+        }
+        // Positive to the right, as the yaw's horizontal part
+        Self { yaw: yaw.iter().map(|yaw| yaw.x).collect(), drift }
+    }
+
+    /// `(yaw radians, drift metres)` at a solved point's index
+    pub fn at(&self, index: usize) -> (f64, f64) {
+        (self.yaw.get(index).copied().unwrap_or_default(), self.drift.get(index).copied().unwrap_or_default())
+    }
+
+    /// The yaw and drift at the points `samples` takes in place of the solved ones
+    pub fn resample(&self, samples: &[Sample]) -> Self {
+        Self { yaw: interpolation::resample(&self.yaw, samples), drift: interpolation::resample(&self.drift, samples) }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ballistics_engine::TrajectoryPoint as EngineTrajectoryPoint;
+
+    /// The 168gr .308 at 2650 fps from a 10 inch right-hand twist, solved with spin drift
+    fn spinning() -> (BallisticInputs, AtmosphericConditions) {
+        let inputs = BallisticInputs {
+            bullet_mass: 168.0 * GRAINS_TO_KG,
+            bullet_diameter: 0.308 * INCHES_TO_METERS,
+            bullet_length: 1.2 * INCHES_TO_METERS,
+            muzzle_velocity: 2650.0 * FPS_TO_MPS,
+            twist_rate: 10.0,
+            is_twist_right: true,
+            use_enhanced_spin_drift: true,
+            ..BallisticInputs::default()
+        };
+        (inputs, AtmosphericConditions::default())
+    }
+
+    #[test]
+    fn drifts_by_litz_between_the_muzzle_and_the_decayed_stability() {
+        let (inputs, atmosphere) = spinning();
+        let spin = Spin::from_inputs(&inputs, &atmosphere);
+        let stability = warnings::stability(&inputs, &atmosphere);
+        let litz = |stability: f64| 1.25 * (stability + 1.2) * 2.0_f64.powf(TIME_EXPONENT) * INCHES_TO_METERS;
+        let drift = spin.drift_at(2.0);
+        // Litz's constant-Sg drift at 2 s, and that with the Sg the spin has decayed to by then
+        assert!(drift < litz(stability) && drift > litz(stability * spin.retained(2.0).powi(2)), "{} m", drift);
+        let left = Spin::from_inputs(&BallisticInputs { is_twist_right: false, ..inputs.clone() }, &atmosphere);
+        assert_eq!(left.drift_at(2.0), -drift);
+        let unstable = Spin::from_inputs(&BallisticInputs { twist_rate: 30.0, ..inputs }, &atmosphere);
+        assert_eq!(unstable.drift_at(2.0), 0.0);
+    }
+
+    #[test]
+    fn spins_at_the_muzzle_velocity_over_the_twist_then_slows_a_few_percent_a_second() {
+        let (inputs, atmosphere) = spinning();
+        let spin = Spin::from_inputs(&inputs, &atmosphere);
+        // 2650 fps through a 10 inch twist: 2650 * 12 / 10 turns a second
+        assert!((spin.rpm_at(0.0) - 190_800.0).abs() < 1e-6);
+        let rates = [0.5, 1.0, 2.0].map(|time| spin.rpm_at(time) / spin.rpm_at(0.0));
+        assert!(rates[1] > 0.9 && rates[0] > rates[1] && rates[1] > rates[2], "{:?}", rates);
+    }
+
+    /// Lift `apply_magnus` adds after `time` seconds to a trajectory sampled every millisecond
+    fn magnus_lift(inputs: &BallisticInputs, atmosphere: &AtmosphericConditions, direction: f64, time: f64) -> f64 {
+        let points = (0..=(time * 1000.0).round() as usize)
+            .map(|index| EngineTrajectoryPoint {
+                time: index as f64 / 1000.0,
+                position: Vector3::zeros(),
+                velocity_magnitude: inputs.muzzle_velocity,
+                kinetic_energy: 0.0,
+            })
+            .collect();
+        let mut result = EngineTrajectoryResult {
+            max_range: 0.0,
+            max_height: 0.0,
+            time_of_flight: time,
+            impact_velocity: inputs.muzzle_velocity,
+            impact_energy: 0.0,
+            points,
+            sampled_points: None,
+            min_pitch_damping: None,
+            transonic_mach: None,
+            angular_state: None,
+            max_yaw_angle: None,
+            max_precession_angle: None,
+        };
+        let wind = WindConditions { speed: 10.0 * MPH_TO_MPS, direction };
+        let spin = Spin::from_inputs(inputs, atmosphere);
+        spin.apply_magnus(&mut result, default_magnus_coefficient(), inputs, &wind, atmosphere);
+        result.points.last().unwrap().position.y
+    }
+
+    #[test]
+    fn lifts_by_the_magnus_force_of_the_decaying_spin_the_way_the_wind_and_twist_turn_it() {
+        let (inputs, atmosphere) = spinning();
+        let spin = Spin::from_inputs(&inputs, &atmosphere);
+        // Air moving right, the engine's direction of a wind from the left
+        let lift = magnus_lift(&inputs, &atmosphere, std::f64::consts::FRAC_PI_2, 1.0);
+        let area = std::f64::consts::FRAC_PI_4 * inputs.bullet_diameter.powi(2);
+        let per_spin = 0.5 * drag::solver_air_density(&atmosphere) * area * inputs.bullet_diameter * 10.0 * MPH_TO_MPS
+            * default_magnus_coefficient() / inputs.bullet_mass;
+        let (decayed, muzzle) = (0.5 * per_spin * spin.spin_rate(1.0), 0.5 * per_spin * spin.spin_rate(0.0));
+        assert!(lift > decayed && lift < muzzle, "{} outside {}..{}", lift, decayed, muzzle);
+
+        assert!((magnus_lift(&inputs, &atmosphere, -std::f64::consts::FRAC_PI_2, 1.0) + lift).abs() < 1e-12);
+        let left = BallisticInputs { is_twist_right: false, ..inputs.clone() };
+        assert!((magnus_lift(&left, &atmosphere, std::f64::consts::FRAC_PI_2, 1.0) + lift).abs() < 1e-12);
+        assert_eq!(magnus_lift(&inputs, &atmosphere, 0.0, 1.0), 0.0);
+    }
+}
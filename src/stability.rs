@@ -0,0 +1,44 @@
+use magnus::{Error, RHash, RModule, Ruby, Value, function, scan_args::scan_args};
+
+use crate::error;
+use crate::projectile::ImperialInputs;
+use crate::solver;
+use crate::validation::{self, Problems};
+use crate::warnings;
+
+// Gyroscopic stability of a load without solving its trajectory: the Miller
+// stability factor at the muzzle, the same one results report.
+
+/// BallisticsEngine.stability(inputs, require_stable: false, environment: nil, atmosphere: nil)
+///
+/// `{stability_factor:, warnings: [...]}` for the inputs' twist, bullet and
+/// muzzle velocity in the atmosphere. With `require_stable: true` a bullet
+/// that is not gyroscopically stable raises `UnstableBulletError` instead.
+fn stability(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let kw = validation::copy(args.keywords)?;
+    let require_stable = kw.delete::<_, Option<Value>>(ruby.to_symbol("require_stable"))?;
+    let (_, atmosphere, _) = solver::read_conditions(ruby, kw)?;
+
+    let mut problems = Problems::default();
+    let inputs = ImperialInputs::from_value(inputs, &mut problems)?;
+    let require_stable = problems.optional("require_stable", require_stable, false);
+    problems.into_result(ruby)?;
+
+    let stability_factor = warnings::stability(&inputs.to_ballistic_inputs(), &atmosphere);
+    let warning = warnings::stability_warning(stability_factor).map(|warning| warning.message);
+    if require_stable && stability_factor < warnings::UNSTABLE {
+        return Err(Error::new(error::unstable_bullet_error(ruby), warning.unwrap_or_default()));
+    }
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("stability_factor"), stability_factor)?;
+    hash.aset(ruby.to_symbol("warnings"), warning.into_iter().collect::<Vec<_>>())?;
+    Ok(hash)
+}
+
+pub fn init(module: RModule) -> Result<(), Error> {
+    module.define_module_function("stability", function!(stability, -1))?;
+    Ok(())
+}
@@ -0,0 +1,13 @@
+// Unit conversion constants
+pub const GRAINS_TO_KG: f64 = 0.00006479891;
+pub const GRAINS_PER_POUND: f64 = 7000.0;
+pub const FPS_TO_MPS: f64 = 0.3048;
+pub const FEET_TO_METERS: f64 = 0.3048;
+pub const YARDS_TO_METERS: f64 = 0.9144;
+pub const INCHES_TO_METERS: f64 = 0.0254;
+pub const MPH_TO_MPS: f64 = 0.44704;
+pub const INHG_TO_HPA: f64 = 33.86389;
+pub const JOULES_TO_FTLBS: f64 = 0.737562;
+pub const DEGREES_TO_RADIANS: f64 = std::f64::consts::PI / 180.0;
+pub const RADIANS_TO_MOA: f64 = 180.0 / std::f64::consts::PI * 60.0;
+pub const RADIANS_TO_MILS: f64 = 1000.0;
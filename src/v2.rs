@@ -0,0 +1,41 @@
+use magnus::{Error, RClass, RHash, RModule, Ruby, Value, function, prelude::*, scan_args::scan_args, typed_data::Obj};
+
+use crate::error;
+use crate::result::TrajectoryResult;
+use crate::solver::{self, Solver};
+
+// BallisticsEngine::V2: the object API on its own, for code migrating off
+// the hash-based `BallisticsEngine.solve` of 0.13.13. The top-level `solve`
+// now returns a `TrajectoryResult` too, its old hash is `to_h`. The classes
+// are shared with the top-level module, so objects from either tree mix.
+
+/// Classes made available under `V2`, besides every error class
+const SHARED_CONSTANTS: &[&str] = &[
+    "Projectile",
+    "Wind",
+    "Atmosphere",
+    "Solver",
+    "TrajectoryResult",
+    "TrajectoryPoint",
+];
+
+/// V2.solve(projectile, environment: nil, wind: nil, atmosphere: nil, output_units: nil, si: false, solver_options: nil)
+///
+/// `Solver.new(projectile).solve(...)` in one call; conditions are keywords only.
+fn solve(ruby: &Ruby, args: &[Value]) -> Result<Obj<TrajectoryResult>, Error> {
+    let args = scan_args::<(Value,), (), (), (), RHash, ()>(args)?;
+    let (inputs,) = args.required;
+    let solver = Solver::new(ruby, inputs)?;
+    let (wind, atmosphere, units, options) = solver::read_solve_keywords(ruby, args.keywords)?;
+    solver.solve_with(ruby, wind, atmosphere, units, options)
+}
+
+/// Register `V2` once the shared classes are defined
+pub fn init(module: RModule) -> Result<(), Error> {
+    let v2 = module.define_module("V2")?;
+    for name in SHARED_CONSTANTS.iter().chain(error::CLASSES.iter().map(|(name, _)| name)) {
+        v2.const_set(*name, module.const_get::<_, RClass>(*name)?)?;
+    }
+    v2.define_module_function("solve", function!(solve, -1))?;
+    Ok(())
+}
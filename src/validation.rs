@@ -0,0 +1,122 @@
+use magnus::{Error, RHash, Ruby, Value, prelude::*};
+
+use crate::coerce::Coerce;
+use crate::error;
+
+/// Every problem found while reading or checking inputs, at most one per field
+#[derive(Default)]
+pub struct Problems {
+    problems: Vec<(String, String)>,
+}
+
+impl Problems {
+    /// Record a problem for `field` unless it already has one
+    pub fn add(&mut self, field: &str, message: String) {
+        if !self.problems.iter().any(|(existing, _)| existing == field) {
+            self.problems.push((field.to_string(), message));
+        }
+    }
+
+    /// Record `"<field> <requirement>"` when `ok` is false
+    pub fn check(&mut self, field: &str, ok: bool, requirement: &str) {
+        if !ok {
+            self.add(field, format!("{} {}", field, requirement));
+        }
+    }
+
+    /// Record a problem unless `value` is finite and positive
+    pub fn check_positive(&mut self, field: &str, value: f64) {
+        self.check(field, value.is_finite() && value > 0.0, "must be positive");
+    }
+
+    /// Record a problem unless `value` is finite and not negative
+    pub fn check_non_negative(&mut self, field: &str, value: f64) {
+        self.check(field, value.is_finite() && value >= 0.0, "must not be negative");
+    }
+
+    /// Record a problem unless `min <= value <= max`
+    pub fn check_range(&mut self, field: &str, value: f64, min: f64, max: f64) {
+        self.check(field, value >= min && value <= max, &format!("must be between {} and {}", min, max));
+    }
+
+    /// Read a required hash entry, recording a problem if it is missing or invalid
+    pub fn fetch<T: Coerce + Default>(&mut self, hash: RHash, field: &str, key: &str) -> T {
+        self.required(field, entry(hash, key))
+    }
+
+    /// Read an optional hash entry, recording a problem if it is invalid
+    pub fn lookup<T: Coerce + Default>(&mut self, hash: RHash, field: &str, key: &str, default: T) -> T {
+        self.optional(field, entry(hash, key), default)
+    }
+
+    /// Convert a required value, recording a problem if it is missing or invalid
+    pub fn required<T: Coerce + Default>(&mut self, field: &str, value: Option<Value>) -> T {
+        match value {
+            Some(value) => self.convert(field, value),
+            None => {
+                self.add(field, format!("{} is missing", field));
+                T::default()
+            }
+        }
+    }
+
+    /// Convert an optional value (nil means default), recording a problem if it is invalid
+    pub fn optional<T: Coerce + Default>(&mut self, field: &str, value: Option<Value>, default: T) -> T {
+        match value {
+            Some(value) if !value.is_nil() => self.convert(field, value),
+            _ => default,
+        }
+    }
+
+    fn convert<T: Coerce + Default>(&mut self, field: &str, value: Value) -> T {
+        T::coerce(value).unwrap_or_else(|message| {
+            self.add(field, invalid_message(field, &message));
+            T::default()
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Problem messages in the order they were found
+    pub fn messages(&self) -> Vec<String> {
+        self.problems.iter().map(|(_, message)| message.clone()).collect()
+    }
+
+    /// Raise a single `InputError` listing every problem, if there are any
+    ///
+    /// An unsupported drag model on its own raises `UnsupportedDragModelError`.
+    pub fn into_result(self, ruby: &Ruby) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let class = if self.problems.iter().all(|(field, _)| field == "drag_model") {
+            error::unsupported_drag_model_error(ruby)
+        } else {
+            error::input_error(ruby)
+        };
+        Err(Error::new(class, format!("Invalid inputs: {}", self.messages().join("; "))))
+    }
+}
+
+/// Unfrozen shallow copy of `hash`
+///
+/// Input hashes are only ever read; anything that needs to remove keys (or
+/// hand the hash to `rb_get_kwargs`, which deletes the keys it extracts)
+/// works on a copy so the caller's hash, frozen or not, is left as it was.
+pub fn copy(hash: RHash) -> Result<RHash, Error> {
+    let copy = Ruby::get_with(hash).hash_new();
+    copy.update(hash)?;
+    Ok(copy)
+}
+
+/// Value for a string key, falling back to the Symbol of the same name
+pub fn entry(hash: RHash, key: &str) -> Option<Value> {
+    hash.get(key).or_else(|| hash.get(Ruby::get_with(hash).to_symbol(key)))
+}
+
+/// `"<field> is invalid (<reason>)"`
+pub fn invalid_message(field: &str, reason: &str) -> String {
+    format!("{} is invalid ({})", field, reason)
+}
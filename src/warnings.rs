@@ -0,0 +1,175 @@
+use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryResult as EngineTrajectoryResult};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::units::*;
+
+// Non-fatal findings about a solve, reported as `TrajectoryResult#warnings`
+// (messages) and `#warning_details` (codes, messages and ranges).
+// The engine does not report its own integration details (such as step-size
+// changes), so only what can be judged from the inputs and the trajectory is
+// checked here.
+
+/// Below this gyroscopic stability factor a bullet is marginally stable
+const MARGINAL_STABILITY: f64 = 1.5;
+/// Below this it is not gyroscopically stable at all
+pub const UNSTABLE: f64 = 1.0;
+/// Start of the transonic region, where drag changes fastest
+pub const TRANSONIC_MACH: f64 = 1.2;
+/// Past this the bullet is subsonic, and dynamic stability is not predicted
+const DYNAMIC_STABILITY_MACH: f64 = 1.0;
+/// The G1, G6, G7 and G8 tables cover Mach 0 to 5; faster is extrapolated
+const DRAG_TABLE_MAX_MACH: f64 = 5.0;
+
+/// Miller stability factor at the muzzle in `atmosphere`
+pub fn stability(inputs: &BallisticInputs, atmosphere: &AtmosphericConditions) -> f64 {
+    let (density, _) = ballistics_engine::atmosphere::calculate_atmosphere(
+        atmosphere.altitude,
+        Some(atmosphere.temperature),
+        Some(atmosphere.pressure),
+        atmosphere.humidity,
+    );
+    ballistics_engine::stability::compute_stability_coefficient(
+        inputs,
+        (atmosphere.altitude, atmosphere.temperature, atmosphere.pressure, density / 1.225),
+    )
+}
+
+/// One finding, with a stable code to match on
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Warning {
+    /// `unstable`, `marginal_stability`, `drag_extrapolated`, `transonic` or `dynamic_stability`
+    pub code: String,
+    pub message: String,
+    /// Downrange distance the finding applies from, metres
+    #[serde(default)]
+    pub range_m: Option<f64>,
+}
+
+impl Warning {
+    fn new(code: &str, message: String, range_m: Option<f64>) -> Self {
+        Self { code: code.to_string(), message, range_m }
+    }
+}
+
+/// A dumped warning: a bare message in dumps written before codes were kept
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WarningDump {
+    Message(String),
+    Warning(Warning),
+}
+
+impl From<WarningDump> for Warning {
+    fn from(dump: WarningDump) -> Self {
+        match dump {
+            WarningDump::Message(message) => Self::new("other", message, None),
+            WarningDump::Warning(warning) => warning,
+        }
+    }
+}
+
+/// Read dumped warnings, old bare messages included
+pub fn deserialize_dumped<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Warning>, D::Error> {
+    Ok(Vec::<WarningDump>::deserialize(deserializer)?.into_iter().map(Warning::from).collect())
+}
+
+/// The warning for a marginal or unstable stability factor, if it is either
+pub fn stability_warning(stability: f64) -> Option<Warning> {
+    if stability < UNSTABLE {
+        Some(Warning::new(
+            "unstable",
+            format!("bullet is not gyroscopically stable (stability factor {:.2}); a faster twist is needed", stability),
+            None,
+        ))
+    } else if stability < MARGINAL_STABILITY {
+        Some(Warning::new("marginal_stability", format!("gyroscopic stability is marginal (stability factor {:.2})", stability), None))
+    } else {
+        None
+    }
+}
+
+/// Downrange distance where the bullet first slows below `speed_mps`, metres
+///
+/// Interpolated between the points either side; None if it never does.
+pub fn slows_below_m(result: &EngineTrajectoryResult, speed_mps: f64) -> Option<f64> {
+    slows_below(result, speed_mps).map(|(range_m, _)| range_m)
+}
+
+/// `(range metres, time seconds)` where the bullet first slows below `speed_mps`
+pub fn slows_below(result: &EngineTrajectoryResult, speed_mps: f64) -> Option<(f64, f64)> {
+    result
+        .points
+        .windows(2)
+        .find(|pair| pair[0].velocity_magnitude >= speed_mps && pair[1].velocity_magnitude < speed_mps)
+        .map(|pair| {
+            let fraction = (pair[0].velocity_magnitude - speed_mps) / (pair[0].velocity_magnitude - pair[1].velocity_magnitude);
+            (
+                pair[0].position.z + (pair[1].position.z - pair[0].position.z) * fraction,
+                pair[0].time + (pair[1].time - pair[0].time) * fraction,
+            )
+        })
+}
+
+/// Every warning for a solved trajectory, in a fixed order
+pub fn collect(
+    inputs: &BallisticInputs,
+    atmosphere: &AtmosphericConditions,
+    result: &EngineTrajectoryResult,
+    speed_of_sound_mps: f64,
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    warnings.extend(stability_warning(stability(inputs, atmosphere)));
+
+    let muzzle_mach = inputs.muzzle_velocity / speed_of_sound_mps;
+    match &inputs.custom_drag_table {
+        Some(curve) => {
+            let curve_max_mach = curve.mach_values.last().copied().unwrap_or_default();
+            if muzzle_mach > curve_max_mach {
+                warnings.push(Warning::new(
+                    "drag_extrapolated",
+                    format!(
+                        "muzzle velocity (Mach {:.2}) is past the end of the drag curve (Mach {}); drag is extrapolated",
+                        muzzle_mach, curve_max_mach
+                    ),
+                    None,
+                ));
+            }
+        }
+        None if muzzle_mach > DRAG_TABLE_MAX_MACH => warnings.push(Warning::new(
+            "drag_extrapolated",
+            format!(
+                "muzzle velocity (Mach {:.2}) is past the end of the drag table (Mach {}); drag is extrapolated",
+                muzzle_mach, DRAG_TABLE_MAX_MACH
+            ),
+            None,
+        )),
+        None => {}
+    }
+
+    if let Some(range_m) = slows_below_m(result, TRANSONIC_MACH * speed_of_sound_mps) {
+        warnings.push(Warning::new(
+            "transonic",
+            format!(
+                "bullet goes transonic (below Mach {}) at {:.1} yards, before the end of the trajectory",
+                TRANSONIC_MACH,
+                range_m / YARDS_TO_METERS
+            ),
+            Some(range_m),
+        ));
+    }
+    if let Some(range_m) = slows_below_m(result, DYNAMIC_STABILITY_MACH * speed_of_sound_mps) {
+        warnings.push(Warning::new(
+            "dynamic_stability",
+            format!(
+                "trajectory extends past the predicted limit of dynamic stability (Mach {} at {:.1} yards); predictions beyond it are unreliable",
+                DYNAMIC_STABILITY_MACH,
+                range_m / YARDS_TO_METERS
+            ),
+            Some(range_m),
+        ));
+    }
+
+    warnings
+}
@@ -0,0 +1,490 @@
+use std::cell::RefCell;
+
+use magnus::{Error, RArray, RHash, RModule, RString, Ruby, Value, function, method, prelude::*, scan_args::{get_kwargs, scan_args}};
+use ballistics_engine::WindConditions;
+
+use crate::coerce::{self, Coerce};
+use crate::config;
+use crate::error;
+use crate::metric;
+use crate::units::*;
+use crate::validation::{self, Problems, invalid_message};
+use crate::wind_field::{self, Gradient, Shear, WindField, Zone};
+
+/// Wind in the imperial units accepted from Ruby
+#[derive(Clone, Copy)]
+pub struct ImperialWind {
+    pub speed_mph: f64,
+    /// Where the wind blows from, clockwise from the line of fire: 0 a
+    /// headwind, 90 from the right
+    pub direction_degrees: f64,
+    /// Updraft, or a downdraft when negative
+    pub vertical_speed_mph: f64,
+    /// Wind gradient with height; None for a uniform wind
+    pub shear: Option<Shear>,
+    pub shear_exponent: f64,
+    pub roughness_length_feet: f64,
+    /// Height `speed_mph` was measured at; None for the muzzle's
+    pub reference_height_feet: Option<f64>,
+    pub muzzle_height_feet: f64,
+    /// Plus or minus on the wind call, for the band of drifts it allows
+    pub speed_uncertainty_mph: f64,
+    pub direction_uncertainty_degrees: f64,
+}
+
+impl Default for ImperialWind {
+    fn default() -> Self {
+        Self {
+            speed_mph: 0.0,
+            direction_degrees: 0.0,
+            vertical_speed_mph: 0.0,
+            shear: None,
+            shear_exponent: wind_field::DEFAULT_SHEAR_EXPONENT,
+            roughness_length_feet: wind_field::DEFAULT_ROUGHNESS_LENGTH_FEET,
+            reference_height_feet: None,
+            muzzle_height_feet: wind_field::DEFAULT_MUZZLE_HEIGHT_FEET,
+            speed_uncertainty_mph: 0.0,
+            direction_uncertainty_degrees: 0.0,
+        }
+    }
+}
+
+impl ImperialWind {
+    /// Read wind from a string-keyed Ruby hash, recording every invalid entry
+    pub fn from_hash(wind_hash: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let wind_hash = metric::to_imperial(wind_hash, metric::WIND, "wind.", problems)?;
+        let default = config::defaults().wind;
+        let direction_given = validation::entry(wind_hash, "direction_degrees").is_some();
+        let clock_degrees = read_clock(validation::entry(wind_hash, "direction_clock"), direction_given, problems);
+        let wind = Self {
+            speed_mph: problems.lookup(wind_hash, "wind.speed_mph", "speed_mph", default.speed_mph),
+            direction_degrees: clock_degrees
+                .unwrap_or_else(|| problems.lookup(wind_hash, "wind.direction_degrees", "direction_degrees", default.direction_degrees)),
+            vertical_speed_mph: problems.lookup(wind_hash, "wind.vertical_speed_mph", "vertical_speed_mph", default.vertical_speed_mph),
+            shear: match validation::entry(wind_hash, "shear") {
+                Some(shear) => wind_field::read_shear(problems.optional("wind.shear", Some(shear), None), problems),
+                None => default.shear,
+            },
+            shear_exponent: problems.lookup(wind_hash, "wind.shear_exponent", "shear_exponent", default.shear_exponent),
+            roughness_length_feet: problems.lookup(wind_hash, "wind.roughness_length_feet", "roughness_length_feet", default.roughness_length_feet),
+            reference_height_feet: match validation::entry(wind_hash, "reference_height_feet") {
+                Some(height) => read_number("wind.reference_height_feet", Some(height), problems),
+                None => default.reference_height_feet,
+            },
+            muzzle_height_feet: problems.lookup(wind_hash, "wind.muzzle_height_feet", "muzzle_height_feet", default.muzzle_height_feet),
+            speed_uncertainty_mph: problems.lookup(wind_hash, "wind.speed_uncertainty_mph", "speed_uncertainty_mph", default.speed_uncertainty_mph),
+            direction_uncertainty_degrees: problems.lookup(
+                wind_hash,
+                "wind.direction_uncertainty_degrees",
+                "direction_uncertainty_degrees",
+                default.direction_uncertainty_degrees,
+            ),
+        };
+        wind.check(problems);
+        Ok(wind)
+    }
+
+    /// Read wind from a symbol-keyed keyword hash, rejecting unknown keys
+    pub fn from_kwargs(kw: RHash, problems: &mut Problems) -> Result<Self, Error> {
+        let kw = metric::to_imperial(kw, metric::WIND, "wind.", problems)?;
+        // Past get_kwargs's limit of optional keys, so taken out first
+        let ruby = Ruby::get_with(kw);
+        let speed_uncertainty_mph = kw.delete::<_, Option<Value>>(ruby.to_symbol("speed_uncertainty_mph"))?;
+        let direction_uncertainty_degrees = kw.delete::<_, Option<Value>>(ruby.to_symbol("direction_uncertainty_degrees"))?;
+        let kwargs = get_kwargs::<_, (), (Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>, Option<Value>), ()>(
+            kw,
+            &[],
+            &["speed_mph", "direction_degrees", "direction_clock", "vertical_speed_mph", "shear", "shear_exponent", "roughness_length_feet", "reference_height_feet", "muzzle_height_feet"],
+        )
+        .map_err(|e| error::as_input_error(&ruby, e))?;
+        let (speed_mph, direction_degrees, direction_clock, vertical_speed_mph, shear, shear_exponent, roughness_length_feet, reference_height_feet, muzzle_height_feet) = kwargs.optional;
+        let default = config::defaults().wind;
+        let clock_degrees = read_clock(direction_clock, direction_degrees.is_some(), problems);
+
+        let wind = Self {
+            speed_mph: problems.optional("wind.speed_mph", speed_mph, default.speed_mph),
+            direction_degrees: clock_degrees.unwrap_or_else(|| problems.optional("wind.direction_degrees", direction_degrees, default.direction_degrees)),
+            vertical_speed_mph: problems.optional("wind.vertical_speed_mph", vertical_speed_mph, default.vertical_speed_mph),
+            shear: match shear {
+                Some(shear) => wind_field::read_shear(problems.optional("wind.shear", Some(shear), None), problems),
+                None => default.shear,
+            },
+            shear_exponent: problems.optional("wind.shear_exponent", shear_exponent, default.shear_exponent),
+            roughness_length_feet: problems.optional("wind.roughness_length_feet", roughness_length_feet, default.roughness_length_feet),
+            reference_height_feet: match reference_height_feet {
+                Some(height) => read_number("wind.reference_height_feet", Some(height), problems),
+                None => default.reference_height_feet,
+            },
+            muzzle_height_feet: problems.optional("wind.muzzle_height_feet", muzzle_height_feet, default.muzzle_height_feet),
+            speed_uncertainty_mph: problems.optional("wind.speed_uncertainty_mph", speed_uncertainty_mph, default.speed_uncertainty_mph),
+            direction_uncertainty_degrees: problems.optional(
+                "wind.direction_uncertainty_degrees",
+                direction_uncertainty_degrees,
+                default.direction_uncertainty_degrees,
+            ),
+        };
+        wind.check(problems);
+        Ok(wind)
+    }
+
+    /// Read wind from an optional keyword value: nil, a `Wind`, or a symbol-keyed hash (or anything with `to_h`)
+    pub fn from_option(value: Option<Value>, problems: &mut Problems) -> Result<Self, Error> {
+        match value {
+            None => Ok(config::defaults().wind),
+            Some(value) if value.is_nil() => Ok(config::defaults().wind),
+            Some(value) => match <&Wind>::try_convert(value) {
+                Ok(wind) => {
+                    let wind = wind.wind();
+                    wind.check(problems);
+                    Ok(wind)
+                }
+                Err(e) => match coerce::hash_like(value)? {
+                    Some(kw) => Self::from_kwargs(kw, problems),
+                    None => Err(e),
+                },
+            },
+        }
+    }
+
+    /// Record every out-of-range value
+    pub fn check(&self, problems: &mut Problems) {
+        problems.check_non_negative("wind.speed_mph", self.speed_mph);
+        problems.check("wind.direction_degrees", self.direction_degrees.is_finite(), "must be finite");
+        problems.check("wind.vertical_speed_mph", self.vertical_speed_mph.is_finite(), "must be finite");
+        problems.check("wind.shear_exponent", self.shear_exponent.is_finite() && self.shear_exponent >= 0.0, "must be non-negative");
+        problems.check_positive("wind.roughness_length_feet", self.roughness_length_feet);
+        problems.check_non_negative("wind.muzzle_height_feet", self.muzzle_height_feet);
+        problems.check_non_negative("wind.speed_uncertainty_mph", self.speed_uncertainty_mph);
+        problems.check_range("wind.direction_uncertainty_degrees", self.direction_uncertainty_degrees, 0.0, 180.0);
+        let (field, reference) = match self.reference_height_feet {
+            Some(height) => ("wind.reference_height_feet", height),
+            None => ("wind.muzzle_height_feet", self.muzzle_height_feet),
+        };
+        match self.shear {
+            Some(Shear::Power) => problems.check_positive(field, reference),
+            Some(Shear::Logarithmic) => problems.check(field, reference > self.roughness_length_feet, "must be above roughness_length_feet"),
+            None => {}
+        }
+    }
+
+    /// Symbol-keyed hash accepted by `from_kwargs`
+    pub fn to_h(self, ruby: &Ruby) -> Result<RHash, Error> {
+        let hash = ruby.hash_new();
+        hash.aset(ruby.to_symbol("speed_mph"), self.speed_mph)?;
+        hash.aset(ruby.to_symbol("direction_degrees"), self.direction_degrees)?;
+        hash.aset(ruby.to_symbol("vertical_speed_mph"), self.vertical_speed_mph)?;
+        hash.aset(ruby.to_symbol("shear"), self.shear.map(Shear::name))?;
+        hash.aset(ruby.to_symbol("shear_exponent"), self.shear_exponent)?;
+        hash.aset(ruby.to_symbol("roughness_length_feet"), self.roughness_length_feet)?;
+        hash.aset(ruby.to_symbol("reference_height_feet"), self.reference_height_feet)?;
+        hash.aset(ruby.to_symbol("muzzle_height_feet"), self.muzzle_height_feet)?;
+        hash.aset(ruby.to_symbol("speed_uncertainty_mph"), self.speed_uncertainty_mph)?;
+        hash.aset(ruby.to_symbol("direction_uncertainty_degrees"), self.direction_uncertainty_degrees)?;
+        Ok(hash)
+    }
+
+    /// Convert to the wind solved in
+    pub fn to_conditions(self) -> WindField {
+        WindField::new(vec![self.to_zone(0.0, f64::INFINITY)])
+    }
+
+    /// This wind between two distances downrange, metres
+    fn to_zone(self, from_m: f64, to_m: f64) -> Zone {
+        Zone {
+            from_m,
+            to_m,
+            measured: WindConditions {
+                speed: self.speed_mph * MPH_TO_MPS,
+                direction: engine_direction(self.direction_degrees * DEGREES_TO_RADIANS),
+            },
+            gradient: self.shear.map(|shear| Gradient {
+                shear,
+                exponent: self.shear_exponent,
+                roughness_length_m: self.roughness_length_feet * FEET_TO_METERS,
+                reference_height_m: self.reference_height_feet.unwrap_or(self.muzzle_height_feet) * FEET_TO_METERS,
+                muzzle_height_m: self.muzzle_height_feet * FEET_TO_METERS,
+            }),
+            vertical: self.vertical_speed_mph * MPH_TO_MPS,
+            speed_uncertainty: self.speed_uncertainty_mph * MPH_TO_MPS,
+            direction_uncertainty: self.direction_uncertainty_degrees * DEGREES_TO_RADIANS,
+        }
+    }
+}
+
+/// The engine's wind direction for one blowing from `from` radians
+///
+/// `direction_degrees` is where the wind blows from, clockwise from the line
+/// of fire: 0 a headwind, 90 from the right. The engine's is where the air
+/// moves to, 0 downrange and 90 to the right, so half a turn round.
+pub fn engine_direction(from: f64) -> f64 {
+    (from + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU)
+}
+
+/// A wind zone as given: `from_yards`, `to_yards` and its wind
+pub type ZoneInput = (Option<f64>, Option<f64>, ImperialWind);
+
+/// Read `wind: [{from_yards:, to_yards:, ...}, ...]`, recording every invalid zone
+///
+/// Each zone takes the keys of a wind hash, symbol- or string-keyed.
+pub fn read_zones(zones: RArray, problems: &mut Problems) -> Result<WindField, Error> {
+    let ruby = Ruby::get_with(zones);
+    let mut inputs = Vec::with_capacity(zones.len());
+    for (index, zone) in zones.into_iter().enumerate() {
+        let Some(hash) = coerce::hash_like(zone)? else {
+            problems.add("wind", format!("wind[{}] expected a Hash, got {}", index, zone.inspect()));
+            continue;
+        };
+        let hash = metric::to_imperial(hash, metric::WIND, &format!("wind[{}].", index), problems)?;
+        let mut bound = |key: &str| -> Result<Option<f64>, Error> {
+            let value = match hash.delete::<_, Option<Value>>(ruby.to_symbol(key))? {
+                Some(value) => Some(value),
+                None => hash.delete(key)?,
+            };
+            Ok(read_number(&format!("wind[{}].{}", index, key), value, problems))
+        };
+        let (from_yards, to_yards) = (bound("from_yards")?, bound("to_yards")?);
+        let string_keyed = hash.to_vec::<Value, Value>()?.iter().any(|(key, _)| RString::from_value(*key).is_some());
+        let wind = if string_keyed { ImperialWind::from_hash(hash, problems)? } else { ImperialWind::from_kwargs(hash, problems)? };
+        inputs.push((from_yards, to_yards, wind));
+    }
+    Ok(zones_field(inputs, problems))
+}
+
+/// The wind solved in for zones in downrange order
+///
+/// A zone starts where the one before it ends (the muzzle for the first) and
+/// ends where the next starts (nowhere for the last) unless given.
+pub fn zones_field(inputs: Vec<ZoneInput>, problems: &mut Problems) -> WindField {
+    problems.check("wind", !inputs.is_empty(), "must have at least one zone");
+    let mut zones = Vec::with_capacity(inputs.len());
+    let mut previous_to_yards = 0.0;
+    for (index, (from_yards, to_yards, wind)) in inputs.iter().enumerate() {
+        let field = |key: &str| format!("wind[{}].{}", index, key);
+        let from_yards = from_yards.unwrap_or(previous_to_yards);
+        let to_yards = to_yards.or_else(|| inputs.get(index + 1).and_then(|(from_yards, _, _)| *from_yards)).unwrap_or(f64::INFINITY);
+        problems.check_non_negative(&field("from_yards"), from_yards);
+        problems.check(&field("to_yards"), to_yards > from_yards, "must be beyond from_yards");
+        if index > 0 {
+            problems.check(&field("from_yards"), from_yards >= previous_to_yards, &format!("must not be before wind[{}].to_yards", index - 1));
+        }
+        zones.push(wind.to_zone(from_yards * YARDS_TO_METERS, to_yards * YARDS_TO_METERS));
+        previous_to_yards = to_yards;
+    }
+    WindField::new(zones)
+}
+
+/// Read `direction_clock` as `direction_degrees`: hours as a number or an
+/// `"H:MM"` string, 12 o'clock a headwind and 3 o'clock from the right
+///
+/// nil gives `None`; so does an unreadable clock, or one given with
+/// `direction_degrees`, after recording it.
+pub fn read_clock(value: Option<Value>, direction_given: bool, problems: &mut Problems) -> Option<f64> {
+    let value = value.filter(|value| !value.is_nil())?;
+    let hours = match RString::from_value(value) {
+        Some(text) => text.to_string().map_err(|e| e.to_string()).and_then(|text| parse_clock(&text)),
+        None => f64::coerce(value),
+    };
+    clock_direction(hours, direction_given, problems)
+}
+
+/// `read_clock` once the clock is read as hours
+pub fn clock_direction(hours: Result<f64, String>, direction_given: bool, problems: &mut Problems) -> Option<f64> {
+    if direction_given {
+        problems.add("wind.direction_clock", "wind.direction_clock and wind.direction_degrees cannot both be given".to_string());
+        return None;
+    }
+    match hours {
+        Ok(hours) => {
+            problems.check_range("wind.direction_clock", hours, 0.0, 12.0);
+            Some(clock_degrees(hours))
+        }
+        Err(reason) => {
+            problems.add("wind.direction_clock", invalid_message("wind.direction_clock", &reason));
+            None
+        }
+    }
+}
+
+/// Hours from a clock position such as `"3"`, `"3.5"` or `"3:30"`
+pub fn parse_clock(text: &str) -> Result<f64, String> {
+    let invalid = || format!("expected a clock position such as \"3:30\", got {:?}", text);
+    let (hours, minutes) = text.trim().split_once(':').unwrap_or((text.trim(), "0"));
+    let hours = hours.parse::<f64>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<f64>().ok().filter(|minutes| (0.0..60.0).contains(minutes)).ok_or_else(invalid)?;
+    Ok(hours + minutes / 60.0)
+}
+
+/// `direction_degrees` for a clock position in hours
+fn clock_degrees(hours: f64) -> f64 {
+    (hours * 30.0).rem_euclid(360.0)
+}
+
+/// The clock position of `direction_degrees`, `"H:MM"` to the nearest minute
+pub fn clock_position(direction_degrees: f64) -> String {
+    let minutes = (direction_degrees.rem_euclid(360.0) * 2.0).round() as u32 % 720;
+    let hours = match minutes / 60 {
+        0 => 12,
+        hours => hours,
+    };
+    format!("{}:{:02}", hours, minutes % 60)
+}
+
+/// An optional number such as `reference_height_feet`, None if nil
+fn read_number(field: &str, value: Option<Value>, problems: &mut Problems) -> Option<f64> {
+    value.filter(|value| !value.is_nil()).map(|value| problems.required(field, Some(value)))
+}
+
+/// Reusable wind conditions
+#[magnus::wrap(class = "BallisticsEngine::Wind", free_immediately, size)]
+pub struct Wind(RefCell<ImperialWind>);
+
+impl Wind {
+    /// Wind.new(speed_mph: 0.0, direction_degrees: 0.0, vertical_speed_mph: 0.0, shear: nil, shear_exponent: 1/7.0,
+    /// roughness_length_feet: 0.1, reference_height_feet: nil, muzzle_height_feet: 5.0,
+    /// speed_uncertainty_mph: 0.0, direction_uncertainty_degrees: 0.0)
+    ///
+    /// `direction_clock: 3` or `"3:30"` may replace `direction_degrees`.
+    fn new(ruby: &Ruby, args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::<(), (), (), (), RHash, ()>(args)?;
+        let mut problems = Problems::default();
+        let wind = ImperialWind::from_kwargs(args.keywords, &mut problems)?;
+        problems.into_result(ruby)?;
+        Ok(Self(RefCell::new(wind)))
+    }
+
+    /// Snapshot of the current values
+    pub fn wind(&self) -> ImperialWind {
+        *self.0.borrow()
+    }
+}
+
+impl Wind {
+    fn direction_clock(&self) -> String {
+        clock_position(self.0.borrow().direction_degrees)
+    }
+
+    /// Sets `direction_degrees`; nil restores its default
+    fn set_direction_clock(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let degrees = read_clock(Some(value), false, &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().direction_degrees = degrees.unwrap_or(config::defaults().wind.direction_degrees);
+        Ok(())
+    }
+
+    /// Fraction of the speed blowing across the line of fire: full value at 3
+    /// and 9 o'clock, half value at 1, 5, 7 and 11, none at 12 and 6
+    fn value(&self) -> f64 {
+        (self.0.borrow().direction_degrees * DEGREES_TO_RADIANS).sin().abs()
+    }
+
+    fn shear(&self) -> Option<&'static str> {
+        self.0.borrow().shear.map(Shear::name)
+    }
+
+    /// nil makes the wind uniform
+    fn set_shear(ruby: &Ruby, rb_self: &Self, name: Option<String>) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let shear = wind_field::read_shear(name, &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().shear = shear;
+        Ok(())
+    }
+
+    fn reference_height_feet(&self) -> Option<f64> {
+        self.0.borrow().reference_height_feet
+    }
+
+    /// nil measures the wind at the muzzle height
+    fn set_reference_height_feet(ruby: &Ruby, rb_self: &Self, value: Value) -> Result<(), Error> {
+        let mut problems = Problems::default();
+        let height = read_number("reference_height_feet", Some(value), &mut problems);
+        problems.into_result(ruby)?;
+        rb_self.0.borrow_mut().reference_height_feet = height;
+        Ok(())
+    }
+}
+
+impl From<ImperialWind> for Wind {
+    fn from(wind: ImperialWind) -> Self {
+        Self(RefCell::new(wind))
+    }
+}
+
+field_accessors!(Wind {
+    speed_mph / set_speed_mph: f64 = config::defaults().wind.speed_mph,
+    direction_degrees / set_direction_degrees: f64 = config::defaults().wind.direction_degrees,
+    vertical_speed_mph / set_vertical_speed_mph: f64 = config::defaults().wind.vertical_speed_mph,
+    shear_exponent / set_shear_exponent: f64 = config::defaults().wind.shear_exponent,
+    roughness_length_feet / set_roughness_length_feet: f64 = config::defaults().wind.roughness_length_feet,
+    muzzle_height_feet / set_muzzle_height_feet: f64 = config::defaults().wind.muzzle_height_feet,
+    speed_uncertainty_mph / set_speed_uncertainty_mph: f64 = config::defaults().wind.speed_uncertainty_mph,
+    direction_uncertainty_degrees / set_direction_uncertainty_degrees: f64 = config::defaults().wind.direction_uncertainty_degrees,
+});
+
+pub fn init(ruby: &Ruby, module: RModule) -> Result<(), Error> {
+    let class = module.define_class("Wind", ruby.class_object())?;
+    class.define_singleton_method("new", function!(Wind::new, -1))?;
+    Wind::define_field_accessors(class)?;
+    class.define_method("direction_clock", method!(Wind::direction_clock, 0))?;
+    class.define_method("direction_clock=", method!(Wind::set_direction_clock, 1))?;
+    class.define_method("value", method!(Wind::value, 0))?;
+    class.define_method("shear", method!(Wind::shear, 0))?;
+    class.define_method("shear=", method!(Wind::set_shear, 1))?;
+    class.define_method("reference_height_feet", method!(Wind::reference_height_feet, 0))?;
+    class.define_method("reference_height_feet=", method!(Wind::set_reference_height_feet, 1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ballistics_engine::{AtmosphericConditions, BallisticInputs, TrajectoryPoint as EngineTrajectoryPoint, TrajectorySolver};
+
+    use super::*;
+
+    /// The engine's trajectory 500 m through the muzzle wind of `wind`
+    pub fn solved_in(wind: &WindField) -> Vec<EngineTrajectoryPoint> {
+        let mut solver = TrajectorySolver::new(BallisticInputs::default(), wind.muzzle.clone(), AtmosphericConditions::default());
+        solver.set_max_range(500.0);
+        solver.solve().unwrap().points
+    }
+
+    /// `(lateral position m, speed m/s)` 400 m downrange, interpolated between the points
+    pub fn at_400_m(points: &[EngineTrajectoryPoint]) -> (f64, f64) {
+        let pair = points.windows(2).find(|pair| pair[1].position.z >= 400.0).unwrap();
+        let fraction = (400.0 - pair[0].position.z) / (pair[1].position.z - pair[0].position.z);
+        let lerp = |from: f64, to: f64| from + (to - from) * fraction;
+        (lerp(pair[0].position.x, pair[1].position.x), lerp(pair[0].velocity_magnitude, pair[1].velocity_magnitude))
+    }
+
+    fn at_clock(hours: f64) -> (f64, f64) {
+        let wind = ImperialWind { speed_mph: 10.0, direction_degrees: clock_degrees(hours), ..ImperialWind::default() };
+        at_400_m(&solved_in(&wind.to_conditions()))
+    }
+
+    #[test]
+    fn a_wind_from_3_oclock_drifts_the_bullet_left() {
+        let (from_right, _) = at_clock(3.0);
+        let (from_left, _) = at_clock(9.0);
+        assert!(from_right < -0.1, "drifted {} m", from_right);
+        assert!((from_left + from_right).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_wind_from_12_oclock_slows_the_bullet() {
+        let (_, still) = at_400_m(&solved_in(&WindField::default()));
+        let ((headwind_drift, headwind), (_, tailwind)) = (at_clock(12.0), at_clock(6.0));
+        assert!(headwind < still - 1.0 && tailwind > still + 1.0, "{} and {} m/s against {}", headwind, tailwind, still);
+        assert!(headwind_drift.abs() < 1e-6);
+    }
+
+    #[test]
+    fn reads_clock_positions() {
+        assert_eq!(parse_clock("3:30"), Ok(3.5));
+        assert_eq!(clock_degrees(parse_clock("4:30").unwrap()), 135.0);
+        assert_eq!(clock_degrees(12.0), 0.0);
+        assert_eq!(clock_position(135.0), "4:30");
+        assert_eq!(clock_position(0.0), "12:00");
+        assert!(parse_clock("3:75").is_err());
+    }
+}
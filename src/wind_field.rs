@@ -0,0 +1,406 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use ballistics_engine::{TrajectoryPoint as EngineTrajectoryPoint, TrajectoryResult as EngineTrajectoryResult, WindConditions};
+use ballistics_engine::constants::G_ACCEL_MPS2;
+use nalgebra::{Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::interpolation::{self, Sample};
+use crate::validation::Problems;
+
+// Wind that changes along the trajectory. The engine solver takes one uniform
+// wind, so a field that is not uniform is integrated by the extension's own
+// integrators, which take the air velocity at the bullet's range at every
+// step (`air_velocity`): a zone's headwind changes the drag and the drop as
+// its crosswind does the drift, and a vertical wind (`vertical_speed_mph`,
+// which the engine has no input for) lifts the bullet in an updraft and drops
+// it in a downdraft. The flat-fire models take no steps, so for them the
+// muzzle wind is solved in and the deflection by the rest is added to the
+// solved points afterwards. Across the line of fire the bullet's velocity
+// relaxes towards the wind's at the drag rate k (the drag deceleration over
+// the speed), so between two points, with k held at its mean, it follows an
+// exact exponential. k is read off the solved points' speeds, less gravity's
+// share of their slowing.
+//
+// The wind gradient (`shear:`) scales the wind speed with height above the
+// ground, which is taken as level. A shot at each point is held onto it, so
+// the bullet's height there is the muzzle's plus its rise above the straight
+// line from the muzzle to the point, the arc of that shot; one trajectory
+// zeroed short of it would be below the ground. The integrators take the wind
+// at the muzzle's height, and the crosswind each point's shot gains above it
+// is added afterwards in the same way. `"power"` is (h / h_ref)^exponent,
+// `"logarithmic"` the boundary layer's ln(h / z0) / ln(h_ref / z0) for a
+// roughness length z0, where h_ref is the height the wind speed was measured
+// at.
+//
+// Wind zones (`wind: [{from_yards:, to_yards:, ...}, ...]`) each hold their own
+// wind over a stretch downrange, and the air outside them is still.
+//
+// A wind call's uncertainty (`speed_uncertainty_mph`, `direction_uncertainty_degrees`)
+// gives each zone a least and greatest crosswind. The drift is linear in them,
+// so the band of drifts at every point is the solved drift plus each zone's
+// response to its crosswind's change, each zone taken at its own extreme.
+//
+// Without a gradient the wind is the same at every height, so one pass down
+// the trajectory carries every point's deflection. With one, each point's shot
+// sees the wind at heights of its own, and its deflection is integrated from
+// the muzzle; that is done at a few hundred points at most, the rest taken
+// between them in time, where the deflection is smooth.
+
+/// Most points a gradient's deflection is integrated from the muzzle for
+const MAX_ANCHORS: usize = 256;
+
+/// Exponent of the power-law gradient over open ground
+pub const DEFAULT_SHEAR_EXPONENT: f64 = 1.0 / 7.0;
+/// Roughness length of mown grass, feet
+pub const DEFAULT_ROUGHNESS_LENGTH_FEET: f64 = 0.1;
+/// Muzzle height above the ground, feet
+pub const DEFAULT_MUZZLE_HEIGHT_FEET: f64 = 5.0;
+
+/// How the wind speed grows with height
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shear {
+    Power,
+    Logarithmic,
+}
+
+pub const SHEARS: &[(&str, Shear)] = &[("power", Shear::Power), ("logarithmic", Shear::Logarithmic)];
+
+impl Shear {
+    pub fn name(self) -> &'static str {
+        SHEARS.iter().find(|(_, shear)| *shear == self).map_or("", |(name, _)| name)
+    }
+}
+
+/// Read a `shear` name (nil is a uniform wind), recording an unsupported one
+pub fn read_shear(name: Option<String>, problems: &mut Problems) -> Option<Shear> {
+    let name = name?;
+    let shear = SHEARS.iter().find(|(shear_name, _)| shear_name.eq_ignore_ascii_case(&name)).map(|(_, shear)| *shear);
+    if shear.is_none() {
+        let valid = SHEARS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+        problems.add("wind.shear", format!("wind.shear {:?} is not supported (supported: {})", name, valid));
+    }
+    shear
+}
+
+/// A wind gradient in engine units
+#[derive(Clone, Copy)]
+pub struct Gradient {
+    pub shear: Shear,
+    pub exponent: f64,
+    pub roughness_length_m: f64,
+    /// Height the wind speed was measured at
+    pub reference_height_m: f64,
+    pub muzzle_height_m: f64,
+}
+
+impl Gradient {
+    /// Wind speed at `height_m` above the ground over that at the reference height
+    pub fn factor(&self, height_m: f64) -> f64 {
+        match self.shear {
+            Shear::Power => (height_m.max(0.0) / self.reference_height_m).powf(self.exponent),
+            Shear::Logarithmic => {
+                let roughness = self.roughness_length_m;
+                (height_m.max(roughness) / roughness).ln() / (self.reference_height_m / roughness).ln()
+            }
+        }
+    }
+}
+
+/// Wind over one stretch of the range
+#[derive(Clone)]
+pub struct Zone {
+    /// Distances downrange of the muzzle the zone starts and ends at
+    pub from_m: f64,
+    pub to_m: f64,
+    /// Wind at the gradient's reference height, or everywhere without one
+    pub measured: WindConditions,
+    pub gradient: Option<Gradient>,
+    /// Upward air speed, m/s; the gradient does not scale it
+    pub vertical: f64,
+    /// Plus or minus on the measured speed, m/s, and direction, radians
+    pub speed_uncertainty: f64,
+    pub direction_uncertainty: f64,
+}
+
+impl Zone {
+    /// Speed at `rise_m` above the muzzle over the measured speed
+    fn factor(&self, rise_m: f64) -> f64 {
+        self.gradient.map_or(1.0, |gradient| gradient.factor(gradient.muzzle_height_m + rise_m))
+    }
+
+    /// Air velocity at the muzzle's height, m/s, in the engine's axes
+    fn air_velocity(&self) -> Vector3<f64> {
+        let speed = self.measured.speed * self.factor(0.0);
+        Vector3::new(speed * self.measured.direction.sin(), self.vertical, speed * self.measured.direction.cos())
+    }
+
+    /// Crosswind and vertical wind at `rise_m` above the muzzle
+    fn wind(&self, rise_m: f64) -> Vector2<f64> {
+        Vector2::new(self.measured.speed * self.measured.direction.sin() * self.factor(rise_m), self.vertical)
+    }
+
+    fn is_uncertain(&self) -> bool {
+        self.speed_uncertainty > 0.0 || self.direction_uncertainty > 0.0
+    }
+
+    /// Least and greatest measured crosswind within the uncertainty, m/s
+    fn crosswind_range(&self) -> (f64, f64) {
+        let speed = self.measured.speed;
+        let speeds = [(speed - self.speed_uncertainty).max(0.0), speed + self.speed_uncertainty];
+        let (low, high) = (self.measured.direction - self.direction_uncertainty, self.measured.direction + self.direction_uncertainty);
+        // sin is extreme at the ends of the directions or at a full-value wind between them
+        let mut sines = vec![low.sin(), high.sin()];
+        let mut peak = ((low - FRAC_PI_2) / PI).ceil() * PI + FRAC_PI_2;
+        while peak <= high && sines.len() < 4 {
+            sines.push(peak.sin());
+            peak += PI;
+        }
+        let (least_sine, greatest_sine) = sines.iter().fold((1.0_f64, -1.0_f64), |(least, greatest), sine| (least.min(*sine), greatest.max(*sine)));
+        let crosswinds = speeds.iter().flat_map(|speed| [speed * least_sine, speed * greatest_sine]);
+        crosswinds.fold((f64::INFINITY, f64::NEG_INFINITY), |(least, greatest), crosswind| (least.min(crosswind), greatest.max(crosswind)))
+    }
+}
+
+/// Wind along the trajectory, as solved
+#[derive(Clone, Default)]
+pub struct WindField {
+    /// Wind at the muzzle, which the engine solves in
+    pub muzzle: WindConditions,
+    /// In downrange order; still air outside them
+    zones: Vec<Zone>,
+}
+
+impl From<WindConditions> for WindField {
+    fn from(wind: WindConditions) -> Self {
+        Self::new(vec![Zone {
+            from_m: 0.0,
+            to_m: f64::INFINITY,
+            measured: wind,
+            gradient: None,
+            vertical: 0.0,
+            speed_uncertainty: 0.0,
+            direction_uncertainty: 0.0,
+        }])
+    }
+}
+
+impl WindField {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        let muzzle = zones
+            .iter()
+            .find(|zone| zone.from_m <= 0.0 && zone.to_m > 0.0)
+            .map(|zone| WindConditions { speed: zone.measured.speed * zone.factor(0.0), ..zone.measured.clone() })
+            .unwrap_or_default();
+        Self { muzzle, zones }
+    }
+
+    /// Whether the engine's uniform wind is the whole field
+    pub fn is_uniform(&self) -> bool {
+        match self.zones.as_slice() {
+            [] => true,
+            [zone] => zone.gradient.is_none() && zone.vertical == 0.0 && zone.from_m <= 0.0 && zone.to_m == f64::INFINITY,
+            _ => false,
+        }
+    }
+
+    /// The zone `range_m` downrange; None in the still air outside them
+    fn zone_at(&self, range_m: f64) -> Option<&Zone> {
+        self.zones.iter().find(|zone| zone.from_m <= range_m && range_m < zone.to_m)
+    }
+
+    /// Air velocity `range_m` downrange at the muzzle's height, m/s, in the
+    /// engine's axes, as the extension's integrators step through it
+    pub fn air_velocity(&self, range_m: f64) -> Vector3<f64> {
+        self.zone_at(range_m).map_or(Vector3::zeros(), Zone::air_velocity)
+    }
+
+    /// Crosswind and vertical wind `range_m` downrange and `rise_m` above the
+    /// muzzle beyond those solved in: the muzzle's for a flat-fire solve, the
+    /// zone's at the muzzle's height for an `integrated` one, m/s
+    fn extra_wind(&self, range_m: f64, rise_m: f64, integrated: bool) -> Vector2<f64> {
+        let zone = self.zone_at(range_m);
+        let solved = match zone {
+            _ if !integrated => Vector2::new(self.muzzle.speed * self.muzzle.direction.sin(), 0.0),
+            Some(zone) => zone.wind(0.0),
+            None => Vector2::zeros(),
+        };
+        zone.map_or(Vector2::zeros(), |zone| zone.wind(rise_m)) - solved
+    }
+
+    /// Add the deflection by the wind the solve did not follow to the lateral
+    /// position and height of every point: beyond the muzzle's for a
+    /// flat-fire solve, and for one `integrated` through the field the
+    /// gradient's above the muzzle's height
+    pub fn apply(&self, result: &mut EngineTrajectoryResult, integrated: bool) {
+        let by_height = self.zones.iter().any(|zone| zone.gradient.is_some());
+        if self.is_uniform() || (integrated && !by_height) || result.points.is_empty() {
+            return;
+        }
+        let drifts = deflection(&result.points, 1, by_height, |range_m, rise_m, winds| winds[0] = self.extra_wind(range_m, rise_m, integrated));
+        for (point, drift) in result.points.iter_mut().zip(drifts) {
+            point.position.x += drift.x;
+            point.position.y += drift.y;
+        }
+    }
+
+    /// Lateral spread of every point within the zones' wind uncertainty;
+    /// None without one
+    pub fn band(&self, result: &EngineTrajectoryResult) -> Option<WindBand> {
+        let uncertain = self.zones.iter().filter(|zone| zone.is_uncertain()).collect::<Vec<_>>();
+        if uncertain.is_empty() || result.points.is_empty() {
+            return None;
+        }
+        let count = result.points.len();
+        let mut band = WindBand { least: vec![0.0; count], greatest: vec![0.0; count] };
+        // Drift per m/s of each zone's measured crosswind, all in one pass
+        let by_height = uncertain.iter().any(|zone| zone.gradient.is_some());
+        let responses = deflection(&result.points, uncertain.len(), by_height, |range_m, rise_m, winds| {
+            for (wind, zone) in winds.iter_mut().zip(&uncertain) {
+                let inside = zone.from_m <= range_m && range_m < zone.to_m;
+                *wind = Vector2::new(if inside { zone.factor(rise_m) } else { 0.0 }, 0.0);
+            }
+        });
+        for (index, responses) in responses.chunks(uncertain.len()).enumerate() {
+            for (response, zone) in responses.iter().zip(&uncertain) {
+                let nominal = zone.measured.speed * zone.measured.direction.sin();
+                let (least, greatest) = zone.crosswind_range();
+                let (low, high) = (response.x * (least - nominal), response.x * (greatest - nominal));
+                band.least[index] += low.min(high);
+                band.greatest[index] += low.max(high);
+            }
+        }
+        Some(band)
+    }
+}
+
+/// Lateral offsets of each solved point at the least and greatest drift within
+/// the wind uncertainty, one entry per point
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct WindBand {
+    /// Metres, positive to the right
+    least: Vec<f64>,
+    greatest: Vec<f64>,
+}
+
+impl WindBand {
+    /// `(least, greatest)` offset from the point at `index`, metres
+    pub fn at(&self, index: usize) -> (f64, f64) {
+        (self.least.get(index).copied().unwrap_or_default(), self.greatest.get(index).copied().unwrap_or_default())
+    }
+
+    /// The offsets at the points `samples` takes in place of the solved ones
+    pub fn resample(&self, samples: &[Sample]) -> Self {
+        Self { least: interpolation::resample(&self.least, samples), greatest: interpolation::resample(&self.greatest, samples) }
+    }
+}
+
+/// Deflection of every point by `channels` winds at once, m, `channels`
+/// entries per point. `wind(range_m, rise_m, winds)` sets each channel's
+/// crosswind and vertical wind beyond those the points were solved in; only
+/// `by_height` ones change with `rise_m`.
+fn deflection(
+    points: &[EngineTrajectoryPoint],
+    channels: usize,
+    by_height: bool,
+    wind: impl Fn(f64, f64, &mut [Vector2<f64>]),
+) -> Vec<Vector2<f64>> {
+    let Some(muzzle) = points.first().map(|point| point.position) else {
+        return Vec::new();
+    };
+    let intervals = points.windows(2).map(|pair| Interval::new(&pair[0], &pair[1], muzzle)).collect::<Vec<_>>();
+    let mut winds = vec![Vector2::zeros(); channels];
+    // Deflections to the end of the intervals, from the muzzle at `slope` up
+    let mut integrate = |intervals: &[Interval], slope: f64, deflections: &mut Vec<Vector2<f64>>, every_point: bool| {
+        let (mut drift, mut drift_velocity) = (vec![Vector2::zeros(); channels], vec![Vector2::zeros(); channels]);
+        for interval in intervals {
+            if interval.dt > 0.0 {
+                wind(interval.range, interval.rise - slope * interval.range, &mut winds);
+                // Exact for a constant rate over the interval
+                let decay = (-interval.rate * interval.dt).exp();
+                let settling = if interval.rate > 0.0 { (1.0 - decay) / interval.rate } else { interval.dt };
+                for ((drift, drift_velocity), wind) in drift.iter_mut().zip(&mut drift_velocity).zip(&winds) {
+                    *drift += wind * interval.dt + (*drift_velocity - wind) * settling;
+                    *drift_velocity = wind + (*drift_velocity - wind) * decay;
+                }
+            }
+            if every_point {
+                deflections.extend_from_slice(&drift);
+            }
+        }
+        if !every_point {
+            deflections.extend_from_slice(&drift);
+        }
+    };
+
+    let mut deflections = vec![Vector2::zeros(); channels];
+    if !by_height {
+        integrate(&intervals, 0.0, &mut deflections, true);
+        return deflections;
+    }
+    // Each point's shot is held onto it, so the wind is taken at the rise above the chord to it
+    let every = points.len().div_ceil(MAX_ANCHORS);
+    let mut anchors = (0..points.len()).step_by(every).collect::<Vec<_>>();
+    if anchors.last() != Some(&(points.len() - 1)) {
+        anchors.push(points.len() - 1);
+    }
+    let mut exact = Vec::with_capacity(anchors.len() * channels);
+    for &index in &anchors {
+        let target = points[index].position - muzzle;
+        let slope = if target.z > 0.0 { target.y / target.z } else { 0.0 };
+        integrate(&intervals[..index], slope, &mut exact, false);
+    }
+    deflections.clear();
+    for (pair, exact) in anchors.windows(2).zip(exact.chunks(channels).collect::<Vec<_>>().windows(2)) {
+        let (from, to) = (&points[pair[0]], &points[pair[1]]);
+        for point in &points[pair[0]..pair[1]] {
+            let fraction = if to.time > from.time { (point.time - from.time) / (to.time - from.time) } else { 0.0 };
+            deflections.extend(exact[0].iter().zip(exact[1]).map(|(before, after)| before + (after - before) * fraction));
+        }
+    }
+    deflections.extend_from_slice(&exact[exact.len() - channels..]);
+    deflections
+}
+
+/// The flight between two solved points
+pub struct Interval {
+    pub dt: f64,
+    /// Drag deceleration over the speed, 1/s
+    pub rate: f64,
+    /// Mean height above the muzzle
+    pub rise: f64,
+    /// Mean distance downrange of the muzzle
+    pub range: f64,
+    /// Mean velocity, m/s
+    pub velocity: Vector3<f64>,
+}
+
+impl Interval {
+    pub fn new(before: &EngineTrajectoryPoint, after: &EngineTrajectoryPoint, muzzle: Vector3<f64>) -> Self {
+        let dt = after.time - before.time;
+        let path = (after.position - before.position).norm();
+        if dt <= 0.0 || path <= 0.0 {
+            return Self { dt: 0.0, rate: 0.0, rise: 0.0, range: 0.0, velocity: Vector3::zeros() };
+        }
+        let speed = 0.5 * (before.velocity_magnitude + after.velocity_magnitude);
+        let climb = (after.position.y - before.position.y) / path;
+        let deceleration = (before.velocity_magnitude - after.velocity_magnitude) / dt - G_ACCEL_MPS2 * climb;
+        Self {
+            dt,
+            rate: (deceleration / speed).max(0.0),
+            rise: 0.5 * (before.position.y + after.position.y) - muzzle.y,
+            range: 0.5 * (before.position.z + after.position.z) - muzzle.z,
+            velocity: (after.position - before.position) / dt,
+        }
+    }
+
+    /// Carry a deflection and its velocity across the interval under a
+    /// constant `acceleration`, the velocity relaxing at the drag rate
+    pub fn relax(&self, deflection: &mut Vector3<f64>, velocity: &mut Vector3<f64>, acceleration: Vector3<f64>) {
+        let decay = (-self.rate * self.dt).exp();
+        let settling = if self.rate > 0.0 { (1.0 - decay) / self.rate } else { self.dt };
+        let drifting = if self.rate > 0.0 { (self.dt - settling) / self.rate } else { 0.5 * self.dt * self.dt };
+        *deflection += *velocity * settling + acceleration * drifting;
+        *velocity = *velocity * decay + acceleration * settling;
+    }
+}
@@ -8,23 +8,21 @@ puts "=" * 50
 
 # Test 1: Basic trajectory calculation
 puts "\n1. Basic trajectory calculation (168gr .308 Winchester)"
-inputs = BallisticsEngine::BallisticInputs.new(
-  0.223,      # BC (G7)
-  168.0,      # bullet weight (grains)
-  2650.0,     # muzzle velocity (fps)
-  0.308,      # bullet diameter (inches)
-  1.2,        # bullet length (inches)
-  1.5,        # sight height (inches)
-  100.0,      # zero distance (yards)
-  0.0,        # shooting angle (degrees)
-  11.25,      # twist rate (inches)
-  true        # right-hand twist
+projectile = BallisticsEngine::Projectile.new(
+  bc: 0.223,                      # BC (G7)
+  bullet_weight_grains: 168.0,
+  muzzle_velocity_fps: 2650.0,
+  bullet_diameter_inches: 0.308,
+  bullet_length_inches: 1.2,
+  sight_height_inches: 1.5,
+  zero_distance_yards: 100.0,
+  shooting_angle_degrees: 0.0,
+  twist_rate_inches: 11.25,
+  is_right_twist: true
 )
 
-puts inputs.to_s
-
-# Create solver without wind/atmosphere (uses defaults)
-solver = BallisticsEngine::TrajectorySolver.new(inputs, nil, nil)
+# Create solver once, solve without wind/atmosphere (uses defaults)
+solver = BallisticsEngine::Solver.new(projectile)
 result = solver.solve
 
 puts "\nResults (no wind):"
@@ -37,9 +35,8 @@ puts "  Number of points: #{result.points.length}"
 
 # Test 2: Trajectory with wind
 puts "\n2. Trajectory with wind (10 mph from right)"
-wind = BallisticsEngine::WindConditions.new(10.0, 90.0)
-solver_wind = BallisticsEngine::TrajectorySolver.new(inputs, wind, nil)
-result_wind = solver_wind.solve
+wind = BallisticsEngine::Wind.new(speed_mph: 10.0, direction_degrees: 90.0)
+result_wind = solver.solve(wind: wind)
 
 puts "\nResults (with wind):"
 puts "  Max range: #{result_wind.max_range_yards.round(1)} yards"
@@ -48,14 +45,13 @@ puts "  Impact velocity: #{result_wind.impact_velocity_fps.round(1)} fps"
 
 # Test 3: Custom atmospheric conditions
 puts "\n3. Custom atmospheric conditions (high altitude, cold)"
-atmosphere = BallisticsEngine::AtmosphericConditions.new(
-  20.0,       # temperature (F)
-  25.84,      # pressure (inHg) - ~5000ft elevation
-  30.0,       # humidity (%)
-  5000.0      # altitude (feet)
+atmosphere = BallisticsEngine::Atmosphere.new(
+  temperature_f: 20.0,
+  pressure_inhg: 25.84,           # ~5000ft elevation
+  humidity_percent: 30.0,
+  altitude_feet: 5000.0
 )
-solver_altitude = BallisticsEngine::TrajectorySolver.new(inputs, nil, atmosphere)
-result_altitude = solver_altitude.solve
+result_altitude = solver.solve(atmosphere: atmosphere)
 
 puts "\nResults (high altitude):"
 puts "  Max range: #{result_altitude.max_range_yards.round(1)} yards"
@@ -76,8 +72,508 @@ end
 
 # Test 5: Drag models
 puts "\n5. Testing drag models"
-puts "  G1: #{BallisticsEngine::DragModel.g1.to_s}"
-puts "  G7: #{BallisticsEngine::DragModel.g7.to_s}"
-puts "  G8: #{BallisticsEngine::DragModel.g8.to_s}"
+%w[G1 G7 G8].each do |model|
+  projectile.drag_model = model
+  result_model = BallisticsEngine.solve(projectile)
+  puts "  #{projectile.drag_model}: impact velocity #{result_model.impact_velocity_fps.round(1)} fps"
+end
+
+# Test 6: Frozen and shared inputs
+puts "\n6. Frozen and shared input hashes"
+inputs = {
+  "bc" => 0.223,
+  "bullet_weight_grains" => 168.0,
+  "muzzle_velocity_fps" => 2650.0,
+  "bullet_diameter_inches" => 0.308,
+  "bullet_length_inches" => 1.2,
+  "sight_height_inches" => 1.5,
+  "zero_distance_yards" => 100.0,
+  "wind" => { "speed_mph" => 10.0, "direction_degrees" => 90.0 }.freeze,
+  "atmosphere" => { "temperature_f" => 20.0, "altitude_feet" => 5000.0 }.freeze
+}.freeze
+keywords = inputs.reject { |key, _| %w[wind atmosphere].include?(key) }
+                 .transform_keys(&:to_sym)
+                 .merge(wind: { speed_mph: 10.0, direction_degrees: 90.0 }.freeze,
+                        atmosphere: { temperature_f: 20.0, altitude_feet: 5000.0 }.freeze)
+                 .freeze
+snapshot = Marshal.load(Marshal.dump([inputs, keywords]))
+
+first = BallisticsEngine.solve(inputs).to_h
+3.times do
+  raise "solve(frozen hash) changed between calls" unless BallisticsEngine.solve(inputs).to_h == first
+  raise "solve_kw(**frozen) differs from solve" unless BallisticsEngine.solve_kw(**keywords).to_h == first
+end
+raise "input hashes were modified" unless [inputs, keywords] == snapshot
+puts "  #{first['points'].length} identical points across repeated solves; inputs untouched"
+
+def check(description, actual, expected, tolerance)
+  return if (actual - expected).abs <= tolerance
+
+  raise "#{description}: expected #{expected} (within #{tolerance}), got #{actual}"
+end
+
+load = {
+  bc: 0.243,
+  bullet_weight_grains: 168.0,
+  muzzle_velocity_fps: 2650.0,
+  bullet_diameter_inches: 0.308,
+  bullet_length_inches: 1.2,
+  sight_height_inches: 1.5,
+  zero_distance_yards: 100.0,
+  twist_rate_inches: 11.25
+}.freeze
+drop_in = ->(result, yards) { result.at_range(yards).y * 36.0 }
+
+# Test 7: Fitting a BC to observed drops
+puts "\n7. fit_drops recovers the BC that generated the drops"
+solved = BallisticsEngine.solve_kw(**load)
+drops = [300, 450, 600].map { |yards| { range_yards: yards, drop_inches: drop_in.call(solved, yards) } }
+fit = BallisticsEngine.fit_drops(load.merge(bc: 0.3), drops: drops, fit: :bc)
+check("fitted bc", fit[:value], 0.243, 0.002)
+raise "residuals too large: #{fit[:rms_in]}" unless fit[:rms_in] < 0.05
+puts "  bc #{fit[:value].round(4)} from a 0.3 start (rms #{fit[:rms_in].round(3)} in)"
+
+# Test 8: Wind direction is where the wind blows from
+puts "\n8. A 3 o'clock wind drifts left and a 12 o'clock wind slows the bullet"
+still = BallisticsEngine.solve_kw(**load).at_range(800)
+from_right = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10, direction_clock: 3 }).at_range(800)
+from_left = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10, direction_clock: 9 }).at_range(800)
+headwind = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10, direction_clock: 12 }).at_range(800)
+raise "3 o'clock wind drifted right: #{from_right.wind_drift}" unless from_right.wind_drift < -1.0
+check("9 o'clock drift", from_left.wind_drift, -from_right.wind_drift, 0.01)
+raise "12 o'clock wind did not slow the bullet" unless headwind.velocity_fps < still.velocity_fps && headwind.time > still.time
+check("12 o'clock drift", headwind.wind_drift, 0.0, 0.01)
+wind = BallisticsEngine::Wind.new(speed_mph: 10, direction_clock: "4:30")
+check("4:30 direction", wind.direction_degrees, 135.0, 1e-9)
+check("1 o'clock value", BallisticsEngine::Wind.new(speed_mph: 10, direction_clock: 1).value, 0.5, 1e-9)
+puts "  800 yd drift #{(from_right.wind_drift * 36).round(1)} in from 3 o'clock, #{(still.velocity_fps - headwind.velocity_fps).round(1)} fps lost to 12 o'clock"
+
+# Test 9: METAR weather
+puts "\n9. METAR weather and the drift of its wind"
+inhg = BallisticsEngine.atmosphere_from_metar("KMSP 121753Z 18008KT 10SM FEW250 15/05 A2992")
+expected = { temperature_f: 59.0, pressure_inhg: 29.92, dew_point_f: 41.0 }
+unless inhg[:atmosphere].keys.sort == (expected.keys + [:pressure_is_absolute]).sort
+  raise "A2992 report: #{inhg[:atmosphere]}"
+end
+raise "A2992 pressure should be sea-level" unless inhg[:atmosphere][:pressure_is_absolute] == false
+expected.each { |key, value| check("A2992 #{key}", inhg[:atmosphere][key], value, 1e-9) }
+hpa = BallisticsEngine.atmosphere_from_metar("EGLL 121750Z 24010KT 9999 M05/M08 Q1013", shot_direction_degrees: 270)
+check("Q1013 pressure", hpa[:atmosphere][:pressure_inhg], 1013 / 33.86389, 1e-6)
+check("M05 temperature", hpa[:atmosphere][:temperature_f], 23.0, 1e-9)
+check("METAR wind speed", hpa[:wind][:speed_mph], 10 * 1.150779, 1e-6)
+check("METAR wind direction", hpa[:wind][:direction_degrees], 330.0, 1e-9)
+# wind from 240 on a shot to 270 comes from the left, so it drifts the bullet right
+metar_drift = BallisticsEngine.solve_kw(**load, wind: hpa[:wind]).at_range(800).wind_drift
+raise "METAR wind from the left drifted #{metar_drift} yards" unless metar_drift > 0.1
+puts "  A2992 -> #{inhg[:atmosphere][:pressure_inhg]} inHg, Q1013 -> #{hpa[:atmosphere][:pressure_inhg].round(3)} inHg, " \
+     "#{(metar_drift * 36).round(1)} in right at 800 yd"
+
+# Test 10: Kestrel weather
+puts "\n10. Kestrel weather and the drift of its wind"
+kestrel = <<~CSV
+  Device Name,Kestrel 5700 Elite
+  FORMATTED DATE_TIME,Temperature,Relative Humidity,Station Pressure,Wind Speed,Compass True Direction
+  yyyy-MM-dd hh:mm:ss a,°F,%,inHg,mph,Deg
+  2026-06-01 10:41:07 AM,70.9,32.0,25.31,7.5,340
+  2026-06-01 10:42:07 AM,71.6,31.0,25.30,8.2,345
+CSV
+weather = BallisticsEngine.parse_kestrel(kestrel, shot_direction_degrees: 270)
+raise "recorded_at #{weather[:recorded_at]}" unless weather[:recorded_at] == "2026-06-01 10:42:07 AM"
+unless weather[:atmosphere] == { temperature_f: 71.6, pressure_inhg: 25.3, humidity_percent: 31.0 }
+  raise "Kestrel atmosphere: #{weather[:atmosphere]}"
+end
+check("Kestrel wind speed", weather[:wind][:speed_mph], 8.2, 1e-9)
+check("Kestrel wind direction", weather[:wind][:direction_degrees], 75.0, 1e-9)
+# wind from 345 on a shot to 270 comes from the right, so it drifts the bullet left
+kestrel_drift = BallisticsEngine.solve_kw(**load, wind: weather[:wind]).at_range(800).wind_drift
+raise "Kestrel wind from the right drifted #{kestrel_drift} yards" unless kestrel_drift < -0.1
+puts "  #{weather[:atmosphere][:temperature_f]} °F, #{(-kestrel_drift * 36).round(1)} in left at 800 yd"
+
+# Test 11: Fixed-step integrators
+puts "\n11. Euler converges to RK4, which carries on below the bore"
+rk4 = BallisticsEngine.solve_kw(**load, solver_options: { integrator: "rk4" })
+raise "integrator not reported" unless rk4.integrator == "rk4"
+errors = [0.001, 0.0001].map do |step|
+  euler = BallisticsEngine.solve_kw(**load, solver_options: { integrator: "euler", time_step: step })
+  (drop_in.call(euler, 1000) - drop_in.call(rk4, 1000)).abs
+end
+raise "Euler does not converge to RK4 as the step shrinks: #{errors}" unless errors[1] < errors[0] / 5 && errors[1] < 0.3
+rkf45 = BallisticsEngine.solve_kw(**load)
+check("RK4 against rkf45 at 1000 yards", drop_in.call(rk4, 1000), drop_in.call(rkf45, 1000), 0.05)
+check("RK4 range against rkf45", rk4.max_range_yards, rkf45.max_range_yards, 5.0)
+puts "  Euler off RK4 by #{errors.map { |error| error.round(2) }.join(' / ')} in at 1000 yards (1 / 0.1 ms)"
+
+# Test 12: Adaptive tolerances
+puts "\n12. Tighter tolerances take more steps and close on RK4"
+adaptive = [1e-4, 1e-6, 1e-8].map do |tolerance|
+  BallisticsEngine.solve_kw(**load, solver_options: { relative_tolerance: tolerance, absolute_tolerance: tolerance })
+end
+counts = adaptive.map(&:point_count)
+raise "tighter tolerances should take more steps: #{counts}" unless counts == counts.sort && counts.last < rk4.point_count / 10
+check("rkf45 at 1e-8 against RK4 at 1000 yards",
+      adaptive.last.at_range(1000, interpolation: :cubic).y * 36.0, drop_in.call(rk4, 1000), 0.05)
+puts "  rkf45 takes #{counts.join(' / ')} steps for 1e-4 / 1e-6 / 1e-8"
+
+# Test 13: Streaming points from the integrator
+puts "\n13. Streaming points to a block"
+streamed = []
+result = BallisticsEngine.solve_kw(**load, solver_options: { integrator: "euler", max_time_s: 0.5 }) { |point| streamed << point }
+raise "streamed #{streamed.length} points for #{result.point_count}" unless streamed.length == result.point_count
+check("last streamed point's time", streamed.last.time, 0.5, 1e-9)
+streamed = []
+result = BallisticsEngine.solve_kw(**load) { |point| streamed << point }
+raise "streamed #{streamed.length} points for #{result.point_count}" unless streamed.length == result.point_count
+raise "a block should stream the default rkf45" unless result.integrator == "rkf45" && streamed.length > 10
+check("streamed rkf45 against the engine's at 1000 yards",
+      result.at_range(1000, interpolation: :cubic).y * 36.0, drop_in.call(rkf45, 1000), 0.5)
+puts "  #{streamed.length} points streamed from the default rkf45"
+
+# Test 14: Wind zones and vertical wind in the integration
+puts "\n14. Each zone's wind, and rising air, are flown through"
+zones = [
+  { from_yards: 0, to_yards: 400, speed_mph: 6, direction_degrees: 90 },
+  { from_yards: 400, to_yards: 1000, speed_mph: 12, direction_degrees: 90 },
+]
+check("zoned drift at 800 yards", BallisticsEngine.solve_kw(**load, wind: zones).at_range(800).wind_drift, -1.44, 0.03)
+still = BallisticsEngine.solve_kw(**load)
+head_beyond = [
+  { from_yards: 0, to_yards: 400, speed_mph: 10, direction_degrees: 180 },
+  { from_yards: 400, to_yards: 1100, speed_mph: 10, direction_degrees: 0 },
+]
+slowed = BallisticsEngine.solve_kw(**load, wind: head_beyond).at_range(1000)
+raise "a headwind beyond 400 yards should slow the bullet" unless slowed.velocity_fps < still.at_range(1000).velocity_fps - 5
+updraft = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 0, vertical_speed_mph: 5 })
+check("5 mph updraft lift at 800 yards", drop_in.call(updraft, 800) - drop_in.call(still, 800), 31.5, 1.0)
+puts "  #{(still.at_range(1000).velocity_fps - slowed.velocity_fps).round(1)} fps lost to a headwind beyond 400 yards"
+
+# Test 15: Modified point mass
+puts "\n15. The yaw of repose is flown, and its drift reported"
+mpm = BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0, solver: "mpm").at_range(1000)
+left = BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0, is_right_twist: false, solver: "mpm").at_range(1000)
+check("mpm spin drift at 1000 yards", mpm.spin_drift, 0.43, 0.02)
+check("mpm spin drift against the position", mpm.spin_drift, mpm.z, 0.005)
+check("left twist mpm drift", left.z, -mpm.z, 0.005)
+raise "a right twist should yaw right" unless mpm.yaw_of_repose.positive? && left.yaw_of_repose.negative?
+puts "  #{(mpm.spin_drift * 36).round(1)} in of drift at 1000 yards, yawed #{mpm.yaw_of_repose.round(4)} degrees"
+
+# Test 16: Summary fields after the corrections
+puts "\n16. The range, height and impact follow the corrected points"
+corrected = BallisticsEngine.solve_kw(**load, latitude_degrees: 45.0, earth_curvature: true, cant_angle_degrees: 5.0,
+                                      magnus: true, wind: { speed_mph: 10, direction_degrees: 90 })
+last = corrected.points.last
+check("max range against the last point", corrected.max_range_yards, last.x, 1e-9)
+check("time of flight against the last point", corrected.time_of_flight, last.time, 1e-9)
+check("impact velocity against the last point", corrected.impact_velocity_fps, last.velocity_fps, 1e-9)
+check("max height against the points", corrected.max_height_yards, corrected.points.map(&:y).max + 1.5 / 36.0, 1e-9)
+puts "  #{corrected.max_range_yards.round(1)} yards, #{(corrected.max_height_yards * 36).round(2)} in above the bore at most"
+
+# Test 17: Speed of sound override
+puts "\n17. A speed of sound override is kept as given"
+sound = BallisticsEngine.solve_kw(**load, atmosphere: { speed_of_sound_fps: 1100 })
+check("speed of sound solved in", sound.speed_of_sound_fps, 1100.0, 0.01)
+begin
+  BallisticsEngine::Atmosphere.new(speed_of_sound_fps: 1100, humidity_percent: 50)
+  raise "a humidity with a speed of sound override should be an InputError"
+rescue BallisticsEngine::InputError => e
+  raise "unexpected message: #{e.message}" unless e.message.include?("speed_of_sound_fps")
+end
+puts "  #{sound.speed_of_sound_fps.round(2)} fps, and a humidity with it is rejected"
+
+# Test 18: Mach against the solver's speed of sound
+puts "\n18. Each point's Mach is the one its drag was looked up at"
+humid = BallisticsEngine.solve_kw(**load, atmosphere: { temperature_f: 86, humidity_percent: 90 })
+dry_sound_fps = Math.sqrt(1.4 * 287.05 * ((86 - 32) * 5.0 / 9.0 + 273.15)) / 0.3048
+point = humid.at_range(500)
+check("speed of sound solved in", humid.speed_of_sound_fps, dry_sound_fps, 0.01)
+check("Mach at 500 yards", point.mach, point.velocity_fps / dry_sound_fps, 1e-9)
+puts "  Mach #{point.mach.round(3)} at 500 yards against #{dry_sound_fps.round(1)} fps"
+
+# Test 19: Form factor of the BC as given
+puts "\n19. The form factor is that of the BC as given"
+sd = 168.0 / 7000.0 / 0.308**2
+plain = BallisticsEngine.solve_kw(**load)
+scaled = BallisticsEngine.solve_kw(**load, drag_scale_factor: 1.1, standard: "asm")
+check("sectional density", plain.sectional_density, sd, 1e-9)
+check("form factor", plain.form_factor, sd / 0.243, 1e-9)
+check("form factor with a drag scale factor and ASM", scaled.form_factor, sd / 0.243, 1e-9)
+raise "a drag curve has no form factor" unless BallisticsEngine.solve_kw(**load.except(:bc), drag_model: "CUSTOM", drag_curve: [[0.5, 0.2], [3.0, 0.3]]).form_factor.nil?
+puts "  form factor #{plain.form_factor.round(3)} at #{sd.round(3)} lb/in²"
+
+# Test 20: JSON export
+puts "\n20. to_json carries the result with unit-suffixed keys"
+require "json"
+exported = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10, direction_degrees: 90 })
+parsed = JSON.parse(exported.to_json)
+check("max range in the JSON", parsed["max_range_yards"], exported.max_range_yards, 1e-9)
+check("time of flight in the JSON", parsed["time_of_flight_s"], exported.time_of_flight, 1e-9)
+raise "the JSON has #{parsed["points"].length} points for #{exported.point_count}" unless parsed["points"].length == exported.point_count
+last_json, last = parsed["points"].last, exported.points.last
+check("last point range in the JSON", last_json["x_yards"], last.x, 1e-9)
+check("last point windage in the JSON", last_json["z_yards"], last.z, 1e-9)
+check("last point velocity in the JSON", last_json["velocity_fps"], last.velocity_fps, 1e-9)
+puts "  #{parsed.keys.length} keys and #{parsed["points"].length} points"
+
+# Test 21: CSV export
+puts "\n21. to_csv writes a row per point in the chosen columns"
+rows = exported.to_csv.lines.map(&:chomp)
+raise "header #{rows.first}" unless rows.first == "time,x_yards,drop_in,windage_in,velocity_fps,energy_ftlbs"
+raise "#{rows.length - 1} rows for #{exported.point_count} points" unless rows.length - 1 == exported.point_count
+time, x_yards, drop, windage, velocity_fps, energy = rows.last.split(",").map(&:to_f)
+check("last row time", time, last.time, 1e-6)
+check("last row range", x_yards, last.x, 1e-6)
+check("last row drop in inches", drop, last.y * 36.0, 1e-6)
+check("last row windage in inches", windage, last.z * 36.0, 1e-6)
+check("last row velocity", velocity_fps, last.velocity_fps, 1e-6)
+check("last row energy", energy, last.energy_ftlbs, 1e-6)
+chosen = exported.to_csv(columns: %w[mach drop_moa], headers: false).lines
+raise "columns: gave #{chosen.first}" unless chosen.length == exported.point_count && chosen.first.split(",").length == 2
+puts "  #{rows.length - 1} rows of #{rows.first.split(",").length} columns"
+
+# Test 22: Interpolation with at_range
+puts "\n22. at_range interpolation"
+result = BallisticsEngine.solve_kw(**load)
+before, after = result.points[100], result.points[101]
+%i[linear cubic].each do |method|
+  check("at_range(#{method}) at a solved point", result.at_range(before.x, interpolation: method).y, before.y, 1e-12)
+end
+check("linear midpoint", result.at_range((before.x + after.x) / 2, interpolation: :linear).y, (before.y + after.y) / 2, 1e-12)
+begin
+  result.at_range(result.points.last.x + 100)
+  raise "at_range past the trajectory should raise"
+rescue BallisticsEngine::InputError
+  puts "  exact at solved points, linear between them, InputError past the end"
+end
+
+# Test 23: Interpolation with at_time
+puts "\n23. at_time interpolation"
+%i[linear cubic].each do |method|
+  check("at_time(#{method}) at a solved point", result.at_time(after.time, interpolation: method).x, after.x, 1e-12)
+end
+check("linear time midpoint", result.at_time((before.time + after.time) / 2).velocity_fps, (before.velocity_fps + after.velocity_fps) / 2, 1e-9)
+begin
+  result.at_time(result.points.last.time + 1)
+  raise "at_time past the trajectory should raise"
+rescue BallisticsEngine::InputError
+  puts "  exact at solved points, linear between them, InputError past the end"
+end
+
+# Test 24: Output units
+puts "\n24. output_units converts the result hashes"
+yards = BallisticsEngine.solve_kw(**load)
+metric = BallisticsEngine.solve_kw(**load, output_units: { distance: "m", drop: "cm", velocity: "mps", energy: "joules" })
+hash, point_hash = metric.to_h, metric.to_h["points"].last
+check("max range in metres", hash["max_range_meters"], yards.max_range_yards * 0.9144, 1e-9)
+check("impact velocity in m/s", hash["impact_velocity_mps"], yards.impact_velocity_fps * 0.3048, 1e-9)
+check("last point drop in cm", point_hash["y"], yards.points.last.y * 91.44, 1e-9)
+check("last point energy in joules", point_hash["energy_joules"], yards.points.last.energy_ftlbs / 0.737562, 1e-6)
+check("readers stay imperial", metric.max_range_yards, yards.max_range_yards, 1e-12)
+puts "  #{hash["max_range_meters"].round(1)} m, last point #{point_hash["y"].round(1)} cm"
+
+# Test 25: Angular units
+puts "\n25. output_units angle adds the drop and windage as angles"
+drifted = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10, direction_degrees: 90 })
+far = drifted.at_range(600)
+check("drop in MOA", far.drop_moa, Math.atan2(far.y, far.x) * 180 / Math::PI * 60, 1e-9)
+check("windage in mils", far.windage_mil, Math.atan2(far.z, far.x) * 1000, 1e-9)
+check("MOA against the 1.047 in per 100 yd rule", far.drop_moa, far.y * 36.0 / (1.047 * 6), 0.05)
+moa_hash = BallisticsEngine.solve_kw(**load, output_units: { angle: "moa" }).to_h["points"].last
+mil_hash = BallisticsEngine.solve_kw(**load, output_units: { angle: "mil" }).to_h["points"].last
+raise "moa keys #{moa_hash.keys}" unless moa_hash.key?("drop_moa") && moa_hash.key?("windage_moa") && !moa_hash.key?("drop_mil")
+raise "mil keys #{mil_hash.keys}" unless mil_hash.key?("drop_mil") && !mil_hash.key?("drop_moa")
+check("MOA and mil agree", moa_hash["drop_moa"], mil_hash["drop_mil"] * 3.4377467707849396, 1e-9)
+puts "  #{far.drop_moa.round(2)} MOA, #{far.drop_mil.round(2)} mil at 600 yards"
+
+# Test 26: Marshal round trip
+puts "\n26. Marshal round trip"
+result = BallisticsEngine.solve_kw(**load, wind: { speed_mph: 10.0, direction_degrees: 90.0 })
+raise "Marshal round trip changed the result" unless Marshal.load(Marshal.dump(result)).to_h == result.to_h
+puts "  #{result.point_count} points survive Marshal.dump/Marshal.load"
+
+# Test 27: Summary report
+puts "\n27. summary reports the zero, apex, supersonic range and impact"
+plain = BallisticsEngine.solve_kw(**load)
+lines = plain.summary.lines.map(&:chomp)
+labels = lines.map { |line| line.split(":").first }
+raise "summary lines #{labels}" unless labels == ["Zero", "Max ordinate", "Supersonic range", "Impact", "Air density", "Time of flight"]
+raise "zero line #{lines[0]}" unless lines[0] == format("Zero: %.1f yards", plain.far_zero_yards)
+impact = format("Impact: %.1f fps, %.1f ftlbs at %.1f yards", plain.impact_velocity_fps, plain.impact_energy_ftlbs, plain.max_range_yards)
+raise "impact line #{lines[3]}" unless lines[3] == impact
+raise "time line #{lines[5]}" unless lines[5] == format("Time of flight: %.3f s", plain.time_of_flight)
+with_earth = BallisticsEngine.solve_kw(**load, latitude_degrees: 45.0, cant_angle_degrees: 3.0).summary
+raise "no Coriolis or Cant line:\n#{with_earth}" unless with_earth.include?("\nCoriolis: ") && with_earth.include?("\nCant: ")
+metric_summary = BallisticsEngine.solve_kw(**load, output_units: { distance: "m" }).summary
+raise "summary ignores the output units:\n#{metric_summary}" unless metric_summary.start_with?("Zero: ") && metric_summary.include?(" meters")
+puts "  #{lines.length} lines, #{lines[0]}"
+
+# Test 28: Columnar points
+puts "\n28. points(format: :columns) holds the points' values as arrays"
+columns = drifted.points(format: :columns)
+objects = drifted.points
+raise "column keys #{columns.keys}" unless columns.keys.first(4) == %i[times x y z] && columns.values.all? { |values| values.length == objects.length }
+{ times: :time, x: :x, y: :y, z: :z, velocity: :velocity_fps, energy: :energy_ftlbs, mach: :mach }.each do |key, reader|
+  raise "#{key} column differs from the points" unless columns[key] == objects.map(&reader)
+end
+in_metres = BallisticsEngine.solve_kw(**load, output_units: { drop: "cm" })
+check("columns in the output units", in_metres.points(format: :columns)[:y].last, in_metres.points.last.y * 91.44, 1e-9)
+raise "to_h columns" unless drifted.to_h(format: :columns)["points"][:x] == columns[:x]
+puts "  #{columns.keys.length} columns of #{objects.length} values"
+
+# Test 29: CDM drag files
+puts "\n29. CDM parsing"
+curve = [[0.5, 0.235], [1.0, 0.38], [2.0, 0.30], [3.0, 0.26]]
+handles = [
+  "Mach,CD\n1.0,0.380\n0.5,0.235\n# shuffled rows\n3.0,0.260\n2.0,0.300\n",
+  "Mach;Cd\n0,5;0,235\n1,0;0,380\n2,0;0,300\n3,0;0,260\n"
+].map { |text| BallisticsEngine.load_cdm(text) }
+raise "handles #{handles}" unless handles.all? { |handle| handle.start_with?("cdm:") } && handles.uniq.length == 2
+cdm_load = load.reject { |key, _| key == :bc }
+by_curve = BallisticsEngine.solve_kw(**cdm_load, drag_model: "CUSTOM", drag_curve: curve).to_h
+handles.each do |handle|
+  raise "#{handle} solves differently from its curve" unless BallisticsEngine.solve_kw(**cdm_load, drag_model: handle).to_h == by_curve
+end
+puts "  comma and semicolon files load the same curve as #{curve.length} drag_curve pairs"
+
+# Test 30: Spin drift
+puts "\n30. Litz's spin drift, right for a right-hand twist"
+drifting = BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0, spin_drift: true).at_range(1000)
+mirrored = BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0, is_right_twist: false, spin_drift: true).at_range(1000)
+check("spin drift at 1000 yards", drifting.spin_drift, 0.357, 0.005)
+check("spin drift is the windage in still air", drifting.z, drifting.spin_drift, 1e-9)
+check("left twist spin drift", mirrored.spin_drift, -drifting.spin_drift, 1e-9)
+raise "no spin drift without spin_drift: true" unless BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0).at_range(1000).spin_drift.zero?
+puts "  #{(drifting.spin_drift * 36).round(1)} in at 1000 yards"
+
+# Test 31: Spin decay
+puts "\n31. The spin starts at the muzzle velocity over the twist and decays"
+spin_points = BallisticsEngine.solve_kw(**load, twist_rate_inches: 10.0).points
+check("muzzle spin", spin_points.first.spin_rpm, 2650.0 * 12 / 10.0 * 60, 1e-6)
+rates = spin_points.map(&:spin_rpm)
+raise "the spin should only slow" unless rates.each_cons(2).all? { |a, b| b <= a }
+raise "the spin should keep most of its rate: #{rates.last}" unless rates.last < rates.first && rates.last > 0.8 * rates.first
+puts "  #{rates.first.round} rpm at the muzzle, #{rates.last.round} at #{spin_points.last.time.round(2)} s"
+
+# Test 32: Magnus lift
+puts "\n32. A crosswind lifts or drops the spinning bullet by the Magnus force"
+magnus_drop = lambda do |direction, magnus|
+  drop_in.(BallisticsEngine.solve_kw(**load, magnus: magnus, wind: { speed_mph: 10, direction_degrees: direction }), 1000)
+end
+from_left = magnus_drop.(270, true) - magnus_drop.(270, false)
+from_right = magnus_drop.(90, true) - magnus_drop.(90, false)
+raise "a wind from the left should lift a right twist: #{from_left}" unless from_left.positive?
+check("Magnus lift mirrored with the wind", from_right, -from_left, 1e-3)
+puts "  #{from_left.round(2)} in at 1000 yards in 10 mph from the left"
+
+# Test 33: Pejsa against the point-mass solver
+puts "\n33. Pejsa's closed form follows the numeric solve"
+point_mass = BallisticsEngine.solve_kw(**load, solver: "point_mass")
+pejsa = BallisticsEngine.solve_kw(**load, solver: "pejsa")
+{ 300 => 0.25, 500 => 0.5, 800 => 1.0 }.each do |yards, tolerance|
+  check("Pejsa drop at #{yards} yards", drop_in.(pejsa, yards), drop_in.(point_mass, yards), tolerance)
+  check("Pejsa velocity at #{yards} yards", pejsa.at_range(yards).velocity_fps, point_mass.at_range(yards).velocity_fps, 10.0)
+end
+puts "  #{drop_in.(pejsa, 800).round(1)} in at 800 yards against #{drop_in.(point_mass, 800).round(1)}"
+
+# Test 34: Siacci against the point-mass solver
+puts "\n34. Siacci's method follows the numeric solve"
+siacci = BallisticsEngine.solve_kw(**load, solver: "siacci")
+{ 300 => 0.25, 500 => 0.25, 800 => 0.5 }.each do |yards, tolerance|
+  check("Siacci drop at #{yards} yards", drop_in.(siacci, yards), drop_in.(point_mass, yards), tolerance)
+  check("Siacci velocity at #{yards} yards", siacci.at_range(yards).velocity_fps, point_mass.at_range(yards).velocity_fps, 1.0)
+end
+puts "  #{drop_in.(siacci, 800).round(1)} in at 800 yards against #{drop_in.(point_mass, 800).round(1)}"
+
+# Test 35: Coriolis deflection
+puts "\n35. The Earth's rotation deflects the bullet by 2 Ω sin(latitude) ∫ t dx"
+earth_rate = 7.292115e-5
+level = BallisticsEngine.solve_kw(**load)
+# Yard-seconds, up to 1000 yards
+time_range = level.points.each_cons(2).select { |_, b| b.x <= 1000 }.sum { |a, b| (a.time + b.time) / 2 * (b.x - a.x) }
+north = BallisticsEngine.solve_kw(**load, latitude_degrees: 45.0).at_range(1000).z
+south = BallisticsEngine.solve_kw(**load, latitude_degrees: -45.0).at_range(1000).z
+raise "the northern hemisphere should deflect right: #{north}" unless north.positive? && south.negative?
+check("Coriolis deflection at 45°", (north - south) / 2, 2 * earth_rate * Math.sin(Math::PI / 4) * time_range, 0.003)
+puts "  #{(north * 36).round(2)} in right at 1000 yards firing north at 45°N"
+
+# Test 36: Eötvös effect
+puts "\n36. Firing east lifts the bullet by 2 Ω cos(latitude) ∫ t dx, and west drops it"
+eotvos = ->(azimuth) { BallisticsEngine.solve_kw(**load, latitude_degrees: 45.0, azimuth_degrees: azimuth).at_range(1000).y - level.at_range(1000).y }
+east = eotvos.(90.0)
+west = eotvos.(270.0)
+raise "firing east should lift: #{east}" unless east.positive? && west.negative?
+check("Eötvös lift at 45°", (east - west) / 2, 2 * earth_rate * Math.cos(Math::PI / 4) * time_range, 0.003)
+check("no Eötvös effect firing north", eotvos.(0.0), 0.0, 1e-9)
+puts "  #{(east * 36).round(2)} in up at 1000 yards firing east at 45°N"
+
+# Test 37: Earth curvature
+puts "\n37. The level surface falls away by range² / 2R"
+round = BallisticsEngine.solve_kw(**load, earth_curvature: true)
+earth_radius_yards = 6_371_000.0 / 0.9144
+check("curvature drop", round.curvature_drop_yards, round.max_range_yards**2 / (2 * earth_radius_yards), 1e-4)
+correction = round.curvature_correction_yards
+raise "the turned gravity should lower the bullet, a little: #{correction}" unless correction.negative? && correction.abs < 0.05 * round.curvature_drop_yards
+check("flat ground has no curvature drop", level.curvature_drop_yards, 0.0, 1e-12)
+puts "  #{(round.curvature_drop_yards * 36).round(1)} in at #{round.max_range_yards.round} yards, corrected #{(correction * 36).round(3)} in"
+
+# Test 38: Maximum point-blank range
+puts "\n38. mpbr keeps the bullet within half the vital zone"
+mpbr = BallisticsEngine.mpbr(load, vital_zone_inches: 8)
+check("peak height", mpbr[:peak_height_in], 4.0, 0.01)
+path = BallisticsEngine.solve_kw(**load, solver_options: { zero_angle_degrees: mpbr[:zero_angle_degrees] })
+check("height at the zero", drop_in.call(path, mpbr[:zero_yards]), 0.0, 0.01)
+check("height at the far limit", drop_in.call(path, mpbr[:far_limit_yards]), -4.0, 0.01)
+raise "bullet leaves the zone before the far limit" unless path.points.all? { |point| point.x > mpbr[:far_limit_yards] || point.y * 36.0 <= 4.0 + 1e-6 }
+puts "  zero #{mpbr[:zero_yards].round(1)} yards, far limit #{mpbr[:far_limit_yards].round(1)} yards"
+
+# Test 39: Rifleman's rule
+puts "\n39. Inclined fire against rifleman's rule"
+on_level = BallisticsEngine.incline(load, slant_range_yards: 600)
+check("level drop on the level", on_level[:drop_in], on_level[:level_drop_in], 1e-6)
+check("rifleman's rule on the level", on_level[:riflemans_rule][:error_in], 0.0, 1e-6)
+uphill = BallisticsEngine.incline(load.merge(shooting_angle_degrees: 30), slant_range_yards: 600)
+check("horizontal range", uphill[:horizontal_range_yards], 600 * Math.cos(30 * Math::PI / 180), 1e-6)
+check("rifleman's rule range", uphill[:riflemans_rule][:equivalent_range_yards], uphill[:horizontal_range_yards], 1e-6)
+%i[riflemans_rule improved_riflemans_rule].each do |rule|
+  check("#{rule} error", uphill[rule][:error_in], uphill[rule][:drop_in] - uphill[:drop_in], 1e-6)
+end
+unless uphill[:improved_riflemans_rule][:error_in].abs < uphill[:riflemans_rule][:error_in].abs
+  raise "the improved rule should beat rifleman's rule"
+end
+puts "  30° uphill: rifleman's rule off by #{uphill[:riflemans_rule][:error_in].round(2)} in, " \
+     "improved by #{uphill[:improved_riflemans_rule][:error_in].round(2)} in"
+
+# Test 40: Fitting the muzzle velocity to drops
+puts "\n40. fit_drops recovers the muzzle velocity that generated the drops"
+velocity = BallisticsEngine.fit_drops(load.merge(muzzle_velocity_fps: 2700.0),
+                                      drops: [{ range_yards: 800, drop_inches: drop_in.call(solved, 800) }],
+                                      fit: :muzzle_velocity)
+check("fitted muzzle velocity", velocity[:value], 2650.0, 2.0)
+puts "  muzzle velocity #{velocity[:value].round(1)} fps"
+
+# Test 41: Truing to DOPE
+puts "\n41. true recovers the muzzle velocity and drag scale factor"
+field = BallisticsEngine.solve_kw(**load, muzzle_velocity_fps: 2600.0, drag_scale_factor: 1.05)
+observations = [300, 500, 700, 900].map { |yards| { range_yards: yards, measured_drop_moa: field.at_range(yards).drop_moa } }
+trued = BallisticsEngine.true(load, observations: observations)
+check("trued muzzle velocity", trued[:muzzle_velocity_fps], 2600.0, 10.0)
+check("trued drag scale factor", trued[:drag_scale_factor], 1.05, 0.02)
+raise "residuals too large: #{trued[:rms_moa]}" unless trued[:rms_moa] < 0.05
+puts "  #{trued[:muzzle_velocity_fps].round(1)} fps, DSF #{trued[:drag_scale_factor].round(3)}"
+
+# Test 42: Cant
+puts "\n42. A canted rifle turns the bore line's offset from the line of sight"
+bore_line_yards = ->(yards) { yards * Math.tan(0.1 * Math::PI / 180) - 1.5 / 36 }
+upright = BallisticsEngine.solve_kw(**load, solver_options: { zero_angle_degrees: 0.1 })
+[5.0, -5.0].each do |degrees|
+  canted = BallisticsEngine.solve_kw(**load, cant_angle_degrees: degrees, solver_options: { zero_angle_degrees: 0.1 })
+  angle = degrees * Math::PI / 180
+  [0, 500, 1000].each do |yards|
+    check("#{degrees}° cant across at #{yards} yards", canted.at_range(yards).z - upright.at_range(yards).z,
+          bore_line_yards.(yards) * Math.sin(angle), 1e-6)
+    check("#{degrees}° cant down at #{yards} yards", canted.at_range(yards).y - upright.at_range(yards).y,
+          bore_line_yards.(yards) * (Math.cos(angle) - 1), 1e-6)
+  end
+  check("#{degrees}° cant at the end", canted.cant_horizontal_yards, bore_line_yards.(canted.max_range_yards) * Math.sin(angle), 1e-6)
+end
+puts "  #{(bore_line_yards.(1000) * Math.sin(5 * Math::PI / 180) * 36).round(2)} in across at 1000 yards canted 5°"
 
 puts "\n✓ All tests passed!"